@@ -1,9 +1,9 @@
 //! Module related to MediaWiki login and information retrieval.
 
-use crate::APIConnection;
+use crate::{APIConnection, ApiLoginConfig};
 use mwapi::{Client, Assert, ErrorFormat};
 use serde::Deserialize;
-use std::collections::{HashMap, HashSet};
+use std::{collections::{HashMap, HashSet}, time::SystemTime};
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
@@ -32,20 +32,60 @@ struct UserInfo {
     rights: HashSet<String>,
 }
 
-pub(crate) async fn get_provider(site: &str, user: &str, password: &str) -> Option<APIConnection> {
+/// How a connection logs in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Login<'a> {
+    Anonymous,
+    BotPassword { user: &'a str, password: &'a str },
+    /// An OAuth 2.0 access token, eg. of an owner-only consumer. The user name is only used in the user agent.
+    OAuth2 { user: &'a str, token: &'a str },
+}
+
+impl<'a> Login<'a> {
+    /// An OAuth 2.0 token takes precedence over a bot password.
+    fn from_config(config: &'a ApiLoginConfig) -> Self {
+        match config.oauth2_token.as_deref() {
+            Some(token) => Self::OAuth2 { user: &config.username, token },
+            None if !config.username.is_empty() => Self::BotPassword { user: &config.username, password: &config.password },
+            None => Self::Anonymous,
+        }
+    }
+}
+
+/// The user agent sent to the site: the configured one if any, or one naming the bot and the user.
+/// Returns `None` if the configured user agent is blank, as many sites block requests without a proper one.
+fn user_agent(configured: Option<&str>, login: Login<'_>) -> Option<String> {
+    let (version, repository) = (env!("CARGO_PKG_VERSION"), env!("CARGO_PKG_REPOSITORY"));
+    match (configured, login) {
+        (Some(ua), _) if ua.trim().is_empty() => None,
+        (Some(ua), _) => Some(ua.trim().to_string()),
+        (None, Login::BotPassword { user, .. } | Login::OAuth2 { user, .. }) if !user.is_empty() => Some(format!("Page List Bot version {version} logged in as `User:{user}`; report issues to `{repository}`")),
+        (None, Login::OAuth2 { .. }) => Some(format!("Page List Bot version {version} logged in with OAuth; report issues to `{repository}`")),
+        (None, _) => Some(format!("Page List Bot version {version} not logged in; report issues to `{repository}`")),
+    }
+}
+
+pub(crate) async fn get_provider(config: &ApiLoginConfig) -> Option<APIConnection> {
+    let (site, user) = (config.api.as_str(), config.username.as_str());
+    let login = Login::from_config(config);
+    let Some(user_agent) = user_agent(config.user_agent.as_deref(), login) else {
+        tracing::warn!(site=site, user=user, "user agent is empty");
+        return None;
+    };
     // attempt to connect to website.
     let mut builder = Client::builder(site)
-        .set_errorformat(ErrorFormat::default());
-    if !user.is_empty() { // login with credential
-        builder = builder
-            .set_botpassword(user, password)
-            .set_assert(Assert::User)
-            .set_user_agent(&format!("Page List Bot version {} logged in as `User:{}`; report issues to `{}`", env!("CARGO_PKG_VERSION"), user, env!("CARGO_PKG_REPOSITORY")));
-    } else {
-        builder = builder
-            .set_assert(Assert::Anonymous)
-            .set_user_agent(&format!("Page List Bot version {} not logged in; report issues to `{}`", env!("CARGO_PKG_VERSION"), env!("CARGO_PKG_REPOSITORY")));
+        .set_errorformat(ErrorFormat::default())
+        .set_user_agent(&user_agent);
+    if let Some(maxlag) = config.maxlag {
+        // the client waits as long as the `Retry-After` header of a `maxlag` error says, then retries.
+        builder = builder.set_maxlag(maxlag);
     }
+    let assert = if config.assert_bot { Assert::Bot } else { Assert::User };
+    builder = match login {
+        Login::BotPassword { user, password } => builder.set_botpassword(user, password).set_assert(assert),
+        Login::OAuth2 { token, .. } => builder.set_oauth2_token(token).set_assert(assert),
+        Login::Anonymous => builder.set_assert(Assert::Anonymous),
+    };
     let api = match builder.build().await {
         Ok(x) => x,
         Err(e) => {
@@ -81,5 +121,38 @@ pub(crate) async fn get_provider(site: &str, user: &str, password: &str) -> Opti
         },
     };
 
-    Some(APIConnection { client: api, site_info, bot, apihighlimits })
+    Some(APIConnection { client: api, site_info, bot, apihighlimits, logged_in: login != Login::Anonymous, refreshed: SystemTime::now() })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ApiLoginConfig;
+    use super::{user_agent, Login};
+
+    #[test]
+    fn test_user_agent() {
+        let bot = Login::BotPassword { user: "Example@bot", password: "secret" };
+        let configured = "PageListBot-enwiki (https://en.wikipedia.org/wiki/User:Example)";
+        assert_eq!(user_agent(Some(configured), bot).as_deref(), Some(configured));
+        assert_eq!(user_agent(Some(&format!(" {configured}\n")), Login::Anonymous).as_deref(), Some(configured));
+        assert_eq!(user_agent(Some(""), bot), None);
+        assert_eq!(user_agent(Some("  "), Login::Anonymous), None);
+
+        // the defaults name the user, if any.
+        assert!(user_agent(None, bot).unwrap().contains("`User:Example@bot`"));
+        assert!(user_agent(None, Login::OAuth2 { user: "", token: "t" }).unwrap().contains("with OAuth"));
+        assert!(user_agent(None, Login::Anonymous).unwrap().contains("not logged in"));
+    }
+
+    #[test]
+    fn test_login() {
+        let parse = |s: &str| toml::from_str::<ApiLoginConfig>(s).unwrap();
+
+        let config = parse("api = \"https://en.wikipedia.org/w/api.php\"\nusername = \"Example\"\noauth2_token = \"abc\"");
+        assert_eq!(Login::from_config(&config), Login::OAuth2 { user: "Example", token: "abc" });
+        let config = parse("api = \"https://en.wikipedia.org/w/api.php\"\nusername = \"Example@bot\"\npassword = \"secret\"");
+        assert_eq!(Login::from_config(&config), Login::BotPassword { user: "Example@bot", password: "secret" });
+        let config = parse("api = \"https://en.wikipedia.org/w/api.php\"");
+        assert_eq!(Login::from_config(&config), Login::Anonymous);
+    }
 }
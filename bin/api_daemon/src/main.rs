@@ -9,7 +9,7 @@
 
 use clap::Parser;
 use pagelistbot_api_daemon_interface::APIServiceInterfaceServer;
-use std::{collections::HashMap, fs, path::{Path, PathBuf}, sync::Arc, time::Duration};
+use std::{collections::HashMap, fs, future::Future, path::{Path, PathBuf}, sync::Arc, time::{Duration, SystemTime}};
 use tokio::sync::RwLock;
 use tracing_subscriber::prelude::*;
 
@@ -24,6 +24,9 @@ struct Arg {
     bind_all: bool,
     #[arg(short = 'p', long = "port", default_value_t = 8848)]
     port: u16,
+    /// Seconds between two reloads of the configuration file.
+    #[arg(long, default_value_t = 3600, value_parser = clap::value_parser!(u64).range(1..))]
+    refresh_interval: u64,
 }
 
 /// `APIConnection` is the main interface to interact with. It contains
@@ -31,12 +34,16 @@ struct Arg {
 /// * A `serde_json::Value` object holding the raw siteinfo query response.
 /// * A boolean flag indicating whether this client has `bot` user right.
 /// * A boolean flag indication whether this client has `apihighlimits` user right.
+/// * A boolean flag indicating whether this client is logged in.
+/// * The time this connection was set up.
 #[derive(Debug, Clone)]
 struct APIConnection {
     client: mwapi::Client,
     site_info: serde_json::Value,
     bot: bool,
     apihighlimits: bool,
+    logged_in: bool,
+    refreshed: SystemTime,
 }
 
 #[tokio::main]
@@ -47,7 +54,7 @@ async fn main() {
     // set up log writer
     let (non_blocking_logfile, _logfile_guard) = tracing_appender::non_blocking(tracing_appender::rolling::daily(pagelistbot_env::pagelistbot_log(), "api-backend.log"));
     let (non_blocking_stderr, _stderr_guard) = tracing_appender::non_blocking(std::io::stderr());
-    tracing_subscriber::registry()
+    let registry = tracing_subscriber::registry()
         .with(
             tracing_subscriber::fmt::layer()
                 .with_writer(non_blocking_logfile)
@@ -59,13 +66,24 @@ async fn main() {
                 .with_writer(non_blocking_stderr)
                 .with_ansi(true)
                 .with_filter(tracing_subscriber::filter::LevelFilter::WARN)
-        )
-        .init();
+        );
+    #[cfg(feature = "otel")]
+    let (registry, _otel_guard, otel_error) = match pagelistbot_env::otel::otlp_endpoint().map(|endpoint| pagelistbot_env::otel::otel_layer("api-daemon", &endpoint)) {
+        Some(Ok((layer, guard))) => (registry.with(Some(layer)), Some(guard), None),
+        Some(Err(e)) => (registry.with(None), None, Some(e)),
+        None => (registry.with(None), None, None),
+    };
+    registry.init();
+    #[cfg(feature = "otel")]
+    if let Some(e) = otel_error {
+        tracing::warn!(warning=e.to_string(), "cannot set up trace export");
+    }
     // set up refresh routine
     let refresh_handle = {
         let api_store = api_store.clone();
         let config_path = config_path.clone();
-        tokio::spawn(load_config(config_path, api_store))
+        let refresh_interval = Duration::from_secs(arg.refresh_interval);
+        tokio::spawn(load_config(config_path, api_store, refresh_interval))
     };
     // set up RPC server
     let server_handle = {
@@ -109,14 +127,94 @@ struct ApiLoginConfig {
     #[serde(default)]
     password: String,
     api: String,
+    /// Replaces the default user agent, eg. to add contact information.
+    #[serde(default)]
+    user_agent: Option<String>,
+    /// Assert that every request is made by a bot account, not only a logged-in one.
+    #[serde(default)]
+    assert_bot: bool,
+    /// Ask the site to refuse requests while its replication lag exceeds this many seconds.
+    #[serde(default)]
+    maxlag: Option<u32>,
+    /// An OAuth 2.0 access token, used instead of the bot password.
+    #[serde(default)]
+    oauth2_token: Option<String>,
 }
 
 type ConfigFile = HashMap<String, ApiLoginConfig>;
 
-async fn load_config<P>(path: P, store: Arc<RwLock<HashMap<String, APIConnection>>>) -> !
+/// What caused the configuration to be reloaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReloadTrigger {
+    /// The refresh interval has elapsed.
+    Timer,
+    /// `SIGHUP` is received.
+    Signal,
+}
+
+/// Wait until the configuration should be reloaded, either when `interval` elapses or when `signal` yields, whichever comes first.
+/// If `signal` yields `None`, it is ignored and only the timer is waited for.
+async fn wait_reload<F>(interval: Duration, signal: F) -> ReloadTrigger
+where
+    F: Future<Output = Option<()>>,
+{
+    tokio::select! {
+        _ = tokio::time::sleep(interval) => ReloadTrigger::Timer,
+        Some(()) = signal => ReloadTrigger::Signal,
+    }
+}
+
+/// Listen to `SIGHUP`, so the configuration can be reloaded on demand.
+#[cfg(unix)]
+fn hangup_signal() -> Option<tokio::signal::unix::Signal> {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::hangup()) {
+        Ok(signal) => Some(signal),
+        Err(e) => {
+            tracing::warn!(warning=e.to_string(), "cannot listen to SIGHUP, reload only periodically");
+            None
+        }
+    }
+}
+
+/// Bring `store` in line with `config`, setting up each connection with `connect`.
+///
+/// Connections that no longer exist in the configuration are dropped. Every other connection is replaced by a fresh one.
+/// If a fresh connection cannot be set up, the existing connection is kept, so a momentary failure does not take a site offline.
+async fn update_store<C, F, Fut>(store: &mut HashMap<String, C>, config: ConfigFile, mut connect: F)
+where
+    F: FnMut(ApiLoginConfig) -> Fut,
+    Fut: Future<Output = Option<C>>,
+{
+    // flush out all connections that no longer exist in the configuration.
+    store.retain(|k, _| {
+        let preserve = config.contains_key(k);
+        if !preserve {
+            tracing::info!("dropped `{}`", k);
+        }
+        preserve
+    });
+    // add or replace other connections.
+    for (k, v) in config {
+        if let Some(new_connection) = connect(v).await {
+            // replace the old connection with the new one.
+            // the old one is automatically dropped.
+            tracing::info!("added `{}`", &k);
+            store.insert(k, new_connection);
+        } else if store.contains_key(&k) {
+            tracing::warn!("cannot refresh `{}`, keep the existing connection", &k);
+        } else {
+            tracing::warn!("cannot add `{}`", &k);
+        }
+    }
+}
+
+async fn load_config<P>(path: P, store: Arc<RwLock<HashMap<String, APIConnection>>>, refresh_interval: Duration) -> !
 where
     P: AsRef<Path>,
 {
+    #[cfg(unix)]
+    let mut hangup = hangup_signal();
     loop {
         '_mainscope: {
             let config = match fs::read_to_string(path.as_ref()) {
@@ -135,30 +233,70 @@ where
             };
             // update the hashmap.
             let mut store = store.write().await;
-            // flush out all connections that no longer exist in the configuration.
-            store.retain(|k, _| {
-                let preserve = config.contains_key(k);
-                if !preserve {
-                    tracing::info!("dropped `{}`", k);
-                }
-                preserve
-            });
-            // add or replace other connections.
-            for (k, v) in config {
-                if let Some(new_connection) = connection::get_provider(&v.api, &v.username, &v.password).await {
-                    // replace the old connection with the new one.
-                    // the old one is automatically dropped.
-                    tracing::info!("added `{}`", &k);
-                    store.insert(k, new_connection);
-                } else {
-                    // new connection generation failed, drop the existing connection.
-                    // TODO: or should we retain the existing connection?
-                    tracing::warn!("dropped `{}`", &k);
-                    store.remove(&k);
-                }
-            }
+            update_store(&mut store, config, |v| async move {
+                connection::get_provider(&v).await
+            }).await;
             break '_mainscope;
         }
-        tokio::time::sleep(Duration::from_secs(3600)).await;  // update once per hour.
+        #[cfg(unix)]
+        let signal = async {
+            match hangup.as_mut() {
+                Some(hangup) => hangup.recv().await,
+                None => None,
+            }
+        };
+        #[cfg(not(unix))]
+        let signal = std::future::pending();
+        let trigger = wait_reload(refresh_interval, signal).await;
+        tracing::info!(trigger=?trigger, "reload configuration file");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{update_store, wait_reload, ApiLoginConfig, Arg, ConfigFile, ReloadTrigger};
+    use clap::Parser;
+    use std::{collections::HashMap, future, time::Duration};
+
+    #[test]
+    fn test_refresh_interval() {
+        assert_eq!(Arg::try_parse_from(["api-daemon"]).unwrap().refresh_interval, 3600);
+        assert_eq!(Arg::try_parse_from(["api-daemon", "--refresh-interval", "60"]).unwrap().refresh_interval, 60);
+        assert!(Arg::try_parse_from(["api-daemon", "--refresh-interval", "0"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_wait_reload() {
+        let hour = Duration::from_secs(3600);
+        assert_eq!(wait_reload(hour, future::ready(Some(()))).await, ReloadTrigger::Signal);
+        // a closed signal stream falls back to the timer.
+        assert_eq!(wait_reload(Duration::from_millis(10), future::ready(None)).await, ReloadTrigger::Timer);
+        assert_eq!(wait_reload(Duration::from_millis(10), future::pending()).await, ReloadTrigger::Timer);
+    }
+
+    #[tokio::test]
+    async fn test_update_store_keeps_connection() {
+        let login = |api: &str| ApiLoginConfig { username: String::new(), password: String::new(), api: api.to_string(), user_agent: None, assert_bot: false, maxlag: None, oauth2_token: None };
+        let config: ConfigFile = [("enwiki".to_string(), login("https://en.wikipedia.org/w/api.php"))].into_iter().collect();
+        let mut store = HashMap::new();
+
+        // the first login succeeds.
+        let mut calls = 0;
+        update_store(&mut store, config.clone(), |_| { calls += 1; future::ready(Some(calls)) }).await;
+        assert_eq!(store, HashMap::from([("enwiki".to_string(), 1)]));
+
+        // the refresh fails, and the first connection survives.
+        update_store(&mut store, config.clone(), |_| future::ready(None)).await;
+        assert_eq!(store, HashMap::from([("enwiki".to_string(), 1)]));
+
+        // a site that never connected is not added.
+        let mut both = config.clone();
+        both.insert("zhwiki".to_string(), login("https://zh.wikipedia.org/w/api.php"));
+        update_store(&mut store, both, |v| future::ready(v.api.starts_with("https://en.").then_some(2))).await;
+        assert_eq!(store, HashMap::from([("enwiki".to_string(), 2)]));
+
+        // the connection is only dropped when the site leaves the configuration.
+        update_store(&mut store, ConfigFile::new(), |_| future::ready(Some(3))).await;
+        assert!(store.is_empty());
     }
 }
@@ -4,10 +4,11 @@
 
 use crate::APIConnection;
 use jsonrpsee::core::RpcResult;
-use pagelistbot_api_daemon_interface::APIServiceInterfaceServer;
+use pagelistbot_api_daemon_interface::{APIServiceInterfaceServer, ConnectionHealth};
 use serde_json::Value;
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::UNIX_EPOCH};
 use tokio::sync::RwLock;
+use tracing::Instrument;
 
 /// All possible errors emitted by API Backend Service.
 #[derive(Debug, thiserror::Error)]
@@ -15,8 +16,22 @@ enum APIServiceError {
     #[error("no connection for `{0}`")]
     NoConnection(String),
 
+    /// The site reports that the connection is no longer logged in, or not as a bot, as asserted.
+    #[error("assertion failed: {0}")]
+    AssertionFailed(mwapi::Error),
+
     #[error(transparent)]
-    MwApi(#[from] mwapi::Error),
+    MwApi(mwapi::Error),
+}
+
+impl From<mwapi::Error> for APIServiceError {
+    fn from(value: mwapi::Error) -> Self {
+        match value {
+            // `assertuserfailed` and `assertbotfailed`.
+            mwapi::Error::NotLoggedIn | mwapi::Error::NotLoggedInAsBot => Self::AssertionFailed(value),
+            _ => Self::MwApi(value),
+        }
+    }
 }
 
 impl APIServiceError {
@@ -25,6 +40,7 @@ impl APIServiceError {
         match self {
             Self::NoConnection(_) => 10000,
             Self::MwApi(_) => 10001,
+            Self::AssertionFailed(_) => 10002,
         }
     }
 
@@ -53,6 +69,19 @@ impl APIServiceImpl {
 
 #[async_trait::async_trait]
 impl APIServiceInterfaceServer for APIServiceImpl {
+    async fn health(&self) -> RpcResult<Vec<ConnectionHealth>> {
+        let store = self.store.read().await;
+        let mut health: Vec<_> = store.iter().map(|(key, connection)| ConnectionHealth {
+            key: key.to_owned(),
+            logged_in: connection.logged_in,
+            bot: connection.bot,
+            apihighlimits: connection.apihighlimits,
+            last_refresh: connection.refreshed.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+        }).collect();
+        health.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(health)
+    }
+
     async fn get_site_info(&self, key: &str) -> RpcResult<Value> {
         let store = self.store.read().await;
         let connection = store.get(key).ok_or(APIServiceError::NoConnection(key.into()))?;
@@ -74,21 +103,75 @@ impl APIServiceInterfaceServer for APIServiceImpl {
     async fn get_value(&self, key: &str, parameters: HashMap<String, String>) -> RpcResult<Value> {
         let store = self.store.read().await;
         let connection = store.get(key).ok_or(APIServiceError::NoConnection(key.into()))?;
-        let ret = connection.client.get_value(parameters).await.map_err(APIServiceError::from)?;
+        let span = request_span(key, "get", &parameters);
+        let ret = connection.client.get_value(parameters).instrument(span).await.map_err(APIServiceError::from)?;
         Ok(ret)
     }
 
     async fn post_value(&self, key: &str, parameters: HashMap<String, String>) -> RpcResult<Value> {
         let store = self.store.read().await;
         let connection = store.get(key).ok_or(APIServiceError::NoConnection(key.into()))?;
-        let ret = connection.client.post_value(parameters).await.map_err(APIServiceError::from)?;
+        let span = request_span(key, "post", &parameters);
+        let ret = connection.client.post_value(parameters).instrument(span).await.map_err(APIServiceError::from)?;
         Ok(ret)
     }
 
     async fn post_value_with_token(&self, key: &str, token_type: &str, parameters: HashMap<String, String>) -> RpcResult<Value> {
         let store = self.store.read().await;
         let connection = store.get(key).ok_or(APIServiceError::NoConnection(key.into()))?;
-        let ret = connection.client.post_with_token(token_type, parameters).await.map_err(APIServiceError::from)?;
+        let span = request_span(key, "post", &parameters);
+        let ret = connection.client.post_with_token(token_type, parameters).instrument(span).await.map_err(APIServiceError::from)?;
         Ok(ret)
     }
 }
+
+/// A span covering one request to the site.
+fn request_span(key: &str, method: &str, parameters: &HashMap<String, String>) -> tracing::Span {
+    tracing::info_span!("site_request", key, method, action = parameters.get("action").map(String::as_str))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::APIConnection;
+    use pagelistbot_api_daemon_interface::{APIServiceInterfaceServer, ConnectionHealth};
+    use std::{collections::HashMap, sync::Arc, time::{Duration, UNIX_EPOCH}};
+    use super::{APIServiceError, APIServiceImpl};
+    use tokio::sync::RwLock;
+
+    #[tokio::test]
+    async fn test_health() {
+        // building a client without credentials does not touch the network.
+        let client = mwapi::Client::new("http://127.0.0.1:1/w/api.php").await.unwrap();
+        let connection = |logged_in: bool, refreshed: u64| APIConnection {
+            client: client.clone(),
+            site_info: serde_json::Value::Null,
+            bot: logged_in,
+            apihighlimits: logged_in,
+            logged_in,
+            refreshed: UNIX_EPOCH + Duration::from_secs(refreshed),
+        };
+        let store = HashMap::from([
+            ("zhwiki".to_string(), connection(false, 1700000000)),
+            ("enwiki".to_string(), connection(true, 1700003600)),
+        ]);
+        let service = APIServiceImpl::new(Arc::new(RwLock::new(store)));
+
+        assert_eq!(service.health().await.unwrap(), vec![
+            ConnectionHealth { key: "enwiki".to_string(), logged_in: true, bot: true, apihighlimits: true, last_refresh: 1700003600 },
+            ConnectionHealth { key: "zhwiki".to_string(), logged_in: false, bot: false, apihighlimits: false, last_refresh: 1700000000 },
+        ]);
+
+        let empty = APIServiceImpl::new(Default::default());
+        assert!(empty.health().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_assertion_failed() {
+        for error in [mwapi::Error::NotLoggedIn, mwapi::Error::NotLoggedInAsBot] {
+            let error = APIServiceError::from(error);
+            assert!(matches!(error, APIServiceError::AssertionFailed(_)));
+            assert_eq!(error.code(), 10002);
+        }
+        assert_eq!(APIServiceError::NoConnection("enwiki".to_string()).code(), 10000);
+    }
+}
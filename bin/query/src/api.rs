@@ -1,30 +1,116 @@
 use async_stream::stream;
-use core::convert::Infallible;
-use futures::Stream;
+use futures::{Stream, StreamExt};
 use itertools::Itertools;
 use jsonrpsee::core::ClientError;
 use mwapi_responses::{query, ApiResponse};
 use mwtitle::{Title, TitleCodec, SiteInfoResponse};
 use pagelistbot_api_daemon_interface::APIServiceInterfaceClient;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solver::NamespaceMap;
 use provider::{
-    DataProvider, PageInfo,
-    FilterRedirect, LinksConfig, BackLinksConfig, EmbedsConfig, CategoryMembersConfig, PrefixConfig,
+    DataProvider, Pacer, PageInfo, ProtectionEntry,
+    FilterRedirect, CategorySort, SortDirection, LinksConfig, BackLinksConfig, EmbedsConfig, TemplatesConfig, ImagesConfig, FileUsageConfig, CategoriesConfig, LangLinksConfig, CategoryMembersConfig, PrefixConfig,
 };
-use std::collections::HashMap;
+use std::{collections::{BTreeMap, BTreeSet, HashMap}, sync::Arc, time::Instant};
+use tracing::Instrument;
 use trio_result::TrioResult;
 
 #[query(
     prop = "info",
-    inprop = "associatedpage|subjectid|talkid",
+    inprop = "associatedpage|subjectid|talkid|protection",
 )]
 struct QueryResponse;
 
+/// Page properties read alongside `QueryResponse`, in the same order as its pages.
+/// All of them are absent on missing pages.
+#[derive(Debug, Default, Deserialize)]
+struct PageDetails {
+    #[serde(default)]
+    pageid: Option<u32>,
+    #[serde(default)]
+    length: Option<u32>,
+    #[serde(default)]
+    touched: Option<String>,
+    #[serde(default)]
+    subjectid: Option<u32>,
+    #[serde(default)]
+    talkid: Option<u32>,
+    #[serde(default)]
+    protection: Option<Vec<PageProtection>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PageProtection {
+    #[serde(rename = "type")]
+    type_: String,
+    level: String,
+    expiry: String,
+}
+
+impl From<PageProtection> for ProtectionEntry {
+    fn from(p: PageProtection) -> Self {
+        ProtectionEntry {
+            action: p.type_,
+            level: p.level,
+            // the API spells a permanent protection as `infinity`.
+            expiry: Some(p.expiry).filter(|e| e != "infinity"),
+        }
+    }
+}
+
+/// `prop=langlinks` is not a generator, so its response is read by hand.
+#[derive(Debug, Deserialize)]
+struct LangLinksResponse {
+    #[serde(default)]
+    query: Option<LangLinksResponseQuery>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LangLinksResponseQuery {
+    #[serde(default)]
+    pages: Vec<LangLinksResponsePage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LangLinksResponsePage {
+    #[serde(default)]
+    langlinks: Vec<LangLinksResponseItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LangLinksResponseItem {
+    lang: String,
+    title: String,
+}
+
+/// An opaque checkpoint of a query, taken from the `continue` field of an API response.
+///
+/// It can be saved, and handed back to `query_resumable` to pick up the query where it was left off.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ContinueToken(HashMap<String, String>);
+
+/// An item yielded by `query_resumable`.
+#[derive(Debug, Clone)]
+pub enum Resumable {
+    /// A page from the latest response.
+    Page(Box<PageInfo>),
+    /// All pages before this item have been yielded. Resuming from this token yields the pages after it.
+    /// No checkpoint is yielded after the last response.
+    Checkpoint(ContinueToken),
+}
+
 #[derive(Debug, Clone)]
 pub struct APIDataProvider<B> {
     backend: B,
     key: String,
     title_codec: TitleCodec,
-    apihighlimits: bool,
+    namespaces: NamespaceMap,
+    chunk_size: usize,
+    max_concurrency: usize,
+    assoc_redirects: bool,
+    pacer: Option<Arc<Pacer>>,
 }
 
 impl<B> APIDataProvider<B>
@@ -32,33 +118,176 @@ where
     B: APIServiceInterfaceClient + Sync,
 {
     pub async fn new(connection: B, key: &str) -> Result<Self, APIDataProviderError> {
-        let title_codec = {
+        let (title_codec, namespaces) = {
             let siteinfo = connection.get_site_info(key).await?;
+            let namespaces = namespace_names(&siteinfo);
             let siteinfo: SiteInfoResponse = serde_json::from_value(siteinfo)?;
-            TitleCodec::from_site_info(siteinfo.query)?
+            (TitleCodec::from_site_info(siteinfo.query)?, namespaces)
         };
         let apihighlimits = connection.get_apihighlimits(key).await?;
         Ok(APIDataProvider {
             backend: connection,
             key: key.to_owned(),
             title_codec,
-            apihighlimits,
+            namespaces,
+            chunk_size: if apihighlimits { 500 } else { 50 },
+            max_concurrency: 1,
+            assoc_redirects: false,
+            pacer: None,
         })
     }
 
+    /// Override the number of titles sent in a single request.
+    /// By default this is 500 if the account has `apihighlimits`, or 50 otherwise.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Result<Self, APIDataProviderError> {
+        if chunk_size == 0 {
+            return Err(APIDataProviderError::InvalidChunkSize);
+        }
+        self.chunk_size = chunk_size;
+        Ok(self)
+    }
+
+    /// The number of titles sent in a single request.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Allow up to `max_concurrency` requests of a single query to be in flight at once.
+    /// By default this is 1, ie. each request is sent after the previous one is answered.
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Result<Self, APIDataProviderError> {
+        if max_concurrency == 0 {
+            return Err(APIDataProviderError::InvalidMaxConcurrency);
+        }
+        self.max_concurrency = max_concurrency;
+        Ok(self)
+    }
+
+    /// The number of requests of a single query allowed in flight at once.
+    pub fn max_concurrency(&self) -> usize {
+        self.max_concurrency
+    }
+
+    /// Tell whether the associated page of each page is a redirect.
+    /// The API cannot answer this in the same request, so this costs one more request per response.
+    pub fn with_assoc_redirects(mut self, assoc_redirects: bool) -> Self {
+        self.assoc_redirects = assoc_redirects;
+        self
+    }
+
+    /// Send one request, in an `api_request` span carrying the generator, the number of input titles or page ids, and
+    /// the number of requests of the same query sent before, ie. how many times it has been continued.
+    /// The time taken and the number of pages returned are logged at `DEBUG`. With a rate limit, the request first waits
+    /// for its turn.
+    async fn post(&self, params: HashMap<String, String>, iteration: usize) -> Result<Value, ClientError> {
+        if let Some(pacer) = &self.pacer {
+            pacer.wait().await;
+        }
+        let generator = params.get("generator").cloned().unwrap_or_default();
+        let titles = params.get("titles").or_else(|| params.get("pageids")).map_or(0, |x| x.split('|').count());
+        let span = tracing::info_span!("api_request", generator = generator.as_str(), titles, iteration);
+        let started = Instant::now();
+        let resp = self.backend.post_value(&self.key, params).instrument(span.clone()).await;
+        span.in_scope(|| match &resp {
+            Ok(x) => {
+                let pages = x.pointer("/query/pages").and_then(Value::as_array).map_or(0, Vec::len);
+                tracing::debug!(elapsed_ms = started.elapsed().as_millis() as u64, pages, "request done");
+            },
+            Err(e) => tracing::debug!(elapsed_ms = started.elapsed().as_millis() as u64, error = e.to_string(), "request failed"),
+        });
+        resp
+    }
+
+    /// Find out which of the associated pages of `pages` are redirects.
+    /// Associated pages that do not exist are not redirects, and are not asked about.
+    async fn fetch_assoc_redirects(&self, pages: &[PageInfo]) -> Result<(BTreeMap<Title, bool>, Vec<ProviderWarning>), APIDataProviderError> {
+        let titles = pages.iter()
+            .filter(|page| page.get_assoc_exists() == Ok(true))
+            .filter_map(|page| page.get_assoc_title().ok().cloned());
+        let mut redirects = BTreeMap::new();
+        let mut warnings = Vec::new();
+        for (iteration, title_chunk) in chunk_titles(unique_titles(titles), self.chunk_size).into_iter().enumerate() {
+            let params = HashMap::from_iter([
+                ("action".to_string(), "query".to_string()),
+                ("prop".to_string(), "info".to_string()),
+                ("titles".to_string(), title_chunk.iter().map(|t| self.title_codec.to_pretty(t)).join("|")),
+            ]);
+            let resp = self.post(params, iteration).await?;
+            warnings.extend(api_warnings(&resp));
+            for page in resp.pointer("/query/pages").and_then(Value::as_array).into_iter().flatten() {
+                let Some(title) = page.get("title").and_then(Value::as_str) else { continue };
+                match self.title_codec.new_title(title) {
+                    Ok(t) => { redirects.insert(t, page.get("redirect").and_then(Value::as_bool).unwrap_or(false)); },
+                    Err(_) => warnings.push(ProviderWarning::InvalidTitle { title: title.to_string() }),
+                }
+            }
+        }
+        Ok((redirects, warnings))
+    }
+
+    /// Send at most `per_second` requests per second, counting every continued and concurrent request.
+    /// Clones share the same pace. By default requests are not paced.
+    pub fn with_rate_limit(mut self, per_second: u32) -> Result<Self, APIDataProviderError> {
+        if per_second == 0 {
+            return Err(APIDataProviderError::InvalidRateLimit);
+        }
+        self.pacer = Some(Arc::new(Pacer::new(per_second)));
+        Ok(self)
+    }
+
     pub fn to_pretty(&self, title: &Title) -> String {
         self.title_codec.to_pretty(title)
     }
 
-    fn query_all(&self, mut params: HashMap<String, String>) -> impl Stream<Item=TrioResult<PageInfo, Infallible, APIDataProviderError>> + '_ {
+    /// Parse a title the way the site does, eg. `main page` is the same as `Main_Page`.
+    pub fn new_title(&self, raw: &str) -> Result<Title, mwtitle::Error> {
+        self.title_codec.new_title(raw)
+    }
+
+    /// Namespace names and aliases of the site, for resolving names in queries.
+    pub fn namespaces(&self) -> &NamespaceMap {
+        &self.namespaces
+    }
+
+    fn query_all(&self, params: HashMap<String, String>) -> impl Stream<Item=TrioResult<PageInfo, ProviderWarning, APIDataProviderError>> + '_ {
         stream! {
-            // set up query parameters
-            params.insert("action".to_string(), "query".to_string());
-            for (k, v) in QueryResponse::params() {
-                params.insert(k.to_string(), v.to_string());
+            for await x in self.query_resumable(params, None) {
+                match x {
+                    TrioResult::Ok(Resumable::Page(page)) => yield TrioResult::Ok(*page),
+                    TrioResult::Ok(Resumable::Checkpoint(token)) => tracing::trace!(?token, "checkpoint"),
+                    TrioResult::Warn(w) => yield TrioResult::Warn(w),
+                    TrioResult::Err(e) => yield TrioResult::Err(e),
+                }
             }
-            // set up continue
-            let mut continue_: Option<HashMap<String, String>> = None;
+        }
+    }
+
+    /// Fetch the page information of one chunk of titles, with the pages in the order of `title_chunk`.
+    /// Warnings and errors come first.
+    async fn page_info_chunk(&self, title_chunk: Vec<Title>) -> Vec<TrioResult<PageInfo, ProviderWarning, APIDataProviderError>> {
+        let params = HashMap::from_iter([
+            ("titles".to_string(), title_chunk.iter().map(|t| self.title_codec.to_pretty(t)).join("|"))
+        ]);
+        let mut items = Vec::new();
+        let mut pages = Vec::new();
+        let mut stream = std::pin::pin!(self.query_all(params));
+        while let Some(x) = stream.next().await {
+            match x {
+                TrioResult::Ok(page) => pages.push(page),
+                x => items.push(x),
+            }
+        }
+        // pages not asked for by that title, if any, go last.
+        pages.sort_by_key(|page| page.get_title().ok().and_then(|t| title_chunk.iter().position(|x| x == t)).unwrap_or(usize::MAX));
+        items.extend(pages.into_iter().map(TrioResult::Ok));
+        items
+    }
+
+    /// Send a query, then continue it until the API has nothing more, yielding each response after its API warnings.
+    /// If `resume` is set, the query starts from that `continue` field instead of from the beginning.
+    fn query_continued(&self, params: HashMap<String, String>, resume: Option<HashMap<String, String>>) -> impl Stream<Item=TrioResult<Value, ProviderWarning, APIDataProviderError>> + '_ {
+        stream! {
+            let mut continue_ = resume;
+            let mut iteration = 0;
             while !(continue_.as_ref().is_some_and(|c| c.is_empty())) {
                 // insert continue params, if needed.
                 let mut params = params.clone();
@@ -66,35 +295,98 @@ where
                     params.extend(continue_);
                 }
                 // try get response, if error then return the error.
-                let resp: QueryResponse = {
-                    match self.backend.post_value(&self.key, params).await {
-                        Ok(x) => match serde_json::from_value(x) {
-                            Ok(v) => v,
-                            Err(e) => { yield TrioResult::Err(e.into()); return; },
+                let resp = match self.post(params, iteration).await {
+                    Ok(x) => x,
+                    Err(e) => { yield TrioResult::Err(e.into()); return; },
+                };
+                for w in api_warnings(&resp) { yield TrioResult::Warn(w); }
+                // register new continue param, the last response has none.
+                continue_ = match resp.get("continue").cloned().map(serde_json::from_value).transpose() {
+                    Ok(c) => Some(c.unwrap_or_default()),
+                    Err(e) => { yield TrioResult::Err(e.into()); return; },
+                };
+                iteration += 1;
+                yield TrioResult::Ok(resp);
+            }
+        }
+    }
+
+    /// Run a query, yielding a checkpoint after each response so that the query can be resumed later.
+    /// If `resume` is set, the query continues from that checkpoint instead of starting over.
+    pub fn query_resumable(&self, mut params: HashMap<String, String>, resume: Option<ContinueToken>) -> impl Stream<Item=TrioResult<Resumable, ProviderWarning, APIDataProviderError>> + '_ {
+        stream! {
+            // set up query parameters
+            params.insert("action".to_string(), "query".to_string());
+            for (k, v) in QueryResponse::params() {
+                params.insert(k.to_string(), v.to_string());
+            }
+            for await x in self.query_continued(params, resume.map(|t| t.0)) {
+                let (resp, details): (QueryResponse, Vec<PageDetails>) = {
+                    match x {
+                        TrioResult::Ok(mut x) => {
+                            for w in take_invalid_pages(&mut x) { yield TrioResult::Warn(w); }
+                            for w in resolved_redirects(&x) { yield TrioResult::Warn(w); }
+                            // a batch with no results omits the `query` field altogether, but it may still be continued,
+                            // eg. a filtered generator. The query ends with the last response, as any other.
+                            if x.get("query").is_none() {
+                                x["query"] = serde_json::json!({ "pages": [] });
+                            }
+                            let details = match x.pointer("/query/pages").cloned().map(serde_json::from_value::<Vec<PageDetails>>).transpose() {
+                                Ok(v) => v.unwrap_or_default(),
+                                Err(e) => { yield TrioResult::Err(e.into()); return; },
+                            };
+                            match serde_json::from_value(x) {
+                                Ok(v) => (v, details),
+                                Err(e) => { yield TrioResult::Err(e.into()); return; },
+                            }
                         },
-                        Err(e) => { yield TrioResult::Err(e.into()); return; },
+                        TrioResult::Warn(w) => { yield TrioResult::Warn(w); continue; },
+                        TrioResult::Err(e) => { yield TrioResult::Err(e); return; },
                     }
                 };
-                // register new continue param.
-                continue_ = Some(resp.continue_);
+                let continue_ = resp.continue_;
                 // read response and extract page info.
-                for page in resp.query.pages {
-                    // get information for subject page, if error then return the error.
+                let mut infos = Vec::new();
+                for (page, details) in resp.query.pages.into_iter().zip(details) {
+                    // get information for subject page, if the title is bad then skip this page.
                     let thispage_title = match self.title_codec.new_title(&page.title) {
                         Ok(t) => Some(t),
-                        Err(e) => { yield TrioResult::Err(e.into()); return; },
+                        Err(_) => { yield TrioResult::Warn(ProviderWarning::InvalidTitle { title: page.title }); continue; },
                     };
                     let thispage_exists = Some(!page.missing);
                     let thispage_redirect = Some(page.redirect);
 
                     let associated_title = match self.title_codec.new_title(&page.associatedpage) {
                         Ok(t) => Some(t),
-                        Err(e) => { yield TrioResult::Err(e.into()); return; },
+                        Err(_) => { yield TrioResult::Warn(ProviderWarning::InvalidTitle { title: page.associatedpage }); continue; },
                     };
                     let associated_exists = Some(page.subjectid.is_some() || page.talkid.is_some());
                     let associated_redirect = None;
 
-                    yield TrioResult::Ok(PageInfo::new(thispage_title, thispage_exists, thispage_redirect, associated_title, associated_exists, associated_redirect));
+                    let mut info = PageInfo::new(thispage_title, thispage_exists, thispage_redirect, associated_title, associated_exists, associated_redirect)
+                        .with_details(details.pageid, details.length, details.touched, details.subjectid.or(details.talkid));
+                    if let Some(protection) = details.protection {
+                        info = info.with_protection(protection.into_iter().map(ProtectionEntry::from).collect());
+                    }
+                    infos.push(info);
+                }
+                // if asked to, ask again about the associated pages.
+                if self.assoc_redirects {
+                    match self.fetch_assoc_redirects(&infos).await {
+                        Ok((redirects, warnings)) => {
+                            for w in warnings { yield TrioResult::Warn(w); }
+                            infos = infos.into_iter().map(|info| {
+                                let redirect = info.get_assoc_title().ok().and_then(|t| redirects.get(t)).copied().unwrap_or(false);
+                                info.with_assoc_redirect(redirect)
+                            }).collect();
+                        },
+                        Err(e) => { yield TrioResult::Err(e); return; },
+                    }
+                }
+                for info in infos { yield TrioResult::Ok(Resumable::Page(Box::new(info))); }
+                // everything so far is out, mark the point to resume from.
+                if !continue_.is_empty() {
+                    yield TrioResult::Ok(Resumable::Checkpoint(ContinueToken(continue_)));
                 }
             }
         }
@@ -106,29 +398,24 @@ where
     B: APIServiceInterfaceClient + Sync,
 {
     type Error = APIDataProviderError;
-    type Warn = Infallible;
+    type Warn = ProviderWarning;
 
     /// Fetch a set of pages' basic information.
     /// This function essentially calls 
-    /// ```action=query&prop=info&inprop=associatedpage|subjectid|talkid&titles=<titles>```
+    /// ```action=query&prop=info&inprop=associatedpage|subjectid|talkid|protection&titles=<titles>```
     /// 
     /// This function is called by `Page` expression. It is assumed that nobody would **hand-write** thousands of page names in a query.
     /// 
     /// This function is not intended to be called during some intermediate step, because at that time there would already be thousands of pages to be queried.
+    ///
+    /// The API lists pages in its own order, so the pages of each request are held back and yielded in the input order.
+    /// Up to `max_concurrency` requests are sent ahead, but their pages are still yielded in the order of the requests.
     fn get_page_info<T: IntoIterator<Item=Title>>(&self, titles: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
-        stream! {
-            let chunk_size = if self.apihighlimits { 500 } else { 50 };
-            let title_chunks: Vec<Vec<Title>> = titles.into_iter()
-                .chunks(chunk_size).into_iter()
-                .map(|f| f.collect())
-                .collect();
-            for title_chunk in title_chunks {
-                let params = HashMap::from_iter([
-                    ("titles".to_string(), title_chunk.into_iter().map(|t| self.title_codec.to_pretty(&t)).join("|"))
-                ]);
-                for await x in self.query_all(params) { yield x; }
-            }
-        }
+        let title_chunks = chunk_titles(unique_titles(titles), self.chunk_size);
+        futures::stream::iter(title_chunks)
+            .map(|title_chunk| self.page_info_chunk(title_chunk))
+            .buffered(self.max_concurrency)
+            .flat_map(futures::stream::iter)
     }
 
     /// Basically the same as `get_page_info`, but convert from string.
@@ -146,9 +433,25 @@ where
         }
     }
 
+    /// Fetch a set of pages' basic information by page id.
+    /// This function essentially calls
+    /// ```action=query&prop=info&inprop=associatedpage|subjectid|talkid|protection&pageids=<pageids>```
+    ///
+    /// This function is called by `PageId` expression. Ids with no page are dropped from the response, it is up to the caller to notice them.
+    fn get_page_info_by_id<T: IntoIterator<Item=u32>>(&self, pageids: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        stream! {
+            for pageid_chunk in &pageids.into_iter().chunks(self.chunk_size) {
+                let params = HashMap::from_iter([
+                    ("pageids".to_string(), pageid_chunk.map(|id| id.to_string()).join("|"))
+                ]);
+                for await x in self.query_all(params) { yield x; }
+            }
+        }
+    }
+
     /// Fetch a page's links on that page.
     /// This function essentially calls
-    /// ```action=query&prop=info&inprop=associatedpage|subjectid|talkid&generator=links&gplnamespace=<ns>&gpllimit=max&redirects=<resolve>&titles=<titles>```
+    /// ```action=query&prop=info&inprop=associatedpage|subjectid|talkid|protection&generator=links&gplnamespace=<ns>&gpllimit=max&redirects=<resolve>&titles=<titles>```
     /// 
     /// This function is called by `Link` expression. A warning will be thrown if `titles` contains more than one page.
     fn get_links(&self, title: Title, config: &LinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
@@ -173,7 +476,7 @@ where
 
     /// Fetch a page's backlinks to that page.
     /// This function essentially calls
-    /// ```action=query&prop=info&inprop=associatedpage|subjectid|talkid&generator=backlinks&gblnamespace=<ns>&gbllimit=max&gbltitle=<title>&gblfilterredir=<filter>&gblredirect=<direct>&redirects=<resolve>```
+    /// ```action=query&prop=info&inprop=associatedpage|subjectid|talkid|protection&generator=backlinks&gblnamespace=<ns>&gbllimit=max&gbltitle=<title>&gblfilterredir=<filter>&gblredirect=<direct>&redirects=<resolve>```
     /// 
     /// This function is called by `LinkTo` expression. A warning will be thrown if `titles` contains more than one page.
     fn get_backlinks(&self, title: Title, config: &BackLinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
@@ -210,7 +513,7 @@ where
 
     /// Fetch a page's embeds.
     /// This function essentially calls
-    /// ```action=query&prop=info&inprop=associatedpage|subjectid|talkid&generator=embeddedin&geinamespace=<ns>&geilimit=max&geititle=<title>&geifilterredir=<filter>&redirects=<resolve>```
+    /// ```action=query&prop=info&inprop=associatedpage|subjectid|talkid|protection&generator=embeddedin&geinamespace=<ns>&geilimit=max&geititle=<title>&geifilterredir=<filter>&redirects=<resolve>```
     /// 
     /// This function is called by `Embed` expression. A warning will be thrown if `titles` contains more than one page.
     fn get_embeds(&self, title: Title, config: &EmbedsConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
@@ -242,37 +545,58 @@ where
         }
     }
 
-    /// Fetch a category's members.
+    /// Fetch the pages using a file.
     /// This function essentially calls
-    /// ```action=query&prop=info&inprop=associatedpage|subjectid|talkid&generator=categorymembers&gcmtitle=<title>&gcmlimit=max&gcmnamespace=<ns>&gcmtype=<...>&redirects=<resolve>```
+    /// ```action=query&prop=info&inprop=associatedpage|subjectid|talkid|protection&generator=imageusage&giunamespace=<ns>&giulimit=max&giutitle=<title>&giufilterredir=<filter>&redirects=<resolve>```
     /// 
-    /// This function is called by `InCat` expression.
-    fn get_category_members(&self, title: Title, config: &CategoryMembersConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+    /// This function is called by `FileUsage` expression.
+    fn get_file_usage(&self, title: Title, config: &FileUsageConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
         stream! {
             let param = {
                 let mut tmp = HashMap::<String, String>::from_iter([
-                    ("generator".to_string(), "categorymembers".to_string()),
-                    ("gcmtitle".to_string(), self.title_codec.to_pretty(&title)),
-                    ("gcmlimit".to_string(), "max".to_string()),
+                    ("generator".to_string(), "imageusage".to_string()),
+                    ("giutitle".to_string(), self.title_codec.to_pretty(&title)),
+                    ("giulimit".to_string(), "max".to_string()),
+                ]);
+                if let Some(filter_redirects) = config.filter_redirects {
+                    tmp.insert(
+                        "giufilterredir".to_string(),
+                        match filter_redirects {
+                            FilterRedirect::NoRedirect => "nonredirects".to_string(),
+                            FilterRedirect::OnlyRedirect => "redirects".to_string(),
+                        }
+                    );
+                }
+                if config.resolve_redirects {
+                    tmp.insert("redirects".to_string(), "1".to_string());
+                }
+                if let Some(ns) = &config.namespace {
+                    tmp.insert("giunamespace".to_string(), ns.iter().map(|n| n.to_string()).collect::<Vec<String>>().join("|"));
+                }
+                tmp
+            };
+            for await x in self.query_all(param) { yield x; }
+        }
+    }
+
+    /// Fetch the templates transcluded in a page.
+    /// This function essentially calls
+    /// ```action=query&prop=info&inprop=associatedpage|subjectid|talkid|protection&generator=templates&gtlnamespace=<ns>&gtllimit=max&redirects=<resolve>&titles=<titles>```
+    /// 
+    /// This function is called by `TemplatesIn` expression.
+    fn get_templates(&self, title: Title, config: &TemplatesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        stream! {
+            let param = {
+                let mut tmp = HashMap::<String, String>::from_iter([
+                    ("generator".to_string(), "templates".to_string()),
+                    ("titles".to_string(), self.title_codec.to_pretty(&title)),
+                    ("gtllimit".to_string(), "max".to_string()),
                 ]);
                 if config.resolve_redirects {
                     tmp.insert("redirects".to_string(), "1".to_string());
                 }
                 if let Some(ns) = config.namespace.as_ref() {
-                    tmp.insert("gcmnamespace".to_string(), ns.iter().map(|n| n.to_string()).collect::<Vec<String>>().join("|"));
-    
-                    let mut ns = ns.to_owned();
-                    let mut cmtype = Vec::new();
-                    if ns.remove(&6) {
-                        cmtype.push("file".to_string());
-                    }
-                    if ns.remove(&14) {
-                        cmtype.push("subcat".to_string());
-                    }
-                    if !ns.is_empty() {
-                        cmtype.push("page".to_string());
-                    }
-                    tmp.insert("gcmtype".to_string(), cmtype.join("|"));
+                    tmp.insert("gtlnamespace".to_string(), ns.iter().map(|n| n.to_string()).collect::<Vec<String>>().join("|"));
                 }
                 tmp
             };
@@ -280,36 +604,297 @@ where
         }
     }
 
-    /// Fetch a page's subpages.
+    /// Fetch the files used in a page.
     /// This function essentially calls
-    /// ```action=query&prop=info&inprop=associatedpage|subjectid|talkid&generator=allpages&gapprefix=<title>&gaplimit=max&gapnamespace=<title>&gapfilterredir=<filter>```
+    /// ```action=query&prop=info&inprop=associatedpage|subjectid|talkid|protection&generator=images&gimlimit=max&redirects=<resolve>&titles=<titles>```
     /// 
-    /// This function is called by `Prefix` expression.
-    /// A warning will be thrown if `titles` contains more than one page.
-    /// This function ignores the `resolve` modifier.
-    fn get_prefix(&self, title: Title, config: &PrefixConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+    /// This function is called by `Files` expression.
+    /// The `images` generator does not accept a namespace parameter. Since every result lies in the File namespace,
+    /// nothing is queried if `namespace` is set and does not contain it.
+    fn get_images(&self, title: Title, config: &ImagesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
         stream! {
+            if config.namespace.as_ref().is_some_and(|ns| !ns.contains(&6)) {
+                return;
+            }
             let param = {
                 let mut tmp = HashMap::<String, String>::from_iter([
-                    ("generator".to_string(), "allpages".to_string()),
-                    ("gaptitle".to_string(), title.dbkey().to_string()),
-                    ("gapnamespace".to_string(), title.namespace().to_string()),
-                    ("gaplimit".to_string(), "max".to_string()),
+                    ("generator".to_string(), "images".to_string()),
+                    ("titles".to_string(), self.title_codec.to_pretty(&title)),
+                    ("gimlimit".to_string(), "max".to_string()),
                 ]);
-                if let Some(filter_redirects) = config.filter_redirects {
-                    tmp.insert(
-                        "gapfilterredir".to_string(),
-                        match filter_redirects {
-                            FilterRedirect::NoRedirect => "nonredirects".to_string(),
-                            FilterRedirect::OnlyRedirect => "redirects".to_string(),
-                        }
-                    );
+                if config.resolve_redirects {
+                    tmp.insert("redirects".to_string(), "1".to_string());
+                }
+                tmp
+            };
+            for await x in self.query_all(param) { yield x; }
+        }
+    }
+
+    /// Fetch the categories a page belongs to.
+    /// This function essentially calls
+    /// ```action=query&prop=info&inprop=associatedpage|subjectid|talkid|protection&generator=categories&gcllimit=max&redirects=<resolve>&titles=<titles>```
+    /// 
+    /// This function is called by `CategoriesOf` expression. A page without categories yields nothing.
+    /// The `categories` generator does not accept a namespace parameter. Since every result lies in the Category namespace,
+    /// nothing is queried if `namespace` is set and does not contain it.
+    fn get_categories(&self, title: Title, config: &CategoriesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        stream! {
+            if config.namespace.as_ref().is_some_and(|ns| !ns.contains(&14)) {
+                return;
+            }
+            let param = {
+                let mut tmp = HashMap::<String, String>::from_iter([
+                    ("generator".to_string(), "categories".to_string()),
+                    ("titles".to_string(), self.title_codec.to_pretty(&title)),
+                    ("gcllimit".to_string(), "max".to_string()),
+                ]);
+                if config.resolve_redirects {
+                    tmp.insert("redirects".to_string(), "1".to_string());
                 }
                 tmp
             };
             for await x in self.query_all(param) { yield x; }
         }
     }
+
+    /// Fetch a page's language links.
+    /// This function essentially calls
+    /// ```action=query&prop=langlinks&lllimit=max&titles=<titles>```
+    /// 
+    /// This function is called by `LangLinks` expression.
+    /// The targets are parsed as interwiki titles, eg. `fr:Exemple`, and yielded with `PageInfo::new_foreign`.
+    /// A target that does not parse is skipped with a warning.
+    fn get_langlinks(&self, title: Title, _config: &LangLinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        stream! {
+            let params = HashMap::<String, String>::from_iter([
+                ("action".to_string(), "query".to_string()),
+                ("prop".to_string(), "langlinks".to_string()),
+                ("titles".to_string(), self.title_codec.to_pretty(&title)),
+                ("lllimit".to_string(), "max".to_string()),
+            ]);
+            for await x in self.query_continued(params, None) {
+                let resp: LangLinksResponse = match x {
+                    TrioResult::Ok(x) => match serde_json::from_value(x) {
+                        Ok(v) => v,
+                        Err(e) => { yield TrioResult::Err(e.into()); return; },
+                    },
+                    TrioResult::Warn(w) => { yield TrioResult::Warn(w); continue; },
+                    TrioResult::Err(e) => { yield TrioResult::Err(e); return; },
+                };
+                // read response and extract the foreign titles, if a target is bad then skip it.
+                for page in resp.query.into_iter().flat_map(|q| q.pages) {
+                    for langlink in page.langlinks {
+                        let raw = format!("{}:{}", langlink.lang, langlink.title);
+                        match self.title_codec.new_title(&raw) {
+                            Ok(t) => yield TrioResult::Ok(PageInfo::new_foreign(t)),
+                            Err(_) => yield TrioResult::Warn(ProviderWarning::InvalidTitle { title: raw }),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Fetch a category's members.
+    /// This function essentially calls
+    /// ```action=query&prop=info&inprop=associatedpage|subjectid|talkid|protection&generator=categorymembers&gcmtitle=<title>&gcmlimit=max&gcmnamespace=<ns>&gcmtype=<...>&redirects=<resolve>```
+    /// 
+    /// This function is called by `InCat` expression.
+    /// If the config bounds the time pages were added, `gcmsort=timestamp&gcmdir=newer&gcmstart=<start>&gcmend=<end>` is added.
+    /// An explicit order adds `gcmsort=<sortkey|timestamp>&gcmdir=<...>`.
+    fn get_category_members(&self, title: Title, config: &CategoryMembersConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        stream! {
+            let param = category_members_params(self.title_codec.to_pretty(&title), config);
+            for await x in self.query_all(param) { yield x; }
+        }
+    }
+
+    /// Fetch a page's subpages.
+    /// This function essentially calls
+    /// ```action=query&prop=info&inprop=associatedpage|subjectid|talkid|protection&generator=allpages&gapprefix=<title>&gaplimit=max&gapnamespace=<title>&gapfilterredir=<filter>```
+    /// 
+    /// This function is called by `Prefix` expression.
+    /// A warning will be thrown if `titles` contains more than one page.
+    /// This function ignores the `resolve` modifier.
+    fn get_prefix(&self, title: Title, config: &PrefixConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        stream! {
+            let param = prefix_params(&title, config);
+            for await x in self.query_all(param) { yield x; }
+        }
+    }
+}
+
+/// Build the `generator=categorymembers` parameters for `get_category_members`. `title` is the pretty category title.
+fn category_members_params(title: String, config: &CategoryMembersConfig) -> HashMap<String, String> {
+    let mut tmp = HashMap::<String, String>::from_iter([
+        ("generator".to_string(), "categorymembers".to_string()),
+        ("gcmtitle".to_string(), title),
+        ("gcmlimit".to_string(), "max".to_string()),
+    ]);
+    if config.resolve_redirects {
+        tmp.insert("redirects".to_string(), "1".to_string());
+    }
+    if let Some(ns) = config.namespace.as_ref() {
+        tmp.insert("gcmnamespace".to_string(), ns.iter().map(|n| n.to_string()).collect::<Vec<String>>().join("|"));
+
+        let mut ns = ns.to_owned();
+        let mut cmtype = Vec::new();
+        if ns.remove(&6) {
+            cmtype.push("file".to_string());
+        }
+        if ns.remove(&14) {
+            cmtype.push("subcat".to_string());
+        }
+        if !ns.is_empty() {
+            cmtype.push("page".to_string());
+        }
+        tmp.insert("gcmtype".to_string(), cmtype.join("|"));
+    }
+    // the API only takes `gcmstart` and `gcmend` when sorting by timestamp.
+    let sort = config.sort.or(config.is_time_bounded().then_some(CategorySort::Timestamp));
+    let descending = config.dir == Some(SortDirection::Descending);
+    match sort {
+        Some(CategorySort::SortKey) => {
+            tmp.insert("gcmsort".to_string(), "sortkey".to_string());
+            tmp.insert("gcmdir".to_string(), if descending { "descending" } else { "ascending" }.to_string());
+        },
+        Some(CategorySort::Timestamp) => {
+            tmp.insert("gcmsort".to_string(), "timestamp".to_string());
+            tmp.insert("gcmdir".to_string(), if descending { "older" } else { "newer" }.to_string());
+            // listing from the newest, the later bound comes first.
+            let (start, end) = if descending { (&config.end, &config.start) } else { (&config.start, &config.end) };
+            if let Some(start) = start {
+                tmp.insert("gcmstart".to_string(), start.to_owned());
+            }
+            if let Some(end) = end {
+                tmp.insert("gcmend".to_string(), end.to_owned());
+            }
+        },
+        None => {
+            if descending {
+                tmp.insert("gcmdir".to_string(), "descending".to_string());
+            }
+        },
+    }
+    tmp
+}
+
+/// Build the `generator=allpages` parameters for `get_prefix`.
+fn prefix_params(title: &Title, config: &PrefixConfig) -> HashMap<String, String> {
+    let mut tmp = HashMap::<String, String>::from_iter([
+        ("generator".to_string(), "allpages".to_string()),
+        ("gapprefix".to_string(), title.dbkey().to_string()),
+        ("gapnamespace".to_string(), title.namespace().to_string()),
+        ("gaplimit".to_string(), "max".to_string()),
+    ]);
+    if let Some(filter_redirects) = config.filter_redirects {
+        tmp.insert(
+            "gapfilterredir".to_string(),
+            match filter_redirects {
+                FilterRedirect::NoRedirect => "nonredirects".to_string(),
+                FilterRedirect::OnlyRedirect => "redirects".to_string(),
+            }
+        );
+    }
+    tmp
+}
+
+/// Drop repeated titles, keeping the first of each. `Title` is not hashable, so `Itertools::unique` is of no use.
+fn unique_titles<T: IntoIterator<Item=Title>>(titles: T) -> impl Iterator<Item=Title> {
+    let mut seen = BTreeSet::new();
+    titles.into_iter().filter(move |t| seen.insert(t.to_owned()))
+}
+
+/// Split titles into chunks of at most `chunk_size` titles, each of which is sent in one request.
+fn chunk_titles<T, I: IntoIterator<Item=T>>(titles: I, chunk_size: usize) -> Vec<Vec<T>> {
+    titles.into_iter()
+        .chunks(chunk_size).into_iter()
+        .map(|f| f.collect())
+        .collect()
+}
+
+/// Read the top-level `warnings` of a response, one warning per module.
+/// Both the `formatversion=2` form `{"main": {"warnings": "..."}}` and the legacy form `{"main": {"*": "..."}}` are understood.
+fn api_warnings(response: &Value) -> Vec<ProviderWarning> {
+    let Some(warnings) = response.get("warnings").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    warnings.iter()
+        .filter_map(|(module, w)| {
+            let text = w.get("warnings").or_else(|| w.get("*"))?.as_str()?;
+            Some(ProviderWarning::Api { module: module.to_owned(), text: text.to_owned() })
+        })
+        .collect()
+}
+
+/// Collect the local names, canonical names and aliases of all namespaces from a site info response.
+/// Both the `formatversion=2` form and the legacy `*` form are understood.
+fn namespace_names(siteinfo: &Value) -> NamespaceMap {
+    let mut map = NamespaceMap::new();
+    let namespaces = siteinfo.pointer("/query/namespaces").and_then(Value::as_object).into_iter().flat_map(|x| x.values());
+    for ns in namespaces {
+        let Some(id) = ns.get("id").and_then(Value::as_i64) else { continue };
+        for key in ["name", "*", "canonical"] {
+            if let Some(name) = ns.get(key).and_then(Value::as_str) {
+                map.insert(name, id as i32);
+            }
+        }
+    }
+    let aliases = siteinfo.pointer("/query/namespacealiases").and_then(Value::as_array).into_iter().flatten();
+    for alias in aliases {
+        let Some(id) = alias.get("id").and_then(Value::as_i64) else { continue };
+        if let Some(name) = alias.get("alias").or_else(|| alias.get("*")).and_then(Value::as_str) {
+            map.insert(name, id as i32);
+        }
+    }
+    map
+}
+
+/// Read the `redirects` of a response, which lists the redirects replaced by their targets when `redirects=1` is set.
+fn resolved_redirects(response: &Value) -> Vec<ProviderWarning> {
+    let Some(redirects) = response.pointer("/query/redirects").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    redirects.iter()
+        .filter_map(|r| {
+            let from = r.get("from")?.as_str()?;
+            let to = r.get("to")?.as_str()?;
+            let to = match r.get("tofragment").and_then(Value::as_str) {
+                Some(fragment) => format!("{to}#{fragment}"),
+                None => to.to_owned(),
+            };
+            Some(ProviderWarning::RedirectResolved { from: from.to_owned(), to })
+        })
+        .collect()
+}
+
+/// Remove the pages flagged `invalid` from a response, as well as the entries of unknown page ids, which carry no title.
+/// Such entries lack most page properties and would otherwise fail the whole response.
+fn take_invalid_pages(response: &mut Value) -> Vec<ProviderWarning> {
+    let Some(pages) = response.pointer_mut("/query/pages").and_then(Value::as_array_mut) else {
+        return Vec::new();
+    };
+    let mut warnings = Vec::new();
+    pages.retain(|page| {
+        let invalid = page.get("invalid").and_then(Value::as_bool).unwrap_or(false);
+        if invalid {
+            let title = page.get("title").and_then(Value::as_str).unwrap_or_default();
+            warnings.push(ProviderWarning::InvalidTitle { title: title.to_owned() });
+        }
+        !invalid && page.get("title").is_some()
+    });
+    warnings
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProviderWarning {
+    #[error("API warning from module `{module}`: {text}")]
+    Api { module: String, text: String },
+    #[error("skipped invalid title `{title}`")]
+    InvalidTitle { title: String },
+    #[error("resolved redirect `{from}` to `{to}`")]
+    RedirectResolved { from: String, to: String },
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -320,4 +905,684 @@ pub enum APIDataProviderError {
     Json(#[from] serde_json::Error),
     #[error(transparent)]
     TitleCodec(#[from] mwtitle::Error),
+    #[error("chunk size must be at least 1")]
+    InvalidChunkSize,
+    #[error("max concurrency must be at least 1")]
+    InvalidMaxConcurrency,
+    #[error("rate limit must be at least 1")]
+    InvalidRateLimit,
+}
+
+#[cfg(test)]
+mod test {
+    use super::{category_members_params, chunk_titles, namespace_names, prefix_params, APIDataProvider, APIDataProviderError, ContinueToken, ProviderWarning, Resumable};
+    use crate::test_backend::{codec, siteinfo, RecordedBackend};
+    use futures::StreamExt;
+    use provider::{CategoriesConfig, CategoryMembersConfig, CategorySort, DataProvider, FileUsageConfig, FilterRedirect, ImagesConfig, LangLinksConfig, LinksConfig, PageInfo, PageInfoError, PrefixConfig, ProtectionEntry, SortDirection, TemplatesConfig};
+    use serde_json::{json, Value};
+    use std::{
+        collections::HashMap,
+        fmt,
+        sync::{atomic::Ordering, Arc, Mutex},
+        time::{Duration, Instant},
+    };
+    use tracing::{field::{Field, Visit}, span, Subscriber};
+    use tracing_subscriber::{layer::{Context, Layer}, prelude::*};
+
+    fn page(id: u32, title: &str) -> Value {
+        json!({
+            "pageid": id, "ns": 0, "title": title,
+            "contentmodel": "wikitext", "pagelanguage": "en", "pagelanguagehtmlcode": "en", "pagelanguagedir": "ltr",
+            "touched": "2024-01-01T00:00:00Z", "lastrevid": id * 100, "length": 1000,
+            "talkid": id + 1000, "associatedpage": format!("Talk:{title}"), "protection": [], "restrictiontypes": ["edit", "move"],
+        })
+    }
+
+    /// `generator=links` on `Main Page`, split in two responses.
+    fn links_backend() -> RecordedBackend {
+        RecordedBackend {
+            siteinfo: siteinfo(),
+            responses: vec![
+                (
+                    HashMap::from([("gplcontinue".to_string(), "1|0|Bar".to_string())]),
+                    json!({ "batchcomplete": true, "query": { "pages": [page(2, "Bar"), page(3, "Baz")] } }),
+                ),
+                (
+                    HashMap::from([("generator".to_string(), "links".to_string())]),
+                    json!({
+                        "continue": { "gplcontinue": "1|0|Bar", "continue": "gplcontinue||" },
+                        "query": { "pages": [page(1, "Foo")] },
+                    }),
+                ),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_chunk_titles() {
+        let titles = |n: usize| 0..n;
+        let lens = |chunks: Vec<Vec<usize>>| chunks.iter().map(Vec::len).collect::<Vec<_>>();
+
+        assert_eq!(lens(chunk_titles(titles(0), 50)), Vec::<usize>::new());
+        assert_eq!(lens(chunk_titles(titles(50), 50)), vec![50]);
+        assert_eq!(lens(chunk_titles(titles(51), 50)), vec![50, 1]);
+        assert_eq!(lens(chunk_titles(titles(1201), 500)), vec![500, 500, 201]);
+        assert_eq!(lens(chunk_titles(titles(3), 1)), vec![1, 1, 1]);
+    }
+
+    #[test]
+    fn test_namespace_names() {
+        let namespaces = namespace_names(&siteinfo());
+
+        assert_eq!(namespaces.get("Category"), Some(14));
+        assert_eq!(namespaces.get("template_talk"), Some(11));
+        assert_eq!(namespaces.get("Wikipedia"), Some(4));
+        assert_eq!(namespaces.get("Project"), Some(4));
+        assert_eq!(namespaces.get("WP"), Some(4));
+        assert_eq!(namespaces.get("image"), Some(6));
+        assert_eq!(namespaces.get(""), Some(0));
+        assert_eq!(namespaces.get("Portal"), None);
+        // titles are shown with the local name.
+        assert_eq!(namespaces.pretty(&codec().new_title("WP:Some_page").unwrap()), "Wikipedia:Some page");
+        assert_eq!(namespaces.pretty(&codec().new_title("Main Page").unwrap()), "Main Page");
+    }
+
+    #[test]
+    fn test_prefix_params() {
+        let title = codec().new_title("User:Example/Sub page").unwrap();
+        let params = prefix_params(&title, &PrefixConfig { filter_redirects: Some(FilterRedirect::NoRedirect) });
+
+        assert_eq!(params.get("generator").map(String::as_str), Some("allpages"));
+        assert_eq!(params.get("gapprefix").map(String::as_str), Some("Example/Sub_page"));
+        assert_eq!(params.get("gapnamespace").map(String::as_str), Some("2"));
+        assert_eq!(params.get("gaplimit").map(String::as_str), Some("max"));
+        assert_eq!(params.get("gapfilterredir").map(String::as_str), Some("nonredirects"));
+        assert!(!params.contains_key("gaptitle"));
+        assert!(params.keys().all(|k| !k.starts_with("gei")));
+    }
+
+    #[test]
+    fn test_category_members_params() {
+        let params = category_members_params("Category:X".to_string(), &CategoryMembersConfig::default());
+        assert!(!params.contains_key("gcmsort"));
+        assert!(!params.contains_key("gcmstart"));
+
+        let config = CategoryMembersConfig::default().with_start("2024-01-01T00:00:00Z");
+        let params = category_members_params("Category:X".to_string(), &config);
+        assert_eq!(params.get("gcmtitle").map(String::as_str), Some("Category:X"));
+        assert_eq!(params.get("gcmsort").map(String::as_str), Some("timestamp"));
+        assert_eq!(params.get("gcmdir").map(String::as_str), Some("newer"));
+        assert_eq!(params.get("gcmstart").map(String::as_str), Some("2024-01-01T00:00:00Z"));
+        assert!(!params.contains_key("gcmend"));
+
+        let config = CategoryMembersConfig::default().with_end("2024-01-08T00:00:00Z");
+        let params = category_members_params("Category:X".to_string(), &config);
+        assert_eq!(params.get("gcmsort").map(String::as_str), Some("timestamp"));
+        assert_eq!(params.get("gcmend").map(String::as_str), Some("2024-01-08T00:00:00Z"));
+
+        let config = CategoryMembersConfig::default().with_sort(CategorySort::SortKey, SortDirection::Descending);
+        let params = category_members_params("Category:X".to_string(), &config);
+        assert_eq!(params.get("gcmsort").map(String::as_str), Some("sortkey"));
+        assert_eq!(params.get("gcmdir").map(String::as_str), Some("descending"));
+
+        // the newest first, so the bounds are swapped.
+        let config = CategoryMembersConfig::default()
+            .with_start("2024-01-01T00:00:00Z")
+            .with_end("2024-01-08T00:00:00Z")
+            .with_sort(CategorySort::Timestamp, SortDirection::Descending);
+        let params = category_members_params("Category:X".to_string(), &config);
+        assert_eq!(params.get("gcmsort").map(String::as_str), Some("timestamp"));
+        assert_eq!(params.get("gcmdir").map(String::as_str), Some("older"));
+        assert_eq!(params.get("gcmstart").map(String::as_str), Some("2024-01-08T00:00:00Z"));
+        assert_eq!(params.get("gcmend").map(String::as_str), Some("2024-01-01T00:00:00Z"));
+    }
+
+    #[tokio::test]
+    async fn test_query_resumable() {
+        let backend = links_backend();
+        let calls = Arc::clone(&backend.calls);
+        let (client, _handle) = backend.serve().await;
+        let provider = APIDataProvider::new(client, "enwiki").await.unwrap();
+        let params = HashMap::from([
+            ("generator".to_string(), "links".to_string()),
+            ("titles".to_string(), "Main Page".to_string()),
+        ]);
+        let titles = |items: &[Resumable]| items.iter()
+            .filter_map(|x| match x {
+                Resumable::Page(p) => Some(provider.to_pretty(p.get_title().unwrap())),
+                Resumable::Checkpoint(_) => None,
+            })
+            .collect::<Vec<_>>();
+
+        // a full run yields a checkpoint between the two responses, but not after the last one.
+        let items: Vec<Resumable> = provider.query_resumable(params.clone(), None)
+            .map(|x| x.ok().unwrap())
+            .collect().await;
+        assert_eq!(titles(&items), vec!["Foo", "Bar", "Baz"]);
+        let token = match &items[1] {
+            Resumable::Checkpoint(token) => token.clone(),
+            x => panic!("expected a checkpoint, got {x:?}"),
+        };
+        assert!(matches!(items[3], Resumable::Page(_)));
+
+        // the token survives a round trip, and resuming from it skips the first response.
+        let token: ContinueToken = serde_json::from_str(&serde_json::to_string(&token).unwrap()).unwrap();
+        let resumed: Vec<Resumable> = provider.query_resumable(params, Some(token))
+            .map(|x| x.ok().unwrap())
+            .collect().await;
+        assert_eq!(titles(&resumed), vec!["Bar", "Baz"]);
+        assert_eq!(resumed.len(), 2);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let (client, _handle) = links_backend().serve().await;
+        let provider = APIDataProvider::new(client, "enwiki").await.unwrap().with_rate_limit(5).unwrap();
+        let started = Instant::now();
+
+        // the continued request waits for its turn too.
+        let items: Vec<_> = provider.get_links(codec().new_title("Main Page").unwrap(), &LinksConfig::default()).collect().await;
+        assert_eq!(items.len(), 3);
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        assert!(matches!(provider.with_rate_limit(0), Err(APIDataProviderError::InvalidRateLimit)));
+    }
+
+    #[tokio::test]
+    async fn test_api_warnings() {
+        let backend = RecordedBackend {
+            siteinfo: siteinfo(),
+            responses: vec![(
+                HashMap::new(),
+                json!({
+                    "batchcomplete": true,
+                    "warnings": {
+                        "main": { "warnings": "Unrecognized parameter: foo." },
+                        "links": { "warnings": "gpllimit may not be over 500 (set to 5000) for users." },
+                    },
+                    "query": { "pages": [page(1, "Foo")] },
+                }),
+            )],
+            ..Default::default()
+        };
+        let (client, _handle) = backend.serve().await;
+        let provider = APIDataProvider::new(client, "enwiki").await.unwrap();
+        let params = HashMap::from([
+            ("generator".to_string(), "links".to_string()),
+            ("titles".to_string(), "Main Page".to_string()),
+        ]);
+
+        let items: Vec<_> = provider.query_all(params).collect().await;
+        let mut warnings: Vec<ProviderWarning> = items.iter().filter_map(|x| x.as_ref().warn().cloned()).collect();
+        warnings.sort_by_key(|w| w.to_string());
+        assert_eq!(warnings, vec![
+            ProviderWarning::Api { module: "links".to_string(), text: "gpllimit may not be over 500 (set to 5000) for users.".to_string() },
+            ProviderWarning::Api { module: "main".to_string(), text: "Unrecognized parameter: foo.".to_string() },
+        ]);
+        assert_eq!(items.iter().filter(|x| x.is_ok()).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_resolved_redirects() {
+        let backend = RecordedBackend {
+            siteinfo: siteinfo(),
+            responses: vec![(
+                HashMap::new(),
+                json!({
+                    "batchcomplete": true,
+                    "query": {
+                        "redirects": [
+                            { "from": "Fooo", "to": "Foo" },
+                            { "from": "Bar (disambiguation)", "to": "Bar", "tofragment": "Meanings" },
+                        ],
+                        "pages": [page(1, "Foo"), page(2, "Bar")],
+                    },
+                }),
+            )],
+            ..Default::default()
+        };
+        let (client, _handle) = backend.serve().await;
+        let provider = APIDataProvider::new(client, "enwiki").await.unwrap();
+        let params = HashMap::from([
+            ("generator".to_string(), "links".to_string()),
+            ("titles".to_string(), "Main Page".to_string()),
+            ("redirects".to_string(), "1".to_string()),
+        ]);
+
+        // one warning per redirect, ahead of the pages.
+        let items: Vec<_> = provider.query_all(params).collect().await;
+        let warnings: Vec<ProviderWarning> = items.iter().filter_map(|x| x.as_ref().warn().cloned()).collect();
+        assert_eq!(warnings, vec![
+            ProviderWarning::RedirectResolved { from: "Fooo".to_string(), to: "Foo".to_string() },
+            ProviderWarning::RedirectResolved { from: "Bar (disambiguation)".to_string(), to: "Bar#Meanings".to_string() },
+        ]);
+        assert_eq!(items.iter().filter(|x| x.is_ok()).count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_titles() {
+        let backend = RecordedBackend {
+            siteinfo: siteinfo(),
+            responses: vec![(
+                HashMap::new(),
+                json!({
+                    "batchcomplete": true,
+                    "query": {
+                        "pages": [
+                            page(1, "Foo"),
+                            { "title": "Foo[bar]", "invalidreason": "The requested page title contains invalid characters: \"[\".", "invalid": true },
+                            page(2, "Bar"),
+                        ],
+                    },
+                }),
+            )],
+            ..Default::default()
+        };
+        let (client, _handle) = backend.serve().await;
+        let provider = APIDataProvider::new(client, "enwiki").await.unwrap();
+        let params = HashMap::from([("titles".to_string(), "Foo|Foo[bar]|Bar".to_string())]);
+
+        // the invalid entry is reported, and the rest of the batch still comes through.
+        let items: Vec<_> = provider.query_all(params).collect().await;
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].as_ref().warn(), Some(&ProviderWarning::InvalidTitle { title: "Foo[bar]".to_string() }));
+        assert_eq!(
+            items[1..].iter().map(|x| provider.to_pretty(x.as_ref().ok().unwrap().get_title().unwrap())).collect::<Vec<_>>(),
+            vec!["Foo", "Bar"],
+        );
+        assert!(items.iter().all(|x| !x.is_err()));
+    }
+
+    #[tokio::test]
+    async fn test_page_info_by_id() {
+        let backend = RecordedBackend {
+            siteinfo: siteinfo(),
+            responses: vec![(
+                HashMap::from([("pageids".to_string(), "1|404|2".to_string())]),
+                json!({
+                    "batchcomplete": true,
+                    "query": { "pages": [page(1, "Foo"), { "pageid": 404, "missing": true }, page(2, "Bar")] },
+                }),
+            )],
+            ..Default::default()
+        };
+        let (client, _handle) = backend.serve().await;
+        let provider = APIDataProvider::new(client, "enwiki").await.unwrap();
+
+        // the unknown id is dropped silently, the rest of the batch still comes through.
+        let items: Vec<PageInfo> = provider.get_page_info_by_id([1, 404, 2]).map(|x| x.ok().unwrap()).collect().await;
+        assert_eq!(items.iter().map(|p| p.get_pageid().unwrap()).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(items.iter().map(|p| provider.to_pretty(p.get_title().unwrap())).collect::<Vec<_>>(), vec!["Foo", "Bar"]);
+    }
+
+    #[tokio::test]
+    async fn test_file_usage() {
+        let backend = RecordedBackend {
+            siteinfo: siteinfo(),
+            responses: vec![(
+                HashMap::from([
+                    ("generator".to_string(), "imageusage".to_string()),
+                    ("giutitle".to_string(), "File:Example.png".to_string()),
+                    ("giunamespace".to_string(), "0".to_string()),
+                ]),
+                json!({ "batchcomplete": true, "query": { "pages": [page(1, "Foo"), page(2, "Bar")] } }),
+            )],
+            ..Default::default()
+        };
+        let (client, _handle) = backend.serve().await;
+        let provider = APIDataProvider::new(client, "enwiki").await.unwrap();
+        let config = FileUsageConfig::default().with_namespace([0]);
+
+        let items: Vec<PageInfo> = provider.get_file_usage(codec().new_title("File:Example.png").unwrap(), &config)
+            .map(|x| x.ok().unwrap())
+            .collect().await;
+        assert_eq!(items.iter().map(|p| provider.to_pretty(p.get_title().unwrap())).collect::<Vec<_>>(), vec!["Foo", "Bar"]);
+    }
+
+    #[tokio::test]
+    async fn test_templates() {
+        let mut template = page(9, "Template:Foo");
+        template["ns"] = json!(10);
+        template["associatedpage"] = json!("Template talk:Foo");
+        let backend = RecordedBackend {
+            siteinfo: siteinfo(),
+            responses: vec![(
+                HashMap::from([
+                    ("generator".to_string(), "templates".to_string()),
+                    ("gtllimit".to_string(), "max".to_string()),
+                    ("gtlnamespace".to_string(), "10".to_string()),
+                    ("titles".to_string(), "Foo".to_string()),
+                ]),
+                json!({ "batchcomplete": true, "query": { "pages": [template] } }),
+            )],
+            ..Default::default()
+        };
+        let (client, _handle) = backend.serve().await;
+        let provider = APIDataProvider::new(client, "enwiki").await.unwrap();
+        let config = TemplatesConfig::default().with_namespace([10]);
+
+        let items: Vec<PageInfo> = provider.get_templates(codec().new_title("Foo").unwrap(), &config)
+            .map(|x| x.ok().unwrap())
+            .collect().await;
+        assert_eq!(items.iter().map(|p| provider.to_pretty(p.get_title().unwrap())).collect::<Vec<_>>(), vec!["Template:Foo"]);
+    }
+
+    #[tokio::test]
+    async fn test_images() {
+        let mut file = page(5, "File:Example.png");
+        file["ns"] = json!(6);
+        file["associatedpage"] = json!("File talk:Example.png");
+        let backend = RecordedBackend {
+            siteinfo: siteinfo(),
+            responses: vec![(
+                HashMap::from([
+                    ("generator".to_string(), "images".to_string()),
+                    ("gimlimit".to_string(), "max".to_string()),
+                    ("titles".to_string(), "Foo".to_string()),
+                ]),
+                json!({ "batchcomplete": true, "query": { "pages": [file] } }),
+            )],
+            ..Default::default()
+        };
+        let calls = Arc::clone(&backend.calls);
+        let (client, _handle) = backend.serve().await;
+        let provider = APIDataProvider::new(client, "enwiki").await.unwrap();
+        let title = codec().new_title("Foo").unwrap();
+
+        let items: Vec<PageInfo> = provider.get_images(title.clone(), &ImagesConfig::default()).map(|x| x.ok().unwrap()).collect().await;
+        assert_eq!(items.iter().map(|p| provider.to_pretty(p.get_title().unwrap())).collect::<Vec<_>>(), vec!["File:Example.png"]);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // no file can be in the main namespace, so nothing is asked.
+        let items: Vec<_> = provider.get_images(title, &ImagesConfig::default().with_namespace([0])).collect().await;
+        assert!(items.is_empty());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_categories() {
+        let mut category = page(7, "Category:Bar");
+        category["ns"] = json!(14);
+        category["associatedpage"] = json!("Category talk:Bar");
+        let backend = RecordedBackend {
+            siteinfo: siteinfo(),
+            responses: vec![
+                (
+                    HashMap::from([
+                        ("generator".to_string(), "categories".to_string()),
+                        ("gcllimit".to_string(), "max".to_string()),
+                        ("titles".to_string(), "Foo".to_string()),
+                    ]),
+                    json!({ "batchcomplete": true, "query": { "pages": [category] } }),
+                ),
+                // a page without categories leaves the generator empty.
+                (
+                    HashMap::from([("titles".to_string(), "Baz".to_string())]),
+                    json!({ "batchcomplete": true }),
+                ),
+            ],
+            ..Default::default()
+        };
+        let (client, _handle) = backend.serve().await;
+        let provider = APIDataProvider::new(client, "enwiki").await.unwrap();
+
+        let items: Vec<PageInfo> = provider.get_categories(codec().new_title("Foo").unwrap(), &CategoriesConfig::default())
+            .map(|x| x.ok().unwrap())
+            .collect().await;
+        assert_eq!(items.iter().map(|p| provider.to_pretty(p.get_title().unwrap())).collect::<Vec<_>>(), vec!["Category:Bar"]);
+
+        let items: Vec<_> = provider.get_categories(codec().new_title("Baz").unwrap(), &CategoriesConfig::default()).collect().await;
+        assert!(items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_empty_batch_continued() {
+        let backend = RecordedBackend {
+            siteinfo: siteinfo(),
+            responses: vec![
+                (
+                    HashMap::from([("gcmcontinue".to_string(), "page|42|Foo".to_string())]),
+                    json!({ "batchcomplete": true, "query": { "pages": [page(1, "Foo")] } }),
+                ),
+                // in miser mode, a batch may have no pages left after filtering, and still go on.
+                (
+                    HashMap::from([("generator".to_string(), "categorymembers".to_string())]),
+                    json!({ "continue": { "gcmcontinue": "page|42|Foo", "continue": "gcmcontinue||" } }),
+                ),
+            ],
+            ..Default::default()
+        };
+        let calls = backend.calls.clone();
+        let (client, _handle) = backend.serve().await;
+        let provider = APIDataProvider::new(client, "enwiki").await.unwrap();
+
+        let items: Vec<PageInfo> = provider.get_category_members(codec().new_title("Category:Bar").unwrap(), &CategoryMembersConfig::default())
+            .map(|x| x.ok().unwrap())
+            .collect().await;
+        assert_eq!(items.iter().map(|p| provider.to_pretty(p.get_title().unwrap())).collect::<Vec<_>>(), vec!["Foo"]);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_langlinks() {
+        let backend = RecordedBackend {
+            siteinfo: siteinfo(),
+            responses: vec![
+                (
+                    HashMap::from([("llcontinue".to_string(), "1|fr".to_string())]),
+                    json!({
+                        "batchcomplete": true,
+                        "query": { "pages": [{ "pageid": 1, "ns": 0, "title": "Foo", "langlinks": [{ "lang": "fr", "title": "Exemple" }] }] },
+                    }),
+                ),
+                (
+                    HashMap::from([
+                        ("prop".to_string(), "langlinks".to_string()),
+                        ("titles".to_string(), "Foo".to_string()),
+                        ("lllimit".to_string(), "max".to_string()),
+                    ]),
+                    json!({
+                        "continue": { "llcontinue": "1|fr", "continue": "||" },
+                        "query": { "pages": [{ "pageid": 1, "ns": 0, "title": "Foo", "langlinks": [
+                            { "lang": "de", "title": "Beispiel" },
+                            { "lang": "de", "title": "Foo[bar]" },
+                        ] }] },
+                    }),
+                ),
+            ],
+            ..Default::default()
+        };
+        let (client, _handle) = backend.serve().await;
+        let provider = APIDataProvider::new(client, "enwiki").await.unwrap();
+
+        // the bad target is reported and skipped, and the query is still continued.
+        let items: Vec<_> = provider.get_langlinks(codec().new_title("Foo").unwrap(), &LangLinksConfig::default()).collect().await;
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[1].as_ref().warn(), Some(&ProviderWarning::InvalidTitle { title: "de:Foo[bar]".to_string() }));
+        let pages: Vec<&PageInfo> = items.iter().filter_map(|x| x.as_ref().ok()).collect();
+        assert!(pages.iter().all(|p| p.is_foreign()));
+        assert_eq!(pages.iter().map(|p| provider.to_pretty(p.get_title().unwrap())).collect::<Vec<_>>(), vec!["de:Beispiel", "fr:Exemple"]);
+    }
+
+    #[tokio::test]
+    async fn test_page_info_order() {
+        let backend = RecordedBackend {
+            siteinfo: siteinfo(),
+            responses: vec![(
+                HashMap::from([("titles".to_string(), "Foo|Bar|Baz".to_string())]),
+                json!({ "batchcomplete": true, "query": { "pages": [page(2, "Bar"), page(3, "Baz"), page(1, "Foo")] } }),
+            )],
+            ..Default::default()
+        };
+        let (client, _handle) = backend.serve().await;
+        let provider = APIDataProvider::new(client, "enwiki").await.unwrap();
+        let titles = ["Foo", "Bar", "Foo", "Baz"].map(|t| codec().new_title(t).unwrap());
+
+        // in the input order, and once each.
+        let items: Vec<PageInfo> = provider.get_page_info(titles).map(|x| x.ok().unwrap()).collect().await;
+        assert_eq!(items.iter().map(|p| provider.to_pretty(p.get_title().unwrap())).collect::<Vec<_>>(), vec!["Foo", "Bar", "Baz"]);
+    }
+
+    #[tokio::test]
+    async fn test_page_info_concurrency() {
+        let names = ["A", "B", "C", "D", "E"];
+        let backend = RecordedBackend {
+            siteinfo: siteinfo(),
+            responses: names.iter().enumerate().map(|(i, name)| (
+                HashMap::from([("titles".to_string(), name.to_string())]),
+                json!({ "batchcomplete": true, "query": { "pages": [page(i as u32 + 1, name)] } }),
+            )).collect(),
+            delay: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let max_in_flight = Arc::clone(&backend.max_in_flight);
+        let (client, _handle) = backend.serve().await;
+        let provider = APIDataProvider::new(client, "enwiki").await.unwrap()
+            .with_chunk_size(1).unwrap()
+            .with_max_concurrency(2).unwrap();
+        let titles = names.map(|t| codec().new_title(t).unwrap());
+
+        // requests overlap, but never more than allowed, and pages keep the input order.
+        let items: Vec<PageInfo> = provider.get_page_info(titles).map(|x| x.ok().unwrap()).collect().await;
+        assert_eq!(items.iter().map(|p| provider.to_pretty(p.get_title().unwrap())).collect::<Vec<_>>(), names);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+    }
+
+    /// Records the fields of every `api_request` span.
+    #[derive(Clone, Default)]
+    struct SpanRecorder(Arc<Mutex<Vec<String>>>);
+
+    impl<S: Subscriber> Layer<S> for SpanRecorder {
+        fn on_new_span(&self, attrs: &span::Attributes<'_>, _id: &span::Id, _ctx: Context<'_, S>) {
+            if attrs.metadata().name() == "api_request" {
+                let mut fields = FieldRecorder::default();
+                attrs.record(&mut fields);
+                self.0.lock().unwrap().push(fields.0.join(" "));
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct FieldRecorder(Vec<String>);
+
+    impl Visit for FieldRecorder {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_spans() {
+        let recorder = SpanRecorder::default();
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(recorder.clone()));
+        let (client, _handle) = links_backend().serve().await;
+        let provider = APIDataProvider::new(client, "enwiki").await.unwrap();
+
+        // one span per request, counting the continuations.
+        let _: Vec<_> = provider.get_links(codec().new_title("Main Page").unwrap(), &LinksConfig::default()).collect().await;
+        assert_eq!(*recorder.0.lock().unwrap(), [
+            r#"generator="links" titles=1 iteration=0"#,
+            r#"generator="links" titles=1 iteration=1"#,
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_assoc_redirects() {
+        let mut missing_talk = page(3, "Qux");
+        missing_talk.as_object_mut().unwrap().remove("talkid");
+        let backend = RecordedBackend {
+            siteinfo: siteinfo(),
+            responses: vec![
+                (
+                    HashMap::from([("titles".to_string(), "Talk:Foo|Talk:Bar".to_string())]),
+                    json!({ "batchcomplete": true, "query": { "pages": [
+                        { "ns": 1, "title": "Talk:Foo", "redirect": true },
+                        { "ns": 1, "title": "Talk:Bar" },
+                    ] } }),
+                ),
+                (
+                    HashMap::from([("titles".to_string(), "Foo|Bar|Qux".to_string())]),
+                    json!({ "batchcomplete": true, "query": { "pages": [page(1, "Foo"), page(2, "Bar"), missing_talk] } }),
+                ),
+            ],
+            ..Default::default()
+        };
+        let calls = Arc::clone(&backend.calls);
+        let (client, _handle) = backend.serve().await;
+        let provider = APIDataProvider::new(client, "enwiki").await.unwrap();
+        let titles = ["Foo", "Bar", "Qux"].map(|t| codec().new_title(t).unwrap());
+
+        // unknown unless asked for.
+        let items: Vec<PageInfo> = provider.get_page_info(titles.clone()).map(|x| x.ok().unwrap()).collect().await;
+        assert_eq!(items[0].get_assoc_isredir(), Err(PageInfoError::UnknownValue));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // the talk page of `Qux` does not exist, so it is not asked about.
+        let provider = provider.with_assoc_redirects(true);
+        let items: Vec<PageInfo> = provider.get_page_info(titles).map(|x| x.ok().unwrap()).collect().await;
+        assert_eq!(items.iter().map(|p| p.get_assoc_isredir().unwrap()).collect::<Vec<_>>(), vec![true, false, false]);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_page_details() {
+        let backend = RecordedBackend {
+            siteinfo: siteinfo(),
+            responses: vec![(
+                HashMap::new(),
+                json!({
+                    "batchcomplete": true,
+                    "query": {
+                        "pages": [
+                            page(1, "Foo"),
+                            { "ns": 0, "title": "Missing", "missing": true, "contentmodel": "wikitext", "pagelanguage": "en", "pagelanguagehtmlcode": "en", "pagelanguagedir": "ltr", "associatedpage": "Talk:Missing", "protection": [], "restrictiontypes": ["create"] },
+                        ],
+                    },
+                }),
+            )],
+            ..Default::default()
+        };
+        let (client, _handle) = backend.serve().await;
+        let provider = APIDataProvider::new(client, "enwiki").await.unwrap();
+        let params = HashMap::from([("titles".to_string(), "Foo|Missing".to_string())]);
+
+        let items: Vec<PageInfo> = provider.query_all(params).map(|x| x.ok().unwrap()).collect().await;
+        assert_eq!(items[0].get_pageid(), Ok(1));
+        assert_eq!(items[0].get_length(), Ok(1000));
+        assert_eq!(items[0].get_touched(), Ok("2024-01-01T00:00:00Z"));
+        assert_eq!(items[0].new_swap().get_pageid(), Ok(1001));
+        assert_eq!(items[1].get_pageid(), Err(PageInfoError::UnknownValue));
+        assert_eq!(items[1].get_length(), Err(PageInfoError::UnknownValue));
+        assert_eq!(items[1].get_touched(), Err(PageInfoError::UnknownValue));
+    }
+
+    #[tokio::test]
+    async fn test_protection() {
+        let mut protected = page(1, "Template:Foo");
+        protected["ns"] = json!(10);
+        protected["associatedpage"] = json!("Template talk:Foo");
+        protected["protection"] = json!([
+            { "type": "edit", "level": "sysop", "expiry": "infinity" },
+            { "type": "move", "level": "sysop", "expiry": "2030-01-01T00:00:00Z" },
+        ]);
+        let backend = RecordedBackend {
+            siteinfo: siteinfo(),
+            responses: vec![(
+                HashMap::new(),
+                json!({ "batchcomplete": true, "query": { "pages": [protected, page(2, "Bar")] } }),
+            )],
+            ..Default::default()
+        };
+        let (client, _handle) = backend.serve().await;
+        let provider = APIDataProvider::new(client, "enwiki").await.unwrap();
+        let params = HashMap::from([("titles".to_string(), "Template:Foo|Bar".to_string())]);
+
+        let items: Vec<PageInfo> = provider.query_all(params).map(|x| x.ok().unwrap()).collect().await;
+        assert_eq!(items[0].get_protection(), Ok(&[
+            ProtectionEntry { action: "edit".to_string(), level: "sysop".to_string(), expiry: None },
+            ProtectionEntry { action: "move".to_string(), level: "sysop".to_string(), expiry: Some("2030-01-01T00:00:00Z".to_string()) },
+        ][..]));
+        assert_eq!(items[1].get_protection(), Ok(&[][..]));
+    }
 }
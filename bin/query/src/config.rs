@@ -0,0 +1,118 @@
+//! Connection settings for the remote backend.
+//!
+//! Each setting is taken from the first place it is found in:
+//! 1. the command line, eg. `--addr`;
+//! 2. the environment, eg. `PAGELISTBOT_ADDR`;
+//! 3. the client configuration file, `$PAGELISTBOT_HOME/client.toml`;
+//! 4. the built-in default, if any.
+//!
+//! The first two are handled by `clap`.
+
+use serde::Deserialize;
+use std::{fs, io, path::{Path, PathBuf}};
+use thiserror::Error;
+
+pub const DEFAULT_BACKEND_ADDR: &str = "127.0.0.1";
+pub const DEFAULT_BACKEND_PORT: u16 = 8848;
+
+/// Defaults read from the client configuration file. Every key can be omitted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClientConfig {
+    pub addr: Option<String>,
+    pub port: Option<u16>,
+    pub key: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum ClientConfigError {
+    #[error("cannot read `{0}`: {1}")]
+    Read(PathBuf, io::Error),
+    #[error("cannot parse `{0}`: {1}")]
+    Parse(PathBuf, toml::de::Error),
+    #[error("no site key given, use `--key`, `PAGELISTBOT_KEY` or `key` in the client configuration file")]
+    NoKey,
+}
+
+/// The resolved connection settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Connection {
+    pub addr: String,
+    pub port: u16,
+    pub key: String,
+}
+
+/// The default location of the client configuration file.
+pub fn client_config_path() -> PathBuf {
+    pagelistbot_env::pagelistbot_home().join("client.toml")
+}
+
+impl ClientConfig {
+    /// Read the client configuration file. A missing file is the same as an empty one.
+    pub fn load(path: &Path) -> Result<Self, ClientConfigError> {
+        match fs::read_to_string(path) {
+            Ok(s) => toml::from_str(&s).map_err(|e| ClientConfigError::Parse(path.to_owned(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(ClientConfigError::Read(path.to_owned(), e)),
+        }
+    }
+
+    /// Fill in the settings not given on the command line or in the environment.
+    pub fn resolve(self, addr: Option<String>, port: Option<u16>, key: Option<String>) -> Result<Connection, ClientConfigError> {
+        Ok(Connection {
+            addr: addr.or(self.addr).unwrap_or_else(|| DEFAULT_BACKEND_ADDR.to_string()),
+            port: port.or(self.port).unwrap_or(DEFAULT_BACKEND_PORT),
+            key: key.or(self.key).ok_or(ClientConfigError::NoKey)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Arg;
+    use clap::CommandFactory;
+    use std::{env, fs};
+    use super::{ClientConfig, ClientConfigError, Connection};
+
+    #[test]
+    fn test_client_config() {
+        let path = env::temp_dir().join(format!("pagelistbot-client-{}.toml", std::process::id()));
+        assert_eq!(ClientConfig::load(&path).unwrap(), ClientConfig::default());
+
+        fs::write(&path, "addr = \"10.0.0.1\"\nkey = \"enwiki\"\n").unwrap();
+        let config = ClientConfig::load(&path).unwrap();
+        assert_eq!(config, ClientConfig { addr: Some("10.0.0.1".to_string()), port: None, key: Some("enwiki".to_string()) });
+
+        fs::write(&path, "address = \"10.0.0.1\"\n").unwrap();
+        assert!(matches!(ClientConfig::load(&path), Err(ClientConfigError::Parse(..))));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_precedence() {
+        let config = ClientConfig { addr: Some("10.0.0.1".to_string()), port: Some(8000), key: Some("enwiki".to_string()) };
+
+        // the configuration file over the defaults.
+        assert_eq!(
+            config.clone().resolve(None, None, None).unwrap(),
+            Connection { addr: "10.0.0.1".to_string(), port: 8000, key: "enwiki".to_string() },
+        );
+        assert_eq!(
+            ClientConfig::default().resolve(None, None, Some("zhwiki".to_string())).unwrap(),
+            Connection { addr: "127.0.0.1".to_string(), port: 8848, key: "zhwiki".to_string() },
+        );
+        assert!(matches!(ClientConfig::default().resolve(None, None, None), Err(ClientConfigError::NoKey)));
+
+        // the command line or the environment over the configuration file. Which of the two is `clap`'s to pick,
+        // so the environment is not touched here, only the variables each setting is read from.
+        assert_eq!(
+            config.resolve(None, Some(9000), Some("frwiki".to_string())).unwrap(),
+            Connection { addr: "10.0.0.1".to_string(), port: 9000, key: "frwiki".to_string() },
+        );
+        let command = Arg::command();
+        let env = |id: &str| command.get_arguments().find(|arg| arg.get_id() == id).and_then(|arg| arg.get_env()).and_then(|v| v.to_str());
+        assert_eq!(env("addr"), Some("PAGELISTBOT_ADDR"));
+        assert_eq!(env("port"), Some("PAGELISTBOT_PORT"));
+        assert_eq!(env("key"), Some("PAGELISTBOT_KEY"));
+    }
+}
@@ -0,0 +1,70 @@
+//! Comparing query results against those of an earlier run.
+
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+/// Read the titles listed in the output of an earlier run.
+///
+/// Both the plain output, one title per line, and the JSON output, one object per line, are understood.
+/// Warnings, errors and summaries in either are skipped, and so are blank lines.
+pub fn baseline_titles(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| match serde_json::from_str::<Value>(line) {
+            // `--format ndjson`, or `--format json`.
+            Ok(Value::Object(object)) => object.get("title")
+                .or_else(|| object.get("content").filter(|_| object.get("type").and_then(Value::as_str) == Some("item")))
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            // titles are capitalized, so these are never titles.
+            _ if ["warning: ", "error: ", "total: "].iter().any(|prefix| line.starts_with(prefix)) => None,
+            _ => Some(line.to_string()),
+        })
+        .collect()
+}
+
+/// Split `results` against `baseline` into the titles added, in result order, and the titles removed, in baseline order.
+pub fn diff<T: Ord + Clone>(baseline: &[T], results: &[T]) -> (Vec<T>, Vec<T>) {
+    let before: BTreeSet<&T> = baseline.iter().collect();
+    let after: BTreeSet<&T> = results.iter().collect();
+    let mut seen = BTreeSet::new();
+    let added = results.iter().filter(|t| !before.contains(t) && seen.insert(*t)).cloned().collect();
+    let removed = baseline.iter().filter(|t| !after.contains(t) && seen.insert(*t)).cloned().collect();
+    (added, removed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{baseline_titles, diff};
+    use std::{env, fs};
+
+    #[test]
+    fn test_baseline_titles() {
+        let path = env::temp_dir().join(format!("pagelistbot-baseline-{}.txt", std::process::id()));
+        fs::write(&path, concat!(
+            "Foo\n",
+            "warning: timeout after 120 seconds\n",
+            "\n",
+            "Category:Bar baz\n",
+            "{\"title\":\"Qux\",\"namespace\":0}\n",
+            "{\"type\":\"item\",\"content\":\"Talk:Quux\"}\n",
+            "{\"type\":\"warning\",\"content\":\"something\"}\n",
+            "{\"total\":4,\"warnings\":1}\n",
+        )).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(baseline_titles(&text), vec!["Foo", "Category:Bar baz", "Qux", "Talk:Quux"]);
+    }
+
+    #[test]
+    fn test_diff() {
+        let (added, removed) = diff(&["A", "B", "C", "B"], &["D", "C", "A", "E", "D"]);
+        assert_eq!(added, vec!["D", "E"]);
+        assert_eq!(removed, vec!["B"]);
+
+        let (added, removed) = diff::<&str>(&[], &[]);
+        assert!(added.is_empty() && removed.is_empty());
+    }
+}
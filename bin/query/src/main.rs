@@ -2,50 +2,111 @@
 
 mod api;
 use api::APIDataProvider;
+mod config;
+use config::ClientConfig;
+mod diff;
+mod output;
+use output::OutputWriter;
 mod writer;
-use futures::StreamExt;
+#[cfg(test)]
+mod test_backend;
+use futures::{future, StreamExt};
 use writer::*;
 
-use ast::Expression;
 use clap::Parser;
 use core::time::Duration;
 use intorinf::IntOrInf;
 use jsonrpsee::http_client::HttpClientBuilder;
-use nom::error::VerboseError;
-use owo_colors::OwoColorize;
 use std::{
-    io::{stdout, BufWriter, IsTerminal, Write},
+    env, fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{self, stdin, stdout, BufWriter, IsTerminal, Read},
+    path::{Path, PathBuf},
     process::ExitCode, 
 };
+use tracing::{Instrument, Level};
+use tracing_subscriber::{filter::Targets, prelude::*};
 use trio_result::TrioResult;
 
 #[derive(Debug, Parser)]
 pub struct Arg {
-    /// The address of the remote backend.
-    #[arg(short, long, default_value_t = DEFAULT_BACKEND_ADDR.to_string())]
-    addr: String,
-    /// The port of the remote backend.
-    #[arg(short, long, default_value_t = 8848)]
-    port: u16,
+    /// The address of the remote backend. Defaults to `127.0.0.1`.
+    #[arg(short, long, env = "PAGELISTBOT_ADDR")]
+    addr: Option<String>,
+    /// The port of the remote backend. Defaults to `8848`.
+    #[arg(short, long, env = "PAGELISTBOT_PORT")]
+    port: Option<u16>,
     /// The key of the remote backend.
-    #[arg(short, long)]
-    key: String,
-    /// The query string.
-    #[arg(short, long)]
-    query: String,
-    /// Maximum time allowed for query, in seconds.
+    #[arg(short, long, env = "PAGELISTBOT_KEY")]
+    key: Option<String>,
+    /// The client configuration file, for defaults of `--addr`, `--port` and `--key`. Defaults to `$PAGELISTBOT_HOME/client.toml`.
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// The query string, or `-` to read it from standard input.
+    #[arg(short, long, required_unless_present = "query_file", conflicts_with = "query_file")]
+    query: Option<String>,
+    /// Read the query string from a file.
+    #[arg(long)]
+    query_file: Option<PathBuf>,
+    /// Maximum time allowed for query, in seconds. `0` means no timeout.
     #[arg(short, long, default_value_t = 120)]
     timeout: u64,
     /// Default maximum query result limit, if it is not overridden by `.limit()` expression modifier.
     #[arg(short, long, default_value_t = 10000)]
     limit: i32,
-    /// Output in JSON format, not in human-readable format.
+    /// Maximum number of categories a category search expands from each of its input pages.
+    #[arg(long, default_value_t = solver::DEFAULT_CATEGORY_BUDGET, value_parser = parse_nonzero)]
+    category_budget: usize,
+    /// Tell apart titles differing only in their fragment, eg. `A#B` and `A`, when removing repeated results.
     #[arg(long)]
+    keep_fragments: bool,
+    /// Output in JSON format, not in human-readable format. Same as `--format json`.
+    #[arg(long, conflicts_with = "format")]
     json: bool,
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = Format::Plain)]
+    format: Format,
+    /// When to color the output. `auto` colors if a terminal is attached, unless `NO_COLOR` is set.
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+    /// Wikitext written before the list, with `--format wikitext`.
+    #[arg(long)]
+    header: Option<String>,
+    /// Wikitext written after the list, with `--format wikitext`.
+    #[arg(long)]
+    footer: Option<String>,
+    /// Number of titles sent in a single request. Defaults to 500 with `apihighlimits`, or 50 otherwise.
+    #[arg(long)]
+    chunk_size: Option<usize>,
+    /// Number of requests of a single step allowed in flight at once.
+    #[arg(long, default_value_t = 1)]
+    max_concurrency: usize,
+    /// Also ask whether the associated page of each page is a redirect. This costs one more request per response.
+    #[arg(long)]
+    assoc_redirects: bool,
+    /// Most requests sent per second, counting continued requests. Unlimited by default.
+    #[arg(long)]
+    rate_limit: Option<u32>,
+    /// Print the query and how it would be evaluated, without running it.
+    #[arg(long)]
+    explain: bool,
+    /// Reorder intersections so that the cheaper operand is evaluated first.
+    #[arg(long)]
+    optimize: bool,
+    /// Compare the results against the output of an earlier run saved in this file, and list only the titles added and removed.
+    #[arg(long, conflicts_with = "explain")]
+    diff_against: Option<PathBuf>,
+    /// Also post the results, in wikitext, to this page. Meant for testing a query on wiki.
+    #[arg(long, conflicts_with_all = ["explain", "diff_against"])]
+    write_to: Option<String>,
+    /// The edit summary used with `--write-to`.
+    #[arg(long, default_value = "Update page list", requires = "write_to")]
+    summary: String,
+    /// Log each API request, with the time it took, to stderr.
+    #[arg(long)]
+    verbose: bool,
 }
 
-const DEFAULT_BACKEND_ADDR: &str = "127.0.0.1";
-
 const FAILURE_PARSE: u8 = 100;
 const FAILURE_INIT: u8 = 101;
 const FAILURE_SEMANTIC: u8 = 102;
@@ -55,94 +116,378 @@ const FAILURE_QUERY: u8 = 103;
 async fn main() -> ExitCode {
     let arg = Arg::parse();
     let stdout = stdout().lock();
-    let color = stdout.is_terminal();
+    let color = use_color(arg.color, stdout.is_terminal(), env::var_os("NO_COLOR"));
     let mut writer = BufWriter::new(stdout);
+    let format = if arg.json { Format::Json } else { arg.format };
+    let registry = tracing_subscriber::registry()
+        .with(arg.verbose.then(|| tracing_subscriber::fmt::layer().with_writer(io::stderr).with_filter(Targets::new().with_target("query", Level::DEBUG))));
+    #[cfg(feature = "otel")]
+    let (registry, _otel_guard) = match pagelistbot_env::otel::otlp_endpoint().map(|endpoint| pagelistbot_env::otel::otel_layer("query", &endpoint)) {
+        Some(Ok((layer, guard))) => (registry.with(Some(layer.with_filter(Targets::new().with_target("query", Level::DEBUG)))), Some(guard)),
+        Some(Err(e)) => {
+            write_err(format_args!("cannot set up trace export: {e}"), ErrorCode::Init, None, writer.get_mut(), color, format).unwrap();
+            return ErrorCode::Init.into();
+        },
+        None => (registry.with(None), None),
+    };
+    registry.init();
 
-    // parse the expression first. only continue if parse successful.
-    let expr = match Expression::parse::<VerboseError<_>>(&arg.query) {
+    // read and parse the expression first. only continue if parse successful.
+    let query = match read_query(arg.query.as_deref(), arg.query_file.as_deref(), stdin().lock()) {
+        Ok(query) => query,
+        Err(e) => {
+            write_err(format_args!("cannot read query: {e}"), ErrorCode::Parse, None, writer.get_mut(), color, format).unwrap();
+            return ErrorCode::Parse.into();
+        }
+    };
+    let mut expr = match ast::parse(&query) {
         Ok(expr) => expr,
         Err(e) => {
-            write_err(e, writer.get_mut(), color, arg.json).unwrap();
-            return ExitCode::from(FAILURE_PARSE);
+            let (line, column) = line_column(&query, e.span.start);
+            write_err(format_args!("{e} at line {line}, column {column}"), ErrorCode::Parse, Some(e.span), writer.get_mut(), color, format).unwrap();
+            return ErrorCode::Parse.into();
+        }
+    };
+
+    let baseline = match arg.diff_against.as_deref().map(fs::read_to_string).transpose() {
+        Ok(baseline) => baseline,
+        Err(e) => {
+            write_err(format_args!("cannot read baseline: {e}"), ErrorCode::Init, None, writer.get_mut(), color, format).unwrap();
+            return ErrorCode::Init.into();
         }
     };
 
     // set up connection to backend.
-    let backend = match HttpClientBuilder::default().build(format!("http://{}:{}", arg.addr, arg.port)) {
+    let connection = match ClientConfig::load(&arg.config.clone().unwrap_or_else(config::client_config_path))
+        .and_then(|config| config.resolve(arg.addr.clone(), arg.port, arg.key.clone()))
+    {
+        Ok(connection) => connection,
+        Err(e) => {
+            write_err(e, ErrorCode::Init, None, writer.get_mut(), color, format).unwrap();
+            return ErrorCode::Init.into();
+        }
+    };
+    let backend = match HttpClientBuilder::default().build(format!("http://{}:{}", connection.addr, connection.port)) {
         Ok(backend) => backend,
         Err(e) => {
-            write_err(e, writer.get_mut(), color, arg.json).unwrap();
-            return ExitCode::from(FAILURE_INIT);
+            write_err(e, ErrorCode::Init, None, writer.get_mut(), color, format).unwrap();
+            return ErrorCode::Init.into();
         } 
     };
-    let provider = match APIDataProvider::new(backend, &arg.key).await {
+    let provider = match APIDataProvider::new(backend.clone(), &connection.key).await
+        .and_then(|provider| match arg.chunk_size {
+            Some(chunk_size) => provider.with_chunk_size(chunk_size),
+            None => Ok(provider),
+        })
+        .and_then(|provider| provider.with_max_concurrency(arg.max_concurrency))
+        .map(|provider| provider.with_assoc_redirects(arg.assoc_redirects))
+        .and_then(|provider| match arg.rate_limit {
+            Some(per_second) => provider.with_rate_limit(per_second),
+            None => Ok(provider),
+        })
+    {
         Ok(provider) => provider,
         Err(e) => {
-            write_err(e, writer.get_mut(), color, arg.json).unwrap();
-            return ExitCode::from(FAILURE_INIT);
+            write_err(e, ErrorCode::Init, None, writer.get_mut(), color, format).unwrap();
+            return ErrorCode::Init.into();
         }
     };
 
     // set up stream.
-    let stream = match solver::from_expr(&expr, provider.clone(), IntOrInf::from(arg.limit)) {
+    let options = solver::SolverOptions {
+        category_budget: arg.category_budget,
+        strip_fragments: !arg.keep_fragments,
+        optimize: arg.optimize,
+    };
+    let stream = match solver::from_expr_with_options(&expr, provider.clone(), IntOrInf::from(arg.limit), provider.namespaces(), options) {
         Ok(stream) => stream,
         Err(e) => {
-            write_err(e, writer.get_mut(), color, arg.json).unwrap();
-            return ExitCode::from(FAILURE_SEMANTIC);
+            write_err(&e, ErrorCode::Semantic, Some(e.get_span()), writer.get_mut(), color, format).unwrap();
+            return ErrorCode::Semantic.into();
         }
     };
     let mut stream = Box::into_pin(stream);
 
-    // perform query.
-    let sleep = tokio::time::sleep(Duration::from_secs(arg.timeout));
-    tokio::pin!(sleep);
-
-    let mut item_count = 0;
-    let mut warn_count = 0;
-
-    loop {
-        tokio::select! {
-            biased;
-            _ = &mut sleep => {
-                // time elapsed.
-                warn_count += 1;
-                write_warn(format_args!("timeout after {} seconds", arg.timeout), writer.get_mut(), color, arg.json).unwrap();
-                break;
-            },
-            item = stream.next() => {
-                if let Some(item) = item {
-                    match item {
-                        TrioResult::Ok(item) => {
-                            let t = match item.get_title() {
-                                Ok(t) => t,
-                                Err(e) => {
-                                    write_err(e, writer.get_mut(), color, arg.json).unwrap();
-                                    return ExitCode::from(FAILURE_QUERY);
-                                },
-                            };
-                            item_count += 1;
-                            write_item(provider.to_pretty(t), writer.get_mut(), arg.json).unwrap();
-                        },
-                        TrioResult::Warn(w) => {
-                            warn_count += 1;
-                            write_warn(w, writer.get_mut(), color, arg.json).unwrap();
-                        },
-                        TrioResult::Err(e) => {
-                            write_err(e, writer.get_mut(), color, arg.json).unwrap();
-                            return ExitCode::from(FAILURE_QUERY);
-                        },
-                    }
-                } else {
-                    // poll finished.
+    // print the plan instead of running it.
+    if arg.explain {
+        // the stream optimizes its own copy. The plan shows the same order.
+        if arg.optimize {
+            solver::optimize(&mut expr);
+        }
+        let plan = match solver::plan(&expr, IntOrInf::from(arg.limit), provider.namespaces()) {
+            Ok(plan) => plan,
+            Err(e) => {
+                write_err(&e, ErrorCode::Semantic, Some(e.get_span()), writer.get_mut(), color, format).unwrap();
+                return ErrorCode::Semantic.into();
+            }
+        };
+        write_explain(&expr, &plan, provider.chunk_size(), provider.max_concurrency(), writer.get_mut(), format).unwrap();
+        return ExitCode::SUCCESS;
+    }
+
+    // perform query, with the requests it makes in the `solve` span.
+    let solve_span = tracing::info_span!("solve", query_hash = %query_hash(&query));
+    async {
+        write_header(writer.get_mut(), format, arg.header.as_deref(), baseline.is_some()).unwrap();
+        let sleep = deadline(arg.timeout);
+        tokio::pin!(sleep);
+
+        let mut item_count = 0;
+        let mut warn_count = 0;
+        let mut timed_out = false;
+
+        // titles of the earlier run, compared after the query is done.
+        let baseline = baseline.map(|text| {
+            diff::baseline_titles(&text).into_iter()
+                .filter_map(|raw| match provider.new_title(&raw) {
+                    Ok(t) => Some(t),
+                    Err(_) => {
+                        warn_count += 1;
+                        write_warn(format_args!("invalid title `{raw}` in baseline"), writer.get_mut(), color, format).unwrap();
+                        None
+                    },
+                })
+                .collect::<Vec<_>>()
+        });
+        let mut results = Vec::new();
+        // pages to post with `--write-to`, each with its namespace.
+        let mut pages = Vec::new();
+
+        loop {
+            tokio::select! {
+                biased;
+                _ = &mut sleep => {
+                    // time elapsed.
+                    warn_count += 1;
+                    timed_out = true;
+                    write_warn(format_args!("timeout after {} seconds", arg.timeout), writer.get_mut(), color, format).unwrap();
                     break;
+                },
+                item = stream.next() => {
+                    if let Some(item) = item {
+                        match item {
+                            TrioResult::Ok(item) => {
+                                let t = match item.get_title() {
+                                    Ok(t) => t,
+                                    Err(e) => {
+                                        write_err(e, ErrorCode::Query, None, writer.get_mut(), color, format).unwrap();
+                                        return ErrorCode::Query.into();
+                                    },
+                                };
+                                item_count += 1;
+                                if baseline.is_some() {
+                                    results.push(t.to_owned());
+                                } else {
+                                    write_item(provider.to_pretty(t), t.namespace(), writer.get_mut(), format).unwrap();
+                                }
+                                if arg.write_to.is_some() {
+                                    pages.push((provider.to_pretty(t), t.namespace()));
+                                }
+                            },
+                            TrioResult::Warn(w) => {
+                                warn_count += 1;
+                                write_warn(w, writer.get_mut(), color, format).unwrap();
+                            },
+                            TrioResult::Err(e) => {
+                                write_err(&e, ErrorCode::Query, Some(e.get_span()), writer.get_mut(), color, format).unwrap();
+                                return ErrorCode::Query.into();
+                            },
+                        }
+                    } else {
+                        // poll finished.
+                        break;
+                    }
                 }
             }
         }
-    }
     
-    // write summary
-    if !arg.json && color {
-        writeln!(writer, "{}", format_args!("total: {item_count}, warning: {warn_count}").bold()).unwrap();
+        // compare against the earlier run. pages missing from partial results are not really removed.
+        if let Some(baseline) = baseline {
+            if timed_out {
+                write_err("cannot compare partial results against the baseline", ErrorCode::Query, None, writer.get_mut(), color, format).unwrap();
+                return ErrorCode::Query.into();
+            }
+            let (added, removed) = diff::diff(&baseline, &results);
+            for (titles, change) in [(&added, Change::Added), (&removed, Change::Removed)] {
+                for t in titles {
+                    write_change(provider.to_pretty(t), t.namespace(), change, writer.get_mut(), color, format).unwrap();
+                }
+            }
+            write_footer(writer.get_mut(), format, arg.footer.as_deref()).unwrap();
+            write_diff_summary(added.len(), removed.len(), warn_count, writer.get_mut(), color, format).unwrap();
+            return ExitCode::SUCCESS;
+        }
+
+        write_footer(writer.get_mut(), format, arg.footer.as_deref()).unwrap();
+
+        // post the list. a partial list would drop pages from the wiki page.
+        if let Some(page) = arg.write_to.as_deref() {
+            if timed_out {
+                write_err(format_args!("cannot write partial results to `{page}`"), ErrorCode::Query, None, writer.get_mut(), color, format).unwrap();
+                return ErrorCode::Query.into();
+            }
+            let output = OutputWriter::new(&arg.summary)
+                .with_header(arg.header.clone())
+                .with_footer(arg.footer.clone())
+                .with_bot(true);
+            if let Err(e) = output.post(&backend, &connection.key, page, &output.compose(pages)).await {
+                write_err(format_args!("cannot write to `{page}`: {e}"), ErrorCode::Query, None, writer.get_mut(), color, format).unwrap();
+                return ErrorCode::Query.into();
+            }
+        }
+
+        // write summary
+        write_summary(item_count, warn_count, writer.get_mut(), color, format).unwrap();
+        ExitCode::SUCCESS
+    }.instrument(solve_span).await
+}
+
+/// The line and column, both from 1, of the byte `offset` in `text`. Columns are counted in characters.
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}
+
+/// Parse a positive integer, for the options where `0` makes no sense.
+fn parse_nonzero(s: &str) -> Result<usize, String> {
+    match s.parse() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// A short hash of the query string, to tell apart the traces of different queries.
+fn query_hash(query: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    query.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// A future that completes after `timeout` seconds, or never if `timeout` is `0`.
+async fn deadline(timeout: u64) {
+    if timeout == 0 {
+        future::pending().await
+    } else {
+        tokio::time::sleep(Duration::from_secs(timeout)).await
+    }
+}
+
+/// Get the query string from `--query`, `--query-file`, or `stdin` if the query is `-`.
+fn read_query<R: Read>(query: Option<&str>, query_file: Option<&Path>, mut stdin: R) -> io::Result<String> {
+    match (query, query_file) {
+        (Some("-"), _) => {
+            let mut query = String::new();
+            stdin.read_to_string(&mut query)?;
+            Ok(query)
+        },
+        (Some(query), _) => Ok(query.to_string()),
+        (None, Some(path)) => fs::read_to_string(path),
+        // rejected by clap.
+        (None, None) => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::writer::{write_err, ErrorCode, Format};
+    use serde_json::{json, Value};
+    use super::{deadline, line_column, query_hash, read_query, Arg};
+    use clap::Parser;
+    use core::time::Duration;
+    use futures::{stream, StreamExt};
+    use std::{env, fs, io::Cursor};
+
+    #[test]
+    fn test_read_query() {
+        let stdin = || Cursor::new("linkto(\"Main Page\")\n  & incat(\"Category:A\")\n");
+        assert_eq!(read_query(Some("\"A\""), None, stdin()).unwrap(), "\"A\"");
+        assert_eq!(read_query(Some("-"), None, stdin()).unwrap(), "linkto(\"Main Page\")\n  & incat(\"Category:A\")\n");
+
+        let path = env::temp_dir().join(format!("pagelistbot-query-{}.txt", std::process::id()));
+        fs::write(&path, "link(\"B\")").unwrap();
+        assert_eq!(read_query(None, Some(&path), stdin()).unwrap(), "link(\"B\")");
+        fs::remove_file(&path).unwrap();
+        assert!(read_query(None, Some(&path), stdin()).is_err());
+    }
+
+    #[test]
+    fn test_query_source() {
+        assert!(Arg::try_parse_from(["query", "-k", "enwiki", "-q", "-"]).is_ok());
+        assert!(Arg::try_parse_from(["query", "-k", "enwiki", "--query-file", "query.txt"]).is_ok());
+        assert!(Arg::try_parse_from(["query", "-k", "enwiki"]).is_err());
+        assert!(Arg::try_parse_from(["query", "-k", "enwiki", "-q", "\"A\"", "--query-file", "query.txt"]).is_err());
+        assert!(Arg::try_parse_from(["query", "-k", "enwiki", "-q", "\"A\"", "--diff-against", "old.txt"]).is_ok());
+        assert!(Arg::try_parse_from(["query", "-k", "enwiki", "-q", "\"A\"", "--diff-against", "old.txt", "--explain"]).is_err());
+        assert!(Arg::try_parse_from(["query", "-k", "enwiki", "-q", "\"A\"", "--write-to", "User:Example/List", "--summary", "Test"]).is_ok());
+        assert!(Arg::try_parse_from(["query", "-k", "enwiki", "-q", "\"A\"", "--write-to", "User:Example/List", "--diff-against", "old.txt"]).is_err());
+        assert!(Arg::try_parse_from(["query", "-k", "enwiki", "-q", "\"A\"", "--summary", "Test"]).is_err());
+        assert_eq!(Arg::try_parse_from(["query", "-k", "enwiki", "-q", "\"A\"", "--category-budget", "3"]).unwrap().category_budget, 3);
+        assert!(Arg::try_parse_from(["query", "-k", "enwiki", "-q", "\"A\"", "--category-budget", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_line_column() {
+        let query = "link(\"Ä\")\n  & incat(";
+        assert_eq!(line_column(query, 0), (1, 1));
+        assert_eq!(line_column(query, 6), (1, 7));
+        assert_eq!(line_column(query, 11), (2, 1));
+        assert_eq!(line_column(query, query.len()), (2, 11));
+    }
+
+    #[test]
+    fn test_query_hash() {
+        assert_eq!(query_hash("link(\"A\")"), query_hash("link(\"A\")"));
+        assert_ne!(query_hash("link(\"A\")"), query_hash("link(\"B\")"));
+        assert_eq!(query_hash("").len(), 16);
+    }
+
+    #[tokio::test]
+    async fn test_no_timeout() {
+        // the same race as in `main`, against a quick query.
+        let sleep = deadline(0);
+        tokio::pin!(sleep);
+        let mut stream = stream::iter(["A", "B"]).then(|t| async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            t
+        }).boxed();
+        let mut items = Vec::new();
+        loop {
+            tokio::select! {
+                biased;
+                _ = &mut sleep => panic!("timed out"),
+                item = stream.next() => match item {
+                    Some(item) => items.push(item),
+                    None => break,
+                },
+            }
+        }
+        assert_eq!(items, vec!["A", "B"]);
+
+        assert!(tokio::time::timeout(Duration::from_millis(50), deadline(0)).await.is_err());
+    }
+
+    #[test]
+    fn test_parse_error_envelope() {
+        let e = ast::parse("link(\"A\").limit(x)").unwrap_err();
+        let span = Some(e.span);
+        let mut out = Vec::new();
+        write_err(&e, ErrorCode::Parse, span, &mut out, false, Format::Ndjson).unwrap();
+        let line: Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(line["error"]["code"], "parse");
+        assert_eq!(line["error"]["message"], e.to_string());
+        assert_eq!(line["error"]["span"], json!({ "offset": 9, "length": 1 }));
+
+        let mut out = Vec::new();
+        write_err(&e, ErrorCode::Parse, span, &mut out, false, Format::Json).unwrap();
+        let line: Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(line["type"], "error");
+        assert_eq!(line["error"]["span"], json!({ "offset": 9, "length": 1 }));
+
+        let mut out = Vec::new();
+        write_err("connection refused", ErrorCode::Init, None, &mut out, false, Format::Ndjson).unwrap();
+        let line: Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(line, json!({ "error": { "code": "init", "message": "connection refused" } }));
     }
-    ExitCode::SUCCESS
 }
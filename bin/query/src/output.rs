@@ -0,0 +1,138 @@
+//! Posting query results to a wiki page.
+
+use crate::writer::{write_footer, write_header, write_item, Format};
+use jsonrpsee::core::ClientError;
+use pagelistbot_api_daemon_interface::APIServiceInterfaceClient;
+use serde_json::Value;
+use std::{collections::HashMap, fmt::Display};
+use thiserror::Error;
+
+/// Writes a list of pages, between a wikitext header and footer, to a wiki page.
+#[derive(Debug, Clone, Default)]
+pub struct OutputWriter {
+    header: Option<String>,
+    footer: Option<String>,
+    summary: String,
+    bot: bool,
+}
+
+impl OutputWriter {
+    pub fn new(summary: &str) -> Self {
+        OutputWriter { summary: summary.to_owned(), ..Default::default() }
+    }
+
+    /// Set the wikitext written before the list.
+    pub fn with_header(mut self, header: Option<String>) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Set the wikitext written after the list.
+    pub fn with_footer(mut self, footer: Option<String>) -> Self {
+        self.footer = footer;
+        self
+    }
+
+    /// Set whether the edit is marked as a bot edit. This has no effect if the account has no `bot` flag.
+    pub fn with_bot(mut self, bot: bool) -> Self {
+        self.bot = bot;
+        self
+    }
+
+    /// The page text for `pages`, each given with its namespace, the same as the output of `--format wikitext`.
+    pub fn compose<T: Display>(&self, pages: impl IntoIterator<Item=(T, i32)>) -> String {
+        let mut text = Vec::new();
+        write_header(&mut text, Format::Wikitext, self.header.as_deref(), false).unwrap();
+        for (title, namespace) in pages {
+            write_item(title, namespace, &mut text, Format::Wikitext).unwrap();
+        }
+        write_footer(&mut text, Format::Wikitext, self.footer.as_deref()).unwrap();
+        // the writers only ever write UTF-8.
+        String::from_utf8(text).unwrap().trim_end().to_owned()
+    }
+
+    /// Replace the content of `page` with `text`, creating the page if it does not exist.
+    pub async fn post<B>(&self, backend: &B, key: &str, page: &str, text: &str) -> Result<(), OutputError>
+    where
+        B: APIServiceInterfaceClient + Sync,
+    {
+        let mut params = HashMap::from([
+            ("action".to_owned(), "edit".to_owned()),
+            ("title".to_owned(), page.to_owned()),
+            ("text".to_owned(), text.to_owned()),
+            ("summary".to_owned(), self.summary.clone()),
+            ("format".to_owned(), "json".to_owned()),
+            ("formatversion".to_owned(), "2".to_owned()),
+        ]);
+        if self.bot {
+            params.insert("bot".to_owned(), "1".to_owned());
+        }
+        let response = backend.post_value_with_token(key, "csrf", params).await?;
+        match response.pointer("/edit/result").and_then(Value::as_str) {
+            Some("Success") => Ok(()),
+            Some(result) => Err(OutputError::Rejected(result.to_owned())),
+            None => Err(OutputError::Rejected(response.pointer("/error/info").and_then(Value::as_str).unwrap_or("unknown response").to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum OutputError {
+    #[error(transparent)]
+    Backend(#[from] ClientError),
+    #[error("edit rejected: {0}")]
+    Rejected(String),
+}
+
+#[cfg(test)]
+mod test {
+    use super::{OutputError, OutputWriter};
+    use crate::test_backend::{siteinfo, RecordedBackend};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_compose() {
+        let writer = OutputWriter::new("Update list")
+            .with_header(Some("{{Div col}}".to_owned()))
+            .with_footer(Some("{{Div col end}}".to_owned()));
+        assert_eq!(
+            writer.compose([("Main Page", 0), ("Category:Example", 14)]),
+            "{{Div col}}\n* [[Main Page]]\n* [[:Category:Example]]\n{{Div col end}}",
+        );
+        assert_eq!(OutputWriter::new("Update list").compose::<&str>([]), "");
+    }
+
+    #[tokio::test]
+    async fn test_post() {
+        let text = "{{Div col}}\n* [[Main Page]]\n{{Div col end}}";
+        let edit = |title: &str| HashMap::from([
+            ("action".to_owned(), "edit".to_owned()),
+            ("title".to_owned(), title.to_owned()),
+            ("text".to_owned(), text.to_owned()),
+            ("summary".to_owned(), "Update list".to_owned()),
+            ("bot".to_owned(), "1".to_owned()),
+        ]);
+        let backend = RecordedBackend {
+            siteinfo: siteinfo(),
+            responses: vec![
+                (edit("User:Example/List"), json!({ "edit": { "result": "Success", "title": "User:Example/List" } })),
+                (edit("User:Example/Protected"), json!({ "error": { "code": "protectedpage", "info": "This page has been protected." } })),
+            ],
+            ..Default::default()
+        };
+        let (client, _handle) = backend.serve().await;
+        let writer = OutputWriter::new("Update list")
+            .with_header(Some("{{Div col}}".to_owned()))
+            .with_footer(Some("{{Div col end}}".to_owned()))
+            .with_bot(true);
+        let composed = writer.compose([("Main Page", 0)]);
+        assert_eq!(composed, text);
+
+        writer.post(&client, "enwiki", "User:Example/List", &composed).await.unwrap();
+        assert!(matches!(
+            writer.post(&client, "enwiki", "User:Example/Protected", &composed).await,
+            Err(OutputError::Rejected(info)) if info == "This page has been protected.",
+        ));
+    }
+}
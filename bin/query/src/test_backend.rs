@@ -0,0 +1,134 @@
+//! A recorded API Daemon, for testing the API data provider without network access.
+
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    http_client::{HttpClient, HttpClientBuilder},
+    server::{Server, ServerHandle},
+};
+use mwtitle::{SiteInfoResponse, TitleCodec};
+use pagelistbot_api_daemon_interface::{APIServiceInterfaceServer, ConnectionHealth};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    sync::{atomic::{AtomicUsize, Ordering}, Arc},
+    time::Duration,
+};
+
+/// An API Daemon that replays recorded responses.
+///
+/// `post_value` answers with the first response whose request parameters are all present in the query,
+/// so a continued request should be listed before the request it continues.
+/// Each `post_value` takes `delay` to answer, and the most requests seen in flight at once are counted.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RecordedBackend {
+    pub siteinfo: Value,
+    pub apihighlimits: bool,
+    pub responses: Vec<(HashMap<String, String>, Value)>,
+    pub calls: Arc<AtomicUsize>,
+    pub delay: Duration,
+    pub in_flight: Arc<AtomicUsize>,
+    pub max_in_flight: Arc<AtomicUsize>,
+}
+
+/// A minimal English Wikipedia site info, enough to build a `TitleCodec`.
+pub(crate) fn siteinfo() -> Value {
+    let namespace = |id: i32, name: &str, canonical: &str| json!({
+        "id": id, "case": "first-letter", "name": name, "canonical": canonical, "subpages": id % 2 == 1, "content": id == 0,
+    });
+    json!({
+        "batchcomplete": true,
+        "query": {
+            "general": {
+                "mainpage": "Main Page",
+                "lang": "en",
+                "legaltitlechars": " %!\"$&'()*,\\-.\\/0-9:;=?@A-Z\\\\^_`a-z~\\x80-\\xFF+",
+            },
+            "namespaces": {
+                "-2": namespace(-2, "Media", "Media"),
+                "-1": namespace(-1, "Special", "Special"),
+                "0": namespace(0, "", ""),
+                "1": namespace(1, "Talk", "Talk"),
+                "2": namespace(2, "User", "User"),
+                "3": namespace(3, "User talk", "User talk"),
+                "4": namespace(4, "Wikipedia", "Project"),
+                "5": namespace(5, "Wikipedia talk", "Project talk"),
+                "6": namespace(6, "File", "File"),
+                "7": namespace(7, "File talk", "File talk"),
+                "10": namespace(10, "Template", "Template"),
+                "11": namespace(11, "Template talk", "Template talk"),
+                "14": namespace(14, "Category", "Category"),
+                "15": namespace(15, "Category talk", "Category talk"),
+            },
+            "namespacealiases": [
+                { "id": 4, "alias": "WP" },
+                { "id": 6, "alias": "Image" },
+            ],
+            "interwikimap": [
+                { "prefix": "de", "local": true, "language": "Deutsch", "url": "https://de.wikipedia.org/wiki/$1" },
+                { "prefix": "fr", "local": true, "language": "français", "url": "https://fr.wikipedia.org/wiki/$1" },
+            ],
+        },
+    })
+}
+
+/// The `TitleCodec` of `siteinfo`, for building titles before a provider exists.
+pub(crate) fn codec() -> TitleCodec {
+    let siteinfo: SiteInfoResponse = serde_json::from_value(siteinfo()).unwrap();
+    TitleCodec::from_site_info(siteinfo.query).unwrap()
+}
+
+impl RecordedBackend {
+    /// Serve this backend on a random local port, and return a client connected to it.
+    pub async fn serve(self) -> (HttpClient, ServerHandle) {
+        let server = Server::builder().build("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = server.start(self.into_rpc());
+        let client = HttpClientBuilder::default().build(format!("http://{addr}")).unwrap();
+        (client, handle)
+    }
+
+    fn lookup(&self, parameters: &HashMap<String, String>) -> Value {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.responses.iter()
+            .find(|(request, _)| request.iter().all(|(k, v)| parameters.get(k) == Some(v)))
+            .map(|(_, response)| response.clone())
+            .unwrap_or_else(|| panic!("no recorded response for {parameters:?}"))
+    }
+}
+
+#[async_trait]
+impl APIServiceInterfaceServer for RecordedBackend {
+    async fn health(&self) -> RpcResult<Vec<ConnectionHealth>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_site_info(&self, _key: &str) -> RpcResult<Value> {
+        Ok(self.siteinfo.clone())
+    }
+
+    async fn get_apihighlimits(&self, _key: &str) -> RpcResult<bool> {
+        Ok(self.apihighlimits)
+    }
+
+    async fn get_bot(&self, _key: &str) -> RpcResult<bool> {
+        Ok(false)
+    }
+
+    async fn get_value(&self, _key: &str, parameters: HashMap<String, String>) -> RpcResult<Value> {
+        Ok(self.lookup(&parameters))
+    }
+
+    async fn post_value(&self, _key: &str, parameters: HashMap<String, String>) -> RpcResult<Value> {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        if !self.delay.is_zero() {
+            tokio::time::sleep(self.delay).await;
+        }
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        Ok(self.lookup(&parameters))
+    }
+
+    async fn post_value_with_token(&self, _key: &str, _token_type: &str, parameters: HashMap<String, String>) -> RpcResult<Value> {
+        Ok(self.lookup(&parameters))
+    }
+}
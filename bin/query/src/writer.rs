@@ -1,53 +1,397 @@
+use crate::{FAILURE_INIT, FAILURE_PARSE, FAILURE_QUERY, FAILURE_SEMANTIC};
+use ast::Span;
+use clap::ValueEnum;
 use core::fmt::Display;
 use owo_colors::OwoColorize;
-use serde_json::json;
-use std::io::{self, Write};
+use serde_json::{json, Value};
+use std::{
+    ffi::OsString,
+    io::{self, stderr, Write},
+    process::ExitCode,
+};
 
-pub fn write_err<T: Display, W: Write>(item: T, mut writer: W, color: bool, json: bool) -> io::Result<()> {
-    if json {
-        writeln!(
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// One title per line, colored if a terminal is attached.
+    Plain,
+    /// One JSON object per line.
+    Json,
+    /// A table with a header row and columns `namespace,title`.
+    Csv,
+    /// A bulleted list of links, ready to be pasted into a wiki page.
+    Wikitext,
+    /// One JSON object per line, flushed as soon as it is written, and a summary line at the end.
+    Ndjson,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color if a terminal is attached, and `NO_COLOR` is not set.
+    Auto,
+    Always,
+    Never,
+}
+
+/// Decide whether to color the output. `no_color` is the value of the `NO_COLOR` environment variable,
+/// which disables color when it is set and not empty. See <https://no-color.org/>.
+pub fn use_color(choice: ColorChoice, is_terminal: bool, no_color: Option<OsString>) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => is_terminal && no_color.is_none_or(|v| v.is_empty()),
+    }
+}
+
+/// The kind of a failure. Each kind has its own exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The query cannot be read or parsed.
+    Parse,
+    /// The API Daemon cannot be reached, or the site cannot be set up.
+    Init,
+    /// The query is well-formed, but its attributes are not.
+    Semantic,
+    /// The query failed while running.
+    Query,
+}
+
+impl ErrorCode {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Parse => "parse",
+            Self::Init => "init",
+            Self::Semantic => "semantic",
+            Self::Query => "query",
+        }
+    }
+}
+
+impl From<ErrorCode> for ExitCode {
+    fn from(code: ErrorCode) -> Self {
+        ExitCode::from(match code {
+            ErrorCode::Parse => FAILURE_PARSE,
+            ErrorCode::Init => FAILURE_INIT,
+            ErrorCode::Semantic => FAILURE_SEMANTIC,
+            ErrorCode::Query => FAILURE_QUERY,
+        })
+    }
+}
+
+/// `{"code": .., "message": .., "span": {"offset": .., "length": ..}}`, where `span` is only present if known.
+fn error_envelope<T: Display>(item: &T, code: ErrorCode, span: Option<Span>) -> Value {
+    let mut envelope = json!({
+        "code": code.as_str(),
+        "message": item.to_string(),
+    });
+    if let Some(span) = span {
+        envelope["span"] = json!({ "offset": span.start, "length": span.end - span.start });
+    }
+    envelope
+}
+
+pub fn write_err<T: Display, W: Write>(item: T, code: ErrorCode, span: Option<Span>, mut writer: W, color: bool, format: Format) -> io::Result<()> {
+    match format {
+        Format::Json => writeln!(
             writer,
             "{}",
             json!({
                 "type": "error",
                 "content": item.to_string(),
+                "error": error_envelope(&item, code, span),
             })
-        )
-    } else if color {
-        writeln!(writer, "{}", format_args!("{}: {item}", "error".red()).bold())
-    } else {
-        writeln!(writer, "error: {item}")
+        ),
+        Format::Ndjson => {
+            writeln!(writer, "{}", json!({ "error": error_envelope(&item, code, span) }))?;
+            writer.flush()
+        },
+        // keep the table, or the list to be pasted, clean.
+        Format::Csv | Format::Wikitext => writeln!(stderr(), "error: {item}"),
+        Format::Plain if color => writeln!(writer, "{}", format_args!("{}: {item}", "error".red()).bold()),
+        Format::Plain => writeln!(writer, "error: {item}"),
     }
 }
 
-pub fn write_warn<T: Display, W: Write>(item: T, mut writer: W, color: bool, json: bool) -> io::Result<()> {
-    if json {
-        writeln!(
+pub fn write_warn<T: Display, W: Write>(item: T, mut writer: W, color: bool, format: Format) -> io::Result<()> {
+    match format {
+        Format::Json => writeln!(
             writer,
             "{}",
             json!({
                 "type": "warning",
                 "content": item.to_string(),
             })
-        )
-    } else if color {
-        writeln!(writer, "{}", format_args!("{}: {item}", "warning".yellow()).bold())
-    } else {
-        writeln!(writer, "warning: {item}")
+        ),
+        Format::Ndjson => {
+            writeln!(writer, "{}", json!({ "warning": item.to_string() }))?;
+            writer.flush()
+        },
+        // keep the table, or the list to be pasted, clean.
+        Format::Csv | Format::Wikitext => writeln!(stderr(), "warning: {item}"),
+        Format::Plain if color => writeln!(writer, "{}", format_args!("{}: {item}", "warning".yellow()).bold()),
+        Format::Plain => writeln!(writer, "warning: {item}"),
     }
 }
 
-pub fn write_item<T: Display, W: Write>(item: T, mut writer: W, json: bool) -> io::Result<()> {
-    if json {
-        writeln!(
+/// Whether a title was added to or removed from the results, when comparing against an earlier run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+}
+
+impl Change {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+        }
+    }
+}
+
+/// Write the header row, if the format has one, or else the user-supplied wikitext header.
+/// When comparing against an earlier run, the table has a `change` column first.
+pub fn write_header<W: Write>(mut writer: W, format: Format, wikitext_header: Option<&str>, diff: bool) -> io::Result<()> {
+    match (format, wikitext_header) {
+        (Format::Csv, _) if diff => writeln!(writer, "change,namespace,title"),
+        (Format::Csv, _) => writeln!(writer, "namespace,title"),
+        (Format::Wikitext, Some(header)) => writeln!(writer, "{header}"),
+        _ => Ok(()),
+    }
+}
+
+/// Write the user-supplied wikitext footer.
+pub fn write_footer<W: Write>(mut writer: W, format: Format, wikitext_footer: Option<&str>) -> io::Result<()> {
+    match (format, wikitext_footer) {
+        (Format::Wikitext, Some(footer)) => writeln!(writer, "{footer}"),
+        _ => Ok(()),
+    }
+}
+
+pub fn write_item<T: Display, W: Write>(item: T, namespace: i32, mut writer: W, format: Format) -> io::Result<()> {
+    match format {
+        Format::Json => writeln!(
             writer,
             "{}",
             json!({
                 "type": "item",
                 "content": item.to_string(),
             })
+        ),
+        Format::Ndjson => {
+            writeln!(writer, "{}", json!({ "title": item.to_string(), "namespace": namespace }))?;
+            writer.flush()
+        },
+        Format::Csv => writeln!(writer, "{namespace},{}", csv_field(&item.to_string())),
+        // a leading colon links to files and categories, instead of embedding or categorizing.
+        Format::Wikitext if namespace == 6 || namespace == 14 => writeln!(writer, "* [[:{item}]]"),
+        Format::Wikitext => writeln!(writer, "* [[{item}]]"),
+        Format::Plain => writeln!(writer, "{item}"),
+    }
+}
+
+/// Write a title added to or removed from the results, when comparing against an earlier run.
+pub fn write_change<T: Display, W: Write>(item: T, namespace: i32, change: Change, mut writer: W, color: bool, format: Format) -> io::Result<()> {
+    match format {
+        Format::Json => writeln!(
+            writer,
+            "{}",
+            json!({
+                "type": change.as_str(),
+                "content": item.to_string(),
+            })
+        ),
+        Format::Ndjson => {
+            writeln!(writer, "{}", json!({ "title": item.to_string(), "namespace": namespace, "change": change.as_str() }))?;
+            writer.flush()
+        },
+        Format::Csv => writeln!(writer, "{},{namespace},{}", change.as_str(), csv_field(&item.to_string())),
+        Format::Wikitext if namespace == 6 || namespace == 14 => writeln!(writer, "* [[:{item}]] ({})", change.as_str()),
+        Format::Wikitext => writeln!(writer, "* [[{item}]] ({})", change.as_str()),
+        Format::Plain if color => match change {
+            Change::Added => writeln!(writer, "{}", format_args!("+ {item}").green()),
+            Change::Removed => writeln!(writer, "{}", format_args!("- {item}").red()),
+        },
+        Format::Plain => match change {
+            Change::Added => writeln!(writer, "+ {item}"),
+            Change::Removed => writeln!(writer, "- {item}"),
+        },
+    }
+}
+
+/// Write the number of titles added and removed, and of warnings, if the format has a summary.
+pub fn write_diff_summary<W: Write>(added: usize, removed: usize, warnings: usize, mut writer: W, color: bool, format: Format) -> io::Result<()> {
+    match format {
+        Format::Ndjson => {
+            writeln!(writer, "{}", json!({ "added": added, "removed": removed, "warnings": warnings }))?;
+            writer.flush()
+        },
+        Format::Plain if color => writeln!(writer, "{}", format_args!("added: {added}, removed: {removed}, warning: {warnings}").bold()),
+        _ => Ok(()),
+    }
+}
+
+/// Write the number of items and warnings, if the format has a summary.
+pub fn write_summary<W: Write>(total: usize, warnings: usize, mut writer: W, color: bool, format: Format) -> io::Result<()> {
+    match format {
+        Format::Ndjson => {
+            writeln!(writer, "{}", json!({ "total": total, "warnings": warnings }))?;
+            writer.flush()
+        },
+        Format::Plain if color => writeln!(writer, "{}", format_args!("total: {total}, warning: {warnings}").bold()),
+        _ => Ok(()),
+    }
+}
+
+pub fn write_explain<T: Display, W: Write>(query: T, plan: &[String], chunk_size: usize, max_concurrency: usize, mut writer: W, format: Format) -> io::Result<()> {
+    if matches!(format, Format::Json | Format::Ndjson) {
+        writeln!(
+            writer,
+            "{}",
+            json!({
+                "type": "explain",
+                "query": query.to_string(),
+                "plan": plan,
+                "chunk_size": chunk_size,
+                "max_concurrency": max_concurrency,
+            })
         )
     } else {
-        writeln!(writer, "{item}")
+        writeln!(writer, "query: {query}")?;
+        writeln!(writer, "plan:")?;
+        for step in plan {
+            writeln!(writer, "    {step}")?;
+        }
+        writeln!(writer, "each step sends its input titles in requests of up to {chunk_size} titles.")?;
+        writeln!(writer, "page information is requested up to {max_concurrency} requests at a time.")
+    }
+}
+
+/// Quote a CSV field if it contains a comma, a quote or a line break, as in RFC 4180.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{use_color, write_change, write_diff_summary, write_err, ErrorCode, write_footer, write_header, write_item, write_summary, write_warn, Change, ColorChoice, Format};
+    use std::ffi::OsString;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_write_csv() {
+        let mut out = Vec::new();
+        write_header(&mut out, Format::Csv, None, false).unwrap();
+        write_item("Main Page", 0, &mut out, Format::Csv).unwrap();
+        write_item("Talk:Foo, Bar", 1, &mut out, Format::Csv).unwrap();
+        write_item("\"Weird\" Al Yankovic", 0, &mut out, Format::Csv).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "namespace,title\n0,Main Page\n1,\"Talk:Foo, Bar\"\n0,\"\"\"Weird\"\" Al Yankovic\"\n",
+        );
+
+        let mut out = Vec::new();
+        write_header(&mut out, Format::Plain, Some("{{Header}}"), false).unwrap();
+        write_item("Talk:Foo, Bar", 1, &mut out, Format::Plain).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "Talk:Foo, Bar\n");
+    }
+
+    #[test]
+    fn test_write_wikitext() {
+        let mut out = Vec::new();
+        write_header(&mut out, Format::Wikitext, Some("{{Header}}"), false).unwrap();
+        write_item("Main Page", 0, &mut out, Format::Wikitext).unwrap();
+        write_item("User talk:Example", 3, &mut out, Format::Wikitext).unwrap();
+        write_item("Category:Example", 14, &mut out, Format::Wikitext).unwrap();
+        write_item("File:Example.png", 6, &mut out, Format::Wikitext).unwrap();
+        write_footer(&mut out, Format::Wikitext, Some("{{Footer}}")).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{{Header}}\n* [[Main Page]]\n* [[User talk:Example]]\n* [[:Category:Example]]\n* [[:File:Example.png]]\n{{Footer}}\n",
+        );
+
+        let mut out = Vec::new();
+        write_header(&mut out, Format::Wikitext, None, false).unwrap();
+        write_item("Main Page", 0, &mut out, Format::Wikitext).unwrap();
+        // warnings and errors go to stderr, so the list can be pasted as is.
+        write_warn("result limit exceeded", &mut out, false, Format::Wikitext).unwrap();
+        write_err("timed out", ErrorCode::Query, None, &mut out, false, Format::Wikitext).unwrap();
+        write_footer(&mut out, Format::Wikitext, None).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "* [[Main Page]]\n");
+    }
+
+    #[test]
+    fn test_write_ndjson() {
+        let mut out = Vec::new();
+        write_header(&mut out, Format::Ndjson, None, false).unwrap();
+        write_item("Main Page", 0, &mut out, Format::Ndjson).unwrap();
+        write_warn("result limit 1 reached", &mut out, true, Format::Ndjson).unwrap();
+        write_item("Talk:\"Quoted\"", 1, &mut out, Format::Ndjson).unwrap();
+        write_summary(2, 1, &mut out, true, Format::Ndjson).unwrap();
+
+        let lines: Vec<Value> = String::from_utf8(out).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines, vec![
+            json!({ "title": "Main Page", "namespace": 0 }),
+            json!({ "warning": "result limit 1 reached" }),
+            json!({ "title": "Talk:\"Quoted\"", "namespace": 1 }),
+            json!({ "total": 2, "warnings": 1 }),
+        ]);
+    }
+
+    #[test]
+    fn test_write_change() {
+        let mut out = Vec::new();
+        write_header(&mut out, Format::Csv, None, true).unwrap();
+        write_change("Main Page", 0, Change::Added, &mut out, false, Format::Csv).unwrap();
+        write_change("Category:Foo, Bar", 14, Change::Removed, &mut out, false, Format::Csv).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "change,namespace,title\nadded,0,Main Page\nremoved,14,\"Category:Foo, Bar\"\n");
+
+        let mut out = Vec::new();
+        write_change("Main Page", 0, Change::Added, &mut out, false, Format::Plain).unwrap();
+        write_change("Category:Foo", 14, Change::Removed, &mut out, false, Format::Wikitext).unwrap();
+        write_diff_summary(1, 1, 0, &mut out, false, Format::Plain).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "+ Main Page\n* [[:Category:Foo]] (removed)\n");
+
+        let mut out = Vec::new();
+        write_change("Main Page", 0, Change::Removed, &mut out, false, Format::Ndjson).unwrap();
+        write_diff_summary(0, 1, 2, &mut out, false, Format::Ndjson).unwrap();
+        let lines: Vec<Value> = String::from_utf8(out).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines, vec![
+            json!({ "title": "Main Page", "namespace": 0, "change": "removed" }),
+            json!({ "added": 0, "removed": 1, "warnings": 2 }),
+        ]);
+    }
+
+    #[test]
+    fn test_no_color() {
+        let no_color = || Some(OsString::from("1"));
+        assert!(use_color(ColorChoice::Auto, true, None));
+        assert!(use_color(ColorChoice::Auto, true, Some(OsString::new())));
+        assert!(!use_color(ColorChoice::Auto, true, no_color()));
+        assert!(!use_color(ColorChoice::Auto, false, None));
+        assert!(use_color(ColorChoice::Always, false, no_color()));
+        assert!(!use_color(ColorChoice::Never, true, None));
+
+        let color = use_color(ColorChoice::Auto, true, no_color());
+        let mut out = Vec::new();
+        write_warn("result limit 1 reached", &mut out, color, Format::Plain).unwrap();
+        write_err("connection refused", ErrorCode::Init, None, &mut out, color, Format::Plain).unwrap();
+        write_summary(1, 1, &mut out, color, Format::Plain).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(!out.contains('\x1b'));
+        assert_eq!(out, "warning: result limit 1 reached\nerror: connection refused\n");
+
+        let mut out = Vec::new();
+        write_err("connection refused", ErrorCode::Init, None, &mut out, true, Format::Plain).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains('\x1b'));
     }
 }
@@ -1,10 +1,30 @@
 use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 
+/// The status of one connection held by the API Backend Service.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionHealth {
+    /// The site key in the configuration file.
+    pub key: String,
+    /// Whether the connection is logged in, or anonymous.
+    pub logged_in: bool,
+    /// The `bot` flag status.
+    pub bot: bool,
+    /// The `apihighlimits` flag status.
+    pub apihighlimits: bool,
+    /// When the connection was last set up, in seconds since the Unix epoch.
+    pub last_refresh: u64,
+}
+
 /// The service interface the API Backend Service provides.
 #[rpc(server, client)]
 pub trait APIServiceInterface {
+    /// Retrieve the status of all connections, sorted by key.
+    #[method(name = "health")]
+    async fn health(&self) -> RpcResult<Vec<ConnectionHealth>>;
+
     /// Retrieve the site info. This can be used to build a `mwtitle::TitleCodec`.
     #[method(name = "getSiteInfo")]
     async fn get_site_info(&self, key: &str) -> RpcResult<Value>;
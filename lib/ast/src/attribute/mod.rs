@@ -1,30 +1,48 @@
 //! Modifier attributes and filter attributes.
-//! Currently only modifier attributes are implemented.
 
-use core::hash::{Hash, Hasher};
+use core::{
+    fmt::{self, Display, Formatter},
+    hash::{Hash, Hasher},
+};
 use crate::{Span, expose_span};
+#[cfg(feature = "use_serde")]
+use serde::{Serialize, Deserialize};
 use crate::token::Dot;
 use crate::modifier::Modifier;
+use crate::filter::Filter;
 
 #[cfg(feature = "parse")]
 pub mod parse;
 
 #[non_exhaustive]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub enum Attribute {
     Modifier(AttributeModifier),
+    Filter(AttributeFilter),
 }
 
 impl Attribute {
     pub fn get_span(&self) -> Span {
         match self {
             Self::Modifier(x) => x.get_span(),
+            Self::Filter(x) => x.get_span(),
+        }
+    }
+}
+
+impl Display for Attribute {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Modifier(x) => write!(f, "{}{}", x.dot, x.modifier),
+            Self::Filter(x) => write!(f, "{}{}", x.dot, x.filter),
         }
     }
 }
 
 /// Attribute for modifiers.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct AttributeModifier {
     span: Span,
     pub dot: Dot,
@@ -38,4 +56,21 @@ impl Hash for AttributeModifier {
     }
 }
 
+/// Attribute for filters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub struct AttributeFilter {
+    span: Span,
+    pub dot: Dot,
+    pub filter: Filter,
+}
+
+impl Hash for AttributeFilter {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.dot.hash(state);
+        self.filter.hash(state);
+    }
+}
+
 expose_span!(AttributeModifier);
+expose_span!(AttributeFilter);
@@ -7,10 +7,11 @@ use crate::{
     parse_util::{whitespace, leading_whitespace},
     token::Dot,
     modifier::Modifier,
+    filter::Filter,
 };
 use super::{
     Attribute,
-    AttributeModifier,
+    AttributeModifier, AttributeFilter,
 };
 
 use nom::{
@@ -42,6 +43,7 @@ impl Attribute {
     {
         alt((
             map(AttributeModifier::parse_internal, Self::Modifier),
+            map(AttributeFilter::parse_internal, Self::Filter),
         ))(program)
     }
 }
@@ -78,29 +80,67 @@ impl AttributeModifier {
     }
 }
 
+impl AttributeFilter {
+    /// Parse the attribute from a raw piece of source text. Leading and trailing whitespaces are automatically removed.
+    pub fn parse<'a, E>(program: &'a str) -> Result<Self, E>
+    where
+        E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
+    {
+        let span = LocatedStr::new(program);
+        all_consuming(
+            whitespace(Self::parse_internal::<E>)
+        )(span).finish().map(|(_, x)| x)
+    }
+
+    /// Parse the attribute from a span. Assume no whitespaces before.
+    pub(crate) fn parse_internal<'a, E>(program: LocatedStr<'a>) -> IResult<LocatedStr<'a>, Self, E>
+    where
+        E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
+    {
+        let (residual, (pos_start, dot, filter, pos_end)) = tuple((
+            position,
+            Dot::parse_internal,
+            leading_whitespace(Filter::parse_internal),
+            position,
+        ))(program)?;
+        let attribute_filter = Self {
+            span: make_range(pos_start.location_offset(), pos_end.location_offset()),
+            dot,
+            filter,
+        };
+        Ok((residual, attribute_filter))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
         LocatedStr,
         modifier::Modifier,
+        filter::Filter,
     };
     use super::{
         Attribute,
-        AttributeModifier,
+        AttributeModifier, AttributeFilter,
     };
     use nom::error::Error;
 
     #[test]
     fn test_parse_attribute() {
         let input_1 = ".direct";
+        let input_2 = " .unprotected";
 
         let attr_1 = Attribute::parse::<Error<LocatedStr<'_>>>(input_1).unwrap();
+        let attr_2 = Attribute::parse::<Error<LocatedStr<'_>>>(input_2).unwrap();
 
         assert!(matches!(attr_1, Attribute::Modifier(_)));
+        assert!(matches!(attr_2, Attribute::Filter(_)));
 
         assert_eq!(&input_1[attr_1.get_span().to_range()], ".direct");
+        assert_eq!(&input_2[attr_2.get_span().to_range()], ".unprotected");
 
         assert_eq!(attr_1.get_span().start, 0);
+        assert_eq!(attr_2.get_span().start, 1);
     }
 
     #[test]
@@ -135,4 +175,22 @@ mod test {
         assert_eq!(attr_3.get_span().start, 0);
         assert_eq!(attr_4.get_span().start, 2);
     }
+
+    #[test]
+    fn test_parse_attribute_filter() {
+        let input_1 = ".protected";
+        let input_2 = " . unprotected () ";
+
+        let attr_1 = AttributeFilter::parse::<Error<LocatedStr<'_>>>(input_1).unwrap();
+        let attr_2 = AttributeFilter::parse::<Error<LocatedStr<'_>>>(input_2).unwrap();
+
+        assert!(matches!(attr_1.filter, Filter::Protected(_)));
+        assert!(matches!(attr_2.filter, Filter::Unprotected(_)));
+
+        assert_eq!(&input_1[attr_1.get_span().to_range()], ".protected");
+        assert_eq!(&input_2[attr_2.get_span().to_range()], ". unprotected ()");
+
+        assert_eq!(attr_1.get_span().start, 0);
+        assert_eq!(attr_2.get_span().start, 1);
+    }
 }
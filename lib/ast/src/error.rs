@@ -0,0 +1,116 @@
+//! A parse error that does not borrow the query, for reporting.
+
+use alloc::{format, string::String};
+use core::fmt::{self, Display, Formatter};
+use crate::{Expression, LocatedStr, Span, make_range, tokenize::tokenize};
+use core::num::ParseIntError;
+use nom::error::{ErrorKind, FromExternalError, ParseError as NomParseError, VerboseError, VerboseErrorKind};
+
+/// Why and where a query cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ParseError {
+    pub message: String,
+    /// The token where parsing failed, or an empty span at the end of the query.
+    pub span: Span,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl ParseError {
+    /// Convert nom's error for `program`, pointing at the location where the parser got the furthest.
+    pub fn from_verbose(program: &str, e: &VerboseError<LocatedStr<'_>>) -> Self {
+        // the first, ie. the innermost, of the errors at the same location.
+        let deepest = e.errors.iter().rev().max_by_key(|(input, _)| input.location_offset());
+        let (offset, kind) = match deepest {
+            Some((input, kind)) => (input.location_offset(), Some(kind)),
+            None => (program.len(), None),
+        };
+        let token = tokenize(&program[offset..]).first()
+            .map(|(_, span)| make_range(offset + span.start, offset + span.end));
+        let found = match token {
+            Some(span) => format!("`{}`", &program[span.to_range()]),
+            None => String::from("end of input"),
+        };
+        let message = match kind {
+            Some(VerboseErrorKind::Char(c)) => format!("expected `{c}`, found {found}"),
+            Some(VerboseErrorKind::Context(context)) => format!("expected {context}, found {found}"),
+            Some(VerboseErrorKind::Nom(_)) | None => format!("unexpected {found}"),
+        };
+        Self {
+            message,
+            span: token.unwrap_or(make_range(program.len(), program.len())),
+        }
+    }
+}
+
+/// The furthest location where an error occurred in a branch of the parser.
+/// `VerboseError` alone only remembers the last branch of an `alt`, which is usually not where the query is wrong.
+struct Deepest<'a>(VerboseError<LocatedStr<'a>>);
+
+impl Deepest<'_> {
+    fn offset(&self) -> usize {
+        self.0.errors.iter().map(|(input, _)| input.location_offset()).max().unwrap_or(0)
+    }
+}
+
+impl<'a> NomParseError<LocatedStr<'a>> for Deepest<'a> {
+    fn from_error_kind(input: LocatedStr<'a>, kind: ErrorKind) -> Self {
+        Self(VerboseError::from_error_kind(input, kind))
+    }
+
+    fn append(input: LocatedStr<'a>, kind: ErrorKind, other: Self) -> Self {
+        Self(VerboseError::append(input, kind, other.0))
+    }
+
+    fn from_char(input: LocatedStr<'a>, c: char) -> Self {
+        Self(VerboseError::from_char(input, c))
+    }
+
+    fn or(self, other: Self) -> Self {
+        if self.offset() > other.offset() { self } else { other }
+    }
+}
+
+impl<'a> FromExternalError<LocatedStr<'a>, ParseIntError> for Deepest<'a> {
+    fn from_external_error(input: LocatedStr<'a>, kind: ErrorKind, e: ParseIntError) -> Self {
+        Self(VerboseError::from_external_error(input, kind, e))
+    }
+}
+
+/// Parse the expression from a raw piece of source text, the same as `Expression::parse`, with an error that does
+/// not borrow `program`.
+pub fn parse(program: &str) -> Result<Expression, ParseError> {
+    Expression::parse::<Deepest<'_>>(program).map_err(|e| ParseError::from_verbose(program, &e.0))
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::string::ToString;
+    use crate::Span;
+    use super::parse;
+
+    #[test]
+    fn test_parse_error() {
+        assert!(parse(" link(\"A\") ").is_ok());
+
+        let e = parse("link(\"A\"").unwrap_err();
+        assert_eq!(e.span, Span::new(8, 8));
+        assert_eq!(e.to_string(), "unexpected end of input");
+
+        let e = parse("(link(\"A\") & \"B\"\n  + \"C\"").unwrap_err();
+        assert_eq!(e.span, Span::new(24, 24));
+
+        // a failing modifier ends the chain, so the error is the rest of the query.
+        let e = parse("link(\"A\").limit(x)").unwrap_err();
+        assert_eq!(e.span, Span::new(9, 10));
+        assert_eq!(e.to_string(), "unexpected `.`");
+
+        let e = parse("link(\"A\" \"B\") + \"C\"").unwrap_err();
+        assert_eq!(e.span, Span::new(9, 12));
+        assert_eq!(e.to_string(), "unexpected `\"B\"`");
+    }
+}
@@ -0,0 +1,76 @@
+//! Graphviz export for expressions.
+
+use alloc::string::{String, ToString};
+use core::fmt::Write;
+use crate::attribute::Attribute;
+use super::Expression;
+
+impl Expression {
+    /// Write the expression tree as a Graphviz digraph, one node per expression, with edges from each node to its children.
+    ///
+    /// Shapes tell the kinds apart: set operations are circles, category operations are folders, page lists are notes,
+    /// parentheses are plain text, and the other operations are boxes. Attributes are listed under the keyword.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph {\n");
+        let mut next_id = 0;
+        self.write_dot(&mut out, &mut next_id);
+        out.push_str("}\n");
+        out
+    }
+
+    /// Write this node and its descendants, and return the id of this node.
+    fn write_dot(&self, out: &mut String, next_id: &mut usize) -> usize {
+        let id = *next_id;
+        *next_id += 1;
+        let (label, shape, children): (String, &str, [Option<&Expression>; 2]) = match self {
+            Self::And(expr) => (expr.and.to_string(), "circle", [Some(&expr.expr1), Some(&expr.expr2)]),
+            Self::Add(expr) => (expr.add.to_string(), "circle", [Some(&expr.expr1), Some(&expr.expr2)]),
+            Self::Sub(expr) => (expr.sub.to_string(), "circle", [Some(&expr.expr1), Some(&expr.expr2)]),
+            Self::Xor(expr) => (expr.xor.to_string(), "circle", [Some(&expr.expr1), Some(&expr.expr2)]),
+            Self::Paren(expr) => (String::from("( )"), "plaintext", [Some(&expr.expr), None]),
+            Self::Page(_) | Self::PageId(_) => (self.to_string(), "note", [None, None]),
+            Self::Link(expr) => (unary_label(&expr.link, &expr.attributes), "box", [Some(&expr.expr), None]),
+            Self::LinkTo(expr) => (unary_label(&expr.linkto, &expr.attributes), "box", [Some(&expr.expr), None]),
+            Self::Embed(expr) => (unary_label(&expr.embed, &expr.attributes), "box", [Some(&expr.expr), None]),
+            Self::InCat(expr) => (unary_label(&expr.incat, &expr.attributes), "folder", [Some(&expr.expr), None]),
+            Self::Prefix(expr) => (unary_label(&expr.prefix, &expr.attributes), "box", [Some(&expr.expr), None]),
+            Self::Toggle(expr) => (unary_label(&expr.toggle, &[]), "box", [Some(&expr.expr), None]),
+            Self::TemplatesIn(expr) => (unary_label(&expr.templatesin, &expr.attributes), "box", [Some(&expr.expr), None]),
+            Self::Files(expr) => (unary_label(&expr.files, &expr.attributes), "box", [Some(&expr.expr), None]),
+            Self::FileUsage(expr) => (unary_label(&expr.fileusage, &expr.attributes), "box", [Some(&expr.expr), None]),
+            Self::CategoriesOf(expr) => (unary_label(&expr.categoriesof, &expr.attributes), "folder", [Some(&expr.expr), None]),
+            Self::LangLinks(expr) => (unary_label(&expr.langlinks, &expr.attributes), "box", [Some(&expr.expr), None]),
+            Self::Not(expr) => (unary_label(&expr.not, &[]), "box", [Some(&expr.expr), expr.universe.as_deref()]),
+        };
+        // writing into a `String` never fails.
+        let _ = writeln!(out, "    n{id} [label=\"{}\", shape={shape}];", escape(&label));
+        for child in children.into_iter().flatten() {
+            let child_id = child.write_dot(out, next_id);
+            let _ = writeln!(out, "    n{id} -> n{child_id};");
+        }
+        id
+    }
+}
+
+/// The keyword, followed by one attribute per line.
+fn unary_label(keyword: &dyn core::fmt::Display, attributes: &[Attribute]) -> String {
+    let mut label = keyword.to_string();
+    for attr in attributes {
+        let _ = write!(label, "\n{attr}");
+    }
+    label
+}
+
+/// Escape a label for a double-quoted DOT string.
+fn escape(label: &str) -> String {
+    let mut escaped = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -4,20 +4,31 @@ use alloc::{
     boxed::Box,
     vec::Vec,
 };
-use core::hash::{Hash, Hasher};
+use core::{
+    fmt::{self, Display, Formatter},
+    hash::{Hash, Hasher},
+};
 use crate::{Span, expose_span};
+#[cfg(feature = "use_serde")]
+use serde::{Serialize, Deserialize};
 use crate::attribute::Attribute;
-use crate::literal::LitString;
+use crate::literal::{LitInt, LitString};
+use crate::semantic_eq::SemanticEq;
+use crate::visit::{Visitor, walk_expression};
 use crate::token::{
     And, Add, Sub, Caret, LeftParen, RightParen, Comma,
-    Page, Link, LinkTo, Embed, InCat, Prefix, Toggle,
+    Page, Link, LinkTo, Embed, InCat, Prefix, Toggle, TemplatesIn, Files, FileUsage, CategoriesOf, LangLinks, Not, PageId,
 };
 
+mod dot;
 #[cfg(feature = "parse")]
 pub mod parse;
+#[cfg(feature = "parse")]
+pub mod recover;
 
 #[non_exhaustive]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub enum Expression {
     And(ExpressionAnd),
     Add(ExpressionAdd),
@@ -25,12 +36,19 @@ pub enum Expression {
     Xor(ExpressionXor),
     Paren(ExpressionParen),
     Page(ExpressionPage),
+    PageId(ExpressionPageId),
     Link(ExpressionLink),
     LinkTo(ExpressionLinkTo),
     Embed(ExpressionEmbed),
     InCat(ExpressionInCat),
     Prefix(ExpressionPrefix),
     Toggle(ExpressionToggle),
+    TemplatesIn(ExpressionTemplatesIn),
+    Files(ExpressionFiles),
+    FileUsage(ExpressionFileUsage),
+    CategoriesOf(ExpressionCategoriesOf),
+    LangLinks(ExpressionLangLinks),
+    Not(ExpressionNot),
 }
 
 impl Expression {
@@ -43,12 +61,157 @@ impl Expression {
             Self::Xor(expr) => expr.get_span(),
             Self::Paren(expr) => expr.get_span(),
             Self::Page(expr) => expr.get_span(),
+            Self::PageId(expr) => expr.get_span(),
             Self::Link(expr) => expr.get_span(),
             Self::LinkTo(expr) => expr.get_span(),
             Self::Embed(expr) => expr.get_span(),
             Self::InCat(expr) => expr.get_span(),
             Self::Prefix(expr) => expr.get_span(),
             Self::Toggle(expr) => expr.get_span(),
+            Self::TemplatesIn(expr) => expr.get_span(),
+            Self::Files(expr) => expr.get_span(),
+            Self::FileUsage(expr) => expr.get_span(),
+            Self::CategoriesOf(expr) => expr.get_span(),
+            Self::LangLinks(expr) => expr.get_span(),
+            Self::Not(expr) => expr.get_span(),
+        }
+    }
+
+    /// Compare the structure and literal values of two expressions, ignoring spans.
+    /// Purely syntactic differences are ignored too, eg. `"A"` equals `page("A")`, and `resolve` equals `resolve()`.
+    pub fn semantic_eq(&self, other: &Expression) -> bool {
+        SemanticEq::semantic_eq(self, other)
+    }
+
+    /// Get the titles written in all `page` nodes, in source order. Duplicates are kept.
+    pub fn page_literals(&self) -> Vec<&str> {
+        struct Collector<'ast>(Vec<&'ast str>);
+        impl<'ast> Visitor<'ast> for Collector<'ast> {
+            fn visit_page(&mut self, expr: &'ast ExpressionPage) {
+                self.0.extend(expr.vals.iter().map(|lit| lit.val.as_str()));
+            }
+        }
+        let mut collector = Collector(Vec::new());
+        collector.visit_expression(self);
+        collector.0
+    }
+
+    /// Count the expression nodes in this tree, including operators and parentheses.
+    pub fn node_count(&self) -> usize {
+        self.measure().0
+    }
+
+    /// Get the number of expression nodes on the longest path from this node down to a leaf.
+    /// A lone `page` has depth 1.
+    pub fn depth(&self) -> usize {
+        self.measure().1
+    }
+
+    /// Get the node count and the depth in one walk.
+    fn measure(&self) -> (usize, usize) {
+        #[derive(Default)]
+        struct Measure {
+            count: usize,
+            current: usize,
+            max: usize,
+        }
+        impl<'ast> Visitor<'ast> for Measure {
+            fn visit_expression(&mut self, expr: &'ast Expression) {
+                self.count += 1;
+                self.current += 1;
+                self.max = self.max.max(self.current);
+                walk_expression(self, expr);
+                self.current -= 1;
+            }
+        }
+        let mut measure = Measure::default();
+        measure.visit_expression(self);
+        (measure.count, measure.max)
+    }
+
+    /// Get the precedence level of this expression, matching the `parse_internal_level_*` functions.
+    /// Parentheses are transparent, since they are only written where the precedence requires.
+    fn level(&self) -> u8 {
+        match self {
+            Self::Add(_) | Self::Sub(_) => 1,
+            Self::Xor(_) => 2,
+            Self::And(_) => 3,
+            Self::Paren(expr) => expr.expr.level(),
+            _ => 4,
+        }
+    }
+
+    /// Write `expr`, parenthesized if it binds looser than `level`.
+    fn fmt_operand(f: &mut Formatter<'_>, expr: &Expression, level: u8) -> fmt::Result {
+        if expr.level() < level {
+            write!(f, "({expr})")
+        } else {
+            write!(f, "{expr}")
+        }
+    }
+
+    /// Write a binary operation. The operators are left-associative, so the right operand needs parentheses on a tie.
+    fn fmt_binary(f: &mut Formatter<'_>, expr1: &Expression, op: &dyn Display, expr2: &Expression, level: u8) -> fmt::Result {
+        Self::fmt_operand(f, expr1, level)?;
+        write!(f, " {op} ")?;
+        Self::fmt_operand(f, expr2, level + 1)
+    }
+
+    /// Write a unary operation, followed by its attributes.
+    fn fmt_unary(f: &mut Formatter<'_>, op: &dyn Display, expr: &Expression, attributes: &[Attribute]) -> fmt::Result {
+        write!(f, "{op}({expr})")?;
+        for attr in attributes {
+            write!(f, "{attr}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Write the expression in canonical form: keywords in lower case, pages always inside `page(...)`,
+/// single spaces around operators and after commas, and only the parentheses the precedence requires.
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::And(expr) => Self::fmt_binary(f, &expr.expr1, &expr.and, &expr.expr2, 3),
+            Self::Add(expr) => Self::fmt_binary(f, &expr.expr1, &expr.add, &expr.expr2, 1),
+            Self::Sub(expr) => Self::fmt_binary(f, &expr.expr1, &expr.sub, &expr.expr2, 1),
+            Self::Xor(expr) => Self::fmt_binary(f, &expr.expr1, &expr.xor, &expr.expr2, 2),
+            Self::Paren(expr) => write!(f, "{}", expr.expr),
+            Self::Page(expr) => {
+                f.write_str("page(")?;
+                for (i, val) in expr.vals.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{val}")?;
+                }
+                f.write_str(")")
+            },
+            Self::PageId(expr) => {
+                write!(f, "{}(", expr.pageid)?;
+                for (i, val) in expr.vals.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{val}")?;
+                }
+                f.write_str(")")
+            },
+            Self::Link(expr) => Self::fmt_unary(f, &expr.link, &expr.expr, &expr.attributes),
+            Self::LinkTo(expr) => Self::fmt_unary(f, &expr.linkto, &expr.expr, &expr.attributes),
+            Self::Embed(expr) => Self::fmt_unary(f, &expr.embed, &expr.expr, &expr.attributes),
+            Self::InCat(expr) => Self::fmt_unary(f, &expr.incat, &expr.expr, &expr.attributes),
+            Self::Prefix(expr) => Self::fmt_unary(f, &expr.prefix, &expr.expr, &expr.attributes),
+            Self::Toggle(expr) => Self::fmt_unary(f, &expr.toggle, &expr.expr, &[]),
+            Self::TemplatesIn(expr) => Self::fmt_unary(f, &expr.templatesin, &expr.expr, &expr.attributes),
+            Self::Files(expr) => Self::fmt_unary(f, &expr.files, &expr.expr, &expr.attributes),
+            Self::FileUsage(expr) => Self::fmt_unary(f, &expr.fileusage, &expr.expr, &expr.attributes),
+            Self::CategoriesOf(expr) => Self::fmt_unary(f, &expr.categoriesof, &expr.expr, &expr.attributes),
+            Self::LangLinks(expr) => Self::fmt_unary(f, &expr.langlinks, &expr.expr, &expr.attributes),
+            Self::Not(expr) => match &expr.universe {
+                Some(universe) => write!(f, "{}({}, {})", expr.not, expr.expr, universe),
+                None => Self::fmt_unary(f, &expr.not, &expr.expr, &[]),
+            },
         }
     }
 }
@@ -56,6 +219,7 @@ impl Expression {
 /// Set operation and
 /// `<expr> & <expr>
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ExpressionAnd {
     span: Span,
     pub expr1: Box<Expression>,
@@ -74,6 +238,7 @@ impl Hash for ExpressionAnd {
 /// Set operation add
 /// `<expr> + <expr>`
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ExpressionAdd {
     span: Span,
     pub expr1: Box<Expression>,
@@ -92,6 +257,7 @@ impl Hash for ExpressionAdd {
 /// Set operation sub
 /// `<expr> - <expr>`
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ExpressionSub {
     span: Span,
     pub expr1: Box<Expression>,
@@ -110,6 +276,7 @@ impl Hash for ExpressionSub {
 /// Set operation xor
 /// `<expr> ^ <expr>`
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ExpressionXor {
     span: Span,
     pub expr1: Box<Expression>,
@@ -126,6 +293,7 @@ impl Hash for ExpressionXor {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ExpressionParen {
     span: Span,
     pub lparen: LeftParen,
@@ -145,6 +313,7 @@ impl Hash for ExpressionParen {
 /// `page("...","...")`
 /// `"...","..."`
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ExpressionPage {
     span: Span,
     pub page: Option<Page>,
@@ -156,14 +325,35 @@ pub struct ExpressionPage {
 
 impl Hash for ExpressionPage {
     fn hash<H: Hasher>(&self, state: &mut H) {
+        // a trailing comma changes nothing.
+        self.vals.hash(state);
+    }
+}
+
+/// Page ids to be resolved into pages. Unlike titles, ids do not change when a page is moved.
+/// `pageid(<int>, <int>, ...)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub struct ExpressionPageId {
+    span: Span,
+    pub pageid: PageId,
+    pub lparen: LeftParen,
+    pub vals: Vec<LitInt>,
+    pub commas: Vec<Comma>,
+    pub rparen: RightParen,
+}
+
+impl Hash for ExpressionPageId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.pageid.hash(state);
         self.vals.hash(state);
-        self.commas.hash(state);
     }
 }
 
 /// Composite operation link
 /// `link(<expr>)<attributes>
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ExpressionLink {
     span: Span,
     pub link: Link,
@@ -186,6 +376,7 @@ impl Hash for ExpressionLink {
 /// Composite operation linkto
 /// `linkto(<expr>)<attributes>
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ExpressionLinkTo {
     span: Span,
     pub linkto: LinkTo,
@@ -208,6 +399,7 @@ impl Hash for ExpressionLinkTo {
 /// Composite operation embed
 /// `embed(<expr>)<attributes>
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ExpressionEmbed {
     span: Span,
     pub embed: Embed,
@@ -230,6 +422,7 @@ impl Hash for ExpressionEmbed {
 /// Composite operation incat
 /// `incat(<expr>)<attributes>
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ExpressionInCat {
     span: Span,
     pub incat: InCat,
@@ -252,6 +445,7 @@ impl Hash for ExpressionInCat {
 /// Composite operation prefix
 /// `prefix(<expr>)<attributes>
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ExpressionPrefix {
     span: Span,
     pub prefix: Prefix,
@@ -274,6 +468,7 @@ impl Hash for ExpressionPrefix {
 /// Composite operation toggle
 /// `toggle(<expr>)
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ExpressionToggle {
     span: Span,
     pub toggle: Toggle,
@@ -291,15 +486,163 @@ impl Hash for ExpressionToggle {
     }
 }
 
+/// Composite operation templatesin
+/// `templatesin(<expr>)<attributes>
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub struct ExpressionTemplatesIn {
+    span: Span,
+    pub templatesin: TemplatesIn,
+    pub lparen: LeftParen,
+    pub expr: Box<Expression>,
+    pub rparen: RightParen,
+    pub attributes: Vec<Attribute>,
+}
+
+impl Hash for ExpressionTemplatesIn {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.templatesin.hash(state);
+        self.lparen.hash(state);
+        self.expr.hash(state);
+        self.rparen.hash(state);
+        self.attributes.hash(state);
+    }
+}
+
+/// Composite operation files
+/// `files(<expr>)<attributes>
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub struct ExpressionFiles {
+    span: Span,
+    pub files: Files,
+    pub lparen: LeftParen,
+    pub expr: Box<Expression>,
+    pub rparen: RightParen,
+    pub attributes: Vec<Attribute>,
+}
+
+impl Hash for ExpressionFiles {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.files.hash(state);
+        self.lparen.hash(state);
+        self.expr.hash(state);
+        self.rparen.hash(state);
+        self.attributes.hash(state);
+    }
+}
+
+/// Composite operation fileusage
+/// `fileusage(<expr>)<attributes>
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub struct ExpressionFileUsage {
+    span: Span,
+    pub fileusage: FileUsage,
+    pub lparen: LeftParen,
+    pub expr: Box<Expression>,
+    pub rparen: RightParen,
+    pub attributes: Vec<Attribute>,
+}
+
+impl Hash for ExpressionFileUsage {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.fileusage.hash(state);
+        self.lparen.hash(state);
+        self.expr.hash(state);
+        self.rparen.hash(state);
+        self.attributes.hash(state);
+    }
+}
+
+/// Composite operation categoriesof
+/// `categoriesof(<expr>)<attributes>
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub struct ExpressionCategoriesOf {
+    span: Span,
+    pub categoriesof: CategoriesOf,
+    pub lparen: LeftParen,
+    pub expr: Box<Expression>,
+    pub rparen: RightParen,
+    pub attributes: Vec<Attribute>,
+}
+
+impl Hash for ExpressionCategoriesOf {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.categoriesof.hash(state);
+        self.lparen.hash(state);
+        self.expr.hash(state);
+        self.rparen.hash(state);
+        self.attributes.hash(state);
+    }
+}
+
+/// Composite operation langlinks
+/// `langlinks(<expr>)<attributes>
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub struct ExpressionLangLinks {
+    span: Span,
+    pub langlinks: LangLinks,
+    pub lparen: LeftParen,
+    pub expr: Box<Expression>,
+    pub rparen: RightParen,
+    pub attributes: Vec<Attribute>,
+}
+
+impl Hash for ExpressionLangLinks {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.langlinks.hash(state);
+        self.lparen.hash(state);
+        self.expr.hash(state);
+        self.rparen.hash(state);
+        self.attributes.hash(state);
+    }
+}
+
+/// Complement operation not, relative to the universe given as the second operand.
+/// Without one, it is only meaningful as an operand of `&`, where the other operand is the universe.
+/// `not(<expr>)`, `not(<expr>, <universe>)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub struct ExpressionNot {
+    span: Span,
+    pub not: Not,
+    pub lparen: LeftParen,
+    pub expr: Box<Expression>,
+    pub comma: Option<Comma>,
+    pub universe: Option<Box<Expression>>,
+    pub rparen: RightParen,
+}
+
+impl Hash for ExpressionNot {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.not.hash(state);
+        self.lparen.hash(state);
+        self.expr.hash(state);
+        self.comma.hash(state);
+        self.universe.hash(state);
+        self.rparen.hash(state);
+    }
+}
+
 expose_span!(ExpressionAdd);
 expose_span!(ExpressionAnd);
 expose_span!(ExpressionSub);
 expose_span!(ExpressionXor);
 expose_span!(ExpressionParen);
 expose_span!(ExpressionPage);
+expose_span!(ExpressionPageId);
 expose_span!(ExpressionLink);
 expose_span!(ExpressionLinkTo);
 expose_span!(ExpressionEmbed);
 expose_span!(ExpressionInCat);
 expose_span!(ExpressionPrefix);
 expose_span!(ExpressionToggle);
+expose_span!(ExpressionTemplatesIn);
+expose_span!(ExpressionFiles);
+expose_span!(ExpressionFileUsage);
+expose_span!(ExpressionCategoriesOf);
+expose_span!(ExpressionLangLinks);
+expose_span!(ExpressionNot);
@@ -1,30 +1,31 @@
 //! Parsing module for expressions.
 
-use alloc::boxed::Box;
+use alloc::{boxed::Box, vec::Vec};
 use core::num::ParseIntError;
 use crate::{
     LocatedStr,
     make_range,
     attribute::Attribute,
-    literal::LitString,
+    literal::{LitInt, LitString},
     parse_util::{whitespace, leading_whitespace, alternating1},
     token::{
         Add, And, Caret, Sub, LeftParen, RightParen, Comma,
-        Page, Link, LinkTo, Embed, InCat, Prefix, Toggle,
+        Page, Link, LinkTo, Embed, InCat, Prefix, Toggle, TemplatesIn, Files, FileUsage, CategoriesOf, LangLinks, Not, PageId,
     }
 };
 use super::{
     Expression,
     ExpressionAnd, ExpressionAdd, ExpressionSub, ExpressionXor,
     ExpressionParen,
-    ExpressionPage, ExpressionLink, ExpressionLinkTo, ExpressionEmbed, ExpressionInCat, ExpressionPrefix, ExpressionToggle,
+    ExpressionPage, ExpressionLink, ExpressionLinkTo, ExpressionEmbed, ExpressionInCat, ExpressionPrefix, ExpressionToggle, ExpressionTemplatesIn, ExpressionFiles, ExpressionFileUsage, ExpressionCategoriesOf, ExpressionLangLinks,
+    ExpressionNot, ExpressionPageId,
 };
 
 use nom::{
     IResult,
     Finish,
     branch::alt,
-    combinator::{all_consuming, map},
+    combinator::{all_consuming, map, opt},
     error::{ParseError, FromExternalError},
     multi::many0,
     sequence::tuple,
@@ -50,7 +51,7 @@ impl Expression {
 
     /// Parse a level-1 expression. Level 1 has the lowest priority, and sits at the top of the AST.
     /// `ExpressionAdd` and `ExpressionSub` sit at this level.
-    fn parse_internal_level_1<'a, E>(program: LocatedStr<'a>) -> IResult<LocatedStr<'a>, Self, E>
+    pub(super) fn parse_internal_level_1<'a, E>(program: LocatedStr<'a>) -> IResult<LocatedStr<'a>, Self, E>
     where
         E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
     {
@@ -154,6 +155,7 @@ impl Expression {
     {
         alt((
             map(ExpressionParen::parse_internal, Expression::Paren),
+            map(ExpressionPageId::parse_internal, Expression::PageId),
             map(ExpressionPage::parse_internal, Expression::Page),
             map(ExpressionLink::parse_internal, Expression::Link),
             map(ExpressionLinkTo::parse_internal, Expression::LinkTo),
@@ -161,6 +163,12 @@ impl Expression {
             map(ExpressionInCat::parse_internal, Expression::InCat),
             map(ExpressionPrefix::parse_internal, Expression::Prefix),
             map(ExpressionToggle::parse_internal, Expression::Toggle),
+            map(ExpressionTemplatesIn::parse_internal, Expression::TemplatesIn),
+            map(ExpressionFiles::parse_internal, Expression::Files),
+            map(ExpressionFileUsage::parse_internal, Expression::FileUsage),
+            map(ExpressionCategoriesOf::parse_internal, Expression::CategoriesOf),
+            map(ExpressionLangLinks::parse_internal, Expression::LangLinks),
+            map(ExpressionNot::parse_internal, Expression::Not),
         ))(program)
     }
 }
@@ -223,18 +231,22 @@ impl ExpressionPage {
     }
 
     /// Parse the expression with the first style.
+    /// Without parentheses, a trailing comma is left for the enclosing expression, eg. `not("A", link("B"))`.
     fn parse_internal_style_1<'a, E>(program: LocatedStr<'a>) -> IResult<LocatedStr<'a>, Self, E>
     where
         E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
     {
-        let (residual, (pos_start, (vals, commas), pos_end)) = tuple((
+        let (residual, (pos_start, first, rest, pos_end)) = tuple((
             position,
-            alternating1(
+            leading_whitespace(LitString::parse_internal),
+            many0(tuple((
                 leading_whitespace(Comma::parse_internal),
                 leading_whitespace(LitString::parse_internal),
-            ),
+            ))),
             position,
         ))(program)?;
+        let (commas, rest): (Vec<_>, Vec<_>) = rest.into_iter().unzip();
+        let vals = core::iter::once(first).chain(rest).collect();
         let expression_page = Self {
             span: make_range(pos_start.location_offset(), pos_end.location_offset()),
             page: None,
@@ -274,6 +286,46 @@ impl ExpressionPage {
     }
 }
 
+impl ExpressionPageId {
+    /// Parse the expression from a raw piece of source text. Leading and trailing whitespaces are automatically removed.
+    pub fn parse<'a, E>(program: &'a str) -> Result<Self, E>
+    where
+        E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
+    {
+        let span = LocatedStr::new(program);
+        all_consuming(
+            whitespace(Self::parse_internal::<E>)
+        )(span).finish().map(|(_, x)| x)
+    }
+
+    /// Parse the expression from a span. Assume no whitespaces before.
+    pub(crate) fn parse_internal<'a, E>(program: LocatedStr<'a>) -> IResult<LocatedStr<'a>, Self, E>
+    where
+        E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
+    {
+        let (residual, (pos_start, pageid, lparen, (vals, commas), rparen, pos_end)) = tuple((
+            position,
+            PageId::parse_internal,
+            leading_whitespace(LeftParen::parse_internal),
+            alternating1(
+                leading_whitespace(Comma::parse_internal),
+                leading_whitespace(LitInt::parse_internal),
+            ),
+            leading_whitespace(RightParen::parse_internal),
+            position,
+        ))(program)?;
+        let expression_pageid = Self {
+            span: make_range(pos_start.location_offset(), pos_end.location_offset()),
+            pageid,
+            lparen,
+            vals,
+            commas,
+            rparen,
+        };
+        Ok((residual, expression_pageid))
+    }
+}
+
 macro_rules! unary_operation_make_parser {
     ($name:ident, $token_field:ident, $token:ident) => {
         impl $name {
@@ -323,6 +375,11 @@ unary_operation_make_parser!(ExpressionLinkTo, linkto, LinkTo);
 unary_operation_make_parser!(ExpressionEmbed, embed, Embed);
 unary_operation_make_parser!(ExpressionInCat, incat, InCat);
 unary_operation_make_parser!(ExpressionPrefix, prefix, Prefix);
+unary_operation_make_parser!(ExpressionTemplatesIn, templatesin, TemplatesIn);
+unary_operation_make_parser!(ExpressionFiles, files, Files);
+unary_operation_make_parser!(ExpressionFileUsage, fileusage, FileUsage);
+unary_operation_make_parser!(ExpressionCategoriesOf, categoriesof, CategoriesOf);
+unary_operation_make_parser!(ExpressionLangLinks, langlinks, LangLinks);
 
 impl ExpressionToggle {
     /// Parse the expression from a raw piece of source text. Leading and trailing whitespaces are automatically removed.
@@ -360,13 +417,60 @@ impl ExpressionToggle {
     }
 }
 
+impl ExpressionNot {
+    /// Parse the expression from a raw piece of source text. Leading and trailing whitespaces are automatically removed.
+    pub fn parse<'a, E>(program: &'a str) -> Result<Self, E>
+    where
+        E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
+    {
+        let span = LocatedStr::new(program);
+        all_consuming(
+            whitespace(Self::parse_internal::<E>)
+        )(span).finish().map(|(_, x)| x)
+    }
+
+    /// Parse the expression from a span. Assume no whitespaces before.
+    pub(crate) fn parse_internal<'a, E>(program: LocatedStr<'a>) -> IResult<LocatedStr<'a>, Self, E>
+    where
+        E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
+    {
+        let (residual, (pos_start, not, lparen, expr, opt_universe, rparen, pos_end)) = tuple((
+            position,
+            Not::parse_internal,
+            leading_whitespace(LeftParen::parse_internal),
+            leading_whitespace(Expression::parse_internal_level_1),
+            opt(tuple((
+                leading_whitespace(Comma::parse_internal),
+                leading_whitespace(Expression::parse_internal_level_1),
+            ))),
+            leading_whitespace(RightParen::parse_internal),
+            position,
+        ))(program)?;
+        let (comma, universe) = match opt_universe {
+            Some((comma, universe)) => (Some(comma), Some(Box::new(universe))),
+            None => (None, None),
+        };
+        let expression_not = Self {
+            span: make_range(pos_start.location_offset(), pos_end.location_offset()),
+            not,
+            lparen,
+            expr: Box::new(expr),
+            comma,
+            universe,
+            rparen,
+        };
+        Ok((residual, expression_not))
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use alloc::format;
+    use alloc::{format, string::ToString, vec};
     use crate::LocatedStr;
     use super::{
         Expression,
-        ExpressionPage, ExpressionLink, ExpressionLinkTo, ExpressionEmbed, ExpressionInCat, ExpressionPrefix, ExpressionToggle,
+        ExpressionPage, ExpressionLink, ExpressionLinkTo, ExpressionEmbed, ExpressionInCat, ExpressionPrefix, ExpressionToggle, ExpressionTemplatesIn, ExpressionFiles, ExpressionFileUsage, ExpressionCategoriesOf, ExpressionLangLinks, ExpressionNot,
+        ExpressionPageId,
     };
     use nom::error::Error;
 
@@ -403,6 +507,178 @@ mod test {
         assert!(matches!(exp_9, Expression::And(_)));
     }
 
+    #[test]
+    fn test_parse_expression_comment() {
+        let input_1 = "# leading\n \"A\" + \"b\" # trailing";
+        let input_2 = "\"A\"# minus\n-\"B\"";
+        let input_3 = "  \"A\" ^ # xor\n # more\n \"B\"";
+        let input_4 = "\"A\"&\"B\" #";
+        let input_5 = "( # open\n\"A\" # close\n)";
+        let input_6 = "\"A\"+\"B\"#\n-\"C\"";
+        let input_7 = "\"A\"+\"B\"^\r\n# windows\r\n\"c\"";
+        let input_8 = "\"A\"^\"B\"&\"c\"#&\"d\"";
+        let input_9 = "(\"A\" ^ \"B\" + \"C\") # group one\n& ( # group two\n(\"D\" - \"E\") &\"F\")";
+        let input_10 = "linkto(page(\"A # B\", # first\n \"C\")) . # dot\n limit(# inner\n 5)";
+
+        let exp_1 = Expression::parse::<Error<LocatedStr<'_>>>(input_1).unwrap();
+        let exp_2 = Expression::parse::<Error<LocatedStr<'_>>>(input_2).unwrap();
+        let exp_3 = Expression::parse::<Error<LocatedStr<'_>>>(input_3).unwrap();
+        let exp_4 = Expression::parse::<Error<LocatedStr<'_>>>(input_4).unwrap();
+        let exp_5 = Expression::parse::<Error<LocatedStr<'_>>>(input_5).unwrap();
+        let exp_6 = Expression::parse::<Error<LocatedStr<'_>>>(input_6).unwrap();
+        let exp_7 = Expression::parse::<Error<LocatedStr<'_>>>(input_7).unwrap();
+        let exp_8 = Expression::parse::<Error<LocatedStr<'_>>>(input_8).unwrap();
+        let exp_9 = Expression::parse::<Error<LocatedStr<'_>>>(input_9).unwrap();
+        let exp_10 = Expression::parse::<Error<LocatedStr<'_>>>(input_10).unwrap();
+
+        assert!(matches!(exp_1, Expression::Add(_)));
+        assert!(matches!(exp_2, Expression::Sub(_)));
+        assert!(matches!(exp_3, Expression::Xor(_)));
+        assert!(matches!(exp_4, Expression::And(_)));
+        assert!(matches!(exp_5, Expression::Paren(_)));
+        assert!(matches!(exp_6, Expression::Sub(_)));
+        assert!(matches!(exp_7, Expression::Add(_)));
+        assert!(matches!(exp_8, Expression::Xor(_)));
+        assert!(matches!(exp_9, Expression::And(_)));
+        assert!(matches!(exp_10, Expression::LinkTo(_)));
+
+        assert_eq!(&input_1[exp_1.get_span().to_range()], "\"A\" + \"b\"");
+        assert_eq!(exp_8.to_string(), "page(\"A\") ^ page(\"B\") & page(\"c\")");
+        // `#` inside a string literal is not a comment.
+        assert_eq!(exp_10.to_string(), "linkto(page(\"A # B\", \"C\")).limit(5)");
+
+        // a comment runs to the end of the line, so it cannot hide the closing parenthesis.
+        assert!(Expression::parse::<Error<LocatedStr<'_>>>("(\"A\" # )").is_err());
+    }
+
+    #[test]
+    fn test_page_literals() {
+        let input = "\"A\" + page(\"B\", 'C') & linkto(\"D\" ^ (\"A\")).ns(0) - toggle(page(\"E\"))";
+        let expr = Expression::parse::<Error<LocatedStr<'_>>>(input).unwrap();
+        assert_eq!(expr.page_literals(), vec!["A", "B", "C", "D", "A", "E"]);
+
+        let expr = Expression::parse::<Error<LocatedStr<'_>>>("page(\"Main \\\"Page\\\"\")").unwrap();
+        assert_eq!(expr.page_literals(), vec!["Main \"Page\""]);
+    }
+
+    #[test]
+    fn test_node_count_and_depth() {
+        let flat = Expression::parse::<Error<LocatedStr<'_>>>("page(\"A\", \"B\", \"C\")").unwrap();
+        assert_eq!(flat.node_count(), 1);
+        assert_eq!(flat.depth(), 1);
+
+        let sum = Expression::parse::<Error<LocatedStr<'_>>>("\"A\" + \"B\" + \"C\"").unwrap();
+        assert_eq!(sum.node_count(), 5);
+        assert_eq!(sum.depth(), 3);
+
+        // parentheses count as nodes.
+        let paren = Expression::parse::<Error<LocatedStr<'_>>>("((\"A\"))").unwrap();
+        assert_eq!(paren.node_count(), 3);
+        assert_eq!(paren.depth(), 3);
+
+        let nested = Expression::parse::<Error<LocatedStr<'_>>>("link(link(link(link(link(\"A\")))))").unwrap();
+        assert_eq!(nested.node_count(), 6);
+        assert_eq!(nested.depth(), 6);
+
+        let mixed = Expression::parse::<Error<LocatedStr<'_>>>("incat(\"A\" ^ toggle(\"B\")) & \"C\"").unwrap();
+        assert_eq!(mixed.node_count(), 7);
+        assert_eq!(mixed.depth(), 5);
+    }
+
+    #[test]
+    fn test_semantic_eq() {
+        let parse = |input| Expression::parse::<Error<LocatedStr<'_>>>(input).unwrap();
+
+        let base = parse("linkto(\"A\" + 'B').ns(0, Category).limit(5) & incat(\"C\").resolve");
+        let same = [
+            "   linkto(\"A\" + 'B').ns(0, Category).limit(5) & incat(\"C\").resolve",
+            "linkto ( page(\"A\") + \"B\" ) . ns( 0 , Category , ) . limit(5)\n& incat(\"C\").resolve() # comment",
+        ];
+        let different = [
+            "linkto(\"A\" + \"B\").ns(0, Category).limit(6) & incat(\"C\").resolve",
+            "linkto(\"A\" + \"B\").ns(0, 14).limit(5) & incat(\"C\").resolve",
+            "linkto(\"A\" + \"B\").limit(5).ns(0, Category) & incat(\"C\").resolve",
+            "linkto(\"A\" - \"B\").ns(0, Category).limit(5) & incat(\"C\").resolve",
+            "linkto(\"A\", \"B\").ns(0, Category).limit(5) & incat(\"C\").resolve",
+            "(linkto(\"A\" + \"B\").ns(0, Category).limit(5)) & incat(\"C\").resolve",
+            "linkto(\"A\" + \"B\").ns(0, Category).limit(5) & incat(\"C\")",
+        ];
+
+        for input in same {
+            let expr = parse(input);
+            assert_ne!(expr, base);
+            assert!(expr.semantic_eq(&base), "{input}");
+        }
+        for input in different {
+            assert!(!parse(input).semantic_eq(&base), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let input = "(incat(\"Category:A\").depth(2).ns(0) - \"B\") & linkto(page(\"C \\\"D\\\"\"))";
+        let expr = Expression::parse::<Error<LocatedStr<'_>>>(input).unwrap();
+        let dot = expr.to_dot();
+
+        assert!(dot.starts_with("digraph {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert_eq!(dot.matches("[label=").count(), expr.node_count());
+        assert_eq!(dot.matches(" -> ").count(), expr.node_count() - 1);
+        assert!(dot.contains("    n0 [label=\"&\", shape=circle];\n"));
+        assert!(dot.contains("[label=\"incat\\n.depth(2)\\n.ns(0)\", shape=folder];"));
+        assert!(dot.contains("[label=\"page(\\\"C \\\\\\\"D\\\\\\\"\\\")\", shape=note];"));
+    }
+
+    #[test]
+    fn test_display_expression() {
+        let cases = [
+            (" \"A\" + \"b\" ", "page(\"A\") + page(\"b\")"),
+            ("\"A\"-\"B\"", "page(\"A\") - page(\"B\")"),
+            ("  \"A\" ^ \"B\"", "page(\"A\") ^ page(\"B\")"),
+            ("\"A\"&\"B\" ", "page(\"A\") & page(\"B\")"),
+            ("(\"A\")", "page(\"A\")"),
+            ("\"A\"+\"B\"-\"C\"", "page(\"A\") + page(\"B\") - page(\"C\")"),
+            ("\"A\"+\"B\"^\"c\"", "page(\"A\") + page(\"B\") ^ page(\"c\")"),
+            ("\"A\"^\"B\"&\"c\"", "page(\"A\") ^ page(\"B\") & page(\"c\")"),
+            (
+                "(\"A\" ^ \"B\" + \"C\") & ((\"D\" - \"E\") &\"F\")",
+                "(page(\"A\") ^ page(\"B\") + page(\"C\")) & ((page(\"D\") - page(\"E\")) & page(\"F\"))",
+            ),
+            ("(\"A\" - \"B\") - \"C\"", "page(\"A\") - page(\"B\") - page(\"C\")"),
+            ("\"A\" - (\"B\" - \"C\")", "page(\"A\") - (page(\"B\") - page(\"C\"))"),
+            ("Page( \"A\",\"B\" )", "page(\"A\", \"B\")"),
+            (
+                "LinkTo ( \"Main \\\"Page\\\"\" ) . Ns(0 ,1). limit( -5 ).RESOLVE()",
+                "linkto(page(\"Main \\\"Page\\\"\")).ns(0, 1).limit(-1).resolve",
+            ),
+            (
+                "incat(link(\"A\") + \"B\" & \"C\").depth(2).unprotected() ^ toggle(\"D\")",
+                "incat(link(page(\"A\")) + page(\"B\") & page(\"C\")).depth(2).unprotected ^ toggle(page(\"D\"))",
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let printed = Expression::parse::<Error<LocatedStr<'_>>>(input).unwrap().to_string();
+            assert_eq!(printed, expected);
+            // the canonical form is stable.
+            let reprinted = Expression::parse::<Error<LocatedStr<'_>>>(&printed).unwrap().to_string();
+            assert_eq!(reprinted, printed);
+        }
+    }
+
+    #[cfg(feature = "use_serde")]
+    #[test]
+    fn test_serde_expression() {
+        let input = "(incat(link(\"A\") + \"B\").depth(-1).ns(0, 14).unprotected() ^ toggle(\"C\")) & page(\"D\", \"E\\\"\") - prefix(\"F\").noredir";
+        let expr = Expression::parse::<Error<LocatedStr<'_>>>(input).unwrap();
+
+        let json = serde_json::to_string(&expr).unwrap();
+        let back: Expression = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, expr);
+        assert_eq!(back.get_span(), expr.get_span());
+        assert_eq!(back.to_string(), expr.to_string());
+    }
+
     #[test]
     fn test_parse_expression_page() {
         let input_1 = "\"Main Page\"";
@@ -431,6 +707,27 @@ mod test {
         assert_eq!(exp_4.get_span().start, 2);
     }
 
+    #[test]
+    fn test_parse_expression_pageid() {
+        let input_1 = "pageid(12345)";
+        let input_2 = " PageId ( 1 , 22,333 )  ";
+
+        let exp_1 = ExpressionPageId::parse::<Error<LocatedStr<'_>>>(input_1).unwrap();
+        let exp_2 = ExpressionPageId::parse::<Error<LocatedStr<'_>>>(input_2).unwrap();
+
+        assert!(exp_1.vals.iter().map(|v| v.val).eq([12345]));
+        assert!(exp_2.vals.iter().map(|v| v.val).eq([1, 22, 333]));
+
+        assert_eq!(&input_1[exp_1.get_span().to_range()], "pageid(12345)");
+        assert_eq!(&input_2[exp_2.get_span().to_range()], "PageId ( 1 , 22,333 )");
+        assert_eq!(exp_2.get_span().start, 1);
+
+        assert!(ExpressionPageId::parse::<Error<LocatedStr<'_>>>("pageid()").is_err());
+        assert!(ExpressionPageId::parse::<Error<LocatedStr<'_>>>("pageid(\"A\")").is_err());
+        // not a page list.
+        assert!(matches!(Expression::parse::<Error<LocatedStr<'_>>>("pageid(1) + page(\"A\")").unwrap(), Expression::Add(_)));
+    }
+
     macro_rules! unary_operation_make_test {
         ($test:ident, $target:ident, $lit:literal) => {
             #[test]
@@ -468,6 +765,11 @@ mod test {
     unary_operation_make_test!(test_parse_expression_embed, ExpressionEmbed, "embed");
     unary_operation_make_test!(test_parse_expression_incat, ExpressionInCat, "incat");
     unary_operation_make_test!(test_parse_expression_prefix, ExpressionPrefix, "prefix");
+    unary_operation_make_test!(test_parse_expression_templatesin, ExpressionTemplatesIn, "templatesin");
+    unary_operation_make_test!(test_parse_expression_files, ExpressionFiles, "files");
+    unary_operation_make_test!(test_parse_expression_fileusage, ExpressionFileUsage, "fileusage");
+    unary_operation_make_test!(test_parse_expression_categoriesof, ExpressionCategoriesOf, "categoriesof");
+    unary_operation_make_test!(test_parse_expression_langlinks, ExpressionLangLinks, "langlinks");
 
     #[test]
     fn test_parse_expression_toggle() {
@@ -491,4 +793,31 @@ mod test {
         assert_eq!(exp_3.get_span().start, 0);
         assert_eq!(exp_4.get_span().start, 2);
     }
+
+    #[test]
+    fn test_parse_expression_not() {
+        let input_1 = "not(\"Main Page\")";
+        let input_2 = " not ( link(\"Hello\") )";
+
+        let exp_1 = ExpressionNot::parse::<Error<LocatedStr<'_>>>(input_1).unwrap();
+        let exp_2 = ExpressionNot::parse::<Error<LocatedStr<'_>>>(input_2).unwrap();
+
+        assert_eq!(&input_1[exp_1.get_span().to_range()], "not(\"Main Page\")");
+        assert_eq!(&input_2[exp_2.get_span().to_range()], "not ( link(\"Hello\") )");
+
+        assert_eq!(exp_1.get_span().start, 0);
+        assert_eq!(exp_2.get_span().start, 1);
+        assert!(matches!(*exp_2.expr, Expression::Link(_)));
+        assert!(exp_2.universe.is_none());
+
+        let input_3 = "not( link(\"A\") , incat(\"Category:B\") + \"C\" )";
+        let exp_3 = ExpressionNot::parse::<Error<LocatedStr<'_>>>(input_3).unwrap();
+        assert_eq!(&input_3[exp_3.get_span().to_range()], input_3);
+        assert!(matches!(exp_3.universe.as_deref(), Some(Expression::Add(_))));
+        // a bare page list leaves the comma to `not`.
+        let exp_4 = ExpressionNot::parse::<Error<LocatedStr<'_>>>("not(\"A\", \"B\", link(\"C\"))").unwrap();
+        assert!(matches!(&*exp_4.expr, Expression::Page(page) if page.vals.len() == 2));
+        assert!(matches!(exp_4.universe.as_deref(), Some(Expression::Link(_))));
+        assert_eq!(Expression::Not(exp_3).to_string(), "not(link(page(\"A\")), incat(page(\"Category:B\")) + page(\"C\"))");
+    }
 }
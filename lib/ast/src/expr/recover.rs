@@ -0,0 +1,261 @@
+//! Parsing with error recovery, for editors.
+//!
+//! The query is split into operands at the binary operators outside of any parentheses. Each operand is parsed on
+//! its own, and one that cannot be parsed is reported and left out of the tree, as if it and its operator were
+//! not there. An operand in parentheses is searched the same way.
+
+use alloc::{boxed::Box, vec::Vec};
+use core::fmt::{self, Display, Formatter};
+use crate::{
+    LocatedStr, Span,
+    make_range,
+    parse_util::whitespace,
+    token::{Add, And, Caret, LeftParen, RightParen, Sub},
+    tokenize::{tokenize, Token},
+};
+use nom::{
+    Slice,
+    combinator::all_consuming,
+    error::Error,
+};
+use super::{Expression, ExpressionAdd, ExpressionAnd, ExpressionParen, ExpressionSub, ExpressionXor};
+
+/// A syntax error found by `Expression::parse_recover`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SyntaxError {
+    /// This operand cannot be parsed.
+    InvalidOperand,
+    /// This operator lacks an operand, eg. the second `+` in `"A" + + "B"`.
+    MissingOperand,
+    /// This parenthesis is not matched.
+    UnmatchedParen,
+}
+
+impl Display for SyntaxError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidOperand => f.write_str("invalid operand"),
+            Self::MissingOperand => f.write_str("missing operand"),
+            Self::UnmatchedParen => f.write_str("unmatched parenthesis"),
+        }
+    }
+}
+
+/// A binary operator, in the order of priority, lowest first.
+#[derive(Debug, Clone)]
+enum Operator {
+    Add(Add),
+    Sub(Sub),
+    Xor(Caret),
+    And(And),
+}
+
+impl Operator {
+    fn level(&self) -> u8 {
+        match self {
+            Self::Add(_) | Self::Sub(_) => 1,
+            Self::Xor(_) => 2,
+            Self::And(_) => 3,
+        }
+    }
+
+    fn get_span(&self) -> Span {
+        match self {
+            Self::Add(add) => add.get_span(),
+            Self::Sub(sub) => sub.get_span(),
+            Self::Xor(caret) => caret.get_span(),
+            Self::And(and) => and.get_span(),
+        }
+    }
+
+    /// Join two operands. If either is missing, the other stands for the whole.
+    fn join(self, expr1: Option<Expression>, expr2: Option<Expression>) -> Option<Expression> {
+        let (expr1, expr2) = match (expr1, expr2) {
+            (Some(expr1), Some(expr2)) => (Box::new(expr1), Box::new(expr2)),
+            (expr1, expr2) => return expr1.or(expr2),
+        };
+        let span = make_range(expr1.get_span().start, expr2.get_span().end);
+        Some(match self {
+            Self::Add(add) => Expression::Add(ExpressionAdd { span, expr1, add, expr2 }),
+            Self::Sub(sub) => Expression::Sub(ExpressionSub { span, expr1, sub, expr2 }),
+            Self::Xor(xor) => Expression::Xor(ExpressionXor { span, expr1, xor, expr2 }),
+            Self::And(and) => Expression::And(ExpressionAnd { span, expr1, and, expr2 }),
+        })
+    }
+}
+
+impl Expression {
+    /// Parse the expression from a raw piece of source text, going on after syntax errors.
+    ///
+    /// Returns the best tree that can be made of the valid parts, if any, and every error found with its span.
+    /// The tree is the same as with `parse` if there is no error. Use `parse` to run a query.
+    pub fn parse_recover(program: &str) -> (Option<Self>, Vec<(SyntaxError, Span)>) {
+        let input = LocatedStr::new(program);
+        if let Ok((_, expr)) = all_consuming(whitespace(Self::parse_internal_level_1::<Error<_>>))(input) {
+            return (Some(expr), Vec::new());
+        }
+        let tokens: Vec<_> = tokenize(program).into_iter().filter(|(token, _)| *token != Token::Comment).collect();
+        let mut errors = Vec::new();
+        let expr = recover(input, &tokens, &mut errors);
+        (expr, errors)
+    }
+}
+
+/// Parse the expression made of `tokens`, reporting the errors to `errors`.
+fn recover(input: LocatedStr<'_>, tokens: &[(Token, Span)], errors: &mut Vec<(SyntaxError, Span)>) -> Option<Expression> {
+    // split at the operators outside of parentheses.
+    let mut operands = Vec::new();
+    let mut operators = Vec::new();
+    let mut open = Vec::new();
+    let mut start = 0;
+    for (i, (token, span)) in tokens.iter().enumerate() {
+        match token {
+            Token::LeftParen => open.push(*span),
+            Token::RightParen if open.pop().is_none() => errors.push((SyntaxError::UnmatchedParen, *span)),
+            Token::Operator if open.is_empty() => {
+                let rest = input.slice(span.start..);
+                let operator = match &input.fragment()[span.to_range()] {
+                    "+" => Add::parse_internal::<Error<_>>(rest).map(|(_, add)| Operator::Add(add)),
+                    "-" => Sub::parse_internal::<Error<_>>(rest).map(|(_, sub)| Operator::Sub(sub)),
+                    "^" => Caret::parse_internal::<Error<_>>(rest).map(|(_, caret)| Operator::Xor(caret)),
+                    _ => And::parse_internal::<Error<_>>(rest).map(|(_, and)| Operator::And(and)),
+                };
+                operands.push(&tokens[start..i]);
+                // the tokenizer found this operator here.
+                operators.push(operator.unwrap());
+                start = i + 1;
+            },
+            _ => {},
+        }
+    }
+    operands.push(&tokens[start..]);
+    errors.extend(open.into_iter().map(|span| (SyntaxError::UnmatchedParen, span)));
+
+    // parse each operand on its own.
+    let mut operands: Vec<_> = operands.into_iter().enumerate().map(|(i, operand)| {
+        if operand.is_empty() {
+            // blame the operator on the side of the gap.
+            let operator = operators.get(i).or(operators.get(i.wrapping_sub(1)));
+            errors.push((SyntaxError::MissingOperand, operator.map_or(make_range(0, 0), Operator::get_span)));
+            return None;
+        }
+        recover_operand(input, operand, errors)
+    }).collect();
+
+    // join the operands, the operators of higher priority first.
+    for level in [3, 2, 1] {
+        let mut i = 0;
+        while i < operators.len() {
+            if operators[i].level() == level {
+                let operator = operators.remove(i);
+                let expr2 = operands.remove(i + 1);
+                let expr1 = operands[i].take();
+                operands[i] = operator.join(expr1, expr2);
+            } else {
+                i += 1;
+            }
+        }
+    }
+    operands.pop().flatten()
+}
+
+/// Parse an operand without operators outside of parentheses.
+fn recover_operand(input: LocatedStr<'_>, tokens: &[(Token, Span)], errors: &mut Vec<(SyntaxError, Span)>) -> Option<Expression> {
+    let span = make_range(tokens[0].1.start, tokens[tokens.len() - 1].1.end);
+    let operand = input.slice(span.to_range());
+    if let Ok((_, expr)) = all_consuming(Expression::parse_internal_level_1::<Error<_>>)(operand) {
+        return Some(expr);
+    }
+    // look inside the parentheses, if they enclose the whole operand.
+    if let [(Token::LeftParen, lparen), inner @ .., (Token::RightParen, rparen)] = tokens {
+        let encloses = inner.iter().try_fold(0usize, |depth, (token, _)| match token {
+            Token::LeftParen => Some(depth + 1),
+            Token::RightParen => depth.checked_sub(1),
+            _ => Some(depth),
+        }) == Some(0);
+        if encloses {
+            if inner.is_empty() {
+                errors.push((SyntaxError::MissingOperand, make_range(lparen.start, rparen.end)));
+                return None;
+            }
+            let expr = recover(input, inner, errors)?;
+            // both are known to be here.
+            let (_, lparen) = LeftParen::parse_internal::<Error<_>>(input.slice(lparen.start..)).unwrap();
+            let (_, rparen) = RightParen::parse_internal::<Error<_>>(input.slice(rparen.start..)).unwrap();
+            return Some(Expression::Paren(ExpressionParen { span, lparen, expr: Box::new(expr), rparen }));
+        }
+    }
+    // unmatched parentheses are already reported.
+    if !errors.iter().any(|(error, at)| *error == SyntaxError::UnmatchedParen && span.start <= at.start && at.end <= span.end) {
+        errors.push((SyntaxError::InvalidOperand, span));
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::{string::ToString, vec, vec::Vec};
+    use crate::{Expression, LocatedStr};
+    use nom::error::Error;
+    use super::SyntaxError;
+
+    /// The errors of `input`, each with the text it covers.
+    fn errors(input: &str) -> Vec<(SyntaxError, &str)> {
+        Expression::parse_recover(input).1.into_iter().map(|(error, span)| (error, &input[span.to_range()])).collect()
+    }
+
+    #[test]
+    fn test_recover_valid() {
+        for input in [
+            " \"A\" + \"b\" ",
+            "\"A\"+\"B\"^\"c\"",
+            "(\"A\" ^ \"B\" + \"C\") & ((\"D\" - \"E\") &\"F\")",
+            "linkto(page(\"A # B\", # first\n \"C\")) . # dot\n limit(# inner\n 5)",
+        ] {
+            let (expr, errors) = Expression::parse_recover(input);
+            assert_eq!(expr, Some(Expression::parse::<Error<LocatedStr<'_>>>(input).unwrap()), "{input}");
+            assert!(errors.is_empty(), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_recover_two_errors() {
+        let input = "link(\"A\").limit(x) + \"B\" & linkto(\"C\" +) - \"D\"";
+        let (expr, _) = Expression::parse_recover(input);
+        assert_eq!(errors(input), vec![
+            (SyntaxError::InvalidOperand, "link(\"A\").limit(x)"),
+            (SyntaxError::InvalidOperand, "linkto(\"C\" +)"),
+        ]);
+        assert_eq!(expr.unwrap().to_string(), "page(\"B\") - page(\"D\")");
+
+        let input = "(\"A\" + ) & (\"B\" ^ ?\"C\") - \"D\"";
+        let (expr, _) = Expression::parse_recover(input);
+        assert_eq!(errors(input), vec![
+            (SyntaxError::MissingOperand, "+"),
+            (SyntaxError::InvalidOperand, "?\"C\""),
+        ]);
+        let Some(Expression::Sub(expr)) = expr else { panic!() };
+        let Expression::And(and) = *expr.expr1 else { panic!() };
+        assert!(matches!(*and.expr1, Expression::Paren(_)));
+        assert!(matches!(*and.expr2, Expression::Paren(_)));
+        assert_eq!(expr.expr2.to_string(), "page(\"D\")");
+    }
+
+    #[test]
+    fn test_recover_parens() {
+        let input = "(\"A\" + \"B\" & \"C\"";
+        let (expr, _) = Expression::parse_recover(input);
+        assert_eq!(errors(input), vec![(SyntaxError::UnmatchedParen, "(")]);
+        assert!(expr.is_none());
+
+        let input = "\"A\") + \"B\"";
+        let (expr, _) = Expression::parse_recover(input);
+        assert_eq!(errors(input), vec![(SyntaxError::UnmatchedParen, ")")]);
+        assert_eq!(expr.unwrap().to_string(), "page(\"B\")");
+
+        assert_eq!(errors("() & \"A\""), vec![(SyntaxError::MissingOperand, "()")]);
+        assert_eq!(errors("& \"A\""), vec![(SyntaxError::MissingOperand, "&")]);
+        assert_eq!(Expression::parse_recover(""), (None, vec![(SyntaxError::MissingOperand, crate::Span::new(0, 0))]));
+    }
+}
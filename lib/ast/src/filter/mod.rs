@@ -0,0 +1,132 @@
+//! Filter expressions.
+
+use core::{
+    fmt::{self, Display, Formatter},
+    hash::{Hash, Hasher},
+};
+use crate::{Span, expose_span};
+use crate::literal::LitString;
+#[cfg(feature = "use_serde")]
+use serde::{Serialize, Deserialize};
+use crate::token::{
+    LeftParen, RightParen,
+    Protected, Unprotected, Matches, MatchTitle,
+};
+
+#[cfg(feature = "parse")]
+pub mod parse;
+
+/// Mega container for all filters.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub enum Filter {
+    Protected(FilterProtected),
+    Unprotected(FilterUnprotected),
+    Matches(FilterMatches),
+}
+
+impl Filter {
+    pub fn get_span(&self) -> Span {
+        match self {
+            Self::Protected(x) => x.get_span(),
+            Self::Unprotected(x) => x.get_span(),
+            Self::Matches(x) => x.get_span(),
+        }
+    }
+}
+
+/// Filters without parameters are written without parentheses.
+impl Display for Filter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Protected(x) => write!(f, "{}", x.protected),
+            Self::Unprotected(x) => write!(f, "{}", x.unprotected),
+            Self::Matches(x) => write!(f, "{}({})", x.keyword, x.val),
+        }
+    }
+}
+
+/// Filter expression that keeps only pages with some protection.
+/// `protected` or `protected()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub struct FilterProtected {
+    span: Span,
+    pub protected: Protected,
+    pub lparen: Option<LeftParen>,
+    pub rparen: Option<RightParen>,
+}
+
+impl Hash for FilterProtected {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.protected.hash(state);
+    }
+}
+
+/// Filter expression that keeps only pages without any protection.
+/// `unprotected` or `unprotected()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub struct FilterUnprotected {
+    span: Span,
+    pub unprotected: Unprotected,
+    pub lparen: Option<LeftParen>,
+    pub rparen: Option<RightParen>,
+}
+
+impl Hash for FilterUnprotected {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.unprotected.hash(state);
+    }
+}
+
+/// Filter expression that keeps only pages whose title matches a regular expression.
+/// `matches("^List of ")` or `matchtitle("(?i)stub$")`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub struct FilterMatches {
+    span: Span,
+    pub keyword: MatchKeyword,
+    pub lparen: LeftParen,
+    pub val: LitString,
+    pub rparen: RightParen,
+}
+
+impl Hash for FilterMatches {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // both spellings are the same filter.
+        self.val.hash(state);
+    }
+}
+
+/// The name of a matches filter. Both spellings mean the same.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub enum MatchKeyword {
+    Matches(Matches),
+    MatchTitle(MatchTitle),
+}
+
+impl MatchKeyword {
+    /// Get the span for this item.
+    pub fn get_span(&self) -> Span {
+        match self {
+            Self::Matches(x) => x.get_span(),
+            Self::MatchTitle(x) => x.get_span(),
+        }
+    }
+}
+
+impl Display for MatchKeyword {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Matches(x) => write!(f, "{x}"),
+            Self::MatchTitle(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+expose_span!(FilterProtected);
+expose_span!(FilterUnprotected);
+expose_span!(FilterMatches);
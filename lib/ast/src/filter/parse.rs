@@ -0,0 +1,214 @@
+//! Parse module for filters.
+
+use core::num::ParseIntError;
+use crate::{
+    LocatedStr,
+    make_range,
+    literal::LitString,
+    parse_util::{whitespace, leading_whitespace},
+    token::{
+        LeftParen, RightParen,
+        Protected, Unprotected, Matches, MatchTitle,
+    },
+};
+use super::{
+    Filter,
+    FilterProtected, FilterUnprotected,
+    FilterMatches, MatchKeyword,
+};
+
+use nom::{
+    IResult,
+    Finish,
+    branch::alt,
+    combinator::{all_consuming, opt, map},
+    error::{ParseError, FromExternalError},
+    sequence::tuple,
+};
+use nom_locate::position;
+
+impl Filter {
+    /// Parse the filter from a raw piece of source text. Leading and trailing whitespaces are automatically removed.
+    pub fn parse<'a, E>(program: &'a str) -> Result<Self, E>
+    where
+        E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
+    {
+        let span = LocatedStr::new(program);
+        all_consuming(
+            whitespace(Self::parse_internal::<E>)
+        )(span).finish().map(|(_, x)| x)
+    }
+
+    /// Parse the filter from a span. Assume no whitespaces before.
+    pub(crate) fn parse_internal<'a, E>(program: LocatedStr<'a>) -> IResult<LocatedStr<'a>, Self, E>
+    where
+        E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
+    {
+        alt((
+            map(FilterProtected::parse_internal, Self::Protected),
+            map(FilterUnprotected::parse_internal, Self::Unprotected),
+            map(FilterMatches::parse_internal, Self::Matches),
+        ))(program)
+    }
+}
+
+impl FilterMatches {
+    /// Parse the filter from a raw piece of source text. Leading and trailing whitespaces are automatically removed.
+    pub fn parse<'a, E>(program: &'a str) -> Result<Self, E>
+    where
+        E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
+    {
+        let span = LocatedStr::new(program);
+        all_consuming(
+            whitespace(Self::parse_internal::<E>)
+        )(span).finish().map(|(_, x)| x)
+    }
+
+    /// Parse the filter from a span. Assume no whitespaces before.
+    pub(crate) fn parse_internal<'a, E>(program: LocatedStr<'a>) -> IResult<LocatedStr<'a>, Self, E>
+    where
+        E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
+    {
+        let (residual, (pos_start, keyword, lparen, val, rparen, pos_end)) = tuple((
+            position,
+            alt((
+                map(Matches::parse_internal, MatchKeyword::Matches),
+                map(MatchTitle::parse_internal, MatchKeyword::MatchTitle),
+            )),
+            leading_whitespace(LeftParen::parse_internal),
+            leading_whitespace(LitString::parse_internal),
+            leading_whitespace(RightParen::parse_internal),
+            position,
+        ))(program)?;
+        let filter = Self {
+            span: make_range(pos_start.location_offset(), pos_end.location_offset()),
+            keyword,
+            lparen,
+            val,
+            rparen,
+        };
+        Ok((residual, filter))
+    }
+}
+
+macro_rules! no_param_filter_parse {
+    ($name:ident, $token_field:ident, $token:ident) => {
+        impl $name {
+            /// Parse the filter from a raw piece of source text. Leading and trailing whitespaces are automatically removed.
+            pub fn parse<'a, E>(program: &'a str) -> Result<Self, E>
+            where
+                E: ParseError<LocatedStr<'a>>,
+            {
+                let span = LocatedStr::new(program);
+                all_consuming(
+                    whitespace(Self::parse_internal::<E>)
+                )(span).finish().map(|(_, x)| x)
+            }
+
+            /// Parse the filter from a span. Assume no whitespaces before.
+            pub(crate) fn parse_internal<'a, E>(program: LocatedStr<'a>) -> IResult<LocatedStr<'a>, Self, E>
+            where
+                E: ParseError<LocatedStr<'a>>,
+            {
+                let (residual, (pos_start, $token_field, opt_paren, pos_end)) = tuple((
+                    position,
+                    $token::parse_internal,
+                    opt(
+                        tuple((
+                            leading_whitespace(LeftParen::parse_internal),
+                            leading_whitespace(RightParen::parse_internal),
+                        ))
+                    ),
+                    position,
+                ))(program)?;
+                let (lparen, rparen) = match opt_paren {
+                    Some((lparen, rparen)) => (Some(lparen), Some(rparen)),
+                    None => (None, None),
+                };
+                let filter = Self {
+                    span: make_range(pos_start.location_offset(), pos_end.location_offset()),
+                    $token_field,
+                    lparen,
+                    rparen,
+                };
+                Ok((residual, filter))
+            }
+        }
+    }
+}
+
+no_param_filter_parse!(FilterProtected, protected, Protected);
+no_param_filter_parse!(FilterUnprotected, unprotected, Unprotected);
+
+#[cfg(test)]
+mod test {
+    use crate::LocatedStr;
+    use super::{
+        Filter,
+        FilterProtected, FilterUnprotected,
+        FilterMatches, MatchKeyword,
+    };
+    use alloc::string::ToString;
+    use nom::error::Error;
+
+    #[test]
+    fn test_parse_filter() {
+        let input_protected = " Protected";
+        let input_unprotected = "unprotected ( ) ";
+
+        let filter_protected = Filter::parse::<Error<LocatedStr<'_>>>(input_protected).unwrap();
+        let filter_unprotected = Filter::parse::<Error<LocatedStr<'_>>>(input_unprotected).unwrap();
+
+        assert!(matches!(filter_protected, Filter::Protected(_)));
+        assert!(matches!(filter_unprotected, Filter::Unprotected(_)));
+
+        assert_eq!(&input_protected[filter_protected.get_span().to_range()], "Protected");
+        assert_eq!(&input_unprotected[filter_unprotected.get_span().to_range()], "unprotected ( )");
+
+        assert_eq!(filter_protected.get_span().start, 1);
+        assert_eq!(filter_unprotected.get_span().start, 0);
+    }
+
+    #[test]
+    fn test_parse_filter_no_param() {
+        let input_1 = "protected";
+        let input_2 = "PROTECTED()";
+        let input_3 = "  unProtected  ";
+
+        let filter_1 = FilterProtected::parse::<Error<LocatedStr<'_>>>(input_1).unwrap();
+        let filter_2 = FilterProtected::parse::<Error<LocatedStr<'_>>>(input_2).unwrap();
+        let filter_3 = FilterUnprotected::parse::<Error<LocatedStr<'_>>>(input_3).unwrap();
+
+        assert!(filter_1.lparen.is_none() && filter_1.rparen.is_none());
+        assert!(filter_2.lparen.is_some() && filter_2.rparen.is_some());
+        assert!(filter_3.lparen.is_none() && filter_3.rparen.is_none());
+
+        assert_eq!(&input_1[filter_1.get_span().to_range()], "protected");
+        assert_eq!(&input_2[filter_2.get_span().to_range()], "PROTECTED()");
+        assert_eq!(&input_3[filter_3.get_span().to_range()], "unProtected");
+
+        assert!(FilterProtected::parse::<Error<LocatedStr<'_>>>("unprotected").is_err());
+    }
+
+    #[test]
+    fn test_parse_filter_matches() {
+        let input_1 = "matches(\"^List of \")";
+        let input_2 = " MatchTitle ( '(?i)stub$' ) ";
+
+        let filter_1 = FilterMatches::parse::<Error<LocatedStr<'_>>>(input_1).unwrap();
+        let filter_2 = FilterMatches::parse::<Error<LocatedStr<'_>>>(input_2).unwrap();
+
+        assert!(matches!(filter_1.keyword, MatchKeyword::Matches(_)));
+        assert!(matches!(filter_2.keyword, MatchKeyword::MatchTitle(_)));
+        assert_eq!(filter_1.val.val, "^List of ");
+        assert_eq!(filter_2.val.val, "(?i)stub$");
+
+        assert_eq!(&input_1[filter_1.get_span().to_range()], "matches(\"^List of \")");
+        assert_eq!(&input_2[filter_2.get_span().to_range()], "MatchTitle ( '(?i)stub$' )");
+        assert_eq!(Filter::Matches(filter_2).to_string(), "matchtitle(\"(?i)stub$\")");
+
+        assert!(matches!(Filter::parse::<Error<LocatedStr<'_>>>("matches(\"A\")").unwrap(), Filter::Matches(_)));
+        assert!(FilterMatches::parse::<Error<LocatedStr<'_>>>("matches").is_err());
+        assert!(FilterMatches::parse::<Error<LocatedStr<'_>>>("matches(A)").is_err());
+    }
+}
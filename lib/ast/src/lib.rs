@@ -7,36 +7,62 @@ extern crate alloc;
 pub(crate) type LocatedStr<'a> = nom_locate::LocatedSpan<&'a str>;
 
 pub mod attribute;
+#[cfg(feature = "parse")]
+pub mod error;
 pub mod expr;
+pub mod filter;
 pub mod literal;
 pub mod modifier;
 pub mod span;
+mod semantic_eq;
 pub mod token;
 #[cfg(feature = "parse")]
+pub mod tokenize;
+pub mod visit;
+#[cfg(feature = "parse")]
 mod parse_util;
 
-pub use attribute::{Attribute, AttributeModifier};
+pub use attribute::{Attribute, AttributeModifier, AttributeFilter};
+#[cfg(feature = "parse")]
+pub use error::{parse, ParseError};
 pub use expr::{
     Expression,
     ExpressionAnd, ExpressionAdd, ExpressionSub, ExpressionXor,
     ExpressionParen,
-    ExpressionPage, ExpressionLink, ExpressionLinkTo, ExpressionEmbed, ExpressionInCat, ExpressionPrefix, ExpressionToggle,
+    ExpressionPage, ExpressionPageId, ExpressionLink, ExpressionLinkTo, ExpressionEmbed, ExpressionInCat, ExpressionPrefix, ExpressionToggle, ExpressionTemplatesIn, ExpressionFiles, ExpressionFileUsage, ExpressionCategoriesOf, ExpressionLangLinks,
+    ExpressionNot,
+};
+#[cfg(feature = "parse")]
+pub use expr::recover::SyntaxError;
+pub use filter::{
+    Filter,
+    FilterProtected, FilterUnprotected,
+    FilterMatches, MatchKeyword,
 };
 pub use intorinf::IntOrInf;
-pub use literal::{LitString, LitIntOrInf};
+pub use literal::{LitString, LitIntOrInf, LitInt, LitNamespace, LitNamespaceNot};
 pub use modifier::{
     Modifier,
     ModifierLimit, ModifierResolve,
     ModifierNs,
     ModifierDepth,
     ModifierNoRedir, ModifierOnlyRedir, ModifierDirect,
+    ModifierOffset,
+    ModifierSort, SortKey,
+    ModifierSince, ModifierBefore,
+    ModifierSortBy, CategorySortKey, SortDirection,
+    ModifierRandom,
 };
 pub use token::{
-    Dot, Comma, LeftParen, RightParen, And, Add, Sub, Caret,
-    Page, Link, LinkTo, Embed, InCat, Prefix, Toggle,
-    Limit, Resolve, Ns, Depth, NoRedir, OnlyRedir, Direct,
+    Dot, Comma, LeftParen, RightParen, And, Add, Sub, Caret, Star, Bang,
+    Page, Link, LinkTo, Embed, InCat, Prefix, Toggle, TemplatesIn, Files, FileUsage, CategoriesOf, LangLinks, Not,
+    Limit, Resolve, Ns, Depth, NoRedir, OnlyRedir, Direct, Offset, Sort, Title, PageId, Since, Before, SortBy, Sortkey, Timestamp, Asc, Desc, Random,
+    Protected, Unprotected, Matches, MatchTitle,
 };
 pub use span::Span;
+#[cfg(feature = "parse")]
+pub use tokenize::{tokenize, Token};
+pub use visit::{Visitor, walk_expression};
 
 pub(crate) use macros::expose_span;
 
@@ -1,13 +1,20 @@
 //! Literal types.
 
-use alloc::string::String;
-use core::hash::{Hash, Hasher};
+use alloc::{boxed::Box, string::String};
+use core::{
+    fmt::{self, Display, Formatter, Write},
+    hash::{Hash, Hasher},
+};
 use crate::{IntOrInf, Span, expose_span};
+use crate::token::{Bang, Star};
+#[cfg(feature = "use_serde")]
+use serde::{Serialize, Deserialize};
 
 #[cfg(feature = "parse")]
 pub mod parse;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct LitString {
     span: Span,
     pub val: String,
@@ -20,6 +27,7 @@ impl Hash for LitString {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct LitIntOrInf {
     span: Span,
     pub val: IntOrInf,
@@ -32,6 +40,7 @@ impl Hash for LitIntOrInf {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct LitInt {
     span: Span,
     pub val: i32,
@@ -43,6 +52,103 @@ impl Hash for LitInt {
     }
 }
 
+/// Write the string quoted, with the characters the parser would choke on escaped.
+impl Display for LitString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.val.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if c.is_control() => write!(f, "\\u{{{:04X}}}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+/// Infinity is written as `-1`, as any negative number parses to it.
+impl Display for LitIntOrInf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.val {
+            IntOrInf::Int(i) => write!(f, "{i}"),
+            IntOrInf::Inf => f.write_str("-1"),
+        }
+    }
+}
+
+impl Display for LitInt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.val)
+    }
+}
+
+/// A namespace, written either as its number or as its name.
+/// A name can be a bare identifier, eg. `Category` or `Template_talk`, or a quoted string.
+/// Names are resolved into numbers later, against the namespaces of the target site.
+/// `*` stands for all namespaces, and `!` before a number or a name for all namespaces but that one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub enum LitNamespace {
+    Id(LitInt),
+    Name(LitString),
+    All(Star),
+    Not(LitNamespaceNot),
+}
+
+/// An excluded namespace, eg. `!0` or `!Talk`. The namespace is always an `Id` or a `Name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub struct LitNamespaceNot {
+    span: Span,
+    pub bang: Bang,
+    pub val: Box<LitNamespace>,
+}
+
+impl Hash for LitNamespaceNot {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bang.hash(state);
+        self.val.hash(state);
+    }
+}
+
+impl LitNamespace {
+    /// Get the span for this item.
+    pub fn get_span(&self) -> Span {
+        match self {
+            Self::Id(x) => x.get_span(),
+            Self::Name(x) => x.get_span(),
+            Self::All(x) => x.get_span(),
+            Self::Not(x) => x.get_span(),
+        }
+    }
+}
+
+/// Names that are valid identifiers are written bare, others are quoted.
+impl Display for LitNamespace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Id(x) => write!(f, "{x}"),
+            Self::Name(x) if is_identifier(&x.val) => f.write_str(&x.val),
+            Self::Name(x) => write!(f, "{x}"),
+            Self::All(x) => write!(f, "{x}"),
+            Self::Not(x) => write!(f, "{}{}", x.bang, x.val),
+        }
+    }
+}
+
+/// Whether `s` can be written as a bare identifier.
+pub(crate) fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
 expose_span!(LitString);
 expose_span!(LitIntOrInf);
 expose_span!(LitInt);
+expose_span!(LitNamespaceNot);
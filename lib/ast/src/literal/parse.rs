@@ -1,18 +1,19 @@
 //! Parse module for literal types.
 
-use alloc::string::String;
+use alloc::{boxed::Box, string::String};
 use core::num::ParseIntError;
 use crate::{
     LocatedStr,
     make_range,
-    parse_util::whitespace,
+    parse_util::{whitespace, leading_whitespace},
+    token::{Bang, Star},
 };
-use super::{LitString, LitInt, LitIntOrInf};
+use super::{LitString, LitInt, LitIntOrInf, LitNamespace, LitNamespaceNot};
 use nom::{
     IResult, Finish,
     error::{FromExternalError, ParseError},
     branch::alt,
-    bytes::complete::{take_while_m_n, is_not},
+    bytes::complete::{take_while, take_while_m_n, take_while1, is_not},
     character::complete::{char, multispace1, one_of},
     combinator::{all_consuming, map_res, map_opt, value, verify, map, recognize, opt},
     multi::{fold_many0, many1},
@@ -157,6 +158,7 @@ where
             value('\\', char('\\')),
             value('/', char('/')),
             value('"', char('"')),
+            value('\'', char('\'')),
         )),
     )(input)
 }
@@ -170,13 +172,15 @@ where
     preceded(char('\\'), multispace1)(input)
 }
 
-/// Parse a non-empty block of text that doesn't include \ or "
-fn parse_literal<'a, E>(input: LocatedStr<'a>) -> IResult<LocatedStr<'a>, LocatedStr<'a>, E>
+/// Parse a non-empty block of text that doesn't include \ or any character in `stop`.
+fn parse_literal<'a, E>(stop: &'static str) -> impl FnMut(LocatedStr<'a>) -> IResult<LocatedStr<'a>, LocatedStr<'a>, E>
 where
     E: ParseError<LocatedStr<'a>>,
 {
-    let not_quote_slash = is_not("\"\\");
-    verify(not_quote_slash, |s: &LocatedStr<'a>| !s.is_empty())(input)
+    move |input| {
+        let not_quote_slash = is_not(stop);
+        verify(not_quote_slash, |s: &LocatedStr<'a>| !s.is_empty())(input)
+    }
 }
 
 /// A string fragment contains a fragment of a string being parsed: either
@@ -191,25 +195,25 @@ enum StringFragment<'a> {
 
 /// Combine parse_literal, parse_escaped_whitespace, and parse_escaped_char
 /// into a StringFragment.
-fn parse_fragment<'a, E>(input: LocatedStr<'a>) -> IResult<LocatedStr<'a>, StringFragment<'a>, E>
+fn parse_fragment<'a, E>(stop: &'static str) -> impl FnMut(LocatedStr<'a>) -> IResult<LocatedStr<'a>, StringFragment<'a>, E>
 where
     E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
 {
     alt((
-        map(parse_literal, StringFragment::Literal),
+        map(parse_literal(stop), StringFragment::Literal),
         map(parse_escaped_char, StringFragment::EscapedChar),
         value(StringFragment::EscapedWS, parse_escaped_whitespace),
-    ))(input)
+    ))
 }
 
-/// Parse a string. Use a loop of parse_fragment and push all of the fragments
-/// into an output string.
-fn parse_string<'a, E>(input: LocatedStr<'a>) -> IResult<LocatedStr<'a>, String, E>
+/// Parse a string delimited by `quote`. Use a loop of parse_fragment and push all of the fragments
+/// into an output string. `stop` holds the backslash and the quote.
+fn parse_quoted<'a, E>(quote: char, stop: &'static str) -> impl FnMut(LocatedStr<'a>) -> IResult<LocatedStr<'a>, String, E>
 where
     E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
 {
     let build_string = fold_many0(
-        parse_fragment,
+        parse_fragment(stop),
         String::new,
         |mut string, fragment| {
             match fragment {
@@ -221,7 +225,19 @@ where
         },
     );
 
-    delimited(char('"'), build_string, char('"'))(input)
+    delimited(char(quote), build_string, char(quote))
+}
+
+/// Parse a string, either double-quoted or single-quoted.
+/// The same escapes are understood in both. A quote of the other kind needs no escaping.
+fn parse_string<'a, E>(input: LocatedStr<'a>) -> IResult<LocatedStr<'a>, String, E>
+where
+    E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
+{
+    alt((
+        parse_quoted('"', "\"\\"),
+        parse_quoted('\'', "'\\"),
+    ))(input)
 }
 
 /// Parse a i32 number. Assume no leading or trailing spaces.
@@ -252,6 +268,87 @@ where
     )(input)
 }
 
+impl LitNamespace {
+    /// Parse a `LitNamespace` from a raw piece of source text. Leading and trailing whitespaces are automatically removed.
+    pub fn parse<'a, E>(program: &'a str) -> Result<Self, E>
+    where
+        E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
+    {
+        let span = LocatedStr::new(program);
+        all_consuming(
+            whitespace(
+                Self::parse_internal::<E>
+            )
+        )(span).finish().map(|(_, x)| x)
+    }
+
+    /// Parse a `LitNamespace` from a span. Assume no whitespaces before.
+    pub(crate) fn parse_internal<'a, E>(program: LocatedStr<'a>) -> IResult<LocatedStr<'a>, Self, E>
+    where
+        E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
+    {
+        alt((
+            Self::parse_internal_single,
+            map(Star::parse_internal, Self::All),
+            map(LitNamespaceNot::parse_internal, Self::Not),
+        ))(program)
+    }
+
+    /// Parse a single namespace, ie. a number or a name.
+    fn parse_internal_single<'a, E>(program: LocatedStr<'a>) -> IResult<LocatedStr<'a>, Self, E>
+    where
+        E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
+    {
+        alt((
+            map(LitInt::parse_internal, Self::Id),
+            map(LitString::parse_internal, Self::Name),
+            map(parse_identifier, Self::Name),
+        ))(program)
+    }
+}
+
+impl LitNamespaceNot {
+    /// Parse a `LitNamespaceNot` from a span. Assume no whitespaces before.
+    pub(crate) fn parse_internal<'a, E>(program: LocatedStr<'a>) -> IResult<LocatedStr<'a>, Self, E>
+    where
+        E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
+    {
+        let (residual, (pos_start, bang, val, pos_end)) = tuple((
+            position,
+            Bang::parse_internal,
+            leading_whitespace(LitNamespace::parse_internal_single),
+            position,
+        ))(program)?;
+        let lit = Self {
+            span: make_range(pos_start.location_offset(), pos_end.location_offset()),
+            bang,
+            val: Box::new(val),
+        };
+        Ok((residual, lit))
+    }
+}
+
+/// Parse a bare identifier into a `LitString`. An identifier is a run of letters, digits and underscores,
+/// not starting with a digit.
+pub(crate) fn parse_identifier<'a, E>(input: LocatedStr<'a>) -> IResult<LocatedStr<'a>, LitString, E>
+where
+    E: ParseError<LocatedStr<'a>>,
+{
+    let (residual, (pos_start, val, pos_end)) = tuple((
+        position,
+        recognize(tuple((
+            take_while1(|c: char| c.is_alphabetic() || c == '_'),
+            take_while(|c: char| c.is_alphanumeric() || c == '_'),
+        ))),
+        position,
+    ))(input)?;
+    let lit_string = LitString {
+        span: make_range(pos_start.location_offset(), pos_end.location_offset()),
+        val: String::from(*val.fragment()),
+    };
+    Ok((residual, lit_string))
+}
+
 #[cfg(test)]
 mod test {
     use crate::{LocatedStr, IntOrInf};
@@ -287,6 +384,36 @@ mod test {
         assert_eq!(lit_4.get_span().start, 1);
     }
 
+    #[test]
+    fn test_parse_litstring_quotes() {
+        let input_1 = "\"Foo \\\"Bar\\\"\"";
+        let input_2 = " 'Foo \"Bar\"' ";
+        let input_3 = "'It\\'s'";
+        let input_4 = "\"C:\\\\Windows\\\\\" ";
+        let input_5 = "'a\\nb\\\\'";
+
+        let lit_1 = LitString::parse::<Error<LocatedStr<'_>>>(input_1).unwrap();
+        let lit_2 = LitString::parse::<Error<LocatedStr<'_>>>(input_2).unwrap();
+        let lit_3 = LitString::parse::<Error<LocatedStr<'_>>>(input_3).unwrap();
+        let lit_4 = LitString::parse::<Error<LocatedStr<'_>>>(input_4).unwrap();
+        let lit_5 = LitString::parse::<Error<LocatedStr<'_>>>(input_5).unwrap();
+
+        assert_eq!(lit_1.val, "Foo \"Bar\"");
+        assert_eq!(lit_2.val, "Foo \"Bar\"");
+        assert_eq!(lit_3.val, "It's");
+        assert_eq!(lit_4.val, "C:\\Windows\\");
+        assert_eq!(lit_5.val, "a\nb\\");
+
+        assert_eq!(&input_1[lit_1.get_span().to_range()], input_1);
+        assert_eq!(&input_2[lit_2.get_span().to_range()], "'Foo \"Bar\"'");
+        assert_eq!(&input_3[lit_3.get_span().to_range()], input_3);
+        assert_eq!(&input_4[lit_4.get_span().to_range()], "\"C:\\\\Windows\\\\\"");
+
+        // mismatched quotes do not close the string.
+        assert!(LitString::parse::<Error<LocatedStr<'_>>>("'Foo\"").is_err());
+        assert!(LitString::parse::<Error<LocatedStr<'_>>>("\"Foo'").is_err());
+    }
+
     #[test]
     fn test_parse_litintorinf() {
         let input_1 = "0";
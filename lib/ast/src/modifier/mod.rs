@@ -1,12 +1,17 @@
 //! Modifier expressions.
 
 use alloc::vec::Vec;
-use core::hash::{Hash, Hasher};
+use core::{
+    fmt::{self, Display, Formatter},
+    hash::{Hash, Hasher},
+};
 use crate::{Span, expose_span};
-use crate::literal::{LitIntOrInf, LitInt};
+#[cfg(feature = "use_serde")]
+use serde::{Serialize, Deserialize};
+use crate::literal::{LitInt, LitIntOrInf, LitNamespace, LitString};
 use crate::token::{
     LeftParen, RightParen, Comma,
-    Limit, Resolve, Ns, Depth, NoRedir, OnlyRedir, Direct,
+    Limit, Resolve, Ns, Depth, NoRedir, OnlyRedir, Direct, Offset, Sort, Title, PageId, Since, Before, SortBy, Sortkey, Timestamp, Asc, Desc, Random,
 };
 
 #[cfg(feature = "parse")]
@@ -15,6 +20,7 @@ pub mod parse;
 /// Mega container for all modifiers.
 #[non_exhaustive]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub enum Modifier {
     Limit(ModifierLimit),
     Resolve(ModifierResolve),
@@ -23,6 +29,12 @@ pub enum Modifier {
     NoRedir(ModifierNoRedir),
     OnlyRedir(ModifierOnlyRedir),
     Direct(ModifierDirect),
+    Offset(ModifierOffset),
+    Sort(ModifierSort),
+    Since(ModifierSince),
+    Before(ModifierBefore),
+    SortBy(ModifierSortBy),
+    Random(ModifierRandom),
 }
 
 impl Modifier {
@@ -35,6 +47,48 @@ impl Modifier {
             Self::NoRedir(x) => x.get_span(),
             Self::OnlyRedir(x) => x.get_span(),
             Self::Direct(x) => x.get_span(),
+            Self::Offset(x) => x.get_span(),
+            Self::Sort(x) => x.get_span(),
+            Self::Since(x) => x.get_span(),
+            Self::Before(x) => x.get_span(),
+            Self::SortBy(x) => x.get_span(),
+            Self::Random(x) => x.get_span(),
+        }
+    }
+}
+
+/// Modifiers without parameters are written without parentheses.
+impl Display for Modifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Limit(x) => write!(f, "{}({})", x.limit, x.val),
+            Self::Resolve(x) => write!(f, "{}", x.resolve),
+            Self::Ns(x) => {
+                write!(f, "{}(", x.ns)?;
+                for (i, val) in x.vals.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{val}")?;
+                }
+                f.write_str(")")
+            },
+            Self::Depth(x) => write!(f, "{}({})", x.depth, x.val),
+            Self::NoRedir(x) => write!(f, "{}", x.noredir),
+            Self::OnlyRedir(x) => write!(f, "{}", x.onlyredir),
+            Self::Direct(x) => write!(f, "{}", x.direct),
+            Self::Offset(x) => write!(f, "{}({})", x.offset, x.val),
+            Self::Sort(x) => write!(f, "{}({})", x.sort, x.key),
+            Self::Since(x) => write!(f, "{}({})", x.since, x.val),
+            Self::Before(x) => write!(f, "{}({})", x.before, x.val),
+            Self::SortBy(x) => match &x.dir {
+                Some(dir) => write!(f, "{}({}, {})", x.sortby, x.key, dir),
+                None => write!(f, "{}({})", x.sortby, x.key),
+            },
+            Self::Random(x) => match &x.seed {
+                Some(seed) => write!(f, "{}({}, {})", x.random, x.val, seed),
+                None => write!(f, "{}({})", x.random, x.val),
+            },
         }
     }
 }
@@ -42,6 +96,7 @@ impl Modifier {
 /// Modifier expression that limit the query count.
 /// `limit(xx)`
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ModifierLimit {
     span: Span,
     pub limit: Limit,
@@ -62,6 +117,7 @@ impl Hash for ModifierLimit {
 /// Modifier expression that defines whether to resolve redirects.
 /// `resolve` or `resolve()`
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ModifierResolve {
     span: Span,
     pub resolve: Resolve,
@@ -76,13 +132,14 @@ impl Hash for ModifierResolve {
 }
 
 /// Modifier expression that contrains the results inside certain namespaces.
-/// `ns(xx,xx)`
+/// `ns(xx,xx)`, where each `xx` is a namespace number or name.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ModifierNs {
     span: Span,
     pub ns: Ns,
     pub lparen: LeftParen,
-    pub vals: Vec<LitInt>,
+    pub vals: Vec<LitNamespace>,
     pub commas: Vec<Comma>,
     pub rparen: RightParen,
 }
@@ -91,8 +148,8 @@ impl Hash for ModifierNs {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.ns.hash(state);
         self.lparen.hash(state);
+        // a trailing comma changes nothing.
         self.vals.hash(state);
-        self.commas.hash(state);
         self.rparen.hash(state);
     }
 }
@@ -100,6 +157,7 @@ impl Hash for ModifierNs {
 /// Modifier expression that tells incat operation how many layers to search.
 /// `depth(xx)`
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ModifierDepth {
     span: Span,
     pub depth: Depth,
@@ -120,6 +178,7 @@ impl Hash for ModifierDepth {
 /// Modifier expression that tells backlinks operation to filter out redirects.
 /// `noredir` or `noredir()`
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ModifierNoRedir {
     span: Span,
     pub noredir: NoRedir,
@@ -136,6 +195,7 @@ impl Hash for ModifierNoRedir {
 /// Modifier expression that tells backlinks operation to show only redirects.
 /// `onlyredir` or `onlyredir()`
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ModifierOnlyRedir {
     span: Span,
     pub onlyredir: OnlyRedir,
@@ -152,6 +212,7 @@ impl Hash for ModifierOnlyRedir {
 /// Modifier expression that tells backlinks operation only to show direct backlinks.
 /// `direct` or `direct()`
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct ModifierDirect {
     span: Span,
     pub direct: Direct,
@@ -165,6 +226,200 @@ impl Hash for ModifierDirect {
     }
 }
 
+/// Modifier expression that skips the first results of the query.
+/// `offset(xx)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub struct ModifierOffset {
+    span: Span,
+    pub offset: Offset,
+    pub lparen: LeftParen,
+    pub val: LitInt,
+    pub rparen: RightParen,
+}
+
+impl Hash for ModifierOffset {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.offset.hash(state);
+        self.lparen.hash(state);
+        self.val.hash(state);
+        self.rparen.hash(state);
+    }
+}
+
+/// Modifier expression that sorts the results of the query.
+/// `sort(title)` or `sort(pageid)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub struct ModifierSort {
+    span: Span,
+    pub sort: Sort,
+    pub lparen: LeftParen,
+    pub key: SortKey,
+    pub rparen: RightParen,
+}
+
+impl Hash for ModifierSort {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.sort.hash(state);
+        self.lparen.hash(state);
+        self.key.hash(state);
+        self.rparen.hash(state);
+    }
+}
+
+/// Modifier expression that tells incat operation to list only pages added to the category at or after a timestamp.
+/// `since("2024-01-01")` or `since("2024-01-01T12:00:00Z")`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub struct ModifierSince {
+    span: Span,
+    pub since: Since,
+    pub lparen: LeftParen,
+    pub val: LitString,
+    pub rparen: RightParen,
+}
+
+impl Hash for ModifierSince {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.since.hash(state);
+        self.lparen.hash(state);
+        self.val.hash(state);
+        self.rparen.hash(state);
+    }
+}
+
+/// Modifier expression that tells incat operation to list only pages added to the category at or before a timestamp.
+/// `before("2024-01-01")` or `before("2024-01-01T12:00:00Z")`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub struct ModifierBefore {
+    span: Span,
+    pub before: Before,
+    pub lparen: LeftParen,
+    pub val: LitString,
+    pub rparen: RightParen,
+}
+
+impl Hash for ModifierBefore {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.before.hash(state);
+        self.lparen.hash(state);
+        self.val.hash(state);
+        self.rparen.hash(state);
+    }
+}
+
+/// Modifier expression that tells incat operation in which order the category lists its members.
+/// `sortby(sortkey)`, `sortby(timestamp)`, optionally followed by a direction, eg. `sortby(timestamp, desc)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub struct ModifierSortBy {
+    span: Span,
+    pub sortby: SortBy,
+    pub lparen: LeftParen,
+    pub key: CategorySortKey,
+    pub comma: Option<Comma>,
+    pub dir: Option<SortDirection>,
+    pub rparen: RightParen,
+}
+
+impl Hash for ModifierSortBy {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.sortby.hash(state);
+        self.lparen.hash(state);
+        self.key.hash(state);
+        // an absent direction is ascending.
+        matches!(self.dir, Some(SortDirection::Desc(_))).hash(state);
+        self.rparen.hash(state);
+    }
+}
+
+/// The order of a sortby modifier.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub enum CategorySortKey {
+    /// By the sort key of the page in the category.
+    Sortkey(Sortkey),
+    /// By the time the page was added to the category.
+    Timestamp(Timestamp),
+}
+
+impl CategorySortKey {
+    /// Get the span for this item.
+    pub fn get_span(&self) -> Span {
+        match self {
+            Self::Sortkey(x) => x.get_span(),
+            Self::Timestamp(x) => x.get_span(),
+        }
+    }
+}
+
+impl Display for CategorySortKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sortkey(x) => write!(f, "{x}"),
+            Self::Timestamp(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+/// The direction of a sortby modifier.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub enum SortDirection {
+    Asc(Asc),
+    Desc(Desc),
+}
+
+impl SortDirection {
+    /// Get the span for this item.
+    pub fn get_span(&self) -> Span {
+        match self {
+            Self::Asc(x) => x.get_span(),
+            Self::Desc(x) => x.get_span(),
+        }
+    }
+}
+
+impl Display for SortDirection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Asc(x) => write!(f, "{x}"),
+            Self::Desc(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+/// The order of a sort modifier.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub enum SortKey {
+    /// By namespace, then by title.
+    Title(Title),
+    /// By page id.
+    PageId(PageId),
+}
+
+impl SortKey {
+    /// Get the span for this item.
+    pub fn get_span(&self) -> Span {
+        match self {
+            Self::Title(x) => x.get_span(),
+            Self::PageId(x) => x.get_span(),
+        }
+    }
+}
+
+impl Display for SortKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Title(x) => write!(f, "{x}"),
+            Self::PageId(x) => write!(f, "{x}"),
+        }
+    }
+}
+
 expose_span!(ModifierLimit);
 expose_span!(ModifierResolve);
 expose_span!(ModifierNs);
@@ -172,3 +427,34 @@ expose_span!(ModifierDepth);
 expose_span!(ModifierNoRedir);
 expose_span!(ModifierOnlyRedir);
 expose_span!(ModifierDirect);
+expose_span!(ModifierOffset);
+expose_span!(ModifierSort);
+expose_span!(ModifierSince);
+expose_span!(ModifierBefore);
+/// Modifier expression that keeps a random sample of the results, optionally with a seed to draw the same sample again.
+/// `random(10)` or `random(10, 42)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
+pub struct ModifierRandom {
+    span: Span,
+    pub random: Random,
+    pub lparen: LeftParen,
+    pub val: LitInt,
+    pub comma: Option<Comma>,
+    pub seed: Option<LitInt>,
+    pub rparen: RightParen,
+}
+
+impl Hash for ModifierRandom {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.random.hash(state);
+        self.lparen.hash(state);
+        self.val.hash(state);
+        self.comma.hash(state);
+        self.seed.hash(state);
+        self.rparen.hash(state);
+    }
+}
+
+expose_span!(ModifierSortBy);
+expose_span!(ModifierRandom);
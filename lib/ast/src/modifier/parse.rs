@@ -5,15 +5,18 @@ use crate::{
     LocatedStr,
     make_range,
     parse_util::{whitespace, leading_whitespace, alternating1},
-    literal::{LitInt, LitIntOrInf},
+    literal::{LitInt, LitIntOrInf, LitNamespace, LitString},
     token::{
         LeftParen, RightParen, Comma,
-        Limit, Resolve, Ns, Depth, NoRedir, OnlyRedir, Direct,
+        Limit, Resolve, Ns, Depth, NoRedir, OnlyRedir, Direct, Offset, Sort, Title, PageId, Since, Before, SortBy, Sortkey, Timestamp, Asc, Desc, Random,
     },
 };
 use super::{
     Modifier,
-    ModifierLimit, ModifierResolve, ModifierNs, ModifierDepth, ModifierNoRedir, ModifierOnlyRedir, ModifierDirect,
+    ModifierLimit, ModifierResolve, ModifierNs, ModifierDepth, ModifierNoRedir, ModifierOnlyRedir, ModifierDirect, ModifierOffset, ModifierSort, SortKey,
+    ModifierSince, ModifierBefore,
+    ModifierSortBy, CategorySortKey, SortDirection,
+    ModifierRandom,
 };
 
 use nom::{
@@ -51,6 +54,12 @@ impl Modifier {
             map(ModifierNoRedir::parse_internal, Self::NoRedir),
             map(ModifierOnlyRedir::parse_internal, Self::OnlyRedir),
             map(ModifierDirect::parse_internal, Self::Direct),
+            map(ModifierOffset::parse_internal, Self::Offset),
+            map(ModifierSort::parse_internal, Self::Sort),
+            map(ModifierSince::parse_internal, Self::Since),
+            map(ModifierBefore::parse_internal, Self::Before),
+            map(ModifierSortBy::parse_internal, Self::SortBy),
+            map(ModifierRandom::parse_internal, Self::Random),
         ))(program)
     }
 }
@@ -78,7 +87,7 @@ impl ModifierNs {
             leading_whitespace(LeftParen::parse_internal),
             alternating1(
                 leading_whitespace(Comma::parse_internal),
-                leading_whitespace(LitInt::parse_internal),
+                leading_whitespace(LitNamespace::parse_internal),
             ),
             leading_whitespace(RightParen::parse_internal),
             position,
@@ -95,8 +104,145 @@ impl ModifierNs {
     }
 }
 
+impl ModifierSort {
+    /// Parse the modifier from a raw piece of source text. Leading and trailing whitespaces are automatically removed.
+    pub fn parse<'a, E>(program: &'a str) -> Result<Self, E>
+    where
+        E: ParseError<LocatedStr<'a>>,
+    {
+        let span = LocatedStr::new(program);
+        all_consuming(
+            whitespace(Self::parse_internal::<E>)
+        )(span).finish().map(|(_, x)| x)
+    }
+
+    /// Parse the modifier from a span. Assume no whitespaces before.
+    pub(crate) fn parse_internal<'a, E>(program: LocatedStr<'a>) -> IResult<LocatedStr<'a>, Self, E>
+    where
+        E: ParseError<LocatedStr<'a>>,
+    {
+        let (residual, (pos_start, sort, lparen, key, rparen, pos_end)) = tuple((
+            position,
+            Sort::parse_internal,
+            leading_whitespace(LeftParen::parse_internal),
+            leading_whitespace(alt((
+                map(Title::parse_internal, SortKey::Title),
+                map(PageId::parse_internal, SortKey::PageId),
+            ))),
+            leading_whitespace(RightParen::parse_internal),
+            position,
+        ))(program)?;
+        let modifier_sort = Self {
+            span: make_range(pos_start.location_offset(), pos_end.location_offset()),
+            sort,
+            lparen,
+            key,
+            rparen,
+        };
+        Ok((residual, modifier_sort))
+    }
+}
+
+impl ModifierSortBy {
+    /// Parse the modifier from a raw piece of source text. Leading and trailing whitespaces are automatically removed.
+    pub fn parse<'a, E>(program: &'a str) -> Result<Self, E>
+    where
+        E: ParseError<LocatedStr<'a>>,
+    {
+        let span = LocatedStr::new(program);
+        all_consuming(
+            whitespace(Self::parse_internal::<E>)
+        )(span).finish().map(|(_, x)| x)
+    }
+
+    /// Parse the modifier from a span. Assume no whitespaces before.
+    pub(crate) fn parse_internal<'a, E>(program: LocatedStr<'a>) -> IResult<LocatedStr<'a>, Self, E>
+    where
+        E: ParseError<LocatedStr<'a>>,
+    {
+        let (residual, (pos_start, sortby, lparen, key, opt_dir, rparen, pos_end)) = tuple((
+            position,
+            SortBy::parse_internal,
+            leading_whitespace(LeftParen::parse_internal),
+            leading_whitespace(alt((
+                map(Sortkey::parse_internal, CategorySortKey::Sortkey),
+                map(Timestamp::parse_internal, CategorySortKey::Timestamp),
+            ))),
+            opt(tuple((
+                leading_whitespace(Comma::parse_internal),
+                leading_whitespace(alt((
+                    map(Asc::parse_internal, SortDirection::Asc),
+                    map(Desc::parse_internal, SortDirection::Desc),
+                ))),
+            ))),
+            leading_whitespace(RightParen::parse_internal),
+            position,
+        ))(program)?;
+        let (comma, dir) = match opt_dir {
+            Some((comma, dir)) => (Some(comma), Some(dir)),
+            None => (None, None),
+        };
+        let modifier_sortby = Self {
+            span: make_range(pos_start.location_offset(), pos_end.location_offset()),
+            sortby,
+            lparen,
+            key,
+            comma,
+            dir,
+            rparen,
+        };
+        Ok((residual, modifier_sortby))
+    }
+}
+
+impl ModifierRandom {
+    /// Parse the modifier from a raw piece of source text. Leading and trailing whitespaces are automatically removed.
+    pub fn parse<'a, E>(program: &'a str) -> Result<Self, E>
+    where
+        E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
+    {
+        let span = LocatedStr::new(program);
+        all_consuming(
+            whitespace(Self::parse_internal::<E>)
+        )(span).finish().map(|(_, x)| x)
+    }
+
+    /// Parse the modifier from a span. Assume no whitespaces before.
+    pub(crate) fn parse_internal<'a, E>(program: LocatedStr<'a>) -> IResult<LocatedStr<'a>, Self, E>
+    where
+        E: ParseError<LocatedStr<'a>> + FromExternalError<LocatedStr<'a>, ParseIntError>,
+    {
+        let (residual, (pos_start, random, lparen, val, opt_seed, rparen, pos_end)) = tuple((
+            position,
+            Random::parse_internal,
+            leading_whitespace(LeftParen::parse_internal),
+            leading_whitespace(LitInt::parse_internal),
+            opt(tuple((
+                leading_whitespace(Comma::parse_internal),
+                leading_whitespace(LitInt::parse_internal),
+            ))),
+            leading_whitespace(RightParen::parse_internal),
+            position,
+        ))(program)?;
+        let (comma, seed) = match opt_seed {
+            Some((comma, seed)) => (Some(comma), Some(seed)),
+            None => (None, None),
+        };
+        let modifier_random = Self {
+            span: make_range(pos_start.location_offset(), pos_end.location_offset()),
+            random,
+            lparen,
+            val,
+            comma,
+            seed,
+            rparen,
+        };
+        Ok((residual, modifier_random))
+    }
+}
+
 macro_rules! intorlimit_modifier_parse {
-    ($name:ident, $token_field:ident, $token:ident) => {
+    ($name:ident, $token_field:ident, $token:ident, $lit:ident) => {
         impl $name {
             /// Parse the modifier from a raw piece of source text. Leading and trailing whitespaces are automatically removed.
             pub fn parse<'a, E>(program: &'a str) -> Result<Self, E>
@@ -118,7 +264,7 @@ macro_rules! intorlimit_modifier_parse {
                     position,
                     $token::parse_internal,
                     leading_whitespace(LeftParen::parse_internal),
-                    leading_whitespace(LitIntOrInf::parse_internal),
+                    leading_whitespace($lit::parse_internal),
                     leading_whitespace(RightParen::parse_internal),
                     position,
                 ))(program)?;
@@ -135,8 +281,11 @@ macro_rules! intorlimit_modifier_parse {
     }
 }
 
-intorlimit_modifier_parse!(ModifierLimit, limit, Limit);
-intorlimit_modifier_parse!(ModifierDepth, depth, Depth);
+intorlimit_modifier_parse!(ModifierLimit, limit, Limit, LitIntOrInf);
+intorlimit_modifier_parse!(ModifierDepth, depth, Depth, LitIntOrInf);
+intorlimit_modifier_parse!(ModifierOffset, offset, Offset, LitInt);
+intorlimit_modifier_parse!(ModifierSince, since, Since, LitString);
+intorlimit_modifier_parse!(ModifierBefore, before, Before, LitString);
 
 macro_rules! no_param_modifier_parse {
     ($name:ident, $token_field:ident, $token:ident) => {
@@ -191,11 +340,14 @@ no_param_modifier_parse!(ModifierDirect, direct, Direct);
 
 #[cfg(test)]
 mod test {
-    use alloc::{format, vec, vec::Vec};
-    use crate::{LocatedStr, IntOrInf, literal::LitInt};
+    use alloc::{format, string::ToString, vec, vec::Vec};
+    use crate::{LocatedStr, IntOrInf, literal::LitNamespace};
     use super::{
         Modifier,
-        ModifierLimit, ModifierResolve, ModifierNs, ModifierDepth, ModifierNoRedir, ModifierOnlyRedir, ModifierDirect,
+        ModifierLimit, ModifierResolve, ModifierNs, ModifierDepth, ModifierNoRedir, ModifierOnlyRedir, ModifierDirect, ModifierOffset, ModifierSort, SortKey,
+        ModifierSince, ModifierBefore,
+        ModifierSortBy, CategorySortKey, SortDirection,
+        ModifierRandom,
     };
     use nom::error::Error;
 
@@ -244,8 +396,11 @@ mod test {
 
     #[test]
     fn test_parse_modifier_ns() {
-        fn extract_nums(lits: &[LitInt]) -> Vec<i32> {
-            lits.iter().map(|x| x.val).collect()
+        fn extract_nums(lits: &[LitNamespace]) -> Vec<i32> {
+            lits.iter().map(|x| match x {
+                LitNamespace::Id(x) => x.val,
+                x => panic!("unexpected namespace `{x}`"),
+            }).collect()
         }
 
         let input_1 = "ns(0)";
@@ -279,6 +434,57 @@ mod test {
         assert_eq!(mod_4.get_span().start, 1);
     }
 
+    #[test]
+    fn test_parse_modifier_ns_names() {
+        let input_1 = "ns(Category, Template)";
+        let input_2 = " ns ( 0, Template_talk , \"User talk\", -1 ) ";
+
+        let mod_1 = ModifierNs::parse::<Error<LocatedStr<'_>>>(input_1).unwrap();
+        let mod_2 = ModifierNs::parse::<Error<LocatedStr<'_>>>(input_2).unwrap();
+
+        assert!(matches!(&mod_1.vals[..], [LitNamespace::Name(a), LitNamespace::Name(b)] if a.val == "Category" && b.val == "Template"));
+        assert!(matches!(
+            &mod_2.vals[..],
+            [LitNamespace::Id(a), LitNamespace::Name(b), LitNamespace::Name(c), LitNamespace::Id(d)]
+                if a.val == 0 && b.val == "Template_talk" && c.val == "User talk" && d.val == -1
+        ));
+
+        assert_eq!(&input_1[mod_1.vals[1].get_span().to_range()], "Template");
+        assert_eq!(&input_2[mod_2.vals[1].get_span().to_range()], "Template_talk");
+        assert_eq!(&input_2[mod_2.vals[2].get_span().to_range()], "\"User talk\"");
+
+        assert_eq!(Modifier::Ns(mod_2).to_string(), "ns(0, Template_talk, \"User talk\", -1)");
+
+        // a name cannot start with a digit.
+        assert!(ModifierNs::parse::<Error<LocatedStr<'_>>>("ns(1abc)").is_err());
+    }
+
+    #[test]
+    fn test_parse_modifier_ns_wildcard() {
+        let input_1 = "ns(*)";
+        let input_2 = " ns ( !0, ! Talk , !\"User talk\", 14 ) ";
+
+        let mod_1 = ModifierNs::parse::<Error<LocatedStr<'_>>>(input_1).unwrap();
+        let mod_2 = ModifierNs::parse::<Error<LocatedStr<'_>>>(input_2).unwrap();
+
+        assert!(matches!(&mod_1.vals[..], [LitNamespace::All(_)]));
+        assert!(matches!(
+            &mod_2.vals[..],
+            [LitNamespace::Not(a), LitNamespace::Not(b), LitNamespace::Not(c), LitNamespace::Id(_)]
+                if matches!(&*a.val, LitNamespace::Id(x) if x.val == 0)
+                    && matches!(&*b.val, LitNamespace::Name(x) if x.val == "Talk")
+                    && matches!(&*c.val, LitNamespace::Name(x) if x.val == "User talk")
+        ));
+        assert_eq!(&input_2[mod_2.vals[1].get_span().to_range()], "! Talk");
+
+        assert_eq!(Modifier::Ns(mod_1).to_string(), "ns(*)");
+        assert_eq!(Modifier::Ns(mod_2).to_string(), "ns(!0, !Talk, !\"User talk\", 14)");
+
+        // only a single namespace can be excluded.
+        assert!(ModifierNs::parse::<Error<LocatedStr<'_>>>("ns(!*)").is_err());
+        assert!(ModifierNs::parse::<Error<LocatedStr<'_>>>("ns(!!0)").is_err());
+    }
+
     macro_rules! intorinf_modifier_make_test {
         ($test:ident, $target:ident, $lit:literal) => {
             #[test]
@@ -319,6 +525,87 @@ mod test {
     intorinf_modifier_make_test!(test_parse_modifier_limit, ModifierLimit, "limit");
     intorinf_modifier_make_test!(test_parse_modifier_depth, ModifierDepth, "depth");
 
+    #[test]
+    fn test_parse_modifier_offset() {
+        let input_1 = "offset(0)";
+        let input_2 = "  OFFSET ( 20 ) ";
+
+        let mod_1 = ModifierOffset::parse::<Error<LocatedStr<'_>>>(input_1).unwrap();
+        let mod_2 = ModifierOffset::parse::<Error<LocatedStr<'_>>>(input_2).unwrap();
+
+        assert_eq!(mod_1.val.val, 0);
+        assert_eq!(mod_2.val.val, 20);
+        assert_eq!(&input_2[mod_2.val.get_span().to_range()], "20");
+        assert_eq!(&input_2[mod_2.get_span().to_range()], "OFFSET ( 20 )");
+        assert_eq!(mod_2.get_span().start, 2);
+        assert_eq!(Modifier::Offset(mod_2).to_string(), "offset(20)");
+
+        assert!(matches!(Modifier::parse::<Error<LocatedStr<'_>>>("offset(5)").unwrap(), Modifier::Offset(_)));
+        // an offset is always finite.
+        assert!(ModifierOffset::parse::<Error<LocatedStr<'_>>>("offset(inf)").is_err());
+    }
+
+    #[test]
+    fn test_parse_modifier_since_before() {
+        let input_1 = "since(\"2024-01-01\")";
+        let input_2 = " BEFORE ( \"2024-01-08T00:00:00Z\" ) ";
+
+        let mod_1 = ModifierSince::parse::<Error<LocatedStr<'_>>>(input_1).unwrap();
+        let mod_2 = ModifierBefore::parse::<Error<LocatedStr<'_>>>(input_2).unwrap();
+
+        assert_eq!(mod_1.val.val, "2024-01-01");
+        assert_eq!(mod_2.val.val, "2024-01-08T00:00:00Z");
+        assert_eq!(&input_2[mod_2.get_span().to_range()], "BEFORE ( \"2024-01-08T00:00:00Z\" )");
+        assert_eq!(Modifier::Since(mod_1).to_string(), "since(\"2024-01-01\")");
+        assert_eq!(Modifier::Before(mod_2).to_string(), "before(\"2024-01-08T00:00:00Z\")");
+
+        assert!(matches!(Modifier::parse::<Error<LocatedStr<'_>>>("since(\"2024-01-01\")").unwrap(), Modifier::Since(_)));
+        assert!(matches!(Modifier::parse::<Error<LocatedStr<'_>>>("before(\"2024-01-01\")").unwrap(), Modifier::Before(_)));
+        assert!(ModifierSince::parse::<Error<LocatedStr<'_>>>("since(20240101)").is_err());
+    }
+
+    #[test]
+    fn test_parse_modifier_sortby() {
+        let input_1 = "sortby(sortkey)";
+        let input_2 = " SortBy ( TIMESTAMP , desc )  ";
+
+        let mod_1 = ModifierSortBy::parse::<Error<LocatedStr<'_>>>(input_1).unwrap();
+        let mod_2 = ModifierSortBy::parse::<Error<LocatedStr<'_>>>(input_2).unwrap();
+
+        assert!(matches!(mod_1.key, CategorySortKey::Sortkey(_)));
+        assert!(mod_1.dir.is_none());
+        assert!(matches!(mod_2.key, CategorySortKey::Timestamp(_)));
+        assert!(matches!(mod_2.dir, Some(SortDirection::Desc(_))));
+        assert_eq!(&input_2[mod_2.get_span().to_range()], "SortBy ( TIMESTAMP , desc )");
+        assert_eq!(Modifier::SortBy(mod_1).to_string(), "sortby(sortkey)");
+        assert_eq!(Modifier::SortBy(mod_2).to_string(), "sortby(timestamp, desc)");
+
+        // `sort` is a prefix of `sortby`, and the other way around.
+        assert!(matches!(Modifier::parse::<Error<LocatedStr<'_>>>("sortby(timestamp, asc)").unwrap(), Modifier::SortBy(_)));
+        assert!(matches!(Modifier::parse::<Error<LocatedStr<'_>>>("sort(title)").unwrap(), Modifier::Sort(_)));
+        assert!(ModifierSortBy::parse::<Error<LocatedStr<'_>>>("sortby(title)").is_err());
+        assert!(ModifierSortBy::parse::<Error<LocatedStr<'_>>>("sortby(sortkey,)").is_err());
+    }
+
+    #[test]
+    fn test_parse_modifier_sort() {
+        let input_1 = "sort(title)";
+        let input_2 = " Sort ( PAGEID )  ";
+
+        let mod_1 = ModifierSort::parse::<Error<LocatedStr<'_>>>(input_1).unwrap();
+        let mod_2 = ModifierSort::parse::<Error<LocatedStr<'_>>>(input_2).unwrap();
+
+        assert!(matches!(mod_1.key, SortKey::Title(_)));
+        assert!(matches!(mod_2.key, SortKey::PageId(_)));
+        assert_eq!(&input_2[mod_2.key.get_span().to_range()], "PAGEID");
+        assert_eq!(&input_2[mod_2.get_span().to_range()], "Sort ( PAGEID )");
+        assert_eq!(Modifier::Sort(mod_2).to_string(), "sort(pageid)");
+
+        assert!(matches!(Modifier::parse::<Error<LocatedStr<'_>>>("sort(title)").unwrap(), Modifier::Sort(_)));
+        assert!(ModifierSort::parse::<Error<LocatedStr<'_>>>("sort(length)").is_err());
+        assert!(ModifierSort::parse::<Error<LocatedStr<'_>>>("sort").is_err());
+    }
+
     macro_rules! no_param_modifier_make_test {
         ($test:ident, $target:ident, $lit:literal) => {
             #[test]
@@ -359,4 +646,26 @@ mod test {
     no_param_modifier_make_test!(test_parse_modifier_noredir, ModifierNoRedir, "noredir");
     no_param_modifier_make_test!(test_parse_modifier_onlyredir, ModifierOnlyRedir, "onlyredir");
     no_param_modifier_make_test!(test_parse_modifier_direct, ModifierDirect, "direct");
+
+    #[test]
+    fn test_parse_modifier_random() {
+        let input_1 = "random(10)";
+        let input_2 = " Random ( 5 , -42 ) ";
+
+        let mod_1 = ModifierRandom::parse::<Error<LocatedStr<'_>>>(input_1).unwrap();
+        let mod_2 = ModifierRandom::parse::<Error<LocatedStr<'_>>>(input_2).unwrap();
+
+        assert_eq!(mod_1.val.val, 10);
+        assert!(mod_1.comma.is_none() && mod_1.seed.is_none());
+        assert_eq!(mod_2.val.val, 5);
+        assert_eq!(mod_2.seed.as_ref().map(|x| x.val), Some(-42));
+        assert_eq!(&input_2[mod_2.get_span().to_range()], "Random ( 5 , -42 )");
+        assert_eq!(Modifier::Random(mod_1).to_string(), "random(10)");
+        assert_eq!(Modifier::Random(mod_2).to_string(), "random(5, -42)");
+
+        assert!(matches!(Modifier::parse::<Error<LocatedStr<'_>>>("random(1, 2)").unwrap(), Modifier::Random(_)));
+        assert!(ModifierRandom::parse::<Error<LocatedStr<'_>>>("random()").is_err());
+        assert!(ModifierRandom::parse::<Error<LocatedStr<'_>>>("random(1,)").is_err());
+        assert!(ModifierRandom::parse::<Error<LocatedStr<'_>>>("random(inf)").is_err());
+    }
 }
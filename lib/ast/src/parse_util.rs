@@ -1,27 +1,51 @@
 //! Parser utilities.
 
 use alloc::vec::Vec;
+use core::ops::RangeFrom;
 use nom::{
     IResult,
-    AsChar, InputLength, InputTakeAtPosition, Parser,
-    character::complete::multispace0,
+    AsChar, InputIter, InputLength, InputTakeAtPosition, Parser, Slice,
+    branch::alt,
+    bytes::complete::take_till,
+    character::complete::{char, multispace1},
+    combinator::value,
     error::ParseError,
+    multi::many0_count,
     sequence::{delimited, preceded, terminated},
 };
 
+/// Consume any amount of insignificant text, that is, whitespaces and line comments.
+/// A line comment starts with `#` and runs to the end of the line.
+pub(crate) fn blank<I, E>(input: I) -> IResult<I, (), E>
+where
+    I: Clone + InputLength + InputIter + InputTakeAtPosition + Slice<RangeFrom<usize>>,
+    <I as InputIter>::Item: AsChar,
+    <I as InputTakeAtPosition>::Item: AsChar + Clone,
+    E: ParseError<I>,
+{
+    value(
+        (),
+        many0_count(alt((
+            multispace1,
+            preceded(char('#'), take_till(|c: <I as InputTakeAtPosition>::Item| c.as_char() == '\n')),
+        ))),
+    )(input)
+}
+
 /// A combinator that takes a parser `inner` and produces a parser that also consumes both leading and 
-/// trailing whitespace, returning the output of `inner`.
+/// trailing whitespace (including comments), returning the output of `inner`.
 pub(crate) fn whitespace<'a, I, O, E, F>(inner: F) -> impl FnMut(I) -> IResult<I, O, E>
 where
-    I: InputTakeAtPosition + 'a,
+    I: Clone + InputLength + InputIter + InputTakeAtPosition + Slice<RangeFrom<usize>> + 'a,
+    <I as InputIter>::Item: AsChar,
     <I as InputTakeAtPosition>::Item: AsChar + Clone,
     F: Parser<I, O, E> + 'a,
     E: ParseError<I>,
 {
     delimited(
-        multispace0,
+        blank,
         inner,
-        multispace0
+        blank
     )
 }
 
@@ -29,13 +53,14 @@ where
 /// whitespace, returning the output of `inner`.
 pub(crate) fn leading_whitespace<'a, I, O, E, F>(inner: F) -> impl FnMut(I) -> IResult<I, O, E>
 where
-    I: InputTakeAtPosition + 'a,
+    I: Clone + InputLength + InputIter + InputTakeAtPosition + Slice<RangeFrom<usize>> + 'a,
+    <I as InputIter>::Item: AsChar,
     <I as InputTakeAtPosition>::Item: AsChar + Clone,
     F: Parser<I, O, E> + 'a,
     E: ParseError<I>,
 {
     preceded(
-        multispace0,
+        blank,
         inner,
     )
 }
@@ -45,14 +70,15 @@ where
 #[allow(dead_code)]
 pub(crate) fn trailing_whitespace<'a, I, O, E, F>(inner: F) -> impl FnMut(I) -> IResult<I, O, E>
 where
-    I: InputTakeAtPosition + 'a,
+    I: Clone + InputLength + InputIter + InputTakeAtPosition + Slice<RangeFrom<usize>> + 'a,
+    <I as InputIter>::Item: AsChar,
     <I as InputTakeAtPosition>::Item: AsChar + Clone,
     F: Parser<I, O, E> + 'a,
     E: ParseError<I>,
 {
     terminated(
         inner,
-        multispace0,
+        blank,
     )
 }
 
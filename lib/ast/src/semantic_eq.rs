@@ -0,0 +1,160 @@
+//! Structural equality that ignores spans.
+//!
+//! Like the `Hash` implementations, only the parts that affect the meaning of a query are compared.
+//! Punctuation is skipped, so `resolve` equals `resolve()` and a bare string list equals `page(...)`.
+//! Parentheses are still nodes of the tree, and are compared as such.
+
+use alloc::{boxed::Box, vec::Vec};
+use crate::attribute::{Attribute, AttributeModifier, AttributeFilter};
+use crate::expr::{
+    Expression,
+    ExpressionAnd, ExpressionAdd, ExpressionSub, ExpressionXor,
+    ExpressionParen,
+    ExpressionPage, ExpressionLink, ExpressionLinkTo, ExpressionEmbed, ExpressionInCat, ExpressionPrefix, ExpressionToggle, ExpressionTemplatesIn, ExpressionFiles, ExpressionFileUsage, ExpressionCategoriesOf, ExpressionLangLinks,
+    ExpressionNot, ExpressionPageId,
+};
+use crate::filter::{Filter, FilterProtected, FilterUnprotected, FilterMatches};
+use crate::literal::{LitString, LitIntOrInf, LitInt, LitNamespace, LitNamespaceNot};
+use crate::token::Star;
+use crate::modifier::{
+    Modifier,
+    ModifierLimit, ModifierResolve, ModifierNs, ModifierDepth, ModifierNoRedir, ModifierOnlyRedir, ModifierDirect, ModifierOffset, ModifierSort, SortKey, ModifierSince, ModifierBefore, ModifierSortBy, SortDirection, ModifierRandom,
+};
+
+pub(crate) trait SemanticEq {
+    fn semantic_eq(&self, other: &Self) -> bool;
+}
+
+impl<T: SemanticEq + ?Sized> SemanticEq for Box<T> {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        (**self).semantic_eq(other)
+    }
+}
+
+impl<T: SemanticEq> SemanticEq for Vec<T> {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a.semantic_eq(b))
+    }
+}
+
+impl<T: SemanticEq> SemanticEq for Option<T> {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Some(a), Some(b)) => a.semantic_eq(b),
+            (a, b) => a.is_none() && b.is_none(),
+        }
+    }
+}
+
+/// Compare the listed fields of a struct.
+macro_rules! semantic_eq_fields {
+    ($class:ident $(, $field:ident)*) => {
+        impl SemanticEq for $class {
+            #[allow(unused_variables)]
+            fn semantic_eq(&self, other: &Self) -> bool {
+                true $(&& self.$field.semantic_eq(&other.$field))*
+            }
+        }
+    }
+}
+
+/// Compare an enum whose variants each hold one value.
+macro_rules! semantic_eq_variants {
+    ($class:ident, $($variant:ident),+) => {
+        impl SemanticEq for $class {
+            fn semantic_eq(&self, other: &Self) -> bool {
+                match (self, other) {
+                    $((Self::$variant(a), Self::$variant(b)) => a.semantic_eq(b),)+
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+/// Compare the values of a literal.
+macro_rules! semantic_eq_val {
+    ($class:ident) => {
+        impl SemanticEq for $class {
+            fn semantic_eq(&self, other: &Self) -> bool {
+                self.val == other.val
+            }
+        }
+    }
+}
+
+semantic_eq_val!(LitString);
+semantic_eq_val!(LitIntOrInf);
+semantic_eq_val!(LitInt);
+semantic_eq_variants!(LitNamespace, Id, Name, All, Not);
+semantic_eq_fields!(LitNamespaceNot, val);
+semantic_eq_fields!(Star);
+
+semantic_eq_variants!(
+    Expression,
+    And, Add, Sub, Xor, Paren, Page, PageId, Link, LinkTo, Embed, InCat, Prefix, Toggle, TemplatesIn, Files, FileUsage, CategoriesOf, LangLinks, Not
+);
+semantic_eq_fields!(ExpressionAnd, expr1, expr2);
+semantic_eq_fields!(ExpressionAdd, expr1, expr2);
+semantic_eq_fields!(ExpressionSub, expr1, expr2);
+semantic_eq_fields!(ExpressionXor, expr1, expr2);
+semantic_eq_fields!(ExpressionParen, expr);
+semantic_eq_fields!(ExpressionPage, vals);
+semantic_eq_fields!(ExpressionPageId, vals);
+semantic_eq_fields!(ExpressionLink, expr, attributes);
+semantic_eq_fields!(ExpressionLinkTo, expr, attributes);
+semantic_eq_fields!(ExpressionEmbed, expr, attributes);
+semantic_eq_fields!(ExpressionInCat, expr, attributes);
+semantic_eq_fields!(ExpressionPrefix, expr, attributes);
+semantic_eq_fields!(ExpressionToggle, expr);
+semantic_eq_fields!(ExpressionNot, expr, universe);
+semantic_eq_fields!(ExpressionTemplatesIn, expr, attributes);
+semantic_eq_fields!(ExpressionFiles, expr, attributes);
+semantic_eq_fields!(ExpressionFileUsage, expr, attributes);
+semantic_eq_fields!(ExpressionCategoriesOf, expr, attributes);
+semantic_eq_fields!(ExpressionLangLinks, expr, attributes);
+
+semantic_eq_variants!(Attribute, Modifier, Filter);
+semantic_eq_fields!(AttributeModifier, modifier);
+semantic_eq_fields!(AttributeFilter, filter);
+
+semantic_eq_variants!(Modifier, Limit, Resolve, Ns, Depth, NoRedir, OnlyRedir, Direct, Offset, Sort, Since, Before, SortBy, Random);
+semantic_eq_fields!(ModifierLimit, val);
+semantic_eq_fields!(ModifierResolve);
+semantic_eq_fields!(ModifierNs, vals);
+semantic_eq_fields!(ModifierDepth, val);
+semantic_eq_fields!(ModifierNoRedir);
+semantic_eq_fields!(ModifierOnlyRedir);
+semantic_eq_fields!(ModifierDirect);
+semantic_eq_fields!(ModifierOffset, val);
+semantic_eq_fields!(ModifierSince, val);
+semantic_eq_fields!(ModifierBefore, val);
+semantic_eq_fields!(ModifierSort, key);
+
+/// Samples of the same size are equal if their seeds are. Two unseeded samples are equal too, though each draws
+/// a different sample. The memo of the solver does not share them.
+impl SemanticEq for ModifierRandom {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        self.val.semantic_eq(&other.val) && self.seed.as_ref().map(|x| x.val) == other.seed.as_ref().map(|x| x.val)
+    }
+}
+
+impl SemanticEq for SortKey {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        core::mem::discriminant(self) == core::mem::discriminant(other)
+    }
+}
+
+/// An absent direction is ascending.
+impl SemanticEq for ModifierSortBy {
+    fn semantic_eq(&self, other: &Self) -> bool {
+        let descending = |x: &Self| matches!(x.dir, Some(SortDirection::Desc(_)));
+        core::mem::discriminant(&self.key) == core::mem::discriminant(&other.key) && descending(self) == descending(other)
+    }
+}
+
+semantic_eq_variants!(Filter, Protected, Unprotected, Matches);
+semantic_eq_fields!(FilterProtected);
+semantic_eq_fields!(FilterUnprotected);
+// `matches` and `matchtitle` are the same filter.
+semantic_eq_fields!(FilterMatches, val);
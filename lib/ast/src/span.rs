@@ -3,8 +3,11 @@
 //! We don't use a span as an iterator itself. Using `Clone` is just awkward.
 
 use core::{hash::Hash, ops::Range};
+#[cfg(feature = "use_serde")]
+use serde::{Serialize, Deserialize};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub struct Span<T=usize> {
     pub start: T,
     pub end: T,
@@ -6,6 +6,7 @@ pub mod parse;
 macro_rules! define_token {
     ($name:ident, $hashas:literal) => {
         #[derive(Debug, Clone, PartialEq, Eq)]
+        #[cfg_attr(feature = "use_serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
         pub struct $name {
             span: crate::Span,
         }
@@ -15,6 +16,11 @@ macro_rules! define_token {
                 $hashas.hash(state);
             }
         }
+        impl core::fmt::Display for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str($hashas)
+            }
+        }
     };
 }
 
@@ -26,6 +32,8 @@ define_token!(And, "&");                    // `&`
 define_token!(Add, "+");                    // `+`
 define_token!(Sub, "-");                    // `-`
 define_token!(Caret, "^");                  // `^`
+define_token!(Star, "*");                   // `*`
+define_token!(Bang, "!");                   // `!`
 define_token!(Page, "page");                // `page`
 define_token!(Link, "link");                // `link`
 define_token!(LinkTo, "linkto");            // `linkto`
@@ -33,6 +41,12 @@ define_token!(Embed, "embed");              // `embed`
 define_token!(InCat, "incat");              // `incat`
 define_token!(Prefix, "prefix");            // `prefix`
 define_token!(Toggle, "toggle");            // `toggle`
+define_token!(TemplatesIn, "templatesin");  // `templatesin`
+define_token!(Files, "files");              // `files`
+define_token!(FileUsage, "fileusage");      // `fileusage`
+define_token!(CategoriesOf, "categoriesof");// `categoriesof`
+define_token!(LangLinks, "langlinks");      // `langlinks`
+define_token!(Not, "not");                  // `not`
 define_token!(Limit, "limit");              // `limit`
 define_token!(Resolve, "resolve");          // `resolve`
 define_token!(Ns, "ns");                    // `ns`
@@ -40,3 +54,19 @@ define_token!(Depth, "depth");              // `depth`
 define_token!(NoRedir, "noredir");          // `noredir`
 define_token!(OnlyRedir, "onlyredir");      // `onlyredir`
 define_token!(Direct, "direct");            // `direct`
+define_token!(Offset, "offset");            // `offset`
+define_token!(Sort, "sort");                // `sort`
+define_token!(Title, "title");              // `title`
+define_token!(PageId, "pageid");            // `pageid`
+define_token!(Since, "since");              // `since`
+define_token!(Before, "before");            // `before`
+define_token!(SortBy, "sortby");            // `sortby`
+define_token!(Sortkey, "sortkey");          // `sortkey`
+define_token!(Timestamp, "timestamp");      // `timestamp`
+define_token!(Asc, "asc");                  // `asc`
+define_token!(Desc, "desc");                // `desc`
+define_token!(Random, "random");            // `random`
+define_token!(Protected, "protected");      // `protected`
+define_token!(Unprotected, "unprotected");  // `unprotected`
+define_token!(Matches, "matches");          // `matches`
+define_token!(MatchTitle, "matchtitle");    // `matchtitle`
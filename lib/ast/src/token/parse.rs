@@ -1,14 +1,18 @@
 //! Parse module for token types.
 
 use super::{
-    Dot, Comma, LeftParen, RightParen, And, Add, Sub, Caret,
-    Page, Link, LinkTo, Embed, InCat, Prefix, Toggle,
-    Limit, Resolve, Ns, Depth, NoRedir, OnlyRedir, Direct,
+    Dot, Comma, LeftParen, RightParen, And, Add, Sub, Caret, Star, Bang,
+    Page, Link, LinkTo, Embed, InCat, Prefix, Toggle, TemplatesIn, Files, FileUsage, CategoriesOf, LangLinks, Not,
+    Limit, Resolve, Ns, Depth, NoRedir, OnlyRedir, Direct, Offset, Sort, Title, PageId, Since, Before, SortBy, Sortkey, Timestamp, Asc, Desc, Random,
+    Protected, Unprotected, Matches, MatchTitle,
 };
 
 macro_rules! parse_token {
     ($token:ident, $lit:literal) => {
         impl $token {
+            /// The text of the token, in lowercase.
+            pub const LIT: &'static str = $lit;
+
             /// Parse the token from a raw piece of source text. Leading and trailing whitespaces are automatically removed.
             pub fn parse<'a, E>(program: &'a str) -> Result<Self, E>
             where
@@ -45,6 +49,8 @@ parse_token!(And, "&");
 parse_token!(Add, "+");
 parse_token!(Sub, "-");
 parse_token!(Caret, "^");
+parse_token!(Star, "*");
+parse_token!(Bang, "!");
 parse_token!(Page, "page");
 parse_token!(Link, "link");
 parse_token!(LinkTo, "linkto");
@@ -52,6 +58,12 @@ parse_token!(Embed, "embed");
 parse_token!(InCat, "incat");
 parse_token!(Prefix, "prefix");
 parse_token!(Toggle, "toggle");
+parse_token!(TemplatesIn, "templatesin");
+parse_token!(Files, "files");
+parse_token!(FileUsage, "fileusage");
+parse_token!(CategoriesOf, "categoriesof");
+parse_token!(LangLinks, "langlinks");
+parse_token!(Not, "not");
 parse_token!(Limit, "limit");
 parse_token!(Resolve, "resolve");
 parse_token!(Ns, "ns");
@@ -59,6 +71,31 @@ parse_token!(Depth, "depth");
 parse_token!(NoRedir, "noredir");
 parse_token!(OnlyRedir, "onlyredir");
 parse_token!(Direct, "direct");
+parse_token!(Offset, "offset");
+parse_token!(Sort, "sort");
+parse_token!(Title, "title");
+parse_token!(PageId, "pageid");
+parse_token!(Since, "since");
+parse_token!(Before, "before");
+parse_token!(SortBy, "sortby");
+parse_token!(Sortkey, "sortkey");
+parse_token!(Timestamp, "timestamp");
+parse_token!(Asc, "asc");
+parse_token!(Desc, "desc");
+parse_token!(Random, "random");
+parse_token!(Protected, "protected");
+parse_token!(Unprotected, "unprotected");
+parse_token!(Matches, "matches");
+parse_token!(MatchTitle, "matchtitle");
+
+/// Whether `word` is a keyword, in any case.
+pub(crate) fn is_keyword(word: &str) -> bool {
+    [
+        Page::LIT, Link::LIT, LinkTo::LIT, Embed::LIT, InCat::LIT, Prefix::LIT, Toggle::LIT, TemplatesIn::LIT, Files::LIT, FileUsage::LIT, CategoriesOf::LIT, LangLinks::LIT, Not::LIT,
+        Limit::LIT, Resolve::LIT, Ns::LIT, Depth::LIT, NoRedir::LIT, OnlyRedir::LIT, Direct::LIT, Offset::LIT, Sort::LIT, Title::LIT, PageId::LIT, Since::LIT, Before::LIT, SortBy::LIT, Sortkey::LIT, Timestamp::LIT, Asc::LIT, Desc::LIT, Random::LIT,
+        Protected::LIT, Unprotected::LIT, Matches::LIT, MatchTitle::LIT,
+    ].iter().any(|keyword| keyword.eq_ignore_ascii_case(word))
+}
 
 #[cfg(test)]
 mod test {
@@ -106,6 +143,8 @@ mod test {
     make_test!(test_parse_add, Add, "+");
     make_test!(test_parse_sub, Sub, "-");
     make_test!(test_parse_caret, Caret, "^");
+    make_test!(test_parse_star, Star, "*");
+    make_test!(test_parse_bang, Bang, "!");
     make_test!(test_parse_page, Page, "PaGe");
     make_test!(test_parse_link, Link, "LiNk");
     make_test!(test_parse_linkto, LinkTo, "LiNkTo");
@@ -113,6 +152,12 @@ mod test {
     make_test!(test_parse_incat, InCat, "InCaT");
     make_test!(test_parse_prefix, Prefix, "PrEfIx");
     make_test!(test_parse_toggle, Toggle, "ToGgLe");
+    make_test!(test_parse_templatesin, TemplatesIn, "TeMpLaTeSiN");
+    make_test!(test_parse_files, Files, "FiLeS");
+    make_test!(test_parse_fileusage, FileUsage, "FiLeUsAgE");
+    make_test!(test_parse_categoriesof, CategoriesOf, "CaTeGoRiEsOf");
+    make_test!(test_parse_langlinks, LangLinks, "LaNgLiNkS");
+    make_test!(test_parse_not, Not, "NoT");
     make_test!(test_parse_limit, Limit, "LiMiT");
     make_test!(test_parse_resolve, Resolve, "ReSoLvE");
     make_test!(test_parse_ns, Ns, "Ns");
@@ -120,4 +165,20 @@ mod test {
     make_test!(test_parse_noredir, NoRedir, "NoReDiR");
     make_test!(test_parse_onlyredir, OnlyRedir, "OnLyReDiR");
     make_test!(test_parse_direct, Direct, "DiReCt");
+    make_test!(test_parse_offset, Offset, "OfFsEt");
+    make_test!(test_parse_sort, Sort, "SoRt");
+    make_test!(test_parse_title, Title, "tItLe");
+    make_test!(test_parse_pageid, PageId, "PageID");
+    make_test!(test_parse_since, Since, "SiNcE");
+    make_test!(test_parse_before, Before, "BeFoRe");
+    make_test!(test_parse_sortby, SortBy, "SoRtBy");
+    make_test!(test_parse_sortkey, Sortkey, "SoRtKeY");
+    make_test!(test_parse_timestamp, Timestamp, "TiMeStAmP");
+    make_test!(test_parse_asc, Asc, "AsC");
+    make_test!(test_parse_desc, Desc, "DeSc");
+    make_test!(test_parse_random, Random, "RaNdOm");
+    make_test!(test_parse_protected, Protected, "PrOtEcTeD");
+    make_test!(test_parse_unprotected, Unprotected, "UnPrOtEcTeD");
+    make_test!(test_parse_matches, Matches, "MaTcHeS");
+    make_test!(test_parse_matchtitle, MatchTitle, "MaTcHtItLe");
 }
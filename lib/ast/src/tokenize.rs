@@ -0,0 +1,167 @@
+//! Splitting a query into lexical tokens, without parsing it.
+//!
+//! This is meant for syntax highlighting, so it never fails: text that is not a token becomes `Token::Error`,
+//! and the query need not be well-formed.
+
+use alloc::vec::Vec;
+use crate::{
+    LitInt, LitString, LocatedStr, Span,
+    Add, And, Bang, Caret, Comma, Dot, LeftParen, RightParen, Star, Sub,
+    literal::parse::parse_identifier,
+    make_range,
+    token::parse::is_keyword,
+};
+use nom::{
+    IResult, Parser, Slice,
+    branch::alt,
+    bytes::complete::take_till,
+    character::complete::{char, multispace0},
+    combinator::{map, recognize, value},
+    error::Error,
+    sequence::preceded,
+};
+
+/// The kind of a lexical token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Token {
+    /// An operation, modifier or filter name, eg. `link` or `limit`, in any case.
+    Keyword,
+    /// Any other bare word, eg. a namespace name in `ns(Talk)`.
+    Identifier,
+    /// A string literal, with its quotes.
+    String,
+    /// An integer literal, with its sign.
+    Number,
+    /// `(`.
+    LeftParen,
+    /// `)`.
+    RightParen,
+    /// `,`.
+    Comma,
+    /// `.`.
+    Dot,
+    /// One of `&`, `+`, `-` and `^`.
+    Operator,
+    /// `*`, all namespaces.
+    Star,
+    /// `!`, before an excluded namespace.
+    Bang,
+    /// A line comment, from `#` to the end of the line.
+    Comment,
+    /// A character that starts no token, eg. the opening quote of an unterminated string.
+    Error,
+}
+
+/// Split `input` into tokens, each with its span in bytes. Whitespaces are left out.
+pub fn tokenize(input: &str) -> Vec<(Token, Span)> {
+    let mut tokens = Vec::new();
+    let mut rest = LocatedStr::new(input);
+    loop {
+        // `multispace0` never fails.
+        if let Ok((residual, _)) = multispace0::<_, Error<_>>(rest) {
+            rest = residual;
+        }
+        let Some(c) = rest.fragment().chars().next() else {
+            break;
+        };
+        let (residual, token) = next_token(rest).unwrap_or_else(|_| (rest.slice(c.len_utf8()..), Token::Error));
+        tokens.push((token, make_range(rest.location_offset(), residual.location_offset())));
+        rest = residual;
+    }
+    tokens
+}
+
+/// Read the token at the start of `input`.
+fn next_token(input: LocatedStr<'_>) -> IResult<LocatedStr<'_>, Token, Error<LocatedStr<'_>>> {
+    alt((
+        value(Token::Comment, recognize(preceded(char('#'), take_till(|c| c == '\n')))),
+        value(Token::String, LitString::parse_internal),
+        // before the operators, so that `-1` is a number.
+        value(Token::Number, LitInt::parse_internal),
+        map(parse_identifier, |word| if is_keyword(&word.val) { Token::Keyword } else { Token::Identifier }),
+        value(Token::LeftParen, LeftParen::parse_internal),
+        value(Token::RightParen, RightParen::parse_internal),
+        value(Token::Comma, Comma::parse_internal),
+        value(Token::Dot, Dot::parse_internal),
+        value(Token::Star, Star::parse_internal),
+        value(Token::Bang, Bang::parse_internal),
+        value(Token::Operator, alt((
+            And::parse_internal.map(|_| ()),
+            Add::parse_internal.map(|_| ()),
+            Sub::parse_internal.map(|_| ()),
+            Caret::parse_internal.map(|_| ()),
+        ))),
+    ))(input)
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::vec::Vec;
+    use crate::Span;
+    use super::{tokenize, Token};
+
+    /// The kinds of the tokens of `input`, and the text they cover.
+    fn tokens(input: &str) -> Vec<(Token, &str)> {
+        tokenize(input).into_iter().map(|(token, span): (Token, Span)| (token, &input[span.to_range()])).collect()
+    }
+
+    #[test]
+    fn test_tokenize_expression() {
+        use Token::*;
+
+        assert_eq!(tokens(" \"A\" + \"b\" "), [(String, "\"A\""), (Operator, "+"), (String, "\"b\"")]);
+        assert_eq!(tokenize(" \"A\" + \"b\" ").iter().map(|(_, span)| span.to_range()).collect::<Vec<_>>(), [1..4, 5..6, 7..10]);
+        assert_eq!(tokens("\"A\"-\"B\""), [(String, "\"A\""), (Operator, "-"), (String, "\"B\"")]);
+        assert_eq!(tokens("  \"A\" ^ \"B\""), [(String, "\"A\""), (Operator, "^"), (String, "\"B\"")]);
+        assert_eq!(tokens("\"A\"&\"B\" "), [(String, "\"A\""), (Operator, "&"), (String, "\"B\"")]);
+        assert_eq!(tokens("(\"A\")"), [(LeftParen, "("), (String, "\"A\""), (RightParen, ")")]);
+        assert_eq!(tokens("\"A\"+\"B\"-\"C\"").len(), 5);
+        assert_eq!(
+            tokens("(\"A\" ^ \"B\" + \"C\") & ((\"D\" - \"E\") &\"F\")").into_iter().map(|(token, _)| token).collect::<Vec<_>>(),
+            [
+                LeftParen, String, Operator, String, Operator, String, RightParen, Operator,
+                LeftParen, LeftParen, String, Operator, String, RightParen, Operator, String, RightParen,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_tokenize_words() {
+        use Token::*;
+
+        assert_eq!(
+            tokens("LinkTo(page('A # B', # first\n \"C\")).ns(Talk, -1).limit(5)"),
+            [
+                (Keyword, "LinkTo"), (LeftParen, "("), (Keyword, "page"), (LeftParen, "("), (String, "'A # B'"), (Comma, ","),
+                (Comment, "# first"), (String, "\"C\""), (RightParen, ")"), (RightParen, ")"),
+                (Dot, "."), (Keyword, "ns"), (LeftParen, "("), (Identifier, "Talk"), (Comma, ","), (Number, "-1"), (RightParen, ")"),
+                (Dot, "."), (Keyword, "limit"), (LeftParen, "("), (Number, "5"), (RightParen, ")"),
+            ],
+        );
+        // the longest word is taken, so `linktoo` is no keyword.
+        assert_eq!(tokens("linktoo"), [(Identifier, "linktoo")]);
+    }
+
+    #[test]
+    fn test_tokenize_namespaces() {
+        use Token::*;
+
+        assert_eq!(
+            tokens("ns(*, !0, ! Talk)"),
+            [
+                (Keyword, "ns"), (LeftParen, "("), (Star, "*"), (Comma, ","), (Bang, "!"), (Number, "0"), (Comma, ","),
+                (Bang, "!"), (Identifier, "Talk"), (RightParen, ")"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_tokenize_error() {
+        use Token::*;
+
+        assert_eq!(tokens("\"A\" @ \"B"), [(String, "\"A\""), (Error, "@"), (Error, "\""), (Identifier, "B")]);
+        assert_eq!(tokens("page(\"A\") ~ é"), [(Keyword, "page"), (LeftParen, "("), (String, "\"A\""), (RightParen, ")"), (Error, "~"), (Identifier, "é")]);
+        assert!(tokenize("").is_empty());
+        assert!(tokenize(" \n ").is_empty());
+    }
+}
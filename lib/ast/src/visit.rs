@@ -0,0 +1,212 @@
+//! Read-only traversal of expressions.
+//!
+//! Every `visit_*` method of `Visitor` recurses into the children of its node by default,
+//! through the matching `walk_*` function. Override only the nodes of interest, and call the
+//! `walk_*` function from the override to keep recursing.
+
+use crate::attribute::Attribute;
+use crate::expr::{
+    Expression,
+    ExpressionAnd, ExpressionAdd, ExpressionSub, ExpressionXor,
+    ExpressionParen,
+    ExpressionPage, ExpressionLink, ExpressionLinkTo, ExpressionEmbed, ExpressionInCat, ExpressionPrefix, ExpressionToggle, ExpressionTemplatesIn, ExpressionFiles, ExpressionFileUsage, ExpressionCategoriesOf, ExpressionLangLinks,
+    ExpressionNot, ExpressionPageId,
+};
+
+pub trait Visitor<'ast> {
+    fn visit_expression(&mut self, expr: &'ast Expression) {
+        walk_expression(self, expr)
+    }
+    fn visit_and(&mut self, expr: &'ast ExpressionAnd) {
+        walk_and(self, expr)
+    }
+    fn visit_add(&mut self, expr: &'ast ExpressionAdd) {
+        walk_add(self, expr)
+    }
+    fn visit_sub(&mut self, expr: &'ast ExpressionSub) {
+        walk_sub(self, expr)
+    }
+    fn visit_xor(&mut self, expr: &'ast ExpressionXor) {
+        walk_xor(self, expr)
+    }
+    fn visit_paren(&mut self, expr: &'ast ExpressionParen) {
+        walk_paren(self, expr)
+    }
+    fn visit_page(&mut self, _expr: &'ast ExpressionPage) {}
+    fn visit_pageid(&mut self, _expr: &'ast ExpressionPageId) {}
+    fn visit_link(&mut self, expr: &'ast ExpressionLink) {
+        walk_link(self, expr)
+    }
+    fn visit_linkto(&mut self, expr: &'ast ExpressionLinkTo) {
+        walk_linkto(self, expr)
+    }
+    fn visit_embed(&mut self, expr: &'ast ExpressionEmbed) {
+        walk_embed(self, expr)
+    }
+    fn visit_incat(&mut self, expr: &'ast ExpressionInCat) {
+        walk_incat(self, expr)
+    }
+    fn visit_prefix(&mut self, expr: &'ast ExpressionPrefix) {
+        walk_prefix(self, expr)
+    }
+    fn visit_toggle(&mut self, expr: &'ast ExpressionToggle) {
+        walk_toggle(self, expr)
+    }
+    fn visit_templatesin(&mut self, expr: &'ast ExpressionTemplatesIn) {
+        walk_templatesin(self, expr)
+    }
+    fn visit_files(&mut self, expr: &'ast ExpressionFiles) {
+        walk_files(self, expr)
+    }
+    fn visit_fileusage(&mut self, expr: &'ast ExpressionFileUsage) {
+        walk_fileusage(self, expr)
+    }
+    fn visit_categoriesof(&mut self, expr: &'ast ExpressionCategoriesOf) {
+        walk_categoriesof(self, expr)
+    }
+    fn visit_langlinks(&mut self, expr: &'ast ExpressionLangLinks) {
+        walk_langlinks(self, expr)
+    }
+    fn visit_not(&mut self, expr: &'ast ExpressionNot) {
+        walk_not(self, expr)
+    }
+    fn visit_attribute(&mut self, _attr: &'ast Attribute) {}
+}
+
+/// Dispatch `expr` to the matching `visit_*` method of `visitor`.
+pub fn walk_expression<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, expr: &'ast Expression) {
+    match expr {
+        Expression::And(expr) => visitor.visit_and(expr),
+        Expression::Add(expr) => visitor.visit_add(expr),
+        Expression::Sub(expr) => visitor.visit_sub(expr),
+        Expression::Xor(expr) => visitor.visit_xor(expr),
+        Expression::Paren(expr) => visitor.visit_paren(expr),
+        Expression::Page(expr) => visitor.visit_page(expr),
+        Expression::PageId(expr) => visitor.visit_pageid(expr),
+        Expression::Link(expr) => visitor.visit_link(expr),
+        Expression::LinkTo(expr) => visitor.visit_linkto(expr),
+        Expression::Embed(expr) => visitor.visit_embed(expr),
+        Expression::InCat(expr) => visitor.visit_incat(expr),
+        Expression::Prefix(expr) => visitor.visit_prefix(expr),
+        Expression::Toggle(expr) => visitor.visit_toggle(expr),
+        Expression::TemplatesIn(expr) => visitor.visit_templatesin(expr),
+        Expression::Files(expr) => visitor.visit_files(expr),
+        Expression::FileUsage(expr) => visitor.visit_fileusage(expr),
+        Expression::CategoriesOf(expr) => visitor.visit_categoriesof(expr),
+        Expression::LangLinks(expr) => visitor.visit_langlinks(expr),
+        Expression::Not(expr) => visitor.visit_not(expr),
+    }
+}
+
+macro_rules! walk_binary {
+    ($walk:ident, $class:ident) => {
+        /// Visit both operands, left first.
+        pub fn $walk<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, expr: &'ast $class) {
+            visitor.visit_expression(&expr.expr1);
+            visitor.visit_expression(&expr.expr2);
+        }
+    }
+}
+
+walk_binary!(walk_and, ExpressionAnd);
+walk_binary!(walk_add, ExpressionAdd);
+walk_binary!(walk_sub, ExpressionSub);
+walk_binary!(walk_xor, ExpressionXor);
+
+/// Visit the inner expression.
+pub fn walk_paren<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, expr: &'ast ExpressionParen) {
+    visitor.visit_expression(&expr.expr);
+}
+
+/// Visit the inner expression.
+pub fn walk_toggle<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, expr: &'ast ExpressionToggle) {
+    visitor.visit_expression(&expr.expr);
+}
+
+/// Visit the inner expression, then the universe if there is one.
+pub fn walk_not<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, expr: &'ast ExpressionNot) {
+    visitor.visit_expression(&expr.expr);
+    if let Some(universe) = &expr.universe {
+        visitor.visit_expression(universe);
+    }
+}
+
+macro_rules! walk_unary {
+    ($walk:ident, $class:ident) => {
+        /// Visit the inner expression, then the attributes.
+        pub fn $walk<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, expr: &'ast $class) {
+            visitor.visit_expression(&expr.expr);
+            for attr in &expr.attributes {
+                visitor.visit_attribute(attr);
+            }
+        }
+    }
+}
+
+walk_unary!(walk_link, ExpressionLink);
+walk_unary!(walk_linkto, ExpressionLinkTo);
+walk_unary!(walk_embed, ExpressionEmbed);
+walk_unary!(walk_incat, ExpressionInCat);
+walk_unary!(walk_prefix, ExpressionPrefix);
+walk_unary!(walk_templatesin, ExpressionTemplatesIn);
+walk_unary!(walk_files, ExpressionFiles);
+walk_unary!(walk_fileusage, ExpressionFileUsage);
+walk_unary!(walk_categoriesof, ExpressionCategoriesOf);
+walk_unary!(walk_langlinks, ExpressionLangLinks);
+
+#[cfg(all(test, feature = "parse"))]
+mod test {
+    use crate::{Attribute, Expression, ExpressionPage, ExpressionToggle};
+    use super::{Visitor, walk_toggle};
+    use nom::error::Error;
+
+    #[derive(Default)]
+    struct Counter {
+        pages: usize,
+        attributes: usize,
+    }
+
+    impl<'ast> Visitor<'ast> for Counter {
+        fn visit_page(&mut self, _expr: &'ast ExpressionPage) {
+            self.pages += 1;
+        }
+        fn visit_attribute(&mut self, _attr: &'ast Attribute) {
+            self.attributes += 1;
+        }
+    }
+
+    /// Does not look into `toggle`.
+    #[derive(Default)]
+    struct SkipToggle(Counter);
+
+    impl<'ast> Visitor<'ast> for SkipToggle {
+        fn visit_page(&mut self, expr: &'ast ExpressionPage) {
+            self.0.visit_page(expr);
+        }
+        fn visit_toggle(&mut self, _expr: &'ast ExpressionToggle) {}
+    }
+
+    #[test]
+    fn test_visitor() {
+        let input = "(\"A\" + link(\"B\", \"C\").ns(0).limit(5)) & incat(toggle(\"D\" ^ \"E\") - \"F\").depth(2)";
+        let expr = Expression::parse::<Error<_>>(input).unwrap();
+
+        let mut counter = Counter::default();
+        counter.visit_expression(&expr);
+        assert_eq!(counter.pages, 5);
+        assert_eq!(counter.attributes, 3);
+
+        let mut skip = SkipToggle::default();
+        skip.visit_expression(&expr);
+        assert_eq!(skip.0.pages, 3);
+
+        // an override can still recurse through the `walk_*` function.
+        let Expression::And(and) = &expr else { unreachable!() };
+        let Expression::InCat(incat) = &*and.expr2 else { unreachable!() };
+        let Expression::Sub(sub) = &*incat.expr else { unreachable!() };
+        let Expression::Toggle(toggle) = &*sub.expr1 else { unreachable!() };
+        let mut counter = Counter::default();
+        walk_toggle(&mut counter, toggle);
+        assert_eq!(counter.pages, 2);
+    }
+}
@@ -20,3 +20,6 @@ pub fn pagelistbot_bin() -> PathBuf {
 pub fn pagelistbot_log() -> PathBuf {
     pagelistbot_home().join("logs")
 }
+
+#[cfg(feature = "otel")]
+pub mod otel;
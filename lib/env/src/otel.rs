@@ -0,0 +1,62 @@
+//! Exporting traces to an OpenTelemetry collector.
+
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{ExporterBuildError, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{trace::{SdkTracer, SdkTracerProvider}, Resource};
+use std::env;
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+/// The environment variable holding the collector endpoint, eg. `http://localhost:4317`.
+pub const OTLP_ENDPOINT_VAR: &str = "PAGELISTBOT_OTLP_ENDPOINT";
+
+/// Exports the remaining spans when dropped. Keep it alive as long as the layer is in use.
+#[derive(Debug)]
+pub struct OtelGuard(SdkTracerProvider);
+
+impl Drop for OtelGuard {
+    fn drop(&mut self) {
+        let _ = self.0.shutdown();
+    }
+}
+
+/// The collector endpoint, if export is enabled.
+#[inline]
+pub fn otlp_endpoint() -> Option<String> {
+    env::var(OTLP_ENDPOINT_VAR).ok().filter(|endpoint| !endpoint.is_empty())
+}
+
+/// A layer exporting spans over gRPC to the collector at `endpoint`, as coming from `service_name`.
+/// The collector is not contacted until the first spans are exported, so this succeeds without one.
+///
+/// Must be called within a Tokio runtime.
+pub fn otel_layer<S>(service_name: &'static str, endpoint: &str) -> Result<(OpenTelemetryLayer<S, SdkTracer>, OtelGuard), ExporterBuildError>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let exporter = SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name).build())
+        .build();
+    let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer(service_name));
+    Ok((layer, OtelGuard(provider)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::otel_layer;
+    use tracing_subscriber::{prelude::*, Registry};
+
+    #[tokio::test]
+    async fn test_otel_layer() {
+        // nothing listens on the discard port.
+        let (layer, _guard) = otel_layer::<Registry>("pagelistbot-test", "http://127.0.0.1:9").unwrap();
+        let _subscriber = tracing_subscriber::registry().with(layer);
+        assert!(otel_layer::<Registry>("pagelistbot-test", "not a uri").is_err());
+    }
+}
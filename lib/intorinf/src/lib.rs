@@ -7,8 +7,11 @@ use core::{
     fmt::{self, Display, Formatter},
     ops::{Add, AddAssign},
 };
+#[cfg(feature = "use_serde")]
+use serde::{Serialize, Deserialize};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize))]
 pub enum IntOrInf {
     Int(i32),
     Inf,
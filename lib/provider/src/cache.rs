@@ -0,0 +1,257 @@
+//! A `DataProvider` wrapper that memoizes query results.
+
+use crate::{
+    config::{
+        FilterRedirect, CategorySort, SortDirection,
+        LinksConfig, BackLinksConfig, EmbedsConfig, TemplatesConfig, ImagesConfig, FileUsageConfig, CategoriesConfig, LangLinksConfig, CategoryMembersConfig, PrefixConfig,
+    },
+    core::DataProvider,
+    pageinfo::PageInfo,
+};
+use async_stream::stream;
+use core::convert::Infallible;
+use futures::Stream;
+use mwtitle::Title;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+use trio_result::TrioResult;
+
+/// Identifies a query by method, input titles and configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum CacheKey {
+    PageInfo(Vec<TitleKey>),
+    PageInfoRaw(Vec<String>),
+    PageInfoById(Vec<u32>),
+    Links(TitleKey, Option<Vec<i32>>, bool),
+    BackLinks(TitleKey, bool, Option<FilterRedirect>, Option<Vec<i32>>, bool),
+    Embeds(TitleKey, Option<FilterRedirect>, Option<Vec<i32>>, bool),
+    Templates(TitleKey, Option<Vec<i32>>, bool),
+    Images(TitleKey, Option<Vec<i32>>, bool),
+    FileUsage(TitleKey, Option<FilterRedirect>, Option<Vec<i32>>, bool),
+    Categories(TitleKey, Option<Vec<i32>>, bool),
+    LangLinks(TitleKey),
+    CategoryMembers(TitleKey, Option<Vec<i32>>, bool, Option<String>, Option<String>, Option<CategorySort>, Option<SortDirection>),
+    Prefix(TitleKey, Option<FilterRedirect>),
+}
+
+/// `Title` is not hashable, so titles are keyed by namespace and dbkey.
+type TitleKey = (i32, String);
+
+fn title_key(title: &Title) -> TitleKey {
+    (title.namespace(), title.dbkey().to_owned())
+}
+
+/// `HashSet` is not hashable, so namespaces are keyed as a sorted list.
+fn namespace_key(namespace: &Option<HashSet<i32>>) -> Option<Vec<i32>> {
+    namespace.as_ref().map(|ns| {
+        let mut ns: Vec<i32> = ns.iter().copied().collect();
+        ns.sort_unstable();
+        ns
+    })
+}
+
+type CachedItems<W> = Vec<TrioResult<PageInfo, W, Infallible>>;
+
+/// A `DataProvider` that remembers every completed query of the inner provider, and replays it on subsequent identical queries.
+///
+/// Clones share the same cache, so the wrapper can be handed to the solver as-is.
+/// A query is only cached once its stream has been polled to the end. Streams that yield an error, or that are dropped
+/// halfway, are not cached, and the next identical query hits the inner provider again.
+#[derive(Debug)]
+pub struct CachingDataProvider<P: DataProvider> {
+    inner: P,
+    cache: Arc<Mutex<HashMap<CacheKey, CachedItems<P::Warn>>>>,
+}
+
+impl<P: DataProvider> CachingDataProvider<P> {
+    pub fn new(inner: P) -> Self {
+        Self { inner, cache: Arc::new(Mutex::new(HashMap::new())) }
+    }
+
+    /// Get a reference to the inner provider.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Replay the cached items under `key`, or poll the stream made by `make` and cache its items.
+    fn cached<'a, S, F>(&'a self, key: CacheKey, make: F) -> impl Stream<Item=TrioResult<PageInfo, P::Warn, P::Error>> + use<'a, P, S, F>
+    where
+        S: Stream<Item=TrioResult<PageInfo, P::Warn, P::Error>>,
+        F: FnOnce() -> S,
+        P::Warn: Clone,
+    {
+        stream! {
+            let hit = self.cache.lock().unwrap().get(&key).cloned();
+            if let Some(items) = hit {
+                for item in items {
+                    yield item.map_err(|e| match e {});
+                }
+                return;
+            }
+            let mut items = Vec::new();
+            let mut failed = false;
+            for await item in make() {
+                match &item {
+                    TrioResult::Ok(info) => items.push(TrioResult::Ok(info.clone())),
+                    TrioResult::Warn(w) => items.push(TrioResult::Warn(w.clone())),
+                    TrioResult::Err(_) => failed = true,
+                }
+                yield item;
+            }
+            if !failed {
+                self.cache.lock().unwrap().insert(key, items);
+            }
+        }
+    }
+}
+
+impl<P: DataProvider + Clone> Clone for CachingDataProvider<P> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone(), cache: Arc::clone(&self.cache) }
+    }
+}
+
+impl<P> DataProvider for CachingDataProvider<P>
+where
+    P: DataProvider,
+    P::Warn: Clone,
+{
+    type Error = P::Error;
+    type Warn = P::Warn;
+
+    fn get_page_info<T: IntoIterator<Item = Title>>(&self, titles: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let titles: Vec<Title> = titles.into_iter().collect();
+        self.cached(CacheKey::PageInfo(titles.iter().map(title_key).collect()), move || self.inner.get_page_info(titles))
+    }
+
+    fn get_page_info_from_raw<T: IntoIterator<Item = String>>(&self, titles_raw: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let titles_raw: Vec<String> = titles_raw.into_iter().collect();
+        self.cached(CacheKey::PageInfoRaw(titles_raw.clone()), move || self.inner.get_page_info_from_raw(titles_raw))
+    }
+
+    fn get_page_info_by_id<T: IntoIterator<Item = u32>>(&self, pageids: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let pageids: Vec<u32> = pageids.into_iter().collect();
+        self.cached(CacheKey::PageInfoById(pageids.clone()), move || self.inner.get_page_info_by_id(pageids))
+    }
+
+    fn get_links(&self, title: Title, config: &LinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let key = CacheKey::Links(title_key(&title), namespace_key(&config.namespace), config.resolve_redirects);
+        self.cached(key, move || self.inner.get_links(title, config))
+    }
+
+    fn get_backlinks(&self, title: Title, config: &BackLinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let key = CacheKey::BackLinks(title_key(&title), config.direct, config.filter_redirects, namespace_key(&config.namespace), config.resolve_redirects);
+        self.cached(key, move || self.inner.get_backlinks(title, config))
+    }
+
+    fn get_embeds(&self, title: Title, config: &EmbedsConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let key = CacheKey::Embeds(title_key(&title), config.filter_redirects, namespace_key(&config.namespace), config.resolve_redirects);
+        self.cached(key, move || self.inner.get_embeds(title, config))
+    }
+
+    fn get_templates(&self, title: Title, config: &TemplatesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let key = CacheKey::Templates(title_key(&title), namespace_key(&config.namespace), config.resolve_redirects);
+        self.cached(key, move || self.inner.get_templates(title, config))
+    }
+
+    fn get_images(&self, title: Title, config: &ImagesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let key = CacheKey::Images(title_key(&title), namespace_key(&config.namespace), config.resolve_redirects);
+        self.cached(key, move || self.inner.get_images(title, config))
+    }
+
+    fn get_file_usage(&self, title: Title, config: &FileUsageConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let key = CacheKey::FileUsage(title_key(&title), config.filter_redirects, namespace_key(&config.namespace), config.resolve_redirects);
+        self.cached(key, move || self.inner.get_file_usage(title, config))
+    }
+
+    fn get_categories(&self, title: Title, config: &CategoriesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let key = CacheKey::Categories(title_key(&title), namespace_key(&config.namespace), config.resolve_redirects);
+        self.cached(key, move || self.inner.get_categories(title, config))
+    }
+
+    fn get_langlinks(&self, title: Title, config: &LangLinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let key = CacheKey::LangLinks(title_key(&title));
+        self.cached(key, move || self.inner.get_langlinks(title, config))
+    }
+
+    fn get_category_members(&self, title: Title, config: &CategoryMembersConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let key = CacheKey::CategoryMembers(title_key(&title), namespace_key(&config.namespace), config.resolve_redirects, config.start.clone(), config.end.clone(), config.sort, config.dir);
+        self.cached(key, move || self.inner.get_category_members(title, config))
+    }
+
+    fn get_prefix(&self, title: Title, config: &PrefixConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let key = CacheKey::Prefix(title_key(&title), config.filter_redirects);
+        self.cached(key, move || self.inner.get_prefix(title, config))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CachingDataProvider;
+    use crate::{
+        codec::test_title,
+        config::*,
+        core::DataProvider,
+        pageinfo::PageInfo,
+        test_provider::CountingProvider,
+    };
+    use core::convert::Infallible;
+    use futures::{executor::block_on, Stream, StreamExt};
+    use mwtitle::Title;
+    use std::sync::atomic::Ordering;
+    use trio_result::TrioResult;
+
+    fn titles<S: Stream<Item=TrioResult<PageInfo, Infallible, ()>>>(stream: S) -> Vec<Title> {
+        block_on(stream.filter_map(|x| async { x.ok() }).collect::<Vec<_>>())
+            .into_iter()
+            .map(|p| p.get_title().unwrap().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_cache_hit() {
+        let inner = CountingProvider::default();
+        let provider = CachingDataProvider::new(inner.clone());
+        let config = LinksConfig { namespace: Some([0, 14].into()), resolve_redirects: false };
+        let title = test_title("Foo");
+
+        let first = titles(provider.get_links(title.clone(), &config));
+        // a clone shares the cache, and namespace order does not matter.
+        let config_2 = LinksConfig { namespace: Some([14, 0].into()), resolve_redirects: false };
+        let second = titles(provider.clone().get_links(title.clone(), &config_2));
+
+        assert_eq!(first, second);
+        assert_eq!(first, vec![title]);
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_cache_miss() {
+        let inner = CountingProvider::default();
+        let provider = CachingDataProvider::new(inner.clone());
+        let title = test_title("Foo");
+
+        titles(provider.get_links(title.clone(), &LinksConfig::default()));
+        titles(provider.get_links(title.clone(), &LinksConfig { namespace: None, resolve_redirects: true }));
+        titles(provider.get_backlinks(title.clone(), &BackLinksConfig::default()));
+        titles(provider.get_links(test_title("Bar"), &LinksConfig::default()));
+
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_cache_dropped_stream() {
+        let inner = CountingProvider::default();
+        let provider = CachingDataProvider::new(inner.clone());
+        let title = test_title("Foo");
+
+        // polled only until the first item, never to the end.
+        let first = block_on(Box::pin(provider.get_links(title.clone(), &LinksConfig::default())).next());
+        assert!(first.is_some());
+        titles(provider.get_links(title, &LinksConfig::default()));
+
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,19 @@
+//! Title codecs built without asking the wiki for its site info.
+
+use mwtitle::{NamespaceInfo, TitleCodec};
+
+/// The default `$wgLegalTitleChars` of MediaWiki.
+const LEGAL_TITLE_CHARS: &str = r#" %!"$&'()*,\-.\/0-9:;=?@A-Z\\^_`a-z~\x80-\xFF+"#;
+
+/// Make a codec knowing only `namespaces`, without namespace aliases or interwikis.
+pub(crate) fn codec_from_namespaces<I: IntoIterator<Item = NamespaceInfo>>(namespaces: I, lang: &str) -> TitleCodec {
+    // only a bad set of legal characters can fail, and that one is fixed.
+    TitleCodec::new_from_iters(namespaces, [], [], "Main Page".to_string(), lang.to_string(), LEGAL_TITLE_CHARS.to_string()).unwrap()
+}
+
+/// Parse a title in the main namespace, for unit tests in this crate.
+#[cfg(test)]
+pub(crate) fn test_title(raw: &str) -> mwtitle::Title {
+    let main = NamespaceInfo { id: 0, case: "first-letter".to_string(), name: String::new(), canonical: None };
+    codec_from_namespaces([main], "en").new_title(raw).unwrap()
+}
@@ -1,20 +1,42 @@
 //! Configuration structs for `DataProvider` trait.
 
+#[cfg(feature = "use_serde")]
+use serde::{Serialize, Deserialize};
 use std::collections::HashSet;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
 pub enum FilterRedirect {
     NoRedirect,
     OnlyRedirect,
 }
 
+/// The order in which a category lists its members.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
+pub enum CategorySort {
+    /// By the sort key of each member, ie. roughly alphabetically.
+    SortKey,
+    /// By the time each member was added to the category.
+    Timestamp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize), serde(default))]
 pub struct LinksConfig {
     pub namespace: Option<HashSet<i32>>,
     pub resolve_redirects: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize), serde(default))]
 pub struct BackLinksConfig {
     pub direct: bool,
     pub filter_redirects: Option<FilterRedirect>,
@@ -23,6 +45,7 @@ pub struct BackLinksConfig {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize), serde(default))]
 pub struct EmbedsConfig {
     pub filter_redirects: Option<FilterRedirect>,
     pub namespace: Option<HashSet<i32>>,
@@ -30,12 +53,167 @@ pub struct EmbedsConfig {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize), serde(default))]
+pub struct TemplatesConfig {
+    pub namespace: Option<HashSet<i32>>,
+    pub resolve_redirects: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize), serde(default))]
+pub struct ImagesConfig {
+    pub namespace: Option<HashSet<i32>>,
+    pub resolve_redirects: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize), serde(default))]
+pub struct FileUsageConfig {
+    pub filter_redirects: Option<FilterRedirect>,
+    pub namespace: Option<HashSet<i32>>,
+    pub resolve_redirects: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize), serde(default))]
+pub struct CategoriesConfig {
+    pub namespace: Option<HashSet<i32>>,
+    pub resolve_redirects: bool,
+}
+
+/// Language links currently take no options.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize), serde(default))]
+pub struct LangLinksConfig {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize), serde(default))]
 pub struct CategoryMembersConfig {
     pub namespace: Option<HashSet<i32>>,
     pub resolve_redirects: bool,
+    /// Only list pages added to the category at or after this timestamp, in ISO 8601, eg. `2024-01-01T00:00:00Z`.
+    pub start: Option<String>,
+    /// Only list pages added to the category at or before this timestamp, in ISO 8601.
+    pub end: Option<String>,
+    /// The order of the members. Defaults to sort key order, or timestamp order if the members are time bounded.
+    pub sort: Option<CategorySort>,
+    /// The direction of the order. Defaults to ascending.
+    pub dir: Option<SortDirection>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "use_serde", derive(Serialize, Deserialize), serde(default))]
 pub struct PrefixConfig {
     pub filter_redirects: Option<FilterRedirect>,
-}
\ No newline at end of file
+}
+
+macro_rules! config_builder {
+    ($config:ident: $($method:ident),+) => {
+        impl $config {
+            $(config_builder!(@method $method);)+
+        }
+    };
+    (@method with_namespace) => {
+        /// Only list pages in these namespaces.
+        pub fn with_namespace(mut self, namespace: impl IntoIterator<Item = i32>) -> Self {
+            self.namespace = Some(namespace.into_iter().collect());
+            self
+        }
+    };
+    (@method with_resolve) => {
+        /// Whether to resolve redirects in the results.
+        pub fn with_resolve(mut self, resolve_redirects: bool) -> Self {
+            self.resolve_redirects = resolve_redirects;
+            self
+        }
+    };
+    (@method with_filter_redirects) => {
+        /// Only list redirects, or only list non-redirects.
+        pub fn with_filter_redirects(mut self, filter_redirects: FilterRedirect) -> Self {
+            self.filter_redirects = Some(filter_redirects);
+            self
+        }
+    };
+    (@method with_direct) => {
+        /// Whether to list direct backlinks only, not those through redirects.
+        pub fn with_direct(mut self, direct: bool) -> Self {
+            self.direct = direct;
+            self
+        }
+    };
+}
+
+impl CategoryMembersConfig {
+    /// Only list pages added to the category at or after this timestamp.
+    pub fn with_start(mut self, start: impl Into<String>) -> Self {
+        self.start = Some(start.into());
+        self
+    }
+
+    /// Only list pages added to the category at or before this timestamp.
+    pub fn with_end(mut self, end: impl Into<String>) -> Self {
+        self.end = Some(end.into());
+        self
+    }
+
+    /// List the members in this order.
+    pub fn with_sort(mut self, sort: CategorySort, dir: SortDirection) -> Self {
+        self.sort = Some(sort);
+        self.dir = Some(dir);
+        self
+    }
+
+    /// Whether the members are bounded by the time they were added. If so, they are listed in that order.
+    pub fn is_time_bounded(&self) -> bool {
+        self.start.is_some() || self.end.is_some()
+    }
+}
+
+config_builder!(LinksConfig: with_namespace, with_resolve);
+config_builder!(BackLinksConfig: with_direct, with_filter_redirects, with_namespace, with_resolve);
+config_builder!(EmbedsConfig: with_filter_redirects, with_namespace, with_resolve);
+config_builder!(TemplatesConfig: with_namespace, with_resolve);
+config_builder!(ImagesConfig: with_namespace, with_resolve);
+config_builder!(FileUsageConfig: with_filter_redirects, with_namespace, with_resolve);
+config_builder!(CategoriesConfig: with_namespace, with_resolve);
+config_builder!(CategoryMembersConfig: with_namespace, with_resolve);
+config_builder!(PrefixConfig: with_filter_redirects);
+
+#[cfg(test)]
+mod test {
+    use super::{BackLinksConfig, FilterRedirect};
+    #[cfg(feature = "use_serde")]
+    use super::{CategoryMembersConfig, PrefixConfig};
+
+    #[test]
+    fn test_builder() {
+        let config = BackLinksConfig::default()
+            .with_direct(true)
+            .with_filter_redirects(FilterRedirect::NoRedirect)
+            .with_namespace([0, 14])
+            .with_resolve(true);
+        assert_eq!(config, BackLinksConfig {
+            direct: true,
+            filter_redirects: Some(FilterRedirect::NoRedirect),
+            namespace: Some([0, 14].into_iter().collect()),
+            resolve_redirects: true,
+        });
+        assert_eq!(BackLinksConfig::default().with_namespace([]).namespace, Some(Default::default()));
+    }
+
+    #[cfg(feature = "use_serde")]
+    #[test]
+    fn test_serde() {
+        let config = CategoryMembersConfig::default().with_namespace([0, 14]).with_resolve(true);
+        let json = serde_json::to_value(&config).unwrap();
+        let mut namespace: Vec<i64> = json["namespace"].as_array().unwrap().iter().map(|x| x.as_i64().unwrap()).collect();
+        namespace.sort();
+        assert_eq!(namespace, [0, 14]);
+        assert_eq!(serde_json::from_value::<CategoryMembersConfig>(json).unwrap(), config);
+
+        let config: PrefixConfig = serde_json::from_str(r#"{"filter_redirects": "onlyredirect"}"#).unwrap();
+        assert_eq!(config, PrefixConfig::default().with_filter_redirects(FilterRedirect::OnlyRedirect));
+        assert_eq!(serde_json::to_string(&FilterRedirect::NoRedirect).unwrap(), r#""noredirect""#);
+        assert_eq!(serde_json::from_str::<CategoryMembersConfig>("{}").unwrap(), CategoryMembersConfig::default());
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    config::{LinksConfig, BackLinksConfig, EmbedsConfig, CategoryMembersConfig, PrefixConfig},
+    config::{LinksConfig, BackLinksConfig, EmbedsConfig, TemplatesConfig, ImagesConfig, FileUsageConfig, CategoriesConfig, LangLinksConfig, CategoryMembersConfig, PrefixConfig},
     pageinfo::PageInfo,
 };
 use futures::{Stream, StreamExt};
@@ -110,9 +110,14 @@ pub trait DataProvider {
     type Warn;
 
     /// Get a stream of input pages' information. Input is `mwtitle::Title`.
+    /// Pages are yielded in the input order, and a page repeated in the input is yielded once.
     fn get_page_info<T: IntoIterator<Item = Title>>(&self, titles: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>>;
     /// Get a stream of input pages' information. Input is raw title string.
+    /// Pages are yielded in the input order, and a page repeated in the input is yielded once.
     fn get_page_info_from_raw<T: IntoIterator<Item = String>>(&self, titles_raw: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>>;
+    /// Get a stream of input pages' information. Input is page id.
+    /// Ids that do not belong to any page yield nothing.
+    fn get_page_info_by_id<T: IntoIterator<Item = u32>>(&self, pageids: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>>;
     /// Get a stream of input pages' internal links.
     fn get_links(&self, title: Title, config: &LinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>>;
 
@@ -140,9 +145,57 @@ pub trait DataProvider {
             .collect::<Vec<_>>();
         futures::stream::iter(streams).flatten()
     }
+    /// Get a stream of templates transcluded in the given pages.
+    fn get_templates(&self, title: Title, config: &TemplatesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>>;
+
+    fn get_templates_multi<T: IntoIterator<Item=Title>>(&self, titles: T, config: &TemplatesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let streams = titles.into_iter()
+            .map(|t| self.get_templates(t, config))
+            .collect::<Vec<_>>();
+        futures::stream::iter(streams).flatten()
+    }
+    /// Get a stream of files used in the given pages.
+    fn get_images(&self, title: Title, config: &ImagesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>>;
+
+    fn get_images_multi<T: IntoIterator<Item=Title>>(&self, titles: T, config: &ImagesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let streams = titles.into_iter()
+            .map(|t| self.get_images(t, config))
+            .collect::<Vec<_>>();
+        futures::stream::iter(streams).flatten()
+    }
+    /// Get a stream of pages in which the given files are used.
+    fn get_file_usage(&self, title: Title, config: &FileUsageConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>>;
+
+    fn get_file_usage_multi<T: IntoIterator<Item=Title>>(&self, titles: T, config: &FileUsageConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let streams = titles.into_iter()
+            .map(|t| self.get_file_usage(t, config))
+            .collect::<Vec<_>>();
+        futures::stream::iter(streams).flatten()
+    }
+    /// Get a stream of categories the given pages belong to.
+    fn get_categories(&self, title: Title, config: &CategoriesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>>;
+
+    fn get_categories_multi<T: IntoIterator<Item=Title>>(&self, titles: T, config: &CategoriesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let streams = titles.into_iter()
+            .map(|t| self.get_categories(t, config))
+            .collect::<Vec<_>>();
+        futures::stream::iter(streams).flatten()
+    }
+    /// Get a stream of pages on other wikis that the given pages link to as language links.
+    /// Items are created by `PageInfo::new_foreign`.
+    fn get_langlinks(&self, title: Title, config: &LangLinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>>;
+
+    fn get_langlinks_multi<T: IntoIterator<Item=Title>>(&self, titles: T, config: &LangLinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let streams = titles.into_iter()
+            .map(|t| self.get_langlinks(t, config))
+            .collect::<Vec<_>>();
+        futures::stream::iter(streams).flatten()
+    }
     /// Get a stream of pages inside the given category pages.
     fn get_category_members(&self, title: Title, config: &CategoryMembersConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>>;
 
+    /// The API cannot query several categories with one `categorymembers` generator, so by default this fans out
+    /// one `get_category_members` call per category and flattens the streams in order.
     fn get_category_members_multi<T: IntoIterator<Item=Title>>(&self, titles: T, config: &CategoryMembersConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
         let streams = titles.into_iter()
             .map(|t| self.get_category_members(t, config))
@@ -0,0 +1,605 @@
+//! A `DataProvider` answering from a MediaWiki XML dump, eg. `pages-articles.xml`.
+
+use crate::{
+    codec::codec_from_namespaces,
+    config::{
+        FilterRedirect, CategorySort, SortDirection,
+        LinksConfig, BackLinksConfig, EmbedsConfig, TemplatesConfig, ImagesConfig, FileUsageConfig, CategoriesConfig, LangLinksConfig, CategoryMembersConfig, PrefixConfig,
+    },
+    core::DataProvider,
+    pageinfo::PageInfo,
+};
+use core::{convert::Infallible, fmt};
+use futures::Stream;
+use mwtitle::{NamespaceInfo, Title, TitleCodec};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    error::Error,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+    sync::Arc,
+};
+use trio_result::TrioResult;
+
+/// Errors met while loading a dump.
+#[derive(Debug)]
+pub enum DumpError {
+    /// The dump could not be read.
+    Io(io::Error),
+    /// A `<page>` element lacks its title, namespace or id, or its title is not valid.
+    Malformed { page: usize },
+    /// The dump has no `<siteinfo>` before its first page.
+    MissingSiteInfo,
+}
+
+impl fmt::Display for DumpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "cannot read dump: {e}"),
+            Self::Malformed { page } => write!(f, "page #{page} of the dump is malformed"),
+            Self::MissingSiteInfo => write!(f, "the dump has no site info"),
+        }
+    }
+}
+
+impl Error for DumpError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Malformed { .. } | Self::MissingSiteInfo => None,
+        }
+    }
+}
+
+impl From<io::Error> for DumpError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// What the dump tells about a single page.
+#[derive(Debug, Clone)]
+struct DumpPage {
+    id: u32,
+    redirect: Option<Title>,
+    length: u32,
+    touched: Option<String>,
+}
+
+/// The pages of a dump, and the relations between them read from their wikitext.
+#[derive(Debug)]
+struct DumpIndex {
+    /// Parses titles with the namespaces of the dump.
+    codec: TitleCodec,
+    pages: BTreeMap<Title, DumpPage>,
+    ids: HashMap<u32, Title>,
+    links: BTreeMap<Title, Vec<Title>>,
+    backlinks: BTreeMap<Title, Vec<Title>>,
+    images: BTreeMap<Title, Vec<Title>>,
+    file_usage: BTreeMap<Title, Vec<Title>>,
+    categories: BTreeMap<Title, Vec<Title>>,
+    /// Members of each category with their sort keys, in dump order.
+    category_members: BTreeMap<Title, Vec<(String, Title)>>,
+}
+
+type Answer = futures::stream::Iter<std::vec::IntoIter<TrioResult<PageInfo, Infallible, Infallible>>>;
+
+/// A `DataProvider` answering from a MediaWiki XML dump, eg. `pages-articles.xml`, loaded into memory.
+///
+/// Page information comes from each page's `<title>`, `<ns>`, `<id>` and `<redirect>`, and from the latest revision in
+/// the dump. Links, categories and files used are read from the `[[...]]` links in the wikitext of that revision, so
+/// anything added by templates is missed. Embeds and templates are not read, and neither are language links, which the
+/// dump cannot tell from links to other namespaces; those queries yield nothing.
+///
+/// The `namespace`, `filter_redirects`, `direct` and `resolve_redirects` settings in the configs are honored.
+/// The dump holds no time a member was added to a category, so category members are listed in dump order, and the
+/// timestamp bounds are ignored. Sorting by sort key uses the key given in the category link, or the page name.
+///
+/// Clones share the loaded dump.
+#[derive(Debug, Clone)]
+pub struct DumpDataProvider {
+    index: Arc<DumpIndex>,
+}
+
+impl DumpDataProvider {
+    /// Load an uncompressed dump file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, DumpError> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Load a dump from `reader`, one `<page>` element at a time.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, DumpError> {
+        let mut index = None;
+        let mut block = String::new();
+        let mut count = 0;
+        for line in reader.lines() {
+            let line = line?;
+            block.push_str(&line);
+            block.push('\n');
+            if line.contains("</siteinfo>") {
+                index = Some(DumpIndex::from_siteinfo(&block));
+                block.clear();
+            } else if line.contains("</page>") {
+                count += 1;
+                let index = index.as_mut().ok_or(DumpError::MissingSiteInfo)?;
+                index.read_page(&block).ok_or(DumpError::Malformed { page: count })?;
+                block.clear();
+            } else if line.contains("<page>") {
+                // drop whatever preceded the page, eg. the `<mediawiki>` root.
+                block.clear();
+                block.push_str(&line);
+                block.push('\n');
+            }
+        }
+        let index = index.ok_or(DumpError::MissingSiteInfo)?;
+        Ok(Self { index: Arc::new(index) })
+    }
+
+    /// Get the page information of `title`. Pages not in the dump are treated as missing.
+    pub fn page_info(&self, title: &Title) -> PageInfo {
+        let page = self.index.pages.get(title);
+        let assoc_title = self.index.associated(title);
+        let assoc_page = assoc_title.as_ref().and_then(|t| self.index.pages.get(t));
+        let assoc_exists = assoc_title.as_ref().map(|_| assoc_page.is_some());
+        let assoc_redirect = assoc_title.as_ref().map(|_| assoc_page.is_some_and(|p| p.redirect.is_some()));
+        PageInfo::new(Some(title.to_owned()), Some(page.is_some()), Some(page.is_some_and(|p| p.redirect.is_some())), assoc_title, assoc_exists, assoc_redirect)
+            .with_details(page.map(|p| p.id), page.map(|p| p.length), page.and_then(|p| p.touched.clone()), assoc_page.map(|p| p.id))
+    }
+
+    /// Parse a title the way the wiki of the dump would, using its namespace names.
+    pub fn parse_title(&self, raw: &str) -> Option<Title> {
+        self.index.parse_title(raw)
+    }
+
+    /// Turn `titles` into page information, following redirects if asked to, and keeping those in `namespace`.
+    /// A page reached twice, eg. through two redirects, is yielded once.
+    fn answer<'a, I>(&self, titles: I, namespace: Option<&HashSet<i32>>, filter: Option<FilterRedirect>, resolve: bool) -> Answer
+    where
+        I: IntoIterator<Item = &'a Title>,
+    {
+        let mut seen = BTreeSet::new();
+        let items: Vec<_> = titles.into_iter()
+            .map(|t| match self.index.pages.get(t).and_then(|p| p.redirect.as_ref()) {
+                Some(target) if resolve => target,
+                _ => t,
+            })
+            .filter(|t| namespace.is_none_or(|ns| ns.contains(&t.namespace())))
+            .filter(|t| match filter {
+                None => true,
+                Some(filter) => {
+                    let redirect = self.index.pages.get(t).is_some_and(|p| p.redirect.is_some());
+                    redirect == (filter == FilterRedirect::OnlyRedirect)
+                },
+            })
+            .filter(|t| seen.insert(*t))
+            .map(|t| TrioResult::Ok(self.page_info(t)))
+            .collect();
+        futures::stream::iter(items)
+    }
+
+    fn relation<'a>(relation: &'a BTreeMap<Title, Vec<Title>>, title: &Title) -> impl Iterator<Item = &'a Title> + Clone {
+        relation.get(title).into_iter().flatten()
+    }
+}
+
+impl DumpIndex {
+    /// Make an empty index knowing the namespaces and the language in `<siteinfo>`.
+    fn from_siteinfo(block: &str) -> Self {
+        let lang = opening_tag(block, "mediawiki").and_then(|tag| attribute(tag, "xml:lang")).unwrap_or_else(|| "en".to_string());
+        let mut namespaces = Vec::new();
+        let mut rest = block;
+        while let Some(start) = rest.find("<namespace ") {
+            rest = &rest[start..];
+            let Some(open_end) = rest.find('>') else { break };
+            let open = &rest[..open_end];
+            let name = if open.ends_with('/') {
+                ""
+            } else {
+                rest[open_end + 1..].split("</namespace>").next().unwrap_or("")
+            };
+            if let Some(id) = attribute(open, "key").and_then(|k| k.parse().ok()) {
+                let case = attribute(open, "case").unwrap_or_else(|| "first-letter".to_string());
+                namespaces.push(NamespaceInfo { id, case, name: unescape(name), canonical: None });
+            }
+            rest = &rest[open_end..];
+        }
+        Self {
+            codec: codec_from_namespaces(namespaces, &lang),
+            pages: BTreeMap::new(),
+            ids: HashMap::new(),
+            links: BTreeMap::new(),
+            backlinks: BTreeMap::new(),
+            images: BTreeMap::new(),
+            file_usage: BTreeMap::new(),
+            categories: BTreeMap::new(),
+            category_members: BTreeMap::new(),
+        }
+    }
+
+    /// Read a `<page>` element, and record it and its links. Returns `None` if the page lacks its title, namespace or id.
+    fn read_page(&mut self, block: &str) -> Option<()> {
+        let namespace: i32 = element(block, "ns")?.trim().parse().ok()?;
+        let full_title = unescape(element(block, "title")?);
+        let name = match namespace {
+            0 => full_title.as_str(),
+            _ => full_title.split_once(':').map_or(full_title.as_str(), |(_, name)| name),
+        };
+        let title = self.codec.new_title_from_database(namespace, name).ok()?;
+        // the page id comes before any revision id.
+        let id = element(block, "id")?.trim().parse().ok()?;
+        let redirect = opening_tag(block, "redirect")
+            .and_then(|tag| attribute(tag, "title"))
+            .and_then(|target| self.parse_title(&target));
+        let text = unescape(element(block, "text").unwrap_or(""));
+        let length = opening_tag(block, "text")
+            .and_then(|tag| attribute(tag, "bytes"))
+            .and_then(|bytes| bytes.parse().ok())
+            .unwrap_or(text.len() as u32);
+        let touched = element(block, "timestamp").map(|t| t.trim().to_owned());
+
+        let mut seen = BTreeSet::new();
+        for (target, label, colon) in wikilinks(&text) {
+            let Some(target) = self.parse_title(target) else { continue };
+            match target.namespace() {
+                14 if !colon => {
+                    if !seen.insert((14, target.clone())) { continue; }
+                    let sortkey = label.map_or_else(|| title.dbkey().replace('_', " "), |l| l.trim().to_owned());
+                    self.categories.entry(title.clone()).or_default().push(target.clone());
+                    self.category_members.entry(target).or_default().push((sortkey, title.clone()));
+                },
+                6 | -2 if !colon || target.namespace() == -2 => {
+                    let Ok(target) = self.codec.new_title_from_database(6, target.dbkey()) else { continue };
+                    if !seen.insert((6, target.clone())) { continue; }
+                    self.images.entry(title.clone()).or_default().push(target.clone());
+                    self.file_usage.entry(target).or_default().push(title.clone());
+                },
+                _ => {
+                    if !seen.insert((0, target.clone())) { continue; }
+                    self.links.entry(title.clone()).or_default().push(target.clone());
+                    self.backlinks.entry(target).or_default().push(title.clone());
+                },
+            }
+        }
+
+        self.ids.insert(id, title.clone());
+        self.pages.insert(title, DumpPage { id, redirect, length, touched });
+        Some(())
+    }
+
+    /// Parse a title with a namespace prefix known to the dump. Anything else lands in the main namespace.
+    /// Returns `None` for a title that is not valid, eg. an empty one.
+    fn parse_title(&self, raw: &str) -> Option<Title> {
+        self.codec.new_title(raw).ok()
+    }
+
+    /// The talk page of a subject page, or the other way around. Virtual namespaces have none, and neither do
+    /// namespaces missing from the dump.
+    fn associated(&self, title: &Title) -> Option<Title> {
+        if title.namespace() < 0 {
+            return None;
+        }
+        self.codec.new_title_from_database(title.namespace() ^ 1, title.dbkey()).ok()
+    }
+}
+
+/// Get the opening tag `<tag ...>` of the first `tag` element in `block`, without the angle brackets.
+fn opening_tag<'a>(block: &'a str, tag: &str) -> Option<&'a str> {
+    let mut from = 0;
+    while let Some(start) = block[from..].find('<').map(|i| i + from) {
+        let rest = &block[start + 1..];
+        if rest.starts_with(tag) && rest[tag.len()..].starts_with([' ', '>', '/']) {
+            return rest.find('>').map(|end| &rest[..end]);
+        }
+        from = start + 1;
+    }
+    None
+}
+
+/// Get the raw content of the first `tag` element in `block`. A self-closing element has empty content.
+fn element<'a>(block: &'a str, tag: &str) -> Option<&'a str> {
+    let open = opening_tag(block, tag)?;
+    if open.ends_with('/') {
+        return Some("");
+    }
+    // `open` borrows from `block`, so its end is where the content starts.
+    let start = open.as_ptr() as usize - block.as_ptr() as usize + open.len() + 1;
+    let end = block[start..].find(&format!("</{tag}>"))?;
+    Some(&block[start..start + end])
+}
+
+/// Get the unescaped value of `name="..."` in an opening tag.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let pattern = format!(" {name}=\"");
+    let start = tag.find(&pattern)? + pattern.len();
+    let end = tag[start..].find('"')?;
+    Some(unescape(&tag[start..start + end]))
+}
+
+/// Replace the XML entities in `raw`. Unknown entities are kept as they are.
+fn unescape(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').and_then(|semi| {
+            let c = match &rest[1..semi] {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                entity => entity.strip_prefix("#x").map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(str::parse))
+                    .and_then(Result::ok)
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, semi + 1))
+        });
+        match decoded {
+            Some((c, len)) => { out.push(c); rest = &rest[len..]; },
+            None => { out.push('&'); rest = &rest[1..]; },
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Find the `[[target|label]]` links in wikitext, in order. The flag tells whether the target has a leading colon,
+/// which makes a link to a category or file a plain link.
+///
+/// Links nested in a file caption are found too, as the caption is not skipped.
+fn wikilinks(text: &str) -> impl Iterator<Item = (&str, Option<&str>, bool)> {
+    text.match_indices("[[").filter_map(|(i, _)| {
+        let rest = &text[i + 2..];
+        let end = rest.find("]]")?;
+        let target_end = rest[..end].find('|').unwrap_or(end);
+        let target = &rest[..target_end];
+        if target.contains(['\n', '[', '{']) {
+            return None;
+        }
+        let label = (target_end < end && !rest[target_end + 1..end].contains("[[")).then(|| &rest[target_end + 1..end]);
+        let (target, colon) = match target.trim_start().strip_prefix(':') {
+            Some(target) => (target, true),
+            None => (target, false),
+        };
+        // links to a section of the same page have nothing before the `#`.
+        let target = target.split('#').next().unwrap_or("");
+        (!target.trim().is_empty()).then_some((target, label, colon))
+    })
+}
+
+impl DataProvider for DumpDataProvider {
+    type Error = Infallible;
+    type Warn = Infallible;
+
+    fn get_page_info<T: IntoIterator<Item = Title>>(&self, titles: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let mut seen = BTreeSet::new();
+        let items: Vec<_> = titles.into_iter()
+            .filter(|t| seen.insert(t.to_owned()))
+            .map(|t| TrioResult::Ok(self.page_info(&t)))
+            .collect();
+        futures::stream::iter(items)
+    }
+
+    fn get_page_info_from_raw<T: IntoIterator<Item = String>>(&self, titles_raw: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let titles: Vec<_> = titles_raw.into_iter().filter_map(|raw| self.parse_title(&raw)).collect();
+        self.get_page_info(titles)
+    }
+
+    fn get_page_info_by_id<T: IntoIterator<Item = u32>>(&self, pageids: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let items: Vec<_> = pageids.into_iter()
+            .filter_map(|id| self.index.ids.get(&id))
+            .map(|t| TrioResult::Ok(self.page_info(t)))
+            .collect();
+        futures::stream::iter(items)
+    }
+
+    fn get_links(&self, title: Title, config: &LinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.answer(Self::relation(&self.index.links, &title), config.namespace.as_ref(), None, config.resolve_redirects)
+    }
+
+    fn get_backlinks(&self, title: Title, config: &BackLinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let direct = Self::relation(&self.index.backlinks, &title);
+        // pages linking to the redirects of `title`, after the redirects themselves.
+        let through_redirects = direct.clone()
+            .filter(|t| !config.direct && self.index.pages.get(*t).and_then(|p| p.redirect.as_ref()) == Some(&title))
+            .flat_map(|t| Self::relation(&self.index.backlinks, t));
+        self.answer(direct.chain(through_redirects), config.namespace.as_ref(), config.filter_redirects, config.resolve_redirects)
+    }
+
+    fn get_embeds(&self, _title: Title, _config: &EmbedsConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        futures::stream::empty()
+    }
+
+    fn get_templates(&self, _title: Title, _config: &TemplatesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        futures::stream::empty()
+    }
+
+    fn get_images(&self, title: Title, config: &ImagesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.answer(Self::relation(&self.index.images, &title), config.namespace.as_ref(), None, config.resolve_redirects)
+    }
+
+    fn get_file_usage(&self, title: Title, config: &FileUsageConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.answer(Self::relation(&self.index.file_usage, &title), config.namespace.as_ref(), config.filter_redirects, config.resolve_redirects)
+    }
+
+    fn get_categories(&self, title: Title, config: &CategoriesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.answer(Self::relation(&self.index.categories, &title), config.namespace.as_ref(), None, config.resolve_redirects)
+    }
+
+    fn get_langlinks(&self, _title: Title, _config: &LangLinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        futures::stream::empty()
+    }
+
+    fn get_category_members(&self, title: Title, config: &CategoryMembersConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let mut members: Vec<_> = self.index.category_members.get(&title).into_iter().flatten().collect();
+        if config.sort == Some(CategorySort::SortKey) {
+            members.sort_by(|(key_a, a), (key_b, b)| key_a.to_uppercase().cmp(&key_b.to_uppercase()).then(a.cmp(b)));
+        }
+        if config.dir == Some(SortDirection::Descending) {
+            members.reverse();
+        }
+        self.answer(members.into_iter().map(|(_, t)| t), config.namespace.as_ref(), None, config.resolve_redirects)
+    }
+
+    fn get_prefix(&self, title: Title, config: &PrefixConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let pages = self.index.pages.range(&title..)
+            .map(|(t, _)| t)
+            .take_while(|t| t.namespace() == title.namespace() && t.dbkey().starts_with(title.dbkey()))
+            .collect::<Vec<_>>();
+        self.answer(pages, None, config.filter_redirects, false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DumpDataProvider, DumpError};
+    use crate::{
+        config::*,
+        core::DataProvider,
+        pageinfo::PageInfo,
+    };
+    use core::convert::Infallible;
+    use futures::{executor::block_on, Stream, StreamExt};
+    use trio_result::TrioResult;
+
+    const DUMP: &str = r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.11/" version="0.11" xml:lang="en">
+  <siteinfo>
+    <sitename>Wikipedia</sitename>
+    <namespaces>
+      <namespace key="0" case="first-letter" />
+      <namespace key="1" case="first-letter">Talk</namespace>
+      <namespace key="6" case="first-letter">File</namespace>
+      <namespace key="14" case="first-letter">Category</namespace>
+    </namespaces>
+  </siteinfo>
+  <page>
+    <title>Foo bar</title>
+    <ns>0</ns>
+    <id>1</id>
+    <revision>
+      <id>100</id>
+      <timestamp>2024-01-01T00:00:00Z</timestamp>
+      <text bytes="120" xml:space="preserve">See [[baz]], [[Qux|qux]] and [[#Top]].
+[[File:Pic.png|thumb|A [[Baz]] picture]] [[:Category:Fruits]]
+[[Category:Fruits|Zeta]]
+[[Category:Things]]</text>
+    </revision>
+  </page>
+  <page>
+    <title>Baz</title>
+    <ns>0</ns>
+    <id>2</id>
+    <revision>
+      <id>101</id>
+      <text bytes="30" xml:space="preserve">[[Category:Fruits|Alpha]] &amp; [[Foo bar]]</text>
+    </revision>
+  </page>
+  <page>
+    <title>Qux</title>
+    <ns>0</ns>
+    <id>3</id>
+    <redirect title="Baz" />
+    <revision>
+      <id>102</id>
+      <text bytes="14" xml:space="preserve">#REDIRECT [[Baz]]</text>
+    </revision>
+  </page>
+  <page>
+    <title>Talk:Baz</title>
+    <ns>1</ns>
+    <id>4</id>
+    <revision>
+      <id>103</id>
+      <text bytes="11" xml:space="preserve">[[Qux]] &lt;3</text>
+    </revision>
+  </page>
+</mediawiki>
+"#;
+
+    fn provider() -> DumpDataProvider {
+        DumpDataProvider::from_reader(DUMP.as_bytes()).unwrap()
+    }
+
+    fn titles<S: Stream<Item=TrioResult<PageInfo, Infallible, Infallible>>>(stream: S) -> Vec<String> {
+        block_on(stream.collect::<Vec<_>>())
+            .into_iter()
+            .map(|x| {
+                let title = x.ok().unwrap().get_title().unwrap().to_owned();
+                format!("{}:{}", title.namespace(), title.dbkey())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_page_info() {
+        let provider = provider();
+        let baz = provider.parse_title("baz").unwrap();
+        let info = provider.page_info(&baz);
+        assert_eq!(info.get_pageid(), Ok(2));
+        assert_eq!(info.get_length(), Ok(30));
+        assert_eq!(info.get_assoc_exists(), Ok(true));
+        assert_eq!(info.get_isredir(), Ok(false));
+
+        let qux = provider.page_info(&provider.parse_title("Qux").unwrap());
+        assert_eq!(qux.get_isredir(), Ok(true));
+        assert_eq!(qux.get_assoc_exists(), Ok(false));
+        assert_eq!(provider.page_info(&provider.parse_title("Missing").unwrap()).get_exists(), Ok(false));
+
+        assert_eq!(titles(provider.get_page_info_by_id([4, 9, 1])), ["1:Baz", "0:Foo_bar"]);
+        assert_eq!(titles(provider.get_page_info_from_raw(["talk:baz".to_string(), "Foo_bar".to_string()])), ["1:Baz", "0:Foo_bar"]);
+    }
+
+    #[test]
+    fn test_links() {
+        let provider = provider();
+        let foo = provider.parse_title("Foo bar").unwrap();
+
+        // the file and the category are not links, the section link is skipped, and a colon link is a plain link.
+        assert_eq!(titles(provider.get_links(foo.clone(), &LinksConfig::default())), ["0:Baz", "0:Qux", "14:Fruits"]);
+        assert_eq!(titles(provider.get_links(foo.clone(), &LinksConfig { namespace: Some([0].into()), resolve_redirects: true })), ["0:Baz"]);
+        assert_eq!(titles(provider.get_images(foo.clone(), &ImagesConfig::default())), ["6:Pic.png"]);
+        assert_eq!(titles(provider.get_categories(foo, &CategoriesConfig::default())), ["14:Fruits", "14:Things"]);
+    }
+
+    #[test]
+    fn test_backlinks() {
+        let provider = provider();
+        let baz = provider.parse_title("Baz").unwrap();
+
+        assert_eq!(titles(provider.get_backlinks(baz.clone(), &BackLinksConfig { direct: true, ..Default::default() })), ["0:Foo_bar", "0:Qux"]);
+        assert_eq!(titles(provider.get_backlinks(baz.clone(), &BackLinksConfig::default())), ["0:Foo_bar", "0:Qux", "1:Baz"]);
+        let config = BackLinksConfig { filter_redirects: Some(FilterRedirect::NoRedirect), ..Default::default() };
+        assert_eq!(titles(provider.get_backlinks(baz, &config)), ["0:Foo_bar", "1:Baz"]);
+    }
+
+    #[test]
+    fn test_category_members() {
+        let provider = provider();
+        let fruits = provider.parse_title("Category:Fruits").unwrap();
+
+        assert_eq!(titles(provider.get_category_members(fruits.clone(), &CategoryMembersConfig::default())), ["0:Foo_bar", "0:Baz"]);
+        let config = CategoryMembersConfig::default().with_sort(CategorySort::SortKey, SortDirection::Ascending);
+        assert_eq!(titles(provider.get_category_members(fruits.clone(), &config)), ["0:Baz", "0:Foo_bar"]);
+        let config = CategoryMembersConfig::default().with_sort(CategorySort::SortKey, SortDirection::Descending);
+        assert_eq!(titles(provider.get_category_members(fruits, &config)), ["0:Foo_bar", "0:Baz"]);
+    }
+
+    #[test]
+    fn test_prefix() {
+        let provider = provider();
+        assert_eq!(titles(provider.get_prefix(provider.parse_title("Ba").unwrap(), &PrefixConfig::default())), ["0:Baz"]);
+        assert_eq!(titles(provider.get_prefix(provider.parse_title("Talk:B").unwrap(), &PrefixConfig::default())), ["1:Baz"]);
+    }
+
+    #[test]
+    fn test_malformed() {
+        let dump = "<mediawiki>\n<page>\n<title>Foo</title>\n<ns>0</ns>\n</page>\n</mediawiki>\n";
+        assert!(matches!(DumpDataProvider::from_reader(dump.as_bytes()), Err(DumpError::MissingSiteInfo)));
+        let (siteinfo, _) = DUMP.split_once("  <page>").unwrap();
+        let dump = format!("{siteinfo}<page>\n<title>Foo</title>\n<ns>0</ns>\n</page>\n</mediawiki>\n");
+        assert!(matches!(DumpDataProvider::from_reader(dump.as_bytes()), Err(DumpError::Malformed { page: 1 })));
+    }
+}
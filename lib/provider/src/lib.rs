@@ -1,15 +1,35 @@
 //! Traits and common data structures for data provider.
 
+pub mod cache;
+#[cfg(any(test, feature = "dump", feature = "sql", feature = "test-util"))]
+mod codec;
 pub mod config;
 pub mod core;
+#[cfg(feature = "dump")]
+pub mod dump;
+#[cfg(any(test, feature = "test-util"))]
+pub mod mock;
 pub mod pageinfo;
+pub mod ratelimit;
+pub mod retry;
+#[cfg(feature = "sql")]
+pub mod sql;
+#[cfg(test)]
+mod test_provider;
 
 // re-exports of core traits and types
+pub use crate::cache::CachingDataProvider;
 pub use crate::config::{
-    FilterRedirect,
-    LinksConfig, BackLinksConfig, EmbedsConfig, CategoryMembersConfig, PrefixConfig,
+    FilterRedirect, CategorySort, SortDirection,
+    LinksConfig, BackLinksConfig, EmbedsConfig, TemplatesConfig, ImagesConfig, FileUsageConfig, CategoriesConfig, LangLinksConfig, CategoryMembersConfig, PrefixConfig,
 };
 pub use crate::core::DataProvider;
+#[cfg(feature = "dump")]
+pub use crate::dump::{DumpDataProvider, DumpError};
+pub use crate::ratelimit::{Pacer, RateLimitedDataProvider};
+pub use crate::retry::RetryingDataProvider;
+#[cfg(feature = "sql")]
+pub use crate::sql::{SqlConnection, SqlDataProvider, SqlError, SqlValue};
 pub use crate::pageinfo::{
-    PageInfo, PageInfoError,
+    PageInfo, PageInfoError, ProtectionEntry,
 };
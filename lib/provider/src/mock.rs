@@ -0,0 +1,265 @@
+//! An in-memory `DataProvider` for tests.
+
+use crate::{
+    codec::codec_from_namespaces,
+    config::{
+        CategorySort, SortDirection,
+        LinksConfig, BackLinksConfig, EmbedsConfig, TemplatesConfig, ImagesConfig, FileUsageConfig, CategoriesConfig, LangLinksConfig, CategoryMembersConfig, PrefixConfig,
+    },
+    core::DataProvider,
+    pageinfo::PageInfo,
+};
+use core::convert::Infallible;
+use futures::Stream;
+use mwtitle::{NamespaceInfo, Title, TitleCodec};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    sync::{Arc, OnceLock, atomic::{AtomicUsize, Ordering}},
+};
+use trio_result::TrioResult;
+
+/// Canonical namespace names understood by `parse_title`.
+const NAMESPACES: [(&str, i32); 16] = [
+    ("Talk", 1), ("User", 2), ("User talk", 3), ("Project", 4), ("Project talk", 5), ("File", 6), ("File talk", 7),
+    ("MediaWiki", 8), ("MediaWiki talk", 9), ("Template", 10), ("Template talk", 11), ("Help", 12), ("Help talk", 13),
+    ("Category", 14), ("Category talk", 15), ("Special", -1),
+];
+
+/// A codec knowing the main namespace and `NAMESPACES`.
+pub(crate) fn codec() -> &'static TitleCodec {
+    static CODEC: OnceLock<TitleCodec> = OnceLock::new();
+    CODEC.get_or_init(|| {
+        let namespaces = NAMESPACES.iter().copied().chain([("", 0)]).map(|(name, id)| NamespaceInfo {
+            id,
+            case: "first-letter".to_string(),
+            name: name.to_string(),
+            canonical: (!name.is_empty()).then(|| name.to_string()),
+        });
+        codec_from_namespaces(namespaces, "en")
+    })
+}
+
+/// Parse a title with an English canonical namespace prefix, eg. `Category:Foo bar`. Anything else lands in the main namespace.
+///
+/// Panics if `raw` is not a valid title.
+pub fn parse_title(raw: &str) -> Title {
+    codec().new_title(raw).unwrap()
+}
+
+/// A `DataProvider` answering from explicit maps, eg. page → links, category → members.
+///
+/// Each relation maps an input title to its results, in order. Missing entries yield nothing.
+/// A result's page information is looked up in `pages`; titles not found there are treated as existing non-redirect
+/// pages without an associated page.
+///
+/// The `namespace` filters in the configs are honored, unless `ignore_namespace` is set. Redirect resolution, filtering and timestamp bounds are ignored.
+/// Category members are listed in the recorded order, taken as the order they were added. An explicit sort by sort key
+/// sorts them by title instead.
+#[derive(Debug, Clone, Default)]
+pub struct MockDataProvider {
+    pub pages: BTreeMap<Title, PageInfo>,
+    pub links: BTreeMap<Title, Vec<Title>>,
+    pub backlinks: BTreeMap<Title, Vec<Title>>,
+    pub embeds: BTreeMap<Title, Vec<Title>>,
+    pub templates: BTreeMap<Title, Vec<Title>>,
+    pub images: BTreeMap<Title, Vec<Title>>,
+    pub file_usage: BTreeMap<Title, Vec<Title>>,
+    pub categories: BTreeMap<Title, Vec<Title>>,
+    pub langlinks: BTreeMap<Title, Vec<Title>>,
+    pub category_members: BTreeMap<Title, Vec<Title>>,
+    pub prefix: BTreeMap<Title, Vec<Title>>,
+    /// Return results in every namespace, like a provider that cannot filter by namespace.
+    pub ignore_namespace: bool,
+    /// The number of relation lookups made so far, shared between clones.
+    queries: Arc<AtomicUsize>,
+}
+
+impl MockDataProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the page information of `title`.
+    pub fn page_info(&self, title: &Title) -> PageInfo {
+        self.pages.get(title).cloned().unwrap_or_else(|| PageInfo::new(Some(title.to_owned()), Some(true), Some(false), None, None, None))
+    }
+
+    /// Get the number of relation lookups made so far by this provider and its clones, eg. one per `get_links` call.
+    pub fn query_count(&self) -> usize {
+        self.queries.load(Ordering::Relaxed)
+    }
+
+    /// Look up `title` in `relation`, and keep the results in `namespace`.
+    fn lookup(&self, relation: &BTreeMap<Title, Vec<Title>>, title: &Title, namespace: Option<&HashSet<i32>>) -> impl Stream<Item=TrioResult<PageInfo, Infallible, Infallible>> {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        let items: Vec<_> = relation.get(title)
+            .into_iter()
+            .flatten()
+            .filter(|t| self.ignore_namespace || namespace.is_none_or(|ns| ns.contains(&t.namespace())))
+            .map(|t| TrioResult::Ok(self.page_info(t)))
+            .collect();
+        futures::stream::iter(items)
+    }
+}
+
+impl DataProvider for MockDataProvider {
+    type Error = Infallible;
+    type Warn = Infallible;
+
+    fn get_page_info<T: IntoIterator<Item = Title>>(&self, titles: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let mut seen = BTreeSet::new();
+        let items: Vec<_> = titles.into_iter()
+            .filter(|t| seen.insert(t.to_owned()))
+            .map(|t| TrioResult::Ok(self.page_info(&t)))
+            .collect();
+        futures::stream::iter(items)
+    }
+
+    fn get_page_info_from_raw<T: IntoIterator<Item = String>>(&self, titles_raw: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.get_page_info(titles_raw.into_iter().filter_map(|raw| codec().new_title(&raw).ok()))
+    }
+
+    fn get_page_info_by_id<T: IntoIterator<Item = u32>>(&self, pageids: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let items: Vec<_> = pageids.into_iter()
+            .filter_map(|id| self.pages.values().find(|info| info.get_pageid() == Ok(id)))
+            .map(|info| TrioResult::Ok(info.clone()))
+            .collect();
+        futures::stream::iter(items)
+    }
+
+    fn get_links(&self, title: Title, config: &LinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.lookup(&self.links, &title, config.namespace.as_ref())
+    }
+
+    fn get_backlinks(&self, title: Title, config: &BackLinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.lookup(&self.backlinks, &title, config.namespace.as_ref())
+    }
+
+    fn get_embeds(&self, title: Title, config: &EmbedsConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.lookup(&self.embeds, &title, config.namespace.as_ref())
+    }
+
+    fn get_templates(&self, title: Title, config: &TemplatesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.lookup(&self.templates, &title, config.namespace.as_ref())
+    }
+
+    fn get_images(&self, title: Title, config: &ImagesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.lookup(&self.images, &title, config.namespace.as_ref())
+    }
+
+    fn get_file_usage(&self, title: Title, config: &FileUsageConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.lookup(&self.file_usage, &title, config.namespace.as_ref())
+    }
+
+    fn get_categories(&self, title: Title, config: &CategoriesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.lookup(&self.categories, &title, config.namespace.as_ref())
+    }
+
+    fn get_langlinks(&self, title: Title, _config: &LangLinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let items: Vec<_> = self.langlinks.get(&title)
+            .into_iter()
+            .flatten()
+            .map(|t| TrioResult::Ok(PageInfo::new_foreign(t.to_owned())))
+            .collect();
+        futures::stream::iter(items)
+    }
+
+    fn get_category_members(&self, title: Title, config: &CategoryMembersConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        let mut members: Vec<_> = self.category_members.get(&title)
+            .into_iter()
+            .flatten()
+            .filter(|t| self.ignore_namespace || config.namespace.as_ref().is_none_or(|ns| ns.contains(&t.namespace())))
+            .collect();
+        if config.sort == Some(CategorySort::SortKey) {
+            members.sort();
+        }
+        if config.dir == Some(SortDirection::Descending) {
+            members.reverse();
+        }
+        let items: Vec<_> = members.into_iter().map(|t| TrioResult::Ok(self.page_info(t))).collect();
+        futures::stream::iter(items)
+    }
+
+    fn get_prefix(&self, title: Title, _config: &PrefixConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.lookup(&self.prefix, &title, None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MockDataProvider, parse_title};
+    use crate::{
+        config::{CategoryMembersConfig, LinksConfig},
+        core::DataProvider,
+        pageinfo::PageInfo,
+    };
+    use futures::{executor::block_on, StreamExt};
+
+    #[test]
+    fn test_parse_title() {
+        let key = |raw: &str| {
+            let title = parse_title(raw);
+            (title.namespace(), title.dbkey().to_owned())
+        };
+        assert_eq!(key("Foo bar"), (0, "Foo_bar".to_string()));
+        assert_eq!(key("Category:foo"), (14, "Foo".to_string()));
+        assert_eq!(key("user talk:Foo"), (3, "Foo".to_string()));
+        assert_eq!(key("Foo: bar"), (0, "Foo:_bar".to_string()));
+    }
+
+    #[test]
+    fn test_lookup() {
+        let mut provider = MockDataProvider::new();
+        provider.links.insert(parse_title("A"), vec![parse_title("B"), parse_title("Category:C")]);
+
+        let all: Vec<_> = block_on(provider.get_links(parse_title("A"), &LinksConfig::default()).collect());
+        let main: Vec<_> = block_on(provider.get_links(parse_title("A"), &LinksConfig { namespace: Some([0].into()), resolve_redirects: false }).collect());
+        let none: Vec<_> = block_on(provider.get_links(parse_title("B"), &LinksConfig::default()).collect());
+
+        assert_eq!(all.len(), 2);
+        assert_eq!(main.len(), 1);
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn test_category_members_multi() {
+        let mut provider = MockDataProvider::new();
+        provider.category_members.insert(parse_title("Category:X"), vec![parse_title("A"), parse_title("B")]);
+        provider.category_members.insert(parse_title("Category:Y"), vec![parse_title("C")]);
+
+        let categories = [parse_title("Category:X"), parse_title("Category:Y")];
+        let items: Vec<_> = block_on(provider.get_category_members_multi(categories, &CategoryMembersConfig::default()).collect());
+        let items: Vec<_> = items.into_iter().map(|x| x.ok().unwrap().get_title().unwrap().to_owned()).collect();
+
+        assert_eq!(items, vec![parse_title("A"), parse_title("B"), parse_title("C")]);
+    }
+
+    #[test]
+    fn test_ignore_namespace() {
+        let mut provider = MockDataProvider::new();
+        provider.links.insert(parse_title("A"), vec![parse_title("B"), parse_title("Category:C")]);
+        provider.category_members.insert(parse_title("Category:X"), vec![parse_title("A"), parse_title("Category:Y")]);
+        provider.ignore_namespace = true;
+
+        let links = LinksConfig { namespace: Some([0].into()), resolve_redirects: false };
+        let members = CategoryMembersConfig { namespace: Some([0].into()), ..Default::default() };
+        assert_eq!(block_on(provider.get_links(parse_title("A"), &links).count()), 2);
+        assert_eq!(block_on(provider.get_category_members(parse_title("Category:X"), &members).count()), 2);
+    }
+
+    #[test]
+    fn test_page_info_by_id() {
+        let mut provider = MockDataProvider::new();
+        for (id, name) in [(1, "A"), (2, "B")] {
+            let info = PageInfo::new(Some(parse_title(name)), Some(true), Some(false), None, None, None).with_details(Some(id), None, None, None);
+            provider.pages.insert(parse_title(name), info);
+        }
+
+        let items: Vec<_> = block_on(provider.get_page_info_by_id([2, 3, 1]).collect());
+        let items: Vec<_> = items.into_iter().map(|x| x.ok().unwrap().get_title().unwrap().to_owned()).collect();
+
+        // unknown ids are skipped, the rest keep the input order.
+        assert_eq!(items, vec![parse_title("B"), parse_title("A")]);
+    }
+}
@@ -1,4 +1,4 @@
-//! Definition and implementations for `PageInfo` and `Pair`.
+//! Definition and implementations for `PageInfo` and `ProtectionEntry`.
 
 use core::{
     cmp::Ordering,
@@ -16,6 +16,26 @@ pub struct PageInfo {
     assoc_title: Option<Title>,
     assoc_exists: Option<bool>,
     assoc_redirect: Option<bool>,
+    pageid: Option<u32>,
+    length: Option<u32>,
+    touched: Option<String>,
+    assoc_pageid: Option<u32>,
+    assoc_length: Option<u32>,
+    assoc_touched: Option<String>,
+    protection: Option<Vec<ProtectionEntry>>,
+    assoc_protection: Option<Vec<ProtectionEntry>>,
+    foreign: bool,
+}
+
+/// a single protection on a page, eg. edit protection for administrators only.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProtectionEntry {
+    /// the protected action, eg. `edit` or `move`.
+    pub action: String,
+    /// the user group allowed to perform the action, eg. `sysop`.
+    pub level: String,
+    /// when the protection expires, `None` if it never does.
+    pub expiry: Option<String>,
 }
 
 impl PageInfo {
@@ -24,7 +44,40 @@ impl PageInfo {
         title: Option<Title>, exists: Option<bool>, redirect: Option<bool>,
         assoc_title: Option<Title>, assoc_exists: Option<bool>, assoc_redirect: Option<bool>
     ) -> Self {
-        Self { title, exists, redirect, assoc_title, assoc_exists, assoc_redirect }
+        Self {
+            title, exists, redirect, assoc_title, assoc_exists, assoc_redirect,
+            pageid: None, length: None, touched: None, assoc_pageid: None, assoc_length: None, assoc_touched: None,
+            protection: None, assoc_protection: None,
+            foreign: false,
+        }
+    }
+
+    /// attaches the page id, byte length and last touched timestamp of the subject page, and the page id of the associated page.
+    /// All of these are unknown for a page that does not exist.
+    pub fn with_details(mut self, pageid: Option<u32>, length: Option<u32>, touched: Option<String>, assoc_pageid: Option<u32>) -> Self {
+        self.pageid = pageid;
+        self.length = length;
+        self.touched = touched;
+        self.assoc_pageid = assoc_pageid;
+        self
+    }
+
+    /// attaches the protections of the subject page. An empty list means the page is not protected.
+    pub fn with_protection(mut self, protection: Vec<ProtectionEntry>) -> Self {
+        self.protection = Some(protection);
+        self
+    }
+
+    /// attaches whether the associated page is a redirect, if that was not known on creation.
+    pub fn with_assoc_redirect(mut self, assoc_redirect: bool) -> Self {
+        self.assoc_redirect = Some(assoc_redirect);
+        self
+    }
+
+    /// creates a new `PageInfo` instance for a page on another wiki, eg. a language link target.
+    /// The title carries the interwiki prefix. Nothing else is known about such a page.
+    pub fn new_foreign(title: Title) -> Self {
+        Self { foreign: true, ..Self::new(Some(title), None, None, None, None, None) }
     }
 
     pub fn new_swap(&self) -> Self {
@@ -43,16 +96,60 @@ impl PageInfo {
         self.exists.ok_or(PageInfoError::UnknownValue)
     }
 
+    /// get a reference to the title of the associated page, returns an error if such value is not known aka not stored.
+    pub fn get_assoc_title(&self) -> Result<&Title, PageInfoError> {
+        self.assoc_title.as_ref().ok_or(PageInfoError::UnknownValue)
+    }
+
+    /// get a bool indicating whether the associated page exists on the wiki, returns an error if such value is not known aka not stored.
+    pub fn get_assoc_exists(&self) -> Result<bool, PageInfoError> {
+        self.assoc_exists.ok_or(PageInfoError::UnknownValue)
+    }
+
     /// get a bool indicating whether this page is a redirect page, returns an error if such value is not known aka not stored.
     pub fn get_isredir(&self) -> Result<bool, PageInfoError> {
         self.redirect.ok_or(PageInfoError::UnknownValue)
     }
 
+    /// get a bool indicating whether the associated page is a redirect page, returns an error if such value is not known aka not stored.
+    pub fn get_assoc_isredir(&self) -> Result<bool, PageInfoError> {
+        self.assoc_redirect.ok_or(PageInfoError::UnknownValue)
+    }
+
+    /// get the page id, returns an error if such value is not known aka not stored.
+    pub fn get_pageid(&self) -> Result<u32, PageInfoError> {
+        self.pageid.ok_or(PageInfoError::UnknownValue)
+    }
+
+    /// get the page length in bytes, returns an error if such value is not known aka not stored.
+    pub fn get_length(&self) -> Result<u32, PageInfoError> {
+        self.length.ok_or(PageInfoError::UnknownValue)
+    }
+
+    /// get the timestamp this page was last touched, as returned by the API, returns an error if such value is not known aka not stored.
+    pub fn get_touched(&self) -> Result<&str, PageInfoError> {
+        self.touched.as_deref().ok_or(PageInfoError::UnknownValue)
+    }
+
+    /// get the protections of this page, returns an error if such value is not known aka not stored.
+    pub fn get_protection(&self) -> Result<&[ProtectionEntry], PageInfoError> {
+        self.protection.as_deref().ok_or(PageInfoError::UnknownValue)
+    }
+
+    /// get a bool indicating whether this page lives on another wiki.
+    pub fn is_foreign(&self) -> bool {
+        self.foreign
+    }
+
     /// Swap the subject page's information and the associated page's information.
     pub fn swap(&mut self) {
         mem::swap(&mut self.title, &mut self.assoc_title);
         mem::swap(&mut self.exists, &mut self.assoc_exists);
         mem::swap(&mut self.redirect, &mut self.assoc_redirect);
+        mem::swap(&mut self.pageid, &mut self.assoc_pageid);
+        mem::swap(&mut self.length, &mut self.assoc_length);
+        mem::swap(&mut self.touched, &mut self.assoc_touched);
+        mem::swap(&mut self.protection, &mut self.assoc_protection);
     }
 }
 
@@ -96,3 +193,50 @@ impl fmt::Display for PageInfoError {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{PageInfo, PageInfoError, ProtectionEntry};
+    use crate::mock::parse_title;
+
+    #[test]
+    fn test_details() {
+        let info = PageInfo::new(
+            Some(parse_title("Foo")), Some(true), Some(false),
+            Some(parse_title("Talk:Foo")), Some(true), None,
+        ).with_details(Some(42), Some(1234), Some("2024-01-01T00:00:00Z".to_string()), Some(43));
+        assert_eq!(info.get_pageid(), Ok(42));
+        assert_eq!(info.get_length(), Ok(1234));
+        assert_eq!(info.get_touched(), Ok("2024-01-01T00:00:00Z"));
+
+        // only the id of the associated page is known.
+        let swapped = info.new_swap();
+        assert_eq!(swapped.get_pageid(), Ok(43));
+        assert_eq!(swapped.get_length(), Err(PageInfoError::UnknownValue));
+        assert_eq!(swapped.get_touched(), Err(PageInfoError::UnknownValue));
+
+        let back = swapped.new_swap();
+        assert_eq!(back.get_pageid(), Ok(42));
+        assert_eq!(back.get_length(), Ok(1234));
+        assert_eq!(back.get_touched(), Ok("2024-01-01T00:00:00Z"));
+    }
+
+    #[test]
+    fn test_no_details() {
+        let info = PageInfo::new(Some(parse_title("Foo")), Some(false), Some(false), None, None, None);
+        assert_eq!(info.get_pageid(), Err(PageInfoError::UnknownValue));
+        assert_eq!(info.get_length(), Err(PageInfoError::UnknownValue));
+        assert_eq!(info.get_touched(), Err(PageInfoError::UnknownValue));
+        assert_eq!(info.get_protection(), Err(PageInfoError::UnknownValue));
+    }
+
+    #[test]
+    fn test_protection() {
+        let edit = ProtectionEntry { action: "edit".to_string(), level: "sysop".to_string(), expiry: None };
+        let info = PageInfo::new(Some(parse_title("Template:Foo")), Some(true), Some(false), None, None, None)
+            .with_protection(vec![edit.clone()]);
+        assert_eq!(info.get_protection(), Ok(&[edit.clone()][..]));
+        assert_eq!(info.new_swap().get_protection(), Err(PageInfoError::UnknownValue));
+        assert_eq!(info.new_swap().new_swap().get_protection(), Ok(&[edit][..]));
+    }
+}
@@ -0,0 +1,196 @@
+//! A `DataProvider` wrapper that paces outgoing queries.
+
+use crate::{
+    config::{
+        LinksConfig, BackLinksConfig, EmbedsConfig, TemplatesConfig, ImagesConfig, FileUsageConfig, CategoriesConfig, LangLinksConfig, CategoryMembersConfig, PrefixConfig,
+    },
+    core::DataProvider,
+    pageinfo::PageInfo,
+};
+use async_stream::stream;
+use core::time::Duration;
+use futures::Stream;
+use mwtitle::Title;
+use std::sync::{Arc, Mutex};
+use tokio::time::{Instant, sleep_until};
+use trio_result::TrioResult;
+
+/// A token bucket holding a single token, refilled once every `1 / per_second` seconds.
+///
+/// Providers that send their own requests can share one, so that each request waits for its turn, continued
+/// requests included.
+#[derive(Debug)]
+pub struct Pacer {
+    period: Duration,
+    next: Mutex<Option<Instant>>,
+}
+
+impl Pacer {
+    /// Panics if `per_second` is zero.
+    pub fn new(per_second: u32) -> Self {
+        assert!(per_second > 0, "rate limit must be positive");
+        Self {
+            period: Duration::from_secs(1) / per_second,
+            next: Mutex::new(None),
+        }
+    }
+
+    /// Wait until the next request is allowed to go out.
+    pub async fn wait(&self) {
+        let at = {
+            let mut next = self.next.lock().unwrap();
+            let now = Instant::now();
+            let at = next.map_or(now, |n| n.max(now));
+            *next = Some(at + self.period);
+            at
+        };
+        sleep_until(at).await;
+    }
+}
+
+/// A `DataProvider` that issues at most `per_second` queries to the inner provider per second.
+///
+/// Every query method waits for its turn before calling into the inner provider. The `*_multi` methods are paced
+/// per title, since they call the single-title methods one by one. A query that the inner provider serves with
+/// several continued requests is counted once, because the wrapper cannot see those requests. To pace those too,
+/// hand a `Pacer` to the provider that sends them instead.
+///
+/// Clones share the same pace.
+#[derive(Debug)]
+pub struct RateLimitedDataProvider<P> {
+    inner: P,
+    pacer: Arc<Pacer>,
+}
+
+impl<P: DataProvider> RateLimitedDataProvider<P> {
+    /// Panics if `per_second` is zero.
+    pub fn new(inner: P, per_second: u32) -> Self {
+        Self { inner, pacer: Arc::new(Pacer::new(per_second)) }
+    }
+
+    /// Get a reference to the inner provider.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Wait for a permit, then poll the stream made by `make`.
+    fn paced<'a, S, F>(&'a self, make: F) -> impl Stream<Item=TrioResult<PageInfo, P::Warn, P::Error>> + use<'a, P, S, F>
+    where
+        S: Stream<Item=TrioResult<PageInfo, P::Warn, P::Error>>,
+        F: FnOnce() -> S,
+    {
+        stream! {
+            self.pacer.wait().await;
+            for await item in make() {
+                yield item;
+            }
+        }
+    }
+}
+
+impl<P: Clone> Clone for RateLimitedDataProvider<P> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone(), pacer: Arc::clone(&self.pacer) }
+    }
+}
+
+impl<P: DataProvider> DataProvider for RateLimitedDataProvider<P> {
+    type Error = P::Error;
+    type Warn = P::Warn;
+
+    fn get_page_info<T: IntoIterator<Item = Title>>(&self, titles: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.paced(move || self.inner.get_page_info(titles))
+    }
+
+    fn get_page_info_from_raw<T: IntoIterator<Item = String>>(&self, titles_raw: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.paced(move || self.inner.get_page_info_from_raw(titles_raw))
+    }
+
+    fn get_page_info_by_id<T: IntoIterator<Item = u32>>(&self, pageids: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.paced(move || self.inner.get_page_info_by_id(pageids))
+    }
+
+    fn get_links(&self, title: Title, config: &LinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.paced(move || self.inner.get_links(title, config))
+    }
+
+    fn get_backlinks(&self, title: Title, config: &BackLinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.paced(move || self.inner.get_backlinks(title, config))
+    }
+
+    fn get_embeds(&self, title: Title, config: &EmbedsConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.paced(move || self.inner.get_embeds(title, config))
+    }
+
+    fn get_templates(&self, title: Title, config: &TemplatesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.paced(move || self.inner.get_templates(title, config))
+    }
+
+    fn get_images(&self, title: Title, config: &ImagesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.paced(move || self.inner.get_images(title, config))
+    }
+
+    fn get_file_usage(&self, title: Title, config: &FileUsageConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.paced(move || self.inner.get_file_usage(title, config))
+    }
+
+    fn get_categories(&self, title: Title, config: &CategoriesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.paced(move || self.inner.get_categories(title, config))
+    }
+
+    fn get_langlinks(&self, title: Title, config: &LangLinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.paced(move || self.inner.get_langlinks(title, config))
+    }
+
+    fn get_category_members(&self, title: Title, config: &CategoryMembersConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.paced(move || self.inner.get_category_members(title, config))
+    }
+
+    fn get_prefix(&self, title: Title, config: &PrefixConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.paced(move || self.inner.get_prefix(title, config))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RateLimitedDataProvider;
+    use crate::{
+        codec::test_title,
+        config::LinksConfig,
+        core::DataProvider,
+        test_provider::CountingProvider,
+    };
+    use core::time::Duration;
+    use futures::StreamExt;
+    use std::sync::atomic::Ordering;
+    use tokio::time::Instant;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit() {
+        let inner = CountingProvider::default();
+        let provider = RateLimitedDataProvider::new(inner.clone(), 4);
+        let titles = (0..9).map(|i| test_title(&format!("Page {i}")));
+
+        let start = Instant::now();
+        let count = provider.get_links_multi(titles, &LinksConfig::default()).count().await;
+
+        // the first request goes out immediately, the other eight wait a quarter second each.
+        assert_eq!(count, 9);
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 9);
+        assert!(start.elapsed() >= Duration::from_secs(2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit_shared() {
+        let provider = RateLimitedDataProvider::new(CountingProvider::default(), 1);
+        let other = provider.clone();
+        let title = test_title("Foo");
+
+        let start = Instant::now();
+        provider.get_links(title.clone(), &LinksConfig::default()).count().await;
+        other.get_links(title.clone(), &LinksConfig::default()).count().await;
+        provider.get_links(title, &LinksConfig::default()).count().await;
+
+        assert!(start.elapsed() >= Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,257 @@
+//! A `DataProvider` wrapper that retries failed queries.
+
+use crate::{
+    config::{
+        LinksConfig, BackLinksConfig, EmbedsConfig, TemplatesConfig, ImagesConfig, FileUsageConfig, CategoriesConfig, LangLinksConfig, CategoryMembersConfig, PrefixConfig,
+    },
+    core::DataProvider,
+    pageinfo::PageInfo,
+};
+use async_stream::stream;
+use core::{
+    fmt,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+use futures::Stream;
+use mwtitle::Title;
+use std::{
+    collections::hash_map::RandomState,
+    sync::Arc,
+};
+use tokio::time::sleep;
+use trio_result::TrioResult;
+
+type Classifier<E> = Arc<dyn Fn(&E) -> bool + Send + Sync>;
+type RetryAfter<E> = Arc<dyn Fn(&E) -> Option<Duration> + Send + Sync>;
+
+/// A `DataProvider` that retries a query when the inner provider fails with a retryable error.
+///
+/// The `n`-th retry waits `base_delay * 2^n`, plus a random jitter of up to the same amount, unless the error tells
+/// how long to wait, see `with_retry_after`.
+/// Which errors are worth retrying, eg. timeouts, 5xx responses or `maxlag`, is decided by the classifier passed to `new`.
+///
+/// A query is only retried if the error is the first thing its stream yields. Once any item or warning has been
+/// yielded, a retry would yield it again, so the error is passed through instead.
+pub struct RetryingDataProvider<P: DataProvider> {
+    inner: P,
+    max_retries: u32,
+    base_delay: Duration,
+    is_retryable: Classifier<P::Error>,
+    retry_after: Option<RetryAfter<P::Error>>,
+}
+
+impl<P: DataProvider> RetryingDataProvider<P> {
+    pub fn new<F>(inner: P, max_retries: u32, base_delay: Duration, is_retryable: F) -> Self
+    where
+        F: Fn(&P::Error) -> bool + Send + Sync + 'static,
+    {
+        Self { inner, max_retries, base_delay, is_retryable: Arc::new(is_retryable), retry_after: None }
+    }
+
+    /// Before retrying, wait as long as `retry_after` reads from the error, if anything, instead of backing off.
+    /// This is for errors that come with the delay the server asks for, eg. the `Retry-After` header of a `maxlag` error.
+    pub fn with_retry_after<F>(mut self, retry_after: F) -> Self
+    where
+        F: Fn(&P::Error) -> Option<Duration> + Send + Sync + 'static,
+    {
+        self.retry_after = Some(Arc::new(retry_after));
+        self
+    }
+
+    /// Get a reference to the inner provider.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// The delay before the `attempt`-th retry, counting from zero.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self.base_delay.saturating_mul(2u32.saturating_pow(attempt));
+        let jitter = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        delay.saturating_add(delay.mul_f64(jitter))
+    }
+
+    /// Poll the stream made by `make`, making a new one if it fails before yielding anything.
+    fn retried<'a, S, F>(&'a self, make: F) -> impl Stream<Item=TrioResult<PageInfo, P::Warn, P::Error>> + use<'a, P, S, F>
+    where
+        S: Stream<Item=TrioResult<PageInfo, P::Warn, P::Error>>,
+        F: Fn() -> S,
+    {
+        stream! {
+            let mut attempt = 0;
+            loop {
+                let mut emitted = false;
+                let mut retry = None;
+                for await item in make() {
+                    match item {
+                        TrioResult::Err(e) if !emitted && attempt < self.max_retries && (self.is_retryable)(&e) => {
+                            retry = Some(self.retry_after.as_ref().and_then(|f| f(&e)));
+                            break;
+                        },
+                        item => {
+                            emitted = true;
+                            yield item;
+                        },
+                    }
+                }
+                let Some(retry_after) = retry else {
+                    break;
+                };
+                sleep(retry_after.unwrap_or_else(|| self.backoff(attempt))).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+impl<P: DataProvider + Clone> Clone for RetryingDataProvider<P> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            max_retries: self.max_retries,
+            base_delay: self.base_delay,
+            is_retryable: Arc::clone(&self.is_retryable),
+            retry_after: self.retry_after.clone(),
+        }
+    }
+}
+
+impl<P: DataProvider + fmt::Debug> fmt::Debug for RetryingDataProvider<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryingDataProvider")
+            .field("inner", &self.inner)
+            .field("max_retries", &self.max_retries)
+            .field("base_delay", &self.base_delay)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<P: DataProvider> DataProvider for RetryingDataProvider<P> {
+    type Error = P::Error;
+    type Warn = P::Warn;
+
+    fn get_page_info<T: IntoIterator<Item = Title>>(&self, titles: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let titles: Vec<Title> = titles.into_iter().collect();
+        self.retried(move || self.inner.get_page_info(titles.clone()))
+    }
+
+    fn get_page_info_from_raw<T: IntoIterator<Item = String>>(&self, titles_raw: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let titles_raw: Vec<String> = titles_raw.into_iter().collect();
+        self.retried(move || self.inner.get_page_info_from_raw(titles_raw.clone()))
+    }
+
+    fn get_page_info_by_id<T: IntoIterator<Item = u32>>(&self, pageids: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let pageids: Vec<u32> = pageids.into_iter().collect();
+        self.retried(move || self.inner.get_page_info_by_id(pageids.clone()))
+    }
+
+    fn get_links(&self, title: Title, config: &LinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.retried(move || self.inner.get_links(title.clone(), config))
+    }
+
+    fn get_backlinks(&self, title: Title, config: &BackLinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.retried(move || self.inner.get_backlinks(title.clone(), config))
+    }
+
+    fn get_embeds(&self, title: Title, config: &EmbedsConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.retried(move || self.inner.get_embeds(title.clone(), config))
+    }
+
+    fn get_templates(&self, title: Title, config: &TemplatesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.retried(move || self.inner.get_templates(title.clone(), config))
+    }
+
+    fn get_images(&self, title: Title, config: &ImagesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.retried(move || self.inner.get_images(title.clone(), config))
+    }
+
+    fn get_file_usage(&self, title: Title, config: &FileUsageConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.retried(move || self.inner.get_file_usage(title.clone(), config))
+    }
+
+    fn get_categories(&self, title: Title, config: &CategoriesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.retried(move || self.inner.get_categories(title.clone(), config))
+    }
+
+    fn get_langlinks(&self, title: Title, config: &LangLinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.retried(move || self.inner.get_langlinks(title.clone(), config))
+    }
+
+    fn get_category_members(&self, title: Title, config: &CategoryMembersConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.retried(move || self.inner.get_category_members(title.clone(), config))
+    }
+
+    fn get_prefix(&self, title: Title, config: &PrefixConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.retried(move || self.inner.get_prefix(title.clone(), config))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RetryingDataProvider;
+    use crate::{
+        codec::test_title,
+        config::LinksConfig,
+        core::DataProvider,
+        test_provider::CountingProvider,
+    };
+    use core::time::Duration;
+    use futures::StreamExt;
+    use std::sync::atomic::Ordering;
+    use tokio::time::Instant;
+    use trio_result::TrioResult;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_success() {
+        let inner = CountingProvider::failing(2);
+        let provider = RetryingDataProvider::new(inner.clone(), 3, Duration::from_millis(100), |_| true);
+        let title = test_title("Foo");
+
+        let items: Vec<_> = provider.get_links(title, &LinksConfig::default()).collect().await;
+
+        assert_eq!(items.len(), 1);
+        assert!(items[0].is_ok());
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_exhausted() {
+        let inner = CountingProvider::failing(2);
+        let provider = RetryingDataProvider::new(inner.clone(), 1, Duration::from_millis(100), |_| true);
+        let title = test_title("Foo");
+
+        let items: Vec<_> = provider.get_links(title, &LinksConfig::default()).collect().await;
+
+        assert!(matches!(items[..], [TrioResult::Err(())]));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_after() {
+        // a lagged server asks to come back in 5 seconds, twice.
+        let inner = CountingProvider::failing(2);
+        let provider = RetryingDataProvider::new(inner.clone(), 3, Duration::from_millis(100), |_| true)
+            .with_retry_after(|_| Some(Duration::from_secs(5)));
+        let title = test_title("Foo");
+
+        let start = Instant::now();
+        let items: Vec<_> = provider.get_links(title, &LinksConfig::default()).collect().await;
+
+        assert!(matches!(items[..], [TrioResult::Ok(_)]));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 3);
+        // exactly as asked, without the jitter of the usual backoff.
+        assert_eq!(start.elapsed(), Duration::from_secs(10));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_permanent() {
+        let inner = CountingProvider::failing(2);
+        let provider = RetryingDataProvider::new(inner.clone(), 3, Duration::from_millis(100), |_| false);
+        let title = test_title("Foo");
+
+        let items: Vec<_> = provider.get_links(title, &LinksConfig::default()).collect().await;
+
+        assert!(matches!(items[..], [TrioResult::Err(())]));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+    }
+}
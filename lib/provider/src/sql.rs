@@ -0,0 +1,585 @@
+//! A `DataProvider` answering from the MediaWiki database, eg. the Toolforge SQL replicas.
+
+use crate::{
+    config::{
+        FilterRedirect, CategorySort, SortDirection,
+        LinksConfig, BackLinksConfig, EmbedsConfig, TemplatesConfig, ImagesConfig, FileUsageConfig, CategoriesConfig, LangLinksConfig, CategoryMembersConfig, PrefixConfig,
+    },
+    core::DataProvider,
+    pageinfo::PageInfo,
+};
+use async_stream::stream;
+use core::{convert::Infallible, fmt, future::Future};
+use futures::Stream;
+use mwtitle::{Title, TitleCodec};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    error::Error,
+    sync::Arc,
+};
+use trio_result::TrioResult;
+
+/// A value in a query result row, or a parameter bound to a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SqlValue {
+    Null,
+    Int(i64),
+    Bytes(Vec<u8>),
+}
+
+impl SqlValue {
+    /// Read an integer. The text protocol sends numbers as decimal strings, so those are accepted too.
+    fn as_int(&self) -> Option<i64> {
+        match self {
+            Self::Null => None,
+            Self::Int(x) => Some(*x),
+            Self::Bytes(x) => std::str::from_utf8(x).ok()?.parse().ok(),
+        }
+    }
+
+    fn as_string(&self) -> Option<String> {
+        match self {
+            Self::Null => None,
+            Self::Int(x) => Some(x.to_string()),
+            Self::Bytes(x) => String::from_utf8(x.to_owned()).ok(),
+        }
+    }
+}
+
+impl From<&str> for SqlValue {
+    fn from(value: &str) -> Self {
+        Self::Bytes(value.as_bytes().to_owned())
+    }
+}
+
+/// A connection to the database, eg. a `mysql_async` pool.
+pub trait SqlConnection {
+    type Error;
+
+    /// Run `query` with `params` bound to its `?` placeholders in order, and return the rows in order.
+    fn query(&self, query: &str, params: Vec<SqlValue>) -> impl Future<Output=Result<Vec<Vec<SqlValue>>, Self::Error>>;
+}
+
+/// Errors returned by `SqlDataProvider`.
+#[derive(Debug)]
+pub enum SqlError<E> {
+    /// The query failed.
+    Query(E),
+    /// A row does not have the expected columns.
+    UnexpectedRow,
+    /// A title that cannot be parsed, eg. a language link target.
+    InvalidTitle(String),
+}
+
+impl<E: fmt::Display> fmt::Display for SqlError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Query(e) => write!(f, "query failed: {e}"),
+            Self::UnexpectedRow => write!(f, "unexpected row in query result"),
+            Self::InvalidTitle(title) => write!(f, "invalid title `{title}`"),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for SqlError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Query(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The page id of the input page, with its namespace and title as the two parameters.
+const INPUT_PAGE_ID: &str = "(SELECT page_id FROM page WHERE page_namespace = ? AND page_title = ?)";
+
+/// The largest number of titles or page ids put in one query.
+const CHUNK: usize = 500;
+
+/// A `DataProvider` answering from the MediaWiki database, eg. the Toolforge SQL replicas.
+///
+/// Each query reads the link tables, eg. `categorylinks` or `pagelinks`, joined with `page`, so it costs one round trip
+/// however many results there are. Links to missing pages are yielded like the API does, without a page id.
+///
+/// Titles read from the database, raw titles and language link targets are parsed by the codec given on creation, which
+/// should know the namespaces and interwikis of the wiki. Raw titles it cannot parse are skipped in the input.
+///
+/// All the settings in the configs are honored. Without an explicit sort, category members are listed by sort key.
+pub struct SqlDataProvider<C: SqlConnection> {
+    connection: C,
+    codec: Arc<TitleCodec>,
+}
+
+impl<C: SqlConnection> SqlDataProvider<C> {
+    pub fn new(connection: C, codec: TitleCodec) -> Self {
+        Self { connection, codec: Arc::new(codec) }
+    }
+
+    /// Get a reference to the connection.
+    pub fn connection(&self) -> &C {
+        &self.connection
+    }
+
+    /// Run a query made by `select`, and read its rows into page information.
+    /// If `resolve` is set, redirects are replaced by their targets, and a page reached twice is yielded once.
+    async fn pages(&self, query: String, params: Vec<SqlValue>, resolve: bool) -> Result<Vec<PageInfo>, SqlError<C::Error>> {
+        let rows = self.connection.query(&query, params).await.map_err(SqlError::Query)?;
+        let mut pages = rows.iter().map(|row| self.page_from_row(row)).collect::<Result<Vec<_>, _>>()?;
+        if !resolve {
+            return Ok(pages);
+        }
+        let redirects: Vec<u32> = pages.iter()
+            .filter(|p| p.get_isredir() == Ok(true))
+            .filter_map(|p| p.get_pageid().ok())
+            .collect();
+        let mut targets = HashMap::new();
+        for chunk in redirects.chunks(CHUNK) {
+            let query = format!(
+                "{}, rd_from FROM redirect{} WHERE rd_from IN ({}) AND rd_interwiki = ''",
+                columns("rd_namespace", "rd_title"),
+                joins("LEFT JOIN page p ON p.page_namespace = rd_namespace AND p.page_title = rd_title", "rd_namespace", "rd_title"),
+                placeholders(chunk.len()),
+            );
+            let params = chunk.iter().map(|id| SqlValue::Int(*id as i64)).collect();
+            let rows = self.connection.query(&query, params).await.map_err(SqlError::Query)?;
+            for row in rows {
+                let from = row.get(8).and_then(SqlValue::as_int).ok_or(SqlError::UnexpectedRow)?;
+                targets.insert(from as u32, self.page_from_row(&row)?);
+            }
+        }
+        let mut seen = BTreeSet::new();
+        pages = pages.into_iter()
+            .map(|p| p.get_pageid().ok().and_then(|id| targets.get(&id).cloned()).unwrap_or(p))
+            .filter(|p| p.get_title().is_ok_and(|t| seen.insert(t.to_owned())))
+            .collect();
+        Ok(pages)
+    }
+
+    /// Read a row of the columns made by `columns`. A result page without a page id does not exist.
+    fn page_from_row(&self, row: &[SqlValue]) -> Result<PageInfo, SqlError<C::Error>> {
+        let Some([pageid, ns, title, redirect, length, touched, assoc_pageid, assoc_redirect]) = row.get(..8) else {
+            return Err(SqlError::UnexpectedRow);
+        };
+        let namespace = ns.as_int().ok_or(SqlError::UnexpectedRow)? as i32;
+        let dbkey = title.as_string().ok_or(SqlError::UnexpectedRow)?;
+        let title = self.codec.new_title_from_database(namespace, &dbkey).map_err(|_| SqlError::InvalidTitle(format!("{namespace}:{dbkey}")))?;
+        let pageid = pageid.as_int().map(|id| id as u32);
+        let assoc_pageid = assoc_pageid.as_int().map(|id| id as u32);
+        let assoc_title = self.associated(&title);
+        let assoc_exists = assoc_title.as_ref().map(|_| assoc_pageid.is_some());
+        let assoc_redirect = assoc_title.as_ref().map(|_| assoc_redirect.as_int() == Some(1));
+        Ok(
+            PageInfo::new(Some(title), Some(pageid.is_some()), Some(redirect.as_int() == Some(1)), assoc_title, assoc_exists, assoc_redirect)
+                .with_details(pageid, length.as_int().map(|l| l as u32), touched.as_string().map(timestamp_from_db), assoc_pageid)
+        )
+    }
+
+    /// The talk page of a subject page, or the other way around. Virtual namespaces have none, and neither do
+    /// namespaces unknown to the codec.
+    fn associated(&self, title: &Title) -> Option<Title> {
+        if title.namespace() < 0 {
+            return None;
+        }
+        self.codec.new_title_from_database(title.namespace() ^ 1, title.dbkey()).ok()
+    }
+
+    /// Stream the pages found by a query.
+    fn stream_pages(&self, query: String, params: Vec<SqlValue>, resolve: bool) -> impl Stream<Item=TrioResult<PageInfo, Infallible, SqlError<C::Error>>> + use<'_, C> {
+        stream! {
+            match self.pages(query, params, resolve).await {
+                Ok(pages) => for page in pages { yield TrioResult::Ok(page); },
+                Err(e) => yield TrioResult::Err(e),
+            }
+        }
+    }
+}
+
+impl<C: SqlConnection + Clone> Clone for SqlDataProvider<C> {
+    fn clone(&self) -> Self {
+        Self { connection: self.connection.clone(), codec: Arc::clone(&self.codec) }
+    }
+}
+
+impl<C: SqlConnection + fmt::Debug> fmt::Debug for SqlDataProvider<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SqlDataProvider").field("connection", &self.connection).finish_non_exhaustive()
+    }
+}
+
+/// The columns read by `page_from_row`. `ns` and `title` name the result page, which is joined as `p` if it exists.
+fn columns(ns: &str, title: &str) -> String {
+    format!("SELECT p.page_id, {ns}, {title}, p.page_is_redirect, p.page_len, p.page_touched, a.page_id, a.page_is_redirect")
+}
+
+/// `from`, joined with the associated page of the result page as `a`.
+fn joins(from: &str, ns: &str, title: &str) -> String {
+    format!(" {from} LEFT JOIN page a ON a.page_namespace = {ns} ^ 1 AND a.page_title = {title}")
+}
+
+/// Make a query listing the result pages named by `ns` and `title`.
+fn select(ns: &str, title: &str, from: &str, conditions: &[String], order: &str) -> String {
+    format!("{} FROM{} WHERE {} ORDER BY {order}", columns(ns, title), joins(from, ns, title), conditions.join(" AND "))
+}
+
+fn placeholders(n: usize) -> String {
+    vec!["?"; n].join(", ")
+}
+
+fn title_params(title: &Title) -> [SqlValue; 2] {
+    [SqlValue::Int(title.namespace() as i64), title.dbkey().into()]
+}
+
+/// Keep the result pages in `namespace`, if any.
+fn namespace_condition(column: &str, namespace: Option<&HashSet<i32>>) -> Option<String> {
+    let mut namespace: Vec<_> = namespace?.iter().collect();
+    namespace.sort_unstable();
+    Some(match namespace.is_empty() {
+        true => "FALSE".to_string(),
+        false => format!("{column} IN ({})", namespace.iter().map(|ns| ns.to_string()).collect::<Vec<_>>().join(", ")),
+    })
+}
+
+fn redirect_condition(filter: Option<FilterRedirect>) -> Option<String> {
+    filter.map(|f| match f {
+        FilterRedirect::NoRedirect => "p.page_is_redirect = 0".to_string(),
+        FilterRedirect::OnlyRedirect => "p.page_is_redirect = 1".to_string(),
+    })
+}
+
+/// Turn `20240101000000` into `2024-01-01T00:00:00Z`, like the API.
+fn timestamp_from_db(raw: String) -> String {
+    if raw.len() != 14 || !raw.bytes().all(|b| b.is_ascii_digit()) {
+        return raw;
+    }
+    format!("{}-{}-{}T{}:{}:{}Z", &raw[0..4], &raw[4..6], &raw[6..8], &raw[8..10], &raw[10..12], &raw[12..14])
+}
+
+/// Turn `2024-01-01T00:00:00Z` into `20240101000000`.
+fn timestamp_to_db(iso: &str) -> String {
+    iso.chars().filter(char::is_ascii_digit).collect()
+}
+
+impl<C: SqlConnection> DataProvider for SqlDataProvider<C> {
+    type Error = SqlError<C::Error>;
+    type Warn = Infallible;
+
+    /// ```SELECT ... FROM page p ... WHERE (p.page_namespace, p.page_title) IN ((<ns>, <title>), ...)```
+    fn get_page_info<T: IntoIterator<Item = Title>>(&self, titles: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let mut seen = BTreeSet::new();
+        let titles: Vec<Title> = titles.into_iter().filter(|t| seen.insert(t.to_owned())).collect();
+        stream! {
+            for chunk in titles.chunks(CHUNK) {
+                let conditions = [format!("(p.page_namespace, p.page_title) IN ({})", vec!["(?, ?)"; chunk.len()].join(", "))];
+                let query = select("p.page_namespace", "p.page_title", "page p", &conditions, "p.page_id");
+                let params = chunk.iter().flat_map(title_params).collect();
+                let mut found: BTreeMap<Title, PageInfo> = match self.pages(query, params, false).await {
+                    Ok(pages) => pages.into_iter().filter_map(|p| Some((p.get_title().ok()?.to_owned(), p))).collect(),
+                    Err(e) => { yield TrioResult::Err(e); return; },
+                };
+                // keep the input order, and list the pages not found as missing.
+                for title in chunk {
+                    yield TrioResult::Ok(found.remove(title).unwrap_or_else(|| {
+                        let assoc_title = self.associated(title);
+                        PageInfo::new(Some(title.to_owned()), Some(false), Some(false), assoc_title, None, None)
+                    }));
+                }
+            }
+        }
+    }
+
+    fn get_page_info_from_raw<T: IntoIterator<Item = String>>(&self, titles_raw: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let titles: Vec<Title> = titles_raw.into_iter().filter_map(|raw| self.codec.new_title(&raw).ok()).collect();
+        self.get_page_info(titles)
+    }
+
+    /// ```SELECT ... FROM page p ... WHERE p.page_id IN (<pageids>)```
+    fn get_page_info_by_id<T: IntoIterator<Item = u32>>(&self, pageids: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let pageids: Vec<u32> = pageids.into_iter().collect();
+        stream! {
+            for chunk in pageids.chunks(CHUNK) {
+                let conditions = [format!("p.page_id IN ({})", placeholders(chunk.len()))];
+                let query = select("p.page_namespace", "p.page_title", "page p", &conditions, "p.page_id");
+                let params = chunk.iter().map(|id| SqlValue::Int(*id as i64)).collect();
+                let found: HashMap<u32, PageInfo> = match self.pages(query, params, false).await {
+                    Ok(pages) => pages.into_iter().filter_map(|p| Some((p.get_pageid().ok()?, p))).collect(),
+                    Err(e) => { yield TrioResult::Err(e); return; },
+                };
+                for id in chunk {
+                    if let Some(page) = found.get(id) {
+                        yield TrioResult::Ok(page.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// ```SELECT ... FROM pagelinks JOIN linktarget ... WHERE pl_from = <page id of title>```
+    fn get_links(&self, title: Title, config: &LinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let conditions: Vec<String> = [Some(format!("pl_from = {INPUT_PAGE_ID}")), namespace_condition("lt_namespace", config.namespace.as_ref())]
+            .into_iter().flatten().collect();
+        let from = "pagelinks JOIN linktarget ON lt_id = pl_target_id LEFT JOIN page p ON p.page_namespace = lt_namespace AND p.page_title = lt_title";
+        let query = select("lt_namespace", "lt_title", from, &conditions, "lt_namespace, lt_title");
+        self.stream_pages(query, title_params(&title).into(), config.resolve_redirects)
+    }
+
+    /// ```SELECT ... FROM pagelinks JOIN linktarget ... JOIN page p ON p.page_id = pl_from WHERE lt_namespace = <ns> AND lt_title = <title>```
+    ///
+    /// Unless `direct` is set, pages linking to a redirect to the title are listed too.
+    fn get_backlinks(&self, title: Title, config: &BackLinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let mut params: Vec<SqlValue> = title_params(&title).into();
+        let target = match config.direct {
+            true => "lt_namespace = ? AND lt_title = ?".to_string(),
+            false => {
+                params.extend(title_params(&title));
+                "(lt_namespace = ? AND lt_title = ? OR (lt_namespace, lt_title) IN (\
+                    SELECT r.page_namespace, r.page_title FROM redirect JOIN page r ON r.page_id = rd_from \
+                    WHERE rd_namespace = ? AND rd_title = ? AND rd_interwiki = ''))".to_string()
+            },
+        };
+        let conditions: Vec<String> = [
+            Some(target),
+            namespace_condition("p.page_namespace", config.namespace.as_ref()),
+            redirect_condition(config.filter_redirects),
+        ].into_iter().flatten().collect();
+        let from = "pagelinks JOIN linktarget ON lt_id = pl_target_id JOIN page p ON p.page_id = pl_from";
+        let query = select("p.page_namespace", "p.page_title", from, &conditions, "p.page_id");
+        self.stream_pages(query, params, config.resolve_redirects)
+    }
+
+    /// ```SELECT ... FROM templatelinks JOIN linktarget ... JOIN page p ON p.page_id = tl_from WHERE lt_namespace = <ns> AND lt_title = <title>```
+    fn get_embeds(&self, title: Title, config: &EmbedsConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let conditions: Vec<String> = [
+            Some("lt_namespace = ? AND lt_title = ?".to_string()),
+            namespace_condition("p.page_namespace", config.namespace.as_ref()),
+            redirect_condition(config.filter_redirects),
+        ].into_iter().flatten().collect();
+        let from = "templatelinks JOIN linktarget ON lt_id = tl_target_id JOIN page p ON p.page_id = tl_from";
+        let query = select("p.page_namespace", "p.page_title", from, &conditions, "p.page_id");
+        self.stream_pages(query, title_params(&title).into(), config.resolve_redirects)
+    }
+
+    /// ```SELECT ... FROM templatelinks JOIN linktarget ... WHERE tl_from = <page id of title>```
+    fn get_templates(&self, title: Title, config: &TemplatesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let conditions: Vec<String> = [Some(format!("tl_from = {INPUT_PAGE_ID}")), namespace_condition("lt_namespace", config.namespace.as_ref())]
+            .into_iter().flatten().collect();
+        let from = "templatelinks JOIN linktarget ON lt_id = tl_target_id LEFT JOIN page p ON p.page_namespace = lt_namespace AND p.page_title = lt_title";
+        let query = select("lt_namespace", "lt_title", from, &conditions, "lt_namespace, lt_title");
+        self.stream_pages(query, title_params(&title).into(), config.resolve_redirects)
+    }
+
+    /// ```SELECT ... FROM imagelinks ... WHERE il_from = <page id of title>```
+    fn get_images(&self, title: Title, config: &ImagesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let conditions: Vec<String> = [Some(format!("il_from = {INPUT_PAGE_ID}")), namespace_condition("6", config.namespace.as_ref())]
+            .into_iter().flatten().collect();
+        let from = "imagelinks LEFT JOIN page p ON p.page_namespace = 6 AND p.page_title = il_to";
+        let query = select("6", "il_to", from, &conditions, "il_to");
+        self.stream_pages(query, title_params(&title).into(), config.resolve_redirects)
+    }
+
+    /// ```SELECT ... FROM imagelinks JOIN page p ON p.page_id = il_from WHERE il_to = <title>```
+    fn get_file_usage(&self, title: Title, config: &FileUsageConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let conditions: Vec<String> = [
+            Some("il_to = ?".to_string()),
+            namespace_condition("p.page_namespace", config.namespace.as_ref()),
+            redirect_condition(config.filter_redirects),
+        ].into_iter().flatten().collect();
+        let query = select("p.page_namespace", "p.page_title", "imagelinks JOIN page p ON p.page_id = il_from", &conditions, "p.page_id");
+        self.stream_pages(query, vec![title.dbkey().into()], config.resolve_redirects)
+    }
+
+    /// ```SELECT ... FROM categorylinks ... WHERE cl_from = <page id of title>```
+    fn get_categories(&self, title: Title, config: &CategoriesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let conditions: Vec<String> = [Some(format!("cl_from = {INPUT_PAGE_ID}")), namespace_condition("14", config.namespace.as_ref())]
+            .into_iter().flatten().collect();
+        let from = "categorylinks LEFT JOIN page p ON p.page_namespace = 14 AND p.page_title = cl_to";
+        let query = select("14", "cl_to", from, &conditions, "cl_to");
+        self.stream_pages(query, title_params(&title).into(), config.resolve_redirects)
+    }
+
+    /// ```SELECT ll_lang, ll_title FROM langlinks WHERE ll_from = <page id of title>```
+    fn get_langlinks(&self, title: Title, _config: &LangLinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        stream! {
+            let query = format!("SELECT ll_lang, ll_title FROM langlinks WHERE ll_from = {INPUT_PAGE_ID} ORDER BY ll_lang");
+            let rows = match self.connection.query(&query, title_params(&title).into()).await {
+                Ok(rows) => rows,
+                Err(e) => { yield TrioResult::Err(SqlError::Query(e)); return; },
+            };
+            for row in rows {
+                let Some((lang, target)) = row.first().and_then(SqlValue::as_string).zip(row.get(1).and_then(SqlValue::as_string)) else {
+                    yield TrioResult::Err(SqlError::UnexpectedRow);
+                    return;
+                };
+                let raw = format!("{lang}:{target}");
+                match self.codec.new_title(&raw) {
+                    Ok(t) => yield TrioResult::Ok(PageInfo::new_foreign(t)),
+                    Err(_) => { yield TrioResult::Err(SqlError::InvalidTitle(raw)); return; },
+                }
+            }
+        }
+    }
+
+    /// ```SELECT ... FROM categorylinks JOIN page p ON p.page_id = cl_from WHERE cl_to = <title> ORDER BY cl_sortkey```
+    ///
+    /// If the config bounds the time pages were added, `cl_timestamp` is bounded, and ordered by instead.
+    fn get_category_members(&self, title: Title, config: &CategoryMembersConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let mut params = vec![title.dbkey().into()];
+        let mut conditions: Vec<String> = [
+            Some("cl_to = ?".to_string()),
+            namespace_condition("p.page_namespace", config.namespace.as_ref()),
+        ].into_iter().flatten().collect();
+        for (bound, op) in [(&config.start, ">="), (&config.end, "<=")] {
+            if let Some(bound) = bound {
+                conditions.push(format!("cl_timestamp {op} ?"));
+                params.push(timestamp_to_db(bound).as_str().into());
+            }
+        }
+        let key = match config.sort {
+            Some(CategorySort::Timestamp) => "cl_timestamp",
+            Some(CategorySort::SortKey) => "cl_sortkey",
+            None if config.is_time_bounded() => "cl_timestamp",
+            None => "cl_sortkey",
+        };
+        let dir = match config.dir {
+            Some(SortDirection::Descending) => "DESC",
+            _ => "ASC",
+        };
+        let order = format!("{key} {dir}, cl_from {dir}");
+        let query = select("p.page_namespace", "p.page_title", "categorylinks JOIN page p ON p.page_id = cl_from", &conditions, &order);
+        self.stream_pages(query, params, config.resolve_redirects)
+    }
+
+    /// ```SELECT ... FROM page p WHERE p.page_namespace = <ns> AND p.page_title LIKE '<title>%'```
+    fn get_prefix(&self, title: Title, config: &PrefixConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        let conditions: Vec<String> = [
+            Some("p.page_namespace = ? AND p.page_title LIKE ?".to_string()),
+            redirect_condition(config.filter_redirects),
+        ].into_iter().flatten().collect();
+        let pattern = format!("{}%", title.dbkey().replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+        let params = vec![SqlValue::Int(title.namespace() as i64), pattern.as_str().into()];
+        let query = select("p.page_namespace", "p.page_title", "page p", &conditions, "p.page_title");
+        self.stream_pages(query, params, false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SqlConnection, SqlDataProvider, SqlError, SqlValue};
+    use crate::{
+        config::*,
+        core::DataProvider,
+        mock::{codec, parse_title},
+        pageinfo::PageInfo,
+    };
+    use core::convert::Infallible;
+    use futures::{executor::block_on, Stream, StreamExt};
+    use std::sync::Mutex;
+    use trio_result::TrioResult;
+
+    /// Answers each query with the rows recorded for the first fragment found in it, and records the queries made.
+    #[derive(Default)]
+    struct RecordedConnection {
+        responses: Vec<(&'static str, Vec<Vec<SqlValue>>)>,
+        queries: Mutex<Vec<(String, Vec<SqlValue>)>>,
+    }
+
+    impl SqlConnection for RecordedConnection {
+        type Error = ();
+
+        async fn query(&self, query: &str, params: Vec<SqlValue>) -> Result<Vec<Vec<SqlValue>>, Self::Error> {
+            self.queries.lock().unwrap().push((query.to_string(), params));
+            self.responses.iter().find(|(fragment, _)| query.contains(fragment)).map(|(_, rows)| rows.clone()).ok_or(())
+        }
+    }
+
+    /// A row of an existing page with no associated page.
+    fn row(id: i64, ns: i64, title: &str, redirect: bool) -> Vec<SqlValue> {
+        vec![
+            SqlValue::Int(id), SqlValue::Int(ns), title.into(), SqlValue::Int(redirect as i64), SqlValue::Int(100),
+            "20240101000000".into(), SqlValue::Null, SqlValue::Null,
+        ]
+    }
+
+    fn provider(responses: Vec<(&'static str, Vec<Vec<SqlValue>>)>) -> SqlDataProvider<RecordedConnection> {
+        let connection = RecordedConnection { responses, ..Default::default() };
+        SqlDataProvider::new(connection, codec().clone())
+    }
+
+    fn collect<S: Stream<Item=TrioResult<PageInfo, Infallible, SqlError<()>>>>(stream: S) -> Vec<PageInfo> {
+        block_on(stream.collect::<Vec<_>>()).into_iter().map(|x| x.ok().unwrap()).collect()
+    }
+
+    fn titles(pages: &[PageInfo]) -> Vec<&str> {
+        pages.iter().map(|p| p.get_title().unwrap().dbkey()).collect()
+    }
+
+    #[test]
+    fn test_category_members() {
+        let provider = provider(vec![("FROM categorylinks", vec![row(2, 0, "Foo", false), row(3, 14, "Bar", false)])]);
+        let config = CategoryMembersConfig { namespace: Some([14, 0].into()), ..Default::default() }
+            .with_start("2024-01-01T00:00:00Z".to_string())
+            .with_sort(CategorySort::Timestamp, SortDirection::Descending);
+        let pages = collect(provider.get_category_members(parse_title("Category:Fruits"), &config));
+
+        assert_eq!(titles(&pages), ["Foo", "Bar"]);
+        assert_eq!(pages[0].get_pageid(), Ok(2));
+        assert_eq!(pages[0].get_touched(), Ok("2024-01-01T00:00:00Z"));
+        assert_eq!(pages[0].get_assoc_exists(), Ok(false));
+
+        let queries = provider.connection().queries.lock().unwrap();
+        let (query, params) = &queries[0];
+        assert!(query.contains("cl_to = ? AND p.page_namespace IN (0, 14) AND cl_timestamp >= ?"));
+        assert!(query.ends_with("ORDER BY cl_timestamp DESC, cl_from DESC"));
+        assert_eq!(params, &["Fruits".into(), "20240101000000".into()]);
+    }
+
+    #[test]
+    fn test_backlinks() {
+        let provider = provider(vec![
+            ("FROM pagelinks", vec![row(4, 0, "A", false), row(5, 0, "Redirect", true), row(1, 0, "Target", false)]),
+            ("FROM redirect", vec![[row(1, 0, "Target", false), vec![SqlValue::Int(5)]].concat()]),
+        ]);
+        let title = parse_title("Foo");
+
+        let pages = collect(provider.get_backlinks(title.clone(), &BackLinksConfig { direct: true, ..Default::default() }));
+        assert_eq!(titles(&pages), ["A", "Redirect", "Target"]);
+
+        // the redirect is replaced by its target, which is yielded once.
+        let pages = collect(provider.get_backlinks(title, &BackLinksConfig { resolve_redirects: true, ..Default::default() }));
+        assert_eq!(titles(&pages), ["A", "Target"]);
+
+        let queries = provider.connection().queries.lock().unwrap();
+        assert_eq!(queries.len(), 3);
+        assert!(queries[1].0.contains("FROM redirect JOIN page r"));
+        assert_eq!(queries[1].1.len(), 4);
+        assert_eq!(queries[2].1, [SqlValue::Int(5)]);
+    }
+
+    #[test]
+    fn test_page_info() {
+        let provider = provider(vec![("FROM page p", vec![row(1, 0, "Foo", false)])]);
+        let pages = collect(provider.get_page_info_from_raw(["Missing page".to_string(), "Foo".to_string(), "Foo".to_string()]));
+
+        // input order is kept, and pages not found are missing.
+        assert_eq!(titles(&pages), ["Missing_page", "Foo"]);
+        assert_eq!(pages[0].get_exists(), Ok(false));
+        assert_eq!(pages[1].get_exists(), Ok(true));
+        assert_eq!(provider.connection().queries.lock().unwrap()[0].1.len(), 4);
+    }
+
+    #[test]
+    fn test_prefix() {
+        let provider = provider(vec![("FROM page p", vec![])]);
+        collect(provider.get_prefix(parse_title("User:Foo 50%"), &PrefixConfig { filter_redirects: Some(FilterRedirect::NoRedirect) }));
+
+        let queries = provider.connection().queries.lock().unwrap();
+        assert!(queries[0].0.contains("p.page_title LIKE ? AND p.page_is_redirect = 0"));
+        assert_eq!(queries[0].1, [SqlValue::Int(2), "Foo\\_50\\%%".into()]);
+    }
+
+    #[test]
+    fn test_query_error() {
+        let provider = provider(vec![]);
+        let items: Vec<_> = block_on(provider.get_embeds(parse_title("Template:Foo"), &EmbedsConfig::default()).collect());
+        assert!(matches!(items[..], [TrioResult::Err(SqlError::Query(()))]));
+    }
+}
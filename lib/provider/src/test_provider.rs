@@ -0,0 +1,62 @@
+//! A trivial `DataProvider` for unit tests in this crate.
+
+use crate::{
+    codec::test_title,
+    config::*,
+    core::DataProvider,
+    pageinfo::PageInfo,
+};
+use core::convert::Infallible;
+use futures::Stream;
+use mwtitle::Title;
+use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+use trio_result::TrioResult;
+
+/// Answers every query with the input title itself, and counts the calls.
+/// The first `failures` calls answer with an error instead.
+#[derive(Clone, Default)]
+pub(crate) struct CountingProvider {
+    pub(crate) calls: Arc<AtomicUsize>,
+    failures: usize,
+}
+
+impl CountingProvider {
+    pub(crate) fn failing(failures: usize) -> Self {
+        Self { calls: Arc::default(), failures }
+    }
+
+    fn echo(&self, title: Title) -> impl Stream<Item=TrioResult<PageInfo, Infallible, ()>> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst);
+        let item = if call < self.failures {
+            TrioResult::Err(())
+        } else {
+            TrioResult::Ok(PageInfo::new(Some(title), Some(true), Some(false), None, None, None))
+        };
+        futures::stream::iter([item])
+    }
+}
+
+impl DataProvider for CountingProvider {
+    type Error = ();
+    type Warn = Infallible;
+
+    fn get_page_info<T: IntoIterator<Item = Title>>(&self, titles: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.echo(titles.into_iter().next().unwrap())
+    }
+    fn get_page_info_from_raw<T: IntoIterator<Item = String>>(&self, titles_raw: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.echo(test_title(&titles_raw.into_iter().next().unwrap()))
+    }
+    fn get_page_info_by_id<T: IntoIterator<Item = u32>>(&self, pageids: T) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> {
+        self.echo(test_title(&pageids.into_iter().next().unwrap().to_string()))
+    }
+    fn get_links(&self, title: Title, _: &LinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> { self.echo(title) }
+    fn get_backlinks(&self, title: Title, _: &BackLinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> { self.echo(title) }
+    fn get_embeds(&self, title: Title, _: &EmbedsConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> { self.echo(title) }
+    fn get_templates(&self, title: Title, _: &TemplatesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> { self.echo(title) }
+    fn get_images(&self, title: Title, _: &ImagesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> { self.echo(title) }
+    fn get_file_usage(&self, title: Title, _: &FileUsageConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> { self.echo(title) }
+    fn get_categories(&self, title: Title, _: &CategoriesConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> { self.echo(title) }
+    fn get_langlinks(&self, title: Title, _: &LangLinksConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> { self.echo(title) }
+    fn get_category_members(&self, title: Title, _: &CategoryMembersConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> { self.echo(title) }
+    fn get_prefix(&self, title: Title, _: &PrefixConfig) -> impl Stream<Item=TrioResult<PageInfo, Self::Warn, Self::Error>> { self.echo(title) }
+}
@@ -1,16 +1,133 @@
 //! Convert attributes to configs.
 
-use ast::{Attribute, Modifier, Span};
+use ast::{Attribute, CategorySortKey, Filter, LitNamespace, LitString, Modifier, ModifierNs, SortKey, Span};
 use crate::SemanticError;
 use intorinf::IntOrInf;
+use mwtitle::Title;
 use provider::{
-    FilterRedirect,
-    LinksConfig, BackLinksConfig, EmbedsConfig, CategoryMembersConfig, PrefixConfig,
+    FilterRedirect, CategorySort, SortDirection,
+    LinksConfig, BackLinksConfig, EmbedsConfig, TemplatesConfig, ImagesConfig, FileUsageConfig, CategoriesConfig, LangLinksConfig, CategoryMembersConfig, PrefixConfig,
 };
+use regex::Regex;
 use std::collections::{HashSet, HashMap};
 
+/// Namespace names and aliases of a site, used to resolve names in the `ns` modifier.
+///
+/// Names are matched case-insensitively, and underscores are treated as spaces.
+/// The first name inserted for a namespace is the one titles are shown with.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NamespaceMap {
+    names: HashMap<String, i32>,
+    display: HashMap<i32, String>,
+}
+
+impl NamespaceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a namespace name or alias.
+    pub fn insert(&mut self, name: &str, id: i32) {
+        self.names.insert(Self::normalize(name), id);
+        self.display.entry(id).or_insert_with(|| name.trim().replace('_', " "));
+    }
+
+    /// Look up the namespace id of `name`.
+    pub fn get(&self, name: &str) -> Option<i32> {
+        self.names.get(&Self::normalize(name)).copied()
+    }
+
+    /// The title in its pretty form, eg. `Category:Foo bar`. A namespace without a name here is left out.
+    pub fn pretty(&self, title: &Title) -> String {
+        let text = title.dbkey().replace('_', " ");
+        match self.display.get(&title.namespace()) {
+            Some(name) if !name.is_empty() => format!("{name}:{text}"),
+            _ => text,
+        }
+    }
+
+    /// The ids of all the namespaces that can hold pages, ie. not `Special` or `Media`.
+    pub fn ids(&self) -> HashSet<i32> {
+        self.names.values().copied().filter(|&id| id >= 0).collect()
+    }
+
+    fn normalize(name: &str) -> String {
+        name.trim().replace('_', " ").to_lowercase()
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<(S, i32)> for NamespaceMap {
+    fn from_iter<T: IntoIterator<Item = (S, i32)>>(iter: T) -> Self {
+        let mut map = Self::new();
+        for (name, id) in iter {
+            map.insert(name.as_ref(), id);
+        }
+        map
+    }
+}
+
+/// Resolve a single namespace, ie. a number or a name, into its id.
+fn namespace_id(lit: &LitNamespace, namespaces: &NamespaceMap) -> Result<i32, SemanticError> {
+    match lit {
+        LitNamespace::Id(id) => Ok(id.val),
+        LitNamespace::Name(name) => namespaces.get(&name.val)
+            .ok_or_else(|| SemanticError::UnknownNamespace { span: name.get_span(), name: name.val.to_owned() }),
+        // the parser only puts numbers and names here.
+        _ => Err(SemanticError::InvalidAttribute { span: lit.get_span() }),
+    }
+}
+
+/// Resolve the namespaces listed in a `ns` modifier into namespace ids. `None` means no constraint, ie. `ns(*)`.
+/// Excluded namespaces are taken from the listed ones, or from all the namespaces of the site if none is listed.
+fn namespaces_from_modifier(item: &ModifierNs, namespaces: &NamespaceMap) -> Result<Option<HashSet<i32>>, SemanticError> {
+    let mut included = HashSet::new();
+    let mut excluded = HashSet::new();
+    let mut all = None;
+    for lit in &item.vals {
+        match lit {
+            LitNamespace::All(star) => all = Some(star.get_span()),
+            LitNamespace::Not(not) => { excluded.insert(namespace_id(&not.val, namespaces)?); },
+            lit => { included.insert(namespace_id(lit, namespaces)?); },
+        }
+    }
+    if let Some(span) = all {
+        return if item.vals.len() > 1 {
+            Err(SemanticError::MixedNamespaceWildcard { span })
+        } else {
+            Ok(None)
+        };
+    }
+    if excluded.is_empty() {
+        Ok(Some(included))
+    } else if included.is_empty() {
+        Ok(Some(&namespaces.ids() - &excluded))
+    } else {
+        Ok(Some(&included - &excluded))
+    }
+}
+
+/// Read a timestamp in a `since` or `before` modifier, and normalize it to `YYYY-MM-DDTHH:MM:SSZ`.
+/// A bare date `YYYY-MM-DD` stands for midnight of that day, in UTC.
+fn timestamp_from_literal(lit: &LitString) -> Result<String, SemanticError> {
+    let val = lit.val.trim();
+    let timestamp = if val.len() == 10 { format!("{val}T00:00:00Z") } else { val.to_owned() };
+    // digits everywhere, except for the separators.
+    let valid = timestamp.len() == 20 && timestamp.char_indices().all(|(i, c)| match i {
+        4 | 7 => c == '-',
+        10 => c == 'T',
+        13 | 16 => c == ':',
+        19 => c == 'Z',
+        _ => c.is_ascii_digit(),
+    });
+    if valid {
+        Ok(timestamp)
+    } else {
+        Err(SemanticError::InvalidTimestamp { span: lit.get_span() })
+    }
+}
+
 /// Convert a collection of `Attribute`s into a `LinksConfig` and a limit.
-pub fn links_config_from_attributes(attrs: &[Attribute]) -> Result<(LinksConfig, Option<IntOrInf>), SemanticError> {
+pub fn links_config_from_attributes(attrs: &[Attribute], namespaces: &NamespaceMap) -> Result<(LinksConfig, Option<IntOrInf>), SemanticError> {
     // core things
     let mut config = LinksConfig::default();
     let mut limit: Option<IntOrInf> = None;
@@ -40,10 +157,11 @@ pub fn links_config_from_attributes(attrs: &[Attribute]) -> Result<(LinksConfig,
                         return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
                     } else {
                         resolved_at.insert("ns", item.get_span());
-                        let namespace = item.vals.iter().map(|lit| lit.val).collect::<HashSet<_>>();
-                        config.namespace = Some(namespace);
+                        config.namespace = namespaces_from_modifier(item, namespaces)?;
                     }
                 },
+                // left to `offset_from_attributes`, `sort_from_attributes` and `sample_from_attributes`.
+                Modifier::Offset(_) | Modifier::Sort(_) | Modifier::Random(_) => {},
                 _ => {
                     return Err(SemanticError::InvalidAttribute { span: attr.get_span() });
                 },
@@ -54,7 +172,7 @@ pub fn links_config_from_attributes(attrs: &[Attribute]) -> Result<(LinksConfig,
 }
 
 /// Convert a collection of `Attribute`s into a `BackLinksConfig` and a limit.
-pub fn backlinks_config_from_attributes(attrs: &[Attribute]) -> Result<(BackLinksConfig, Option<IntOrInf>), SemanticError> {
+pub fn backlinks_config_from_attributes(attrs: &[Attribute], namespaces: &NamespaceMap) -> Result<(BackLinksConfig, Option<IntOrInf>), SemanticError> {
     // core things
     let mut config = BackLinksConfig::default();
     let mut limit: Option<IntOrInf> = None;
@@ -84,8 +202,7 @@ pub fn backlinks_config_from_attributes(attrs: &[Attribute]) -> Result<(BackLink
                         return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
                     } else {
                         resolved_at.insert("ns", item.get_span());
-                        let namespace = item.vals.iter().map(|lit| lit.val).collect::<HashSet<_>>();
-                        config.namespace = Some(namespace);
+                        config.namespace = namespaces_from_modifier(item, namespaces)?;
                     }
                 },
                 Modifier::NoRedir(item) => {
@@ -116,6 +233,8 @@ pub fn backlinks_config_from_attributes(attrs: &[Attribute]) -> Result<(BackLink
                         config.direct = true;
                     }
                 },
+                // left to `offset_from_attributes`, `sort_from_attributes` and `sample_from_attributes`.
+                Modifier::Offset(_) | Modifier::Sort(_) | Modifier::Random(_) => {},
                 _ => {
                     return Err(SemanticError::InvalidAttribute { span: attr.get_span() });
                 },
@@ -126,7 +245,7 @@ pub fn backlinks_config_from_attributes(attrs: &[Attribute]) -> Result<(BackLink
 }
 
 /// Convert a collection of `Attribute`s into a `EmbedsConfig` and a limit.
-pub fn embeds_config_from_attributes(attrs: &[Attribute]) -> Result<(EmbedsConfig, Option<IntOrInf>), SemanticError> {
+pub fn embeds_config_from_attributes(attrs: &[Attribute], namespaces: &NamespaceMap) -> Result<(EmbedsConfig, Option<IntOrInf>), SemanticError> {
     // core things
     let mut config = EmbedsConfig::default();
     let mut limit: Option<IntOrInf> = None;
@@ -156,8 +275,7 @@ pub fn embeds_config_from_attributes(attrs: &[Attribute]) -> Result<(EmbedsConfi
                         return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
                     } else {
                         resolved_at.insert("ns", item.get_span());
-                        let namespace = item.vals.iter().map(|lit| lit.val).collect::<HashSet<_>>();
-                        config.namespace = Some(namespace);
+                        config.namespace = namespaces_from_modifier(item, namespaces)?;
                     }
                 },
                 Modifier::NoRedir(item) => {
@@ -180,6 +298,237 @@ pub fn embeds_config_from_attributes(attrs: &[Attribute]) -> Result<(EmbedsConfi
                         config.filter_redirects = Some(FilterRedirect::OnlyRedirect);
                     }
                 },
+                // left to `offset_from_attributes`, `sort_from_attributes` and `sample_from_attributes`.
+                Modifier::Offset(_) | Modifier::Sort(_) | Modifier::Random(_) => {},
+                _ => {
+                    return Err(SemanticError::InvalidAttribute { span: attr.get_span() });
+                },
+            }
+        }
+    }
+    Ok((config, limit))
+}
+
+/// Convert a collection of `Attribute`s into a `FileUsageConfig` and a limit.
+pub fn fileusage_config_from_attributes(attrs: &[Attribute], namespaces: &NamespaceMap) -> Result<(FileUsageConfig, Option<IntOrInf>), SemanticError> {
+    // core things
+    let mut config = FileUsageConfig::default();
+    let mut limit: Option<IntOrInf> = None;
+    // resolved at objects.
+    let mut resolved_at: HashMap<&str, Span> = HashMap::new();
+    for attr in attrs {
+        if let Attribute::Modifier(attr) = attr {
+            match &attr.modifier {
+                Modifier::Limit(item) => {
+                    if let Some(span) = resolved_at.get("limit") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("limit", item.get_span());
+                        limit = Some(item.val.val);
+                    }
+                },
+                Modifier::Resolve(item) => {
+                    if let Some(span) = resolved_at.get("resolve") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("resolve", item.get_span());
+                        config.resolve_redirects = true;
+                    }
+                },
+                Modifier::Ns(item) => {
+                    if let Some(span) = resolved_at.get("ns") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("ns", item.get_span());
+                        config.namespace = namespaces_from_modifier(item, namespaces)?;
+                    }
+                },
+                Modifier::NoRedir(item) => {
+                    if let Some(span) = resolved_at.get("noredir") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else if let Some(span) = resolved_at.get("onlyredir") {
+                        return Err(SemanticError::ConflictAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("noredir", item.get_span());
+                        config.filter_redirects = Some(FilterRedirect::NoRedirect);
+                    }
+                },
+                Modifier::OnlyRedir(item) => {
+                    if let Some(span) = resolved_at.get("onlyredir") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else if let Some(span) = resolved_at.get("noredir") {
+                        return Err(SemanticError::ConflictAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("onlyredir", item.get_span());
+                        config.filter_redirects = Some(FilterRedirect::OnlyRedirect);
+                    }
+                },
+                // left to `offset_from_attributes`, `sort_from_attributes` and `sample_from_attributes`.
+                Modifier::Offset(_) | Modifier::Sort(_) | Modifier::Random(_) => {},
+                _ => {
+                    return Err(SemanticError::InvalidAttribute { span: attr.get_span() });
+                },
+            }
+        }
+    }
+    Ok((config, limit))
+}
+
+/// Convert a collection of `Attribute`s into a `TemplatesConfig` and a limit.
+pub fn templates_config_from_attributes(attrs: &[Attribute], namespaces: &NamespaceMap) -> Result<(TemplatesConfig, Option<IntOrInf>), SemanticError> {
+    // core things
+    let mut config = TemplatesConfig::default();
+    let mut limit: Option<IntOrInf> = None;
+    // resolved at objects.
+    let mut resolved_at: HashMap<&str, Span> = HashMap::new();
+    for attr in attrs {
+        if let Attribute::Modifier(attr) = attr {
+            match &attr.modifier {
+                Modifier::Limit(item) => {
+                    if let Some(span) = resolved_at.get("limit") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("limit", attr.get_span());
+                        limit = Some(item.val.val);
+                    }
+                },
+                Modifier::Resolve(item) => {
+                    if let Some(span) = resolved_at.get("resolve") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("resolve", item.get_span());
+                        config.resolve_redirects = true;
+                    }
+                },
+                Modifier::Ns(item) => {
+                    if let Some(span) = resolved_at.get("ns") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("ns", item.get_span());
+                        config.namespace = namespaces_from_modifier(item, namespaces)?;
+                    }
+                },
+                // left to `offset_from_attributes`, `sort_from_attributes` and `sample_from_attributes`.
+                Modifier::Offset(_) | Modifier::Sort(_) | Modifier::Random(_) => {},
+                _ => {
+                    return Err(SemanticError::InvalidAttribute { span: attr.get_span() });
+                },
+            }
+        }
+    }
+    Ok((config, limit))
+}
+
+/// Convert a collection of `Attribute`s into a `ImagesConfig` and a limit.
+pub fn images_config_from_attributes(attrs: &[Attribute], namespaces: &NamespaceMap) -> Result<(ImagesConfig, Option<IntOrInf>), SemanticError> {
+    // core things
+    let mut config = ImagesConfig::default();
+    let mut limit: Option<IntOrInf> = None;
+    // resolved at objects.
+    let mut resolved_at: HashMap<&str, Span> = HashMap::new();
+    for attr in attrs {
+        if let Attribute::Modifier(attr) = attr {
+            match &attr.modifier {
+                Modifier::Limit(item) => {
+                    if let Some(span) = resolved_at.get("limit") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("limit", attr.get_span());
+                        limit = Some(item.val.val);
+                    }
+                },
+                Modifier::Resolve(item) => {
+                    if let Some(span) = resolved_at.get("resolve") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("resolve", item.get_span());
+                        config.resolve_redirects = true;
+                    }
+                },
+                Modifier::Ns(item) => {
+                    if let Some(span) = resolved_at.get("ns") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("ns", item.get_span());
+                        config.namespace = namespaces_from_modifier(item, namespaces)?;
+                    }
+                },
+                // left to `offset_from_attributes`, `sort_from_attributes` and `sample_from_attributes`.
+                Modifier::Offset(_) | Modifier::Sort(_) | Modifier::Random(_) => {},
+                _ => {
+                    return Err(SemanticError::InvalidAttribute { span: attr.get_span() });
+                },
+            }
+        }
+    }
+    Ok((config, limit))
+}
+
+/// Convert a collection of `Attribute`s into a `CategoriesConfig` and a limit.
+pub fn categories_config_from_attributes(attrs: &[Attribute], namespaces: &NamespaceMap) -> Result<(CategoriesConfig, Option<IntOrInf>), SemanticError> {
+    // core things
+    let mut config = CategoriesConfig::default();
+    let mut limit: Option<IntOrInf> = None;
+    // resolved at objects.
+    let mut resolved_at: HashMap<&str, Span> = HashMap::new();
+    for attr in attrs {
+        if let Attribute::Modifier(attr) = attr {
+            match &attr.modifier {
+                Modifier::Limit(item) => {
+                    if let Some(span) = resolved_at.get("limit") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("limit", attr.get_span());
+                        limit = Some(item.val.val);
+                    }
+                },
+                Modifier::Resolve(item) => {
+                    if let Some(span) = resolved_at.get("resolve") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("resolve", item.get_span());
+                        config.resolve_redirects = true;
+                    }
+                },
+                Modifier::Ns(item) => {
+                    if let Some(span) = resolved_at.get("ns") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("ns", item.get_span());
+                        config.namespace = namespaces_from_modifier(item, namespaces)?;
+                    }
+                },
+                // left to `offset_from_attributes`, `sort_from_attributes` and `sample_from_attributes`.
+                Modifier::Offset(_) | Modifier::Sort(_) | Modifier::Random(_) => {},
+                _ => {
+                    return Err(SemanticError::InvalidAttribute { span: attr.get_span() });
+                },
+            }
+        }
+    }
+    Ok((config, limit))
+}
+
+/// Convert a collection of `Attribute`s into a `LangLinksConfig` and a limit.
+pub fn langlinks_config_from_attributes(attrs: &[Attribute]) -> Result<(LangLinksConfig, Option<IntOrInf>), SemanticError> {
+    // core things
+    let config = LangLinksConfig::default();
+    let mut limit: Option<IntOrInf> = None;
+    // resolved at objects.
+    let mut resolved_at: HashMap<&str, Span> = HashMap::new();
+    for attr in attrs {
+        if let Attribute::Modifier(attr) = attr {
+            match &attr.modifier {
+                Modifier::Limit(item) => {
+                    if let Some(span) = resolved_at.get("limit") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("limit", item.get_span());
+                        limit = Some(item.val.val);
+                    }
+                },
+                // left to `offset_from_attributes`, `sort_from_attributes` and `sample_from_attributes`.
+                Modifier::Offset(_) | Modifier::Sort(_) | Modifier::Random(_) => {},
                 _ => {
                     return Err(SemanticError::InvalidAttribute { span: attr.get_span() });
                 },
@@ -190,7 +539,7 @@ pub fn embeds_config_from_attributes(attrs: &[Attribute]) -> Result<(EmbedsConfi
 }
 
 /// Convert a collection of `Attribute`s into a `CategoryMembersConfig` and a limit and a depth.
-pub fn categorymembers_config_from_attributes(attrs: &[Attribute]) -> Result<(CategoryMembersConfig, Option<IntOrInf>, Option<IntOrInf>), SemanticError> {
+pub fn categorymembers_config_from_attributes(attrs: &[Attribute], namespaces: &NamespaceMap) -> Result<(CategoryMembersConfig, Option<IntOrInf>, Option<IntOrInf>), SemanticError> {
     // core things
     let mut config = CategoryMembersConfig::default();
     let mut limit: Option<IntOrInf> = None;
@@ -221,8 +570,7 @@ pub fn categorymembers_config_from_attributes(attrs: &[Attribute]) -> Result<(Ca
                         return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
                     } else {
                         resolved_at.insert("ns", item.get_span());
-                        let namespace = item.vals.iter().map(|lit| lit.val).collect::<HashSet<_>>();
-                        config.namespace = Some(namespace);
+                        config.namespace = namespaces_from_modifier(item, namespaces)?;
                     }
                 },
                 Modifier::Depth(item) => {
@@ -233,12 +581,57 @@ pub fn categorymembers_config_from_attributes(attrs: &[Attribute]) -> Result<(Ca
                         depth = Some(item.val.val);
                     }
                 },
+                Modifier::Since(item) => {
+                    if let Some(span) = resolved_at.get("since") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("since", item.get_span());
+                        config.start = Some(timestamp_from_literal(&item.val)?);
+                    }
+                },
+                Modifier::Before(item) => {
+                    if let Some(span) = resolved_at.get("before") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("before", item.get_span());
+                        config.end = Some(timestamp_from_literal(&item.val)?);
+                    }
+                },
+                Modifier::SortBy(item) => {
+                    if let Some(span) = resolved_at.get("sortby") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("sortby", item.get_span());
+                        config.sort = Some(match item.key {
+                            CategorySortKey::Sortkey(_) => CategorySort::SortKey,
+                            CategorySortKey::Timestamp(_) => CategorySort::Timestamp,
+                        });
+                        config.dir = Some(match item.dir {
+                            Some(ast::SortDirection::Desc(_)) => SortDirection::Descending,
+                            _ => SortDirection::Ascending,
+                        });
+                    }
+                },
+                // left to `offset_from_attributes`, `sort_from_attributes` and `sample_from_attributes`.
+                Modifier::Offset(_) | Modifier::Sort(_) | Modifier::Random(_) => {},
                 _ => {
                     return Err(SemanticError::InvalidAttribute { span: attr.get_span() });
                 },
             }
         }
     }
+    // the members can only be bounded by the time they were added if they are listed in that order.
+    if config.sort == Some(CategorySort::SortKey) {
+        if let Some(other) = resolved_at.get("since").or(resolved_at.get("before")) {
+            return Err(SemanticError::ConflictAttribute { span: resolved_at["sortby"], other: *other });
+        }
+    }
+    // normalized timestamps compare in time order.
+    if let (Some(start), Some(end)) = (&config.start, &config.end) {
+        if start > end {
+            return Err(SemanticError::ConflictAttribute { span: resolved_at["before"], other: resolved_at["since"] });
+        }
+    }
     Ok((config, limit, depth))
 }
 
@@ -280,6 +673,8 @@ pub fn prefix_config_from_attributes(attrs: &[Attribute]) -> Result<(PrefixConfi
                         config.filter_redirects = Some(FilterRedirect::OnlyRedirect);
                     }
                 },
+                // left to `offset_from_attributes`, `sort_from_attributes` and `sample_from_attributes`.
+                Modifier::Offset(_) | Modifier::Sort(_) | Modifier::Random(_) => {},
                 _ => {
                     return Err(SemanticError::InvalidAttribute { span: attr.get_span() });
                 },
@@ -288,3 +683,145 @@ pub fn prefix_config_from_attributes(attrs: &[Attribute]) -> Result<(PrefixConfi
     }
     Ok((config, limit))
 }
+
+/// Read the number of results to skip from a collection of `Attribute`s.
+pub fn offset_from_attributes(attrs: &[Attribute]) -> Result<Option<usize>, SemanticError> {
+    let mut offset: Option<usize> = None;
+    // resolved at objects.
+    let mut resolved_at: HashMap<&str, Span> = HashMap::new();
+    for attr in attrs {
+        if let Attribute::Modifier(attr) = attr {
+            if let Modifier::Offset(item) = &attr.modifier {
+                if let Some(span) = resolved_at.get("offset") {
+                    return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                } else if item.val.val < 0 {
+                    return Err(SemanticError::InvalidAttribute { span: attr.get_span() });
+                } else {
+                    resolved_at.insert("offset", attr.get_span());
+                    offset = Some(item.val.val as usize);
+                }
+            }
+        }
+    }
+    Ok(offset)
+}
+
+/// The order in which the results of an operation are sorted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    /// By namespace, then by title.
+    Title,
+    /// By page id. Pages without one, eg. missing pages, come last.
+    PageId,
+}
+
+/// Read the sort order from a collection of `Attribute`s.
+pub fn sort_from_attributes(attrs: &[Attribute]) -> Result<Option<SortOrder>, SemanticError> {
+    let mut order: Option<SortOrder> = None;
+    // resolved at objects.
+    let mut resolved_at: HashMap<&str, Span> = HashMap::new();
+    for attr in attrs {
+        if let Attribute::Modifier(attr) = attr {
+            if let Modifier::Sort(item) = &attr.modifier {
+                if let Some(span) = resolved_at.get("sort") {
+                    return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                } else {
+                    resolved_at.insert("sort", attr.get_span());
+                    order = Some(match item.key {
+                        SortKey::Title(_) => SortOrder::Title,
+                        SortKey::PageId(_) => SortOrder::PageId,
+                    });
+                }
+            }
+        }
+    }
+    Ok(order)
+}
+
+/// A random sample of the results of an operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    /// The number of results to keep.
+    pub size: usize,
+    /// The seed to draw the sample with. Without one, each run draws a different sample.
+    pub seed: Option<i32>,
+}
+
+/// Read the random sample from a collection of `Attribute`s.
+pub fn sample_from_attributes(attrs: &[Attribute]) -> Result<Option<Sample>, SemanticError> {
+    let mut sample: Option<Sample> = None;
+    // resolved at objects.
+    let mut resolved_at: HashMap<&str, Span> = HashMap::new();
+    for attr in attrs {
+        if let Attribute::Modifier(attr) = attr {
+            if let Modifier::Random(item) = &attr.modifier {
+                if let Some(span) = resolved_at.get("random") {
+                    return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                } else if item.val.val < 0 {
+                    return Err(SemanticError::InvalidAttribute { span: attr.get_span() });
+                } else {
+                    resolved_at.insert("random", attr.get_span());
+                    sample = Some(Sample {
+                        size: item.val.val as usize,
+                        seed: item.seed.as_ref().map(|seed| seed.val),
+                    });
+                }
+            }
+        }
+    }
+    Ok(sample)
+}
+
+/// A filter applied on the results of an operation.
+#[derive(Debug, Clone)]
+pub enum PageFilter {
+    /// Keep only pages with at least one protection.
+    Protected,
+    /// Keep only pages without any protection.
+    Unprotected,
+    /// Keep only pages whose title, in its pretty form, matches the regex.
+    Matches(Regex),
+}
+
+/// Collect the filters from a collection of `Attribute`s. Modifiers are left to the `*_config_from_attributes` functions.
+pub fn filters_from_attributes(attrs: &[Attribute]) -> Result<Vec<PageFilter>, SemanticError> {
+    let mut filters = Vec::new();
+    // resolved at objects.
+    let mut resolved_at: HashMap<&str, Span> = HashMap::new();
+    for attr in attrs {
+        if let Attribute::Filter(attr) = attr {
+            match &attr.filter {
+                Filter::Protected(item) => {
+                    if let Some(span) = resolved_at.get("protected") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else if let Some(span) = resolved_at.get("unprotected") {
+                        return Err(SemanticError::ConflictAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("protected", item.get_span());
+                        filters.push(PageFilter::Protected);
+                    }
+                },
+                Filter::Unprotected(item) => {
+                    if let Some(span) = resolved_at.get("unprotected") {
+                        return Err(SemanticError::DuplicateAttribute { span: attr.get_span(), other: *span });
+                    } else if let Some(span) = resolved_at.get("protected") {
+                        return Err(SemanticError::ConflictAttribute { span: attr.get_span(), other: *span });
+                    } else {
+                        resolved_at.insert("unprotected", item.get_span());
+                        filters.push(PageFilter::Unprotected);
+                    }
+                },
+                // several patterns must all match.
+                Filter::Matches(item) => {
+                    let regex = Regex::new(&item.val.val)
+                        .map_err(|e| SemanticError::InvalidRegex { span: item.val.get_span(), error: e.to_string() })?;
+                    filters.push(PageFilter::Matches(regex));
+                },
+                _ => {
+                    return Err(SemanticError::InvalidAttribute { span: attr.get_span() });
+                },
+            }
+        }
+    }
+    Ok(filters)
+}
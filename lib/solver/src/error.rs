@@ -7,7 +7,12 @@ use std::error::Error;
 #[non_exhaustive]
 pub enum RuntimeWarning<P: DataProvider> {
     Provider { span: Span, warn: P::Warn },
-    ResultLimitExceeded { span: Span, limit: usize },
+    /// More than `limit` results. `default` tells whether the limit is the default one, rather than one set with `limit`.
+    ResultLimitExceeded { span: Span, limit: usize, default: bool },
+    /// A category search stopped expanding subcategories after `budget` categories.
+    CategoryBudgetExceeded { span: Span, budget: usize },
+    /// No page has this page id.
+    UnknownPageId { span: Span, pageid: u32 },
 }
 
 impl<P> Error for RuntimeWarning<P>
@@ -19,6 +24,8 @@ where
         match self {
             RuntimeWarning::Provider { warn, .. } => Some(warn),
             RuntimeWarning::ResultLimitExceeded { .. } => None,
+            RuntimeWarning::CategoryBudgetExceeded { .. } => None,
+            RuntimeWarning::UnknownPageId { .. } => None,
         }
     }
 }
@@ -31,7 +38,10 @@ where
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             RuntimeWarning::Provider { span, warn } => f.write_fmt(format_args!("provider warning at `{}:{}`: {}", span.start, span.end, warn)),
-            RuntimeWarning::ResultLimitExceeded { span, limit } => f.write_fmt(format_args!("result limit `{}` exceeded at `{}:{}`", limit, span.start, span.end)),
+            RuntimeWarning::ResultLimitExceeded { span, limit, default: false } => f.write_fmt(format_args!("result limit `{}` exceeded at `{}:{}`", limit, span.start, span.end)),
+            RuntimeWarning::ResultLimitExceeded { span, limit, default: true } => f.write_fmt(format_args!("default limit `{}` reached at `{}:{}`, use `limit` to raise it", limit, span.start, span.end)),
+            RuntimeWarning::CategoryBudgetExceeded { span, budget } => f.write_fmt(format_args!("category search truncated after `{}` categories at `{}:{}`", budget, span.start, span.end)),
+            RuntimeWarning::UnknownPageId { span, pageid } => f.write_fmt(format_args!("unknown page id `{}` at `{}:{}`", pageid, span.start, span.end)),
         }
     }
 }
@@ -44,7 +54,9 @@ where
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Provider { span, warn } => f.debug_struct("Provider").field("span", span).field("warn", warn).finish(),
-            Self::ResultLimitExceeded { span, limit } => f.debug_struct("ResultLimitExceeded").field("span", span).field("limit", limit).finish(),
+            Self::ResultLimitExceeded { span, limit, default } => f.debug_struct("ResultLimitExceeded").field("span", span).field("limit", limit).field("default", default).finish(),
+            Self::CategoryBudgetExceeded { span, budget } => f.debug_struct("CategoryBudgetExceeded").field("span", span).field("budget", budget).finish(),
+            Self::UnknownPageId { span, pageid } => f.debug_struct("UnknownPageId").field("span", span).field("pageid", pageid).finish(),
         }
     }
 }
@@ -56,6 +68,16 @@ pub enum RuntimeError<P: DataProvider> {
     PageInfo { span: Span, error: PageInfoError },
 }
 
+impl<P: DataProvider> RuntimeError<P> {
+    /// The span of the expression that raised this error.
+    pub fn get_span(&self) -> Span {
+        match self {
+            Self::Provider { span, .. } => *span,
+            Self::PageInfo { span, .. } => *span,
+        }
+    }
+}
+
 impl<P> Error for RuntimeError<P>
 where
     P: DataProvider,
@@ -104,6 +126,38 @@ pub enum SemanticError {
     DuplicateAttribute { span: Span, other: Span },
     /// This attribute is invalid under this operation.
     InvalidAttribute { span: Span },
+    /// This namespace name is not known to the site.
+    UnknownNamespace { span: Span, name: String },
+    /// This complement is not an operand of an intersection with something else, so it has nothing to be taken from.
+    UnboundedComplement { span: Span },
+    /// This literal is not a valid page id, ie. not positive.
+    InvalidPageId { span: Span },
+    /// This literal is not a timestamp, ie. `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SSZ`.
+    InvalidTimestamp { span: Span },
+    /// This literal is not a valid regular expression.
+    InvalidRegex { span: Span, error: String },
+    /// This `*` in a `ns` modifier is not the only namespace listed.
+    MixedNamespaceWildcard { span: Span },
+    /// This kind of expression is not known to the solver.
+    UnsupportedExpression { span: Span },
+}
+
+impl SemanticError {
+    /// The span of the offending attribute or literal.
+    pub fn get_span(&self) -> Span {
+        match self {
+            Self::ConflictAttribute { span, .. } => *span,
+            Self::DuplicateAttribute { span, .. } => *span,
+            Self::InvalidAttribute { span } => *span,
+            Self::UnknownNamespace { span, .. } => *span,
+            Self::UnboundedComplement { span } => *span,
+            Self::InvalidPageId { span } => *span,
+            Self::InvalidTimestamp { span } => *span,
+            Self::InvalidRegex { span, .. } => *span,
+            Self::MixedNamespaceWildcard { span } => *span,
+            Self::UnsupportedExpression { span } => *span,
+        }
+    }
 }
 
 impl Error for SemanticError {}
@@ -113,6 +167,13 @@ impl Display for SemanticError {
             Self::ConflictAttribute { span, other } => f.write_fmt(format_args!("conflict attributes at `{}:{}` and `{}:{}`", span.start, span.end, other.start, other.end)),
             Self::DuplicateAttribute { span, other } => f.write_fmt(format_args!("duplicate attributes at `{}:{}` and `{}:{}`", span.start, span.end, other.start, other.end)),
             Self::InvalidAttribute { span } => f.write_fmt(format_args!("invalid attribute at `{}:{}`", span.start, span.end)),
+            Self::UnknownNamespace { span, name } => f.write_fmt(format_args!("unknown namespace `{}` at `{}:{}`", name, span.start, span.end)),
+            Self::UnboundedComplement { span } => f.write_fmt(format_args!("unbounded complement at `{}:{}`", span.start, span.end)),
+            Self::InvalidPageId { span } => f.write_fmt(format_args!("invalid page id at `{}:{}`", span.start, span.end)),
+            Self::InvalidTimestamp { span } => f.write_fmt(format_args!("invalid timestamp at `{}:{}`", span.start, span.end)),
+            Self::InvalidRegex { span, error } => f.write_fmt(format_args!("invalid regular expression at `{}:{}`: {}", span.start, span.end, error)),
+            Self::MixedNamespaceWildcard { span } => f.write_fmt(format_args!("`*` mixed with other namespaces at `{}:{}`", span.start, span.end)),
+            Self::UnsupportedExpression { span } => f.write_fmt(format_args!("unsupported expression at `{}:{}`", span.start, span.end)),
         }
     }
 }
@@ -3,11 +3,19 @@
 // pub mod builder;
 pub mod attr;
 pub mod error;
+mod memo;
+pub mod optimize;
+pub mod plan;
 pub mod streams;
 
 // re-exports from core
 // pub use crate::streams::SolverStream;
+pub use crate::attr::NamespaceMap;
 pub use crate::error::{RuntimeWarning, RuntimeError, SemanticError};
-pub use crate::streams::from_expr;
+pub use crate::optimize::optimize;
+pub use crate::plan::plan;
+pub use crate::streams::{count, from_expr, from_expr_with_options, SolverOptions, DEFAULT_CATEGORY_BUDGET};
 
 pub type SolverResult<P> = trio_result::TrioResult<provider::PageInfo, RuntimeWarning<P>, RuntimeError<P>>;
+/// The number of distinct results and the warnings met, or the first error.
+pub type CountResult<P> = Result<(usize, Vec<RuntimeWarning<P>>), RuntimeError<P>>;
@@ -0,0 +1,164 @@
+//! Sharing the results of sub-expressions that appear more than once in a query.
+
+use ast::{Attribute, Expression, Modifier, Visitor, walk_expression};
+use async_stream::stream;
+use core::hash::{Hash, Hasher};
+use crate::SolverResult;
+use futures::{Stream, StreamExt, future::{FutureExt, LocalBoxFuture, Shared}};
+use provider::{DataProvider, PageInfo};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use trio_result::TrioResult;
+
+/// An expression compared by meaning, ignoring spans, so `link("A")` is the same wherever it is written.
+#[derive(Debug, Clone, Copy)]
+struct Key<'e>(&'e Expression);
+
+impl Hash for Key<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // spans are not hashed.
+        self.0.hash(state);
+    }
+}
+
+impl PartialEq for Key<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.semantic_eq(other.0)
+    }
+}
+
+impl Eq for Key<'_> {}
+
+/// The collected results of a sub-expression.
+struct Collected<P: DataProvider> {
+    pages: Vec<PageInfo>,
+    /// Warnings and errors, taken by the first occurrence to replay them, so each is reported once.
+    others: RefCell<Option<Vec<SolverResult<P>>>>,
+}
+
+type SharedResults<'a, P> = Shared<LocalBoxFuture<'a, Rc<Collected<P>>>>;
+
+/// The repeated sub-expressions of a query, and their results once evaluated.
+pub(crate) struct Memo<'e, 'a, P: DataProvider> {
+    repeated: HashMap<Key<'e>, usize>,
+    shared: HashMap<Key<'e>, SharedResults<'a, P>>,
+}
+
+impl<'e, 'a, P: DataProvider + 'a> Memo<'e, 'a, P> {
+    /// Find the repeated sub-expressions of `expr`. Only the outermost repeat is shared, so the parts of a repeated
+    /// sub-expression are not buffered once more on their own.
+    pub(crate) fn new(expr: &'e Expression) -> Self {
+        struct Counter<'e>(HashMap<Key<'e>, usize>);
+        impl<'e> Visitor<'e> for Counter<'e> {
+            fn visit_expression(&mut self, expr: &'e Expression) {
+                // parentheses are shared through their content, and an unseeded sample is drawn anew each time.
+                if matches!(expr, Expression::Paren(_)) || unseeded(expr) {
+                    return walk_expression(self, expr);
+                }
+                let count = self.0.entry(Key(expr)).or_default();
+                *count += 1;
+                if *count == 1 {
+                    walk_expression(self, expr);
+                }
+            }
+        }
+
+        let mut counter = Counter(HashMap::new());
+        counter.visit_expression(expr);
+        counter.0.retain(|_, count| *count > 1);
+        Self { repeated: counter.0, shared: HashMap::new() }
+    }
+
+    /// Whether `expr` appears more than once.
+    pub(crate) fn is_repeated(&self, expr: &'e Expression) -> bool {
+        self.repeated.contains_key(&Key(expr))
+    }
+
+    /// Replay the results of `expr`, if it has been evaluated before.
+    pub(crate) fn replay(&self, expr: &'e Expression) -> Option<impl Stream<Item=SolverResult<P>> + 'a> {
+        self.shared.get(&Key(expr)).cloned().map(replay)
+    }
+
+    /// Buffer the results of `st`, the stream of `expr`, to be replayed by every occurrence of `expr`.
+    pub(crate) fn share<S>(&mut self, expr: &'e Expression, st: S) -> impl Stream<Item=SolverResult<P>> + 'a
+    where
+        S: Stream<Item=SolverResult<P>> + 'a,
+    {
+        let collect = async move {
+            let mut st = Box::pin(st);
+            let mut pages = Vec::new();
+            let mut others = Vec::new();
+            while let Some(item) = st.next().await {
+                match item {
+                    TrioResult::Ok(info) => pages.push(info),
+                    x => others.push(x),
+                }
+            }
+            Rc::new(Collected { pages, others: RefCell::new(Some(others)) })
+        };
+        let shared = collect.boxed_local().shared();
+        self.shared.insert(Key(expr), shared.clone());
+        replay(shared)
+    }
+}
+
+/// Whether `expr` or a sub-expression of it draws a random sample without a seed.
+fn unseeded(expr: &Expression) -> bool {
+    struct Finder(bool);
+    impl<'e> Visitor<'e> for Finder {
+        fn visit_attribute(&mut self, attr: &'e Attribute) {
+            if let Attribute::Modifier(attr) = attr {
+                self.0 |= matches!(&attr.modifier, Modifier::Random(random) if random.seed.is_none());
+            }
+        }
+    }
+
+    let mut finder = Finder(false);
+    finder.visit_expression(expr);
+    finder.0
+}
+
+/// Yield the collected results once they are all in.
+fn replay<'a, P>(shared: SharedResults<'a, P>) -> impl Stream<Item=SolverResult<P>> + 'a
+where
+    P: DataProvider + 'a,
+{
+    stream! {
+        let collected = shared.await;
+        let others = collected.others.borrow_mut().take();
+        for item in others.into_iter().flatten() {
+            yield item;
+        }
+        for info in collected.pages.iter() {
+            yield TrioResult::Ok(info.to_owned());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ast::Expression;
+    use nom::error::VerboseError;
+    use std::{collections::hash_map::RandomState, hash::BuildHasher};
+    use super::Key;
+
+    /// `semantic_eq` and `Hash` must agree, or equal sub-expressions may land in different buckets and not be shared.
+    #[test]
+    fn test_key_hash() {
+        let pairs = [
+            ("link(\"A\").resolve", "link( \"A\" ).resolve()"),
+            ("\"A\", \"B\"", "page(\"A\", \"B\")"),
+            ("page(\"A\", \"B\",)", "page(\"A\", \"B\")"),
+            ("pageid(1, 2,)", "pageid(1, 2)"),
+            ("link(\"A\").ns(0, Category,)", "link(\"A\").ns(0, Category)"),
+            ("incat(\"Category:A\").sortby(sortkey)", "incat(\"Category:A\").sortby(sortkey, asc)"),
+            ("link(\"A\").matches(\"x\")", "link(\"A\").matchtitle(\"x\")"),
+        ];
+        let state = RandomState::new();
+        for (a, b) in pairs {
+            let a = Expression::parse::<VerboseError<_>>(a).unwrap();
+            let b = Expression::parse::<VerboseError<_>>(b).unwrap();
+            assert!(Key(&a) == Key(&b), "{a} and {b}");
+            assert_eq!(state.hash_one(Key(&a)), state.hash_one(Key(&b)), "{a} and {b}");
+        }
+    }
+}
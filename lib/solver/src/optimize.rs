@@ -0,0 +1,108 @@
+//! Rewrites that keep the results of an expression, but make it cheaper to evaluate.
+
+use ast::{Attribute, Expression, Modifier};
+use intorinf::IntOrInf;
+
+/// Reorder the operands of every intersection, so that the cheaper one comes first.
+///
+/// The first operand is polled first, and the cheaper one is likely to end first. If it ends without any result,
+/// the intersection stops without finishing the other operand.
+pub fn optimize(expr: &mut Expression) {
+    optimize_inner(expr);
+}
+
+/// Optimize `expr` in place, and return its estimated cost.
+///
+/// The cost is a rough guess of the number of API requests: a page list costs one, and every operation on it
+/// costs a few times its input, more so for operations that usually return many pages.
+fn optimize_inner(expr: &mut Expression) -> u64 {
+    match expr {
+        Expression::And(expr) => {
+            let c1 = optimize_inner(&mut expr.expr1);
+            let c2 = optimize_inner(&mut expr.expr2);
+            if c2 < c1 {
+                core::mem::swap(&mut expr.expr1, &mut expr.expr2);
+            }
+            c1.saturating_add(c2)
+        },
+        Expression::Add(expr) => optimize_inner(&mut expr.expr1).saturating_add(optimize_inner(&mut expr.expr2)),
+        Expression::Sub(expr) => optimize_inner(&mut expr.expr1).saturating_add(optimize_inner(&mut expr.expr2)),
+        Expression::Xor(expr) => optimize_inner(&mut expr.expr1).saturating_add(optimize_inner(&mut expr.expr2)),
+        Expression::Paren(expr) => optimize_inner(&mut expr.expr),
+        Expression::Page(_) => 1,
+        Expression::PageId(_) => 1,
+        Expression::Link(expr) => optimize_inner(&mut expr.expr).saturating_mul(4),
+        Expression::LinkTo(expr) => optimize_inner(&mut expr.expr).saturating_mul(8),
+        Expression::Embed(expr) => optimize_inner(&mut expr.expr).saturating_mul(8),
+        Expression::FileUsage(expr) => optimize_inner(&mut expr.expr).saturating_mul(8),
+        Expression::InCat(expr) => {
+            let factor = match depth(&expr.attributes) {
+                IntOrInf::Int(d) => 8u64.saturating_mul(d.max(0) as u64 + 1),
+                IntOrInf::Inf => 1 << 16,
+            };
+            optimize_inner(&mut expr.expr).saturating_mul(factor)
+        },
+        Expression::Prefix(expr) => optimize_inner(&mut expr.expr).saturating_mul(8),
+        Expression::Toggle(expr) => optimize_inner(&mut expr.expr),
+        Expression::TemplatesIn(expr) => optimize_inner(&mut expr.expr).saturating_mul(4),
+        Expression::Files(expr) => optimize_inner(&mut expr.expr).saturating_mul(4),
+        Expression::CategoriesOf(expr) => optimize_inner(&mut expr.expr).saturating_mul(4),
+        Expression::LangLinks(expr) => optimize_inner(&mut expr.expr).saturating_mul(4),
+        Expression::Not(expr) => {
+            let cost = optimize_inner(&mut expr.expr);
+            match &mut expr.universe {
+                Some(universe) => cost.saturating_add(optimize_inner(universe)),
+                None => cost,
+            }
+        },
+        _ => u64::MAX,
+    }
+}
+
+/// The depth of a category search, without checking the attributes. Those are checked when the expression is solved.
+fn depth(attrs: &[Attribute]) -> IntOrInf {
+    attrs.iter()
+        .find_map(|attr| match attr {
+            Attribute::Modifier(attr) => match &attr.modifier {
+                Modifier::Depth(item) => Some(item.val.val),
+                _ => None,
+            },
+            _ => None,
+        })
+        .unwrap_or(IntOrInf::Int(0))
+}
+
+#[cfg(test)]
+mod test {
+    use ast::Expression;
+    use crate::{NamespaceMap, plan};
+    use super::optimize;
+    use intorinf::IntOrInf;
+    use nom::error::VerboseError;
+
+    #[test]
+    fn test_optimize() {
+        let namespaces = NamespaceMap::new();
+        let mut expr = Expression::parse::<VerboseError<_>>("incat(\"Category:A\").depth(-1) & \"B\"").unwrap();
+        optimize(&mut expr);
+        assert_eq!(plan(&expr, IntOrInf::Inf, &namespaces).unwrap(), vec![
+            "#1 page info of \"B\"",
+            "#2 page info of \"Category:A\"",
+            "#3 category members of each page in #2 (all subcategories; no result limit)",
+            "#4 pages in both #1 and #3",
+        ]);
+
+        // already in order, or as cheap as each other.
+        for query in ["\"B\" & incat(\"Category:A\").depth(-1)", "link(\"A\") & link(\"B\")"] {
+            let original = Expression::parse::<VerboseError<_>>(query).unwrap();
+            let mut expr = original.clone();
+            optimize(&mut expr);
+            assert_eq!(expr, original);
+        }
+
+        // nested intersections are reordered too.
+        let mut expr = Expression::parse::<VerboseError<_>>("toggle(linkto(\"A\") & link(\"B\"))").unwrap();
+        optimize(&mut expr);
+        assert_eq!(expr.to_string(), "toggle(link(page(\"B\")) & linkto(page(\"A\")))");
+    }
+}
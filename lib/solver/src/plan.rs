@@ -0,0 +1,297 @@
+//! Human-readable evaluation plans.
+
+use ast::{Attribute, Expression};
+use crate::{SemanticError, attr::*, streams::as_not};
+use intorinf::IntOrInf;
+use provider::{CategorySort, FilterRedirect, SortDirection};
+use std::collections::HashSet;
+
+/// Describe how `expr` is evaluated, one step per line.
+///
+/// Steps are numbered in evaluation order, eg. `#1`, and refer to the steps they read from by number.
+/// The same attribute conversions as `from_expr` are done, so an expression is accepted here if and only if it is accepted there.
+pub fn plan(expr: &Expression, default_count_limit: IntOrInf, namespaces: &NamespaceMap) -> Result<Vec<String>, SemanticError> {
+    let mut steps = Vec::new();
+    plan_inner(expr, default_count_limit, namespaces, &mut steps)?;
+    Ok(steps)
+}
+
+/// Add the steps of `expr` to `steps`, and return the number of its last step.
+fn plan_inner(expr: &Expression, default_count_limit: IntOrInf, namespaces: &NamespaceMap, steps: &mut Vec<String>) -> Result<usize, SemanticError> {
+    let description = match expr {
+        Expression::And(expr) => {
+            let (universe, complement) = match (as_not(&expr.expr1), as_not(&expr.expr2)) {
+                (None, None) => (&expr.expr1, None),
+                (None, Some(not)) => (&expr.expr1, Some(not)),
+                (Some(not), None) => (&expr.expr2, Some(not)),
+                (Some(_), Some(not)) => return Err(SemanticError::UnboundedComplement { span: not.get_span() }),
+            };
+            let st1 = plan_inner(universe, default_count_limit, namespaces, steps)?;
+            match complement {
+                Some(not) => {
+                    let st2 = plan_inner(&not.expr, default_count_limit, namespaces, steps)?;
+                    format!("pages in #{st1} but not in #{st2}")
+                },
+                None => {
+                    let st2 = plan_inner(&expr.expr2, default_count_limit, namespaces, steps)?;
+                    format!("pages in both #{st1} and #{st2}")
+                },
+            }
+        },
+        Expression::Add(expr) => {
+            let st1 = plan_inner(&expr.expr1, default_count_limit, namespaces, steps)?;
+            let st2 = plan_inner(&expr.expr2, default_count_limit, namespaces, steps)?;
+            format!("pages in #{st1} or #{st2}")
+        },
+        Expression::Sub(expr) => {
+            let st1 = plan_inner(&expr.expr1, default_count_limit, namespaces, steps)?;
+            let st2 = plan_inner(&expr.expr2, default_count_limit, namespaces, steps)?;
+            format!("pages in #{st1} but not in #{st2}")
+        },
+        Expression::Xor(expr) => {
+            let st1 = plan_inner(&expr.expr1, default_count_limit, namespaces, steps)?;
+            let st2 = plan_inner(&expr.expr2, default_count_limit, namespaces, steps)?;
+            format!("pages in exactly one of #{st1} and #{st2}")
+        },
+        Expression::Paren(expr) => {
+            return plan_inner(&expr.expr, default_count_limit, namespaces, steps);
+        },
+        Expression::Page(expr) => {
+            let pages = expr.vals.iter().map(|lit| lit.to_string()).collect::<Vec<_>>().join(", ");
+            with_default_limit(format!("page info of {pages}"), default_count_limit)
+        },
+        Expression::PageId(expr) => {
+            if let Some(lit) = expr.vals.iter().find(|lit| lit.val <= 0) {
+                return Err(SemanticError::InvalidPageId { span: lit.get_span() });
+            }
+            let pageids = expr.vals.iter().map(|lit| lit.to_string()).collect::<Vec<_>>().join(", ");
+            with_default_limit(format!("page info of page ids {pageids}"), default_count_limit)
+        },
+        Expression::Link(expr) => {
+            let (config, limit) = links_config_from_attributes(&expr.attributes, namespaces)?;
+            let st = plan_inner(&expr.expr, default_count_limit, namespaces, steps)?;
+            let options = options(config.namespace.as_ref(), config.resolve_redirects, None);
+            unary("links", st, options, limit, default_count_limit, &expr.attributes)?
+        },
+        Expression::LinkTo(expr) => {
+            let (config, limit) = backlinks_config_from_attributes(&expr.attributes, namespaces)?;
+            let st = plan_inner(&expr.expr, default_count_limit, namespaces, steps)?;
+            let mut options = options(config.namespace.as_ref(), config.resolve_redirects, config.filter_redirects);
+            if config.direct {
+                options.push("direct links only".to_string());
+            }
+            unary("backlinks", st, options, limit, default_count_limit, &expr.attributes)?
+        },
+        Expression::Embed(expr) => {
+            let (config, limit) = embeds_config_from_attributes(&expr.attributes, namespaces)?;
+            let st = plan_inner(&expr.expr, default_count_limit, namespaces, steps)?;
+            let options = options(config.namespace.as_ref(), config.resolve_redirects, config.filter_redirects);
+            unary("transclusions", st, options, limit, default_count_limit, &expr.attributes)?
+        },
+        Expression::FileUsage(expr) => {
+            let (config, limit) = fileusage_config_from_attributes(&expr.attributes, namespaces)?;
+            let st = plan_inner(&expr.expr, default_count_limit, namespaces, steps)?;
+            let options = options(config.namespace.as_ref(), config.resolve_redirects, config.filter_redirects);
+            unary("file usage", st, options, limit, default_count_limit, &expr.attributes)?
+        },
+        Expression::TemplatesIn(expr) => {
+            let (config, limit) = templates_config_from_attributes(&expr.attributes, namespaces)?;
+            let st = plan_inner(&expr.expr, default_count_limit, namespaces, steps)?;
+            let options = options(config.namespace.as_ref(), config.resolve_redirects, None);
+            unary("templates", st, options, limit, default_count_limit, &expr.attributes)?
+        },
+        Expression::InCat(expr) => {
+            let (config, limit, depth) = categorymembers_config_from_attributes(&expr.attributes, namespaces)?;
+            let st = plan_inner(&expr.expr, default_count_limit, namespaces, steps)?;
+            let mut options = options(config.namespace.as_ref(), config.resolve_redirects, None);
+            options.push(match depth.unwrap_or(IntOrInf::Int(0)) {
+                IntOrInf::Int(0) => "no subcategories".to_string(),
+                IntOrInf::Int(d) => format!("subcategories up to depth {d}"),
+                IntOrInf::Inf => "all subcategories".to_string(),
+            });
+            if let Some(start) = &config.start {
+                options.push(format!("added since {start}"));
+            }
+            if let Some(end) = &config.end {
+                options.push(format!("added before {end}"));
+            }
+            if let Some(sort) = config.sort {
+                let descending = if config.dir == Some(SortDirection::Descending) { ", descending" } else { "" };
+                options.push(match sort {
+                    CategorySort::SortKey => format!("listed by sort key{descending}"),
+                    CategorySort::Timestamp => format!("listed by time added{descending}"),
+                });
+            }
+            unary("category members", st, options, limit, default_count_limit, &expr.attributes)?
+        },
+        Expression::Prefix(expr) => {
+            let (config, limit) = prefix_config_from_attributes(&expr.attributes)?;
+            let st = plan_inner(&expr.expr, default_count_limit, namespaces, steps)?;
+            let options = options(None, false, config.filter_redirects);
+            unary("subpages", st, options, limit, default_count_limit, &expr.attributes)?
+        },
+        Expression::Files(expr) => {
+            let (config, limit) = images_config_from_attributes(&expr.attributes, namespaces)?;
+            let st = plan_inner(&expr.expr, default_count_limit, namespaces, steps)?;
+            let options = options(config.namespace.as_ref(), config.resolve_redirects, None);
+            unary("files", st, options, limit, default_count_limit, &expr.attributes)?
+        },
+        Expression::CategoriesOf(expr) => {
+            let (config, limit) = categories_config_from_attributes(&expr.attributes, namespaces)?;
+            let st = plan_inner(&expr.expr, default_count_limit, namespaces, steps)?;
+            let options = options(config.namespace.as_ref(), config.resolve_redirects, None);
+            unary("categories", st, options, limit, default_count_limit, &expr.attributes)?
+        },
+        Expression::LangLinks(expr) => {
+            let (_, limit) = langlinks_config_from_attributes(&expr.attributes)?;
+            let st = plan_inner(&expr.expr, default_count_limit, namespaces, steps)?;
+            unary("language links", st, Vec::new(), limit, default_count_limit, &expr.attributes)?
+        },
+        Expression::Toggle(expr) => {
+            let st = plan_inner(&expr.expr, default_count_limit, namespaces, steps)?;
+            with_default_limit(format!("subject or talk pages of #{st}"), default_count_limit)
+        },
+        Expression::Not(expr) => {
+            let Some(universe) = &expr.universe else {
+                return Err(SemanticError::UnboundedComplement { span: expr.get_span() });
+            };
+            let st1 = plan_inner(universe, default_count_limit, namespaces, steps)?;
+            let st2 = plan_inner(&expr.expr, default_count_limit, namespaces, steps)?;
+            format!("pages in #{st1} but not in #{st2}")
+        },
+        _ => return Err(SemanticError::UnsupportedExpression { span: expr.get_span() }),
+    };
+    steps.push(format!("#{} {}", steps.len() + 1, description));
+    Ok(steps.len())
+}
+
+/// Describe the limit of an expression that has no `limit` modifier.
+fn with_default_limit(description: String, default_count_limit: IntOrInf) -> String {
+    match default_count_limit {
+        IntOrInf::Int(limit) => format!("{description} (at most {limit} results)"),
+        IntOrInf::Inf => description,
+    }
+}
+
+/// Describe the options shared by most operations.
+fn options(namespace: Option<&HashSet<i32>>, resolve_redirects: bool, filter_redirects: Option<FilterRedirect>) -> Vec<String> {
+    let mut options = Vec::new();
+    if let Some(namespace) = namespace {
+        let mut namespace: Vec<_> = namespace.iter().copied().collect();
+        namespace.sort_unstable();
+        options.push(format!("namespaces {}", namespace.iter().map(|ns| ns.to_string()).collect::<Vec<_>>().join(", ")));
+    }
+    if resolve_redirects {
+        options.push("resolve redirects".to_string());
+    }
+    match filter_redirects {
+        Some(FilterRedirect::NoRedirect) => options.push("no redirects".to_string()),
+        Some(FilterRedirect::OnlyRedirect) => options.push("only redirects".to_string()),
+        None => {},
+    }
+    options
+}
+
+/// Describe an operation on each page of step `st`, with its options, filters and limit.
+fn unary(operation: &str, st: usize, mut options: Vec<String>, limit: Option<IntOrInf>, default_count_limit: IntOrInf, attrs: &[Attribute]) -> Result<String, SemanticError> {
+    for filter in filters_from_attributes(attrs)? {
+        options.push(match filter {
+            PageFilter::Protected => "protected pages only".to_string(),
+            PageFilter::Unprotected => "unprotected pages only".to_string(),
+            PageFilter::Matches(regex) => format!("titles matching `{}` only", regex.as_str()),
+        });
+    }
+    if let Some(offset) = offset_from_attributes(attrs)?.filter(|&offset| offset > 0) {
+        options.push(format!("skip the first {offset} results"));
+    }
+    options.push(match limit.unwrap_or(default_count_limit) {
+        IntOrInf::Int(limit) => format!("at most {limit} results"),
+        IntOrInf::Inf => "no result limit".to_string(),
+    });
+    if let Some(sample) = sample_from_attributes(attrs)? {
+        options.push(match sample.seed {
+            Some(seed) => format!("a random sample of {} results with seed {seed}", sample.size),
+            None => format!("a random sample of {} results", sample.size),
+        });
+    }
+    match sort_from_attributes(attrs)? {
+        Some(SortOrder::Title) => options.push("sorted by title".to_string()),
+        Some(SortOrder::PageId) => options.push("sorted by page id".to_string()),
+        None => {},
+    }
+    Ok(format!("{operation} of each page in #{st} ({})", options.join("; ")))
+}
+
+#[cfg(test)]
+mod test {
+    use ast::Expression;
+    use crate::{NamespaceMap, SemanticError};
+    use super::plan;
+    use intorinf::IntOrInf;
+    use nom::error::VerboseError;
+
+    #[test]
+    fn test_plan() {
+        let namespaces: NamespaceMap = [("Category", 14)].into_iter().collect();
+        let expr = Expression::parse::<VerboseError<_>>(
+            "(incat(\"Category:A\").depth(2).ns(0, Category) - \"B\") & linkto(\"C\", 'D').noredir.limit(-1).unprotected"
+        ).unwrap();
+
+        assert_eq!(plan(&expr, IntOrInf::Int(100), &namespaces).unwrap(), vec![
+            "#1 page info of \"Category:A\" (at most 100 results)",
+            "#2 category members of each page in #1 (namespaces 0, 14; subcategories up to depth 2; at most 100 results)",
+            "#3 page info of \"B\" (at most 100 results)",
+            "#4 pages in #2 but not in #3",
+            "#5 page info of \"C\", \"D\" (at most 100 results)",
+            "#6 backlinks of each page in #5 (no redirects; unprotected pages only; no result limit)",
+            "#7 pages in both #4 and #6",
+        ]);
+
+        let expr = Expression::parse::<VerboseError<_>>("link(\"A\").offset(10).limit(5).sort(pageid)").unwrap();
+        assert_eq!(plan(&expr, IntOrInf::Inf, &namespaces).unwrap(), vec![
+            "#1 page info of \"A\"",
+            "#2 links of each page in #1 (skip the first 10 results; at most 5 results; sorted by page id)",
+        ]);
+
+        let expr = Expression::parse::<VerboseError<_>>("not(\"B\") & link(\"A\")").unwrap();
+        assert_eq!(plan(&expr, IntOrInf::Inf, &namespaces).unwrap(), vec![
+            "#1 page info of \"A\"",
+            "#2 links of each page in #1 (no result limit)",
+            "#3 page info of \"B\"",
+            "#4 pages in #2 but not in #3",
+        ]);
+
+        let expr = Expression::parse::<VerboseError<_>>("not(\"B\", link(\"A\"))").unwrap();
+        assert_eq!(plan(&expr, IntOrInf::Inf, &namespaces).unwrap(), vec![
+            "#1 page info of \"A\"",
+            "#2 links of each page in #1 (no result limit)",
+            "#3 page info of \"B\"",
+            "#4 pages in #2 but not in #3",
+        ]);
+
+        let expr = Expression::parse::<VerboseError<_>>("link(\"A\").depth(2)").unwrap();
+        assert!(matches!(plan(&expr, IntOrInf::Inf, &namespaces), Err(SemanticError::InvalidAttribute { .. })));
+
+        let expr = Expression::parse::<VerboseError<_>>("incat(\"Category:A\").since(\"2024-01-01\").before(\"2024-01-07T12:00:00Z\")").unwrap();
+        assert_eq!(plan(&expr, IntOrInf::Inf, &namespaces).unwrap(), vec![
+            "#1 page info of \"Category:A\"",
+            "#2 category members of each page in #1 (no subcategories; added since 2024-01-01T00:00:00Z; added before 2024-01-07T12:00:00Z; no result limit)",
+        ]);
+
+        let expr = Expression::parse::<VerboseError<_>>("incat(\"Category:A\").sortby(timestamp, desc).limit(10)").unwrap();
+        assert_eq!(plan(&expr, IntOrInf::Inf, &namespaces).unwrap(), vec![
+            "#1 page info of \"Category:A\"",
+            "#2 category members of each page in #1 (no subcategories; listed by time added, descending; at most 10 results)",
+        ]);
+
+        for (query, is_err) in [
+            ("incat(\"Category:A\").since(\"last week\")", true),
+            ("incat(\"Category:A\").since(\"2024-01-08\").before(\"2024-01-01\")", true),
+            ("link(\"A\").since(\"2024-01-01\")", true),
+            ("incat(\"Category:A\").since(\"2024-01-01\").before(\"2024-01-01\")", false),
+        ] {
+            let expr = Expression::parse::<VerboseError<_>>(query).unwrap();
+            assert_eq!(plan(&expr, IntOrInf::Inf, &namespaces).is_err(), is_err, "{query}");
+        }
+    }
+}
@@ -1,18 +1,21 @@
 //! Streams for query execution
 
-use ast::{Span, Expression};
+use ast::{Attribute, Span, Expression, ExpressionNot};
 use async_stream::stream;
 use mwtitle::Title;
-use core::mem;
-use crate::{SolverResult, RuntimeError, RuntimeWarning, SemanticError, attr::*};
+use core::{future::Future, mem, pin::pin};
+use crate::{CountResult, SolverResult, memo::Memo, optimize::optimize, RuntimeError, RuntimeWarning, SemanticError, attr::*};
 use futures::{Stream, StreamExt};
 use intorinf::IntOrInf;
-use provider::DataProvider;
-use std::collections::BTreeSet;
+use provider::{DataProvider, PageInfo, PageInfoError};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    hash::{BuildHasher, RandomState},
+};
 use trio_result::TrioResult;
 
-/// Make the output unique.
-fn unique<I, P>(stream: I, span: Span) -> impl Stream<Item=SolverResult<P>>
+/// Make the output unique. With `strip_fragments`, titles differing only in their fragment are the same.
+fn unique<I, P>(stream: I, strip_fragments: bool, span: Span) -> impl Stream<Item=SolverResult<P>>
 where
     I: Stream<Item=SolverResult<P>>,
     P: DataProvider,
@@ -29,8 +32,7 @@ where
                             continue;
                         },
                     };
-                    if !yielded.contains(t) {
-                        yielded.insert(t.to_owned());
+                    if yielded.insert(dedup_key(t, strip_fragments)) {
                         yield TrioResult::Ok(info);
                     }
                 },
@@ -40,8 +42,10 @@ where
     }
 }
 
-/// Make the output counted.
-fn counted<I, P>(stream: I, limit: usize, span: Span) -> impl Stream<Item=SolverResult<P>>
+/// Make the output counted. The limit is inclusive: up to `limit` results are yielded, and `ResultLimitExceeded`
+/// is only raised when there is one more, after which the stream ends. A stream of exactly `limit` results passes as is.
+/// `default` tells whether `limit` is the default limit, rather than one set by a `limit` modifier.
+fn counted<I, P>(stream: I, limit: usize, default: bool, span: Span) -> impl Stream<Item=SolverResult<P>>
 where
     I: Stream<Item=SolverResult<P>>,
     P: DataProvider,
@@ -55,7 +59,7 @@ where
                     if count <= limit {
                         yield x;
                     } else {
-                        yield TrioResult::Warn(RuntimeWarning::ResultLimitExceeded { span, limit });
+                        yield TrioResult::Warn(RuntimeWarning::ResultLimitExceeded { span, limit, default });
                         break;
                     }
                 },
@@ -65,6 +69,117 @@ where
     }
 }
 
+/// Skip the first `offset` results. Warnings and errors are passed through.
+fn skipped<I, P>(stream: I, offset: usize) -> impl Stream<Item=SolverResult<P>>
+where
+    I: Stream<Item=SolverResult<P>>,
+    P: DataProvider,
+{
+    stream! {
+        let mut count = 0;
+        for await item in stream {
+            match item {
+                x @ TrioResult::Ok(_) => {
+                    count += 1;
+                    if count > offset {
+                        yield x;
+                    }
+                },
+                x => yield x,
+            }
+        }
+    }
+}
+
+/// Sort the output. Nothing is yielded until the input is exhausted, so all results are held in memory at once;
+/// warnings and errors are still passed through as they come.
+fn sorted<I, P>(stream: I, order: SortOrder, span: Span) -> impl Stream<Item=SolverResult<P>>
+where
+    I: Stream<Item=SolverResult<P>>,
+    P: DataProvider,
+{
+    stream! {
+        let mut buffer = Vec::new();
+        for await item in stream {
+            match item {
+                TrioResult::Ok(info) => {
+                    let t = match info.get_title() {
+                        Ok(x) => x.to_owned(),
+                        Err(e) => {
+                            yield TrioResult::Err(RuntimeError::PageInfo { span, error: e });
+                            continue;
+                        },
+                    };
+                    buffer.push((info.get_pageid().ok(), t, info));
+                },
+                x => yield x,
+            }
+        }
+        match order {
+            SortOrder::Title => buffer.sort_by(|(_, t1, _), (_, t2, _)| (t1.namespace(), t1.dbkey()).cmp(&(t2.namespace(), t2.dbkey()))),
+            // `None` is less than any `Some`, so missing ids are flipped to sort last.
+            SortOrder::PageId => buffer.sort_by_key(|(id, t, _)| (id.is_none(), *id, t.namespace(), t.dbkey().to_owned())),
+        }
+        for (_, _, info) in buffer {
+            yield TrioResult::Ok(info);
+        }
+    }
+}
+
+/// A small pseudo-random generator (SplitMix64). Samples only need to look random, and be the same for the same seed.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..bound`. `bound` must not be `0`.
+    fn below(&mut self, bound: usize) -> usize {
+        ((self.next() as u128 * bound as u128) >> 64) as usize
+    }
+}
+
+/// Keep a random sample of `sample.size` results, in their original order. Only the sample is held in memory, but
+/// nothing is yielded until the input is exhausted; warnings and errors are still passed through as they come.
+fn sampled<I, P>(stream: I, sample: Sample) -> impl Stream<Item=SolverResult<P>>
+where
+    I: Stream<Item=SolverResult<P>>,
+    P: DataProvider,
+{
+    stream! {
+        let mut rng = SplitMix64(sample.seed.map_or_else(|| RandomState::new().hash_one(0), |seed| seed as u64));
+        // reservoir sampling, keeping the index of each result to restore the order. The reservoir grows with the
+        // results, as the sample may be far larger than the input.
+        let mut reservoir = Vec::new();
+        let mut seen = 0;
+        for await item in stream {
+            match item {
+                TrioResult::Ok(info) => {
+                    if reservoir.len() < sample.size {
+                        reservoir.push((seen, info));
+                    } else {
+                        let j = rng.below(seen + 1);
+                        if j < sample.size {
+                            reservoir[j] = (seen, info);
+                        }
+                    }
+                    seen += 1;
+                },
+                x => yield x,
+            }
+        }
+        reservoir.sort_by_key(|(i, _)| *i);
+        for (_, info) in reservoir {
+            yield TrioResult::Ok(info);
+        }
+    }
+}
+
 /// After the first error, the stream is cut and no longer returns anything.
 fn cut<I, P>(stream: I) -> impl Stream<Item=SolverResult<P>>
 where
@@ -84,6 +199,134 @@ where
     }
 }
 
+/// Keep only the pages passing all the filters. Titles are matched in the pretty form given by `namespaces`.
+fn filtered<I, P>(stream: I, filters: Vec<PageFilter>, namespaces: NamespaceMap, span: Span) -> impl Stream<Item=SolverResult<P>>
+where
+    I: Stream<Item=SolverResult<P>>,
+    P: DataProvider,
+{
+    stream! {
+        for await item in stream {
+            if let TrioResult::Ok(info) = item {
+                match passes(&info, &filters, &namespaces) {
+                    Ok(true) => yield TrioResult::Ok(info),
+                    Ok(false) => {},
+                    Err(e) => yield TrioResult::Err(RuntimeError::PageInfo { span, error: e }),
+                }
+            } else {
+                // yield any warnings or errors
+                yield item;
+            }
+        }
+    }
+}
+
+/// Whether `info` passes all the filters. Only the values the filters need are looked at.
+fn passes(info: &PageInfo, filters: &[PageFilter], namespaces: &NamespaceMap) -> Result<bool, PageInfoError> {
+    for filter in filters {
+        let pass = match filter {
+            PageFilter::Protected => !info.get_protection()?.is_empty(),
+            PageFilter::Unprotected => info.get_protection()?.is_empty(),
+            PageFilter::Matches(regex) => regex.is_match(&namespaces.pretty(info.get_title()?)),
+        };
+        if !pass {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Keep only the pages in `namespace`.
+fn in_namespace<I, P>(stream: I, namespace: HashSet<i32>, span: Span) -> impl Stream<Item=SolverResult<P>>
+where
+    I: Stream<Item=SolverResult<P>>,
+    P: DataProvider,
+{
+    stream! {
+        for await item in stream {
+            if let TrioResult::Ok(info) = item {
+                match info.get_title() {
+                    Ok(title) if namespace.contains(&title.namespace()) => yield TrioResult::Ok(info),
+                    Ok(_) => {},
+                    Err(e) => yield TrioResult::Err(RuntimeError::PageInfo { span, error: e }),
+                }
+            } else {
+                // yield any warnings or errors
+                yield item;
+            }
+        }
+    }
+}
+
+/// Apply the `ns` modifier on the stream again, if there is one, for providers that cannot filter by namespace themselves.
+/// This only looks at the titles already fetched, so it costs nothing more if the provider did filter.
+fn with_namespace<'a, P>(st: Box<dyn Stream<Item=SolverResult<P>> + 'a>, namespace: Option<HashSet<i32>>, span: Span) -> Box<dyn Stream<Item=SolverResult<P>> + 'a>
+where
+    P: DataProvider + 'a,
+{
+    match namespace {
+        Some(namespace) => Box::new(in_namespace(Box::into_pin(st), namespace, span)),
+        None => st,
+    }
+}
+
+/// Apply the filter attributes on the stream, if there are any.
+fn with_filters<'a, P>(st: Box<dyn Stream<Item=SolverResult<P>> + 'a>, attrs: &[Attribute], namespaces: &NamespaceMap, span: Span) -> Result<Box<dyn Stream<Item=SolverResult<P>> + 'a>, SemanticError>
+where
+    P: DataProvider + 'a,
+{
+    let filters = filters_from_attributes(attrs)?;
+    if filters.is_empty() {
+        Ok(st)
+    } else {
+        Ok(Box::new(filtered(Box::into_pin(st), filters, namespaces.clone(), span)))
+    }
+}
+
+/// Apply the offset attribute on the stream, if there is one.
+fn with_offset<'a, P>(st: Box<dyn Stream<Item=SolverResult<P>> + 'a>, attrs: &[Attribute]) -> Result<Box<dyn Stream<Item=SolverResult<P>> + 'a>, SemanticError>
+where
+    P: DataProvider + 'a,
+{
+    match offset_from_attributes(attrs)? {
+        Some(offset) if offset > 0 => Ok(Box::new(skipped(Box::into_pin(st), offset))),
+        _ => Ok(st),
+    }
+}
+
+/// Apply the sort attribute on the stream, if there is one.
+fn with_sort<'a, P>(st: Box<dyn Stream<Item=SolverResult<P>> + 'a>, attrs: &[Attribute], span: Span) -> Result<Box<dyn Stream<Item=SolverResult<P>> + 'a>, SemanticError>
+where
+    P: DataProvider + 'a,
+{
+    match sort_from_attributes(attrs)? {
+        Some(order) => Ok(Box::new(sorted(Box::into_pin(st), order, span))),
+        None => Ok(st),
+    }
+}
+
+/// Apply the random attribute on the stream, if there is one.
+fn with_sample<'a, P>(st: Box<dyn Stream<Item=SolverResult<P>> + 'a>, attrs: &[Attribute]) -> Result<Box<dyn Stream<Item=SolverResult<P>> + 'a>, SemanticError>
+where
+    P: DataProvider + 'a,
+{
+    match sample_from_attributes(attrs)? {
+        Some(sample) => Ok(Box::new(sampled(Box::into_pin(st), sample))),
+        None => Ok(st),
+    }
+}
+
+/// Apply the default limit on the stream of an expression without a `limit` modifier, if the default is finite.
+fn with_default_limit<'a, P>(st: Box<dyn Stream<Item=SolverResult<P>> + 'a>, default_count_limit: IntOrInf, span: Span) -> Box<dyn Stream<Item=SolverResult<P>> + 'a>
+where
+    P: DataProvider + 'a,
+{
+    match default_count_limit {
+        IntOrInf::Int(limit) => Box::new(counted(Box::into_pin(st), limit as usize, true, span)),
+        IntOrInf::Inf => st,
+    }
+}
+
 /// Raw page info stream.
 fn pageinfo<I, P>(titles: I, provider: P, span: Span) -> impl Stream<Item=SolverResult<P>>
 where
@@ -102,6 +345,32 @@ where
     }
 }
 
+/// Make a page info stream of page ids. Ids that come back with no page are reported once the provider is done.
+fn pageinfo_by_id<P>(pageids: Vec<u32>, provider: P, span: Span) -> impl Stream<Item=SolverResult<P>>
+where
+    P: DataProvider,
+{
+    stream! {
+        let mut resolved = BTreeSet::new();
+        let st = provider.get_page_info_by_id(pageids.iter().copied());
+        for await item in st {
+            match item {
+                TrioResult::Ok(item) => {
+                    if let Ok(pageid) = item.get_pageid() {
+                        resolved.insert(pageid);
+                    }
+                    yield TrioResult::Ok(item)
+                },
+                TrioResult::Warn(w) => yield TrioResult::Warn(RuntimeWarning::Provider { span, warn: w }),
+                TrioResult::Err(e) => yield TrioResult::Err(RuntimeError::Provider { span, error: e }),
+            }
+        }
+        for pageid in pageids.into_iter().filter(|id| !resolved.contains(id)) {
+            yield TrioResult::Warn(RuntimeWarning::UnknownPageId { span, pageid });
+        }
+    }
+}
+
 macro_rules! make_query {
     ($method:ident, $trait_method:ident, $config_class:ty) => {
         /// Make a normal query stream.
@@ -143,10 +412,21 @@ macro_rules! make_query {
 make_query!(links, get_links, provider::LinksConfig);
 make_query!(backlinks, get_backlinks, provider::BackLinksConfig);
 make_query!(embeds, get_embeds, provider::EmbedsConfig);
+make_query!(templates, get_templates, provider::TemplatesConfig);
+make_query!(images, get_images, provider::ImagesConfig);
+make_query!(file_usage, get_file_usage, provider::FileUsageConfig);
+make_query!(categories, get_categories, provider::CategoriesConfig);
+make_query!(langlinks, get_langlinks, provider::LangLinksConfig);
 make_query!(prefix, get_prefix, provider::PrefixConfig);
 
-// Make a category member stream.
-fn categorymembers<I, P>(stream: I, provider: P, config: provider::CategoryMembersConfig, max_depth: IntOrInf, span: Span) -> impl Stream<Item=SolverResult<P>>
+/// Make a category member stream.
+///
+/// The search from each input category expands at most `budget` categories, counting the input itself.
+/// Subcategories past the budget are still yielded, but not searched, and a warning is raised once.
+///
+/// Layers are only queried as the results are pulled, so once a limit downstream is reached and the stream is dropped,
+/// no deeper layer is queried.
+fn categorymembers<I, P>(stream: I, provider: P, config: provider::CategoryMembersConfig, max_depth: IntOrInf, budget: usize, span: Span) -> impl Stream<Item=SolverResult<P>>
 where
     I: Stream<Item=SolverResult<P>>,
     P: DataProvider,
@@ -165,6 +445,7 @@ where
                 let mut current_depth = IntOrInf::Int(0);
                 let mut visited_categories = BTreeSet::new();
                 let mut to_visit = BTreeSet::new();
+                let mut truncated = false;
 
                 to_visit.insert(t.clone());
                 visited_categories.insert(t);
@@ -194,11 +475,15 @@ where
                                 };
                                 // add to visit queue?
                                 if t.is_category() && !visited_categories.contains(t) && current_depth < max_depth {
-                                    to_visit.insert(t.to_owned());
-                                    visited_categories.insert(t.to_owned());
+                                    if visited_categories.len() < budget {
+                                        to_visit.insert(t.to_owned());
+                                        visited_categories.insert(t.to_owned());
+                                    } else {
+                                        truncated = true;
+                                    }
                                 }
-                                // yield this item?
-                                if !config.namespace.as_ref().is_some_and(|ns| !ns.contains(&t.namespace())) {
+                                // yield this item? subcategories are searched outside of `namespace`, so this filters on its own.
+                                if config.namespace.as_ref().is_none_or(|ns| ns.contains(&t.namespace())) {
                                     yield TrioResult::Ok(item);
                                 }
                             },
@@ -209,6 +494,9 @@ where
                     // end of this layer.
                     current_depth += 1;
                 }
+                if truncated {
+                    yield TrioResult::Warn(RuntimeWarning::CategoryBudgetExceeded { span, budget });
+                }
             } else {
                 // yield any warnings or errors
                 yield t;
@@ -226,6 +514,10 @@ where
     stream! {
         for await item in stream {
             if let TrioResult::Ok(mut item) = item {
+                // Pages on other wikis have no associated page known to us.
+                if item.is_foreign() {
+                    continue;
+                }
                 item.swap();
 
                 // TODO: do we still need this?
@@ -250,10 +542,73 @@ where
     }
 }
 
+/// The title results are told apart by when removing repeats, with the fragment removed if `strip_fragments`.
+fn dedup_key(title: &Title, strip_fragments: bool) -> Title {
+    let mut title = title.to_owned();
+    if strip_fragments {
+        title = title.remove_fragment();
+    }
+    title
+}
+
+/// A result of a set operation, compared by its deduplication key only.
+struct Keyed(Option<Title>, PageInfo);
+
+impl Keyed {
+    fn new(info: PageInfo, strip_fragments: bool) -> Self {
+        Self(info.get_title().ok().map(|t| dedup_key(t, strip_fragments)), info)
+    }
+}
+
+impl PartialEq for Keyed {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Keyed {}
+
+impl PartialOrd for Keyed {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Keyed {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+/// The rank of a copy of a page, among the copies of the same page met by a set operation, the lower the better.
+///
+/// A copy whose associated page is known to exist comes first, then one whose associated page is known, by its title,
+/// then one without a fragment, by its fragment. This keeps the choice independent of the order the copies arrive in.
+fn preference(info: &PageInfo) -> (bool, bool, Option<&Title>, Option<&str>) {
+    let assoc_title = info.get_assoc_title().ok();
+    let fragment = info.get_title().ok().and_then(Title::fragment);
+    (info.get_assoc_exists() != Ok(true), assoc_title.is_none(), assoc_title, fragment)
+}
+
+/// Whether no copy of the same page can be preferred over `info`, taking the associated page of a page to be always
+/// the same. Such a copy is known to exist, and has no fragment unless fragments tell pages apart.
+fn settled(info: &PageInfo, strip_fragments: bool) -> bool {
+    let (assoc_missing, assoc_unknown, _, fragment) = preference(info);
+    !assoc_missing && !assoc_unknown && (!strip_fragments || fragment.is_none())
+}
+
+/// Insert `item` into `set`, replacing a copy of the same page if `item` is preferred over it.
+fn insert_preferred(set: &mut BTreeSet<Keyed>, item: Keyed) {
+    match set.get(&item) {
+        Some(other) if preference(&other.1) <= preference(&item.1) => {},
+        _ => { set.replace(item); },
+    }
+}
+
 macro_rules! set_operation {
     ($method:ident, $op:path) => {
         /// Make a set operation stream.
-        fn $method<I1, I2, P>(stream1: I1, stream2: I2) -> impl Stream<Item = SolverResult<P>>
+        fn $method<I1, I2, P>(stream1: I1, stream2: I2, strip_fragments: bool) -> impl Stream<Item = SolverResult<P>>
         where
             I1: Stream<Item = SolverResult<P>>, // + core::marker::Unpin,
             I2: Stream<Item = SolverResult<P>>, // + core::marker::Unpin,
@@ -268,115 +623,1054 @@ macro_rules! set_operation {
 
                 for await item in combined {
                     match item {
-                        (TrioResult::Ok(item), false) => { set1.insert(item); },
-                        (TrioResult::Ok(item), true) => { set2.insert(item); },
+                        (TrioResult::Ok(item), false) => insert_preferred(&mut set1, Keyed::new(item, strip_fragments)),
+                        (TrioResult::Ok(item), true) => insert_preferred(&mut set2, Keyed::new(item, strip_fragments)),
                         (x, _) => { yield x; },
                     }
                 }
 
                 for item in $op(&set1, &set2) {
-                    yield TrioResult::Ok(item.to_owned());
+                    yield TrioResult::Ok(item.1.to_owned());
                 }
             }
         }
     }
 }
 
-set_operation!(set_intersection, BTreeSet::intersection);
-set_operation!(set_union, BTreeSet::union);
+/// Make a set union stream. Unlike the other set operations, a settled copy of a page is yielded as soon as it arrives,
+/// and only its title is kept to drop repeats. Other copies are held until both sides end, so that the preferred copy
+/// is kept whatever the order they arrive in. Pages without a title cannot be told apart, and are all yielded at once.
+fn set_union<I1, I2, P>(stream1: I1, stream2: I2, strip_fragments: bool) -> impl Stream<Item = SolverResult<P>>
+where
+    I1: Stream<Item = SolverResult<P>>,
+    I2: Stream<Item = SolverResult<P>>,
+    P: DataProvider,
+{
+    stream! {
+        let combined = futures::stream_select!(Box::pin(stream1), Box::pin(stream2));
+        let mut yielded = BTreeSet::new();
+        let mut held = BTreeMap::new();
+
+        for await item in combined {
+            match item {
+                TrioResult::Ok(info) => {
+                    let Ok(title) = info.get_title() else {
+                        yield TrioResult::Ok(info);
+                        continue;
+                    };
+                    let key = dedup_key(title, strip_fragments);
+                    if yielded.contains(&key) {
+                        continue;
+                    }
+                    if settled(&info, strip_fragments) {
+                        held.remove(&key);
+                        yielded.insert(key);
+                        yield TrioResult::Ok(info);
+                    } else if held.get(&key).is_none_or(|other| preference(&info) < preference(other)) {
+                        held.insert(key, info);
+                    }
+                },
+                x => yield x,
+            }
+        }
+
+        for info in held.into_values() {
+            yield TrioResult::Ok(info);
+        }
+    }
+}
+
+/// Make a set intersection stream. Once either side ends without any item, the intersection is known to be empty,
+/// and the other side is dropped without being polled further.
+fn set_intersection<I1, I2, P>(stream1: I1, stream2: I2, strip_fragments: bool) -> impl Stream<Item = SolverResult<P>>
+where
+    I1: Stream<Item = SolverResult<P>>,
+    I2: Stream<Item = SolverResult<P>>,
+    P: DataProvider,
+{
+    stream! {
+        // `None` marks the end of a side.
+        let st1 = Box::pin(stream1.map(|x| (Some(x), false)).chain(futures::stream::once(async { (None, false) })));
+        let st2 = Box::pin(stream2.map(|x| (Some(x), true)).chain(futures::stream::once(async { (None, true) })));
+        let combined = futures::stream_select!(st1, st2);
+        let mut set1 = BTreeSet::new();
+        let mut set2 = BTreeSet::new();
+
+        for await item in combined {
+            match item {
+                (Some(TrioResult::Ok(item)), false) => insert_preferred(&mut set1, Keyed::new(item, strip_fragments)),
+                (Some(TrioResult::Ok(item)), true) => insert_preferred(&mut set2, Keyed::new(item, strip_fragments)),
+                (Some(x), _) => { yield x; },
+                (None, false) if set1.is_empty() => break,
+                (None, true) if set2.is_empty() => break,
+                (None, _) => {},
+            }
+        }
+
+        for item in set1.intersection(&set2) {
+            // both sides hold a copy, which may differ in the associated page.
+            let item = match set2.get(item) {
+                Some(other) if preference(&other.1) < preference(&item.1) => other,
+                _ => item,
+            };
+            yield TrioResult::Ok(item.1.to_owned());
+        }
+    }
+}
+
 set_operation!(set_difference, BTreeSet::difference);
 set_operation!(set_xor, BTreeSet::symmetric_difference);
 
-/// Create a stream from an expression.
-pub fn from_expr<'a, P>(expr: &Expression, provider: P, default_count_limit: IntOrInf) -> Result<Box<dyn Stream<Item=SolverResult<P>> + 'a>, SemanticError>
+/// The number of categories a category search expands from each input page, unless configured otherwise.
+pub const DEFAULT_CATEGORY_BUDGET: usize = 10000;
+
+/// How an expression is solved, besides the expression itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SolverOptions {
+    /// The number of categories a category search expands from each of its input pages.
+    pub category_budget: usize,
+    /// Whether titles differing only in their fragment, eg. `A#B` and `A`, are the same page when repeats are removed.
+    pub strip_fragments: bool,
+    /// Whether the expression is rewritten by `optimize` before it is solved.
+    pub optimize: bool,
+}
+
+impl Default for SolverOptions {
+    fn default() -> Self {
+        Self { category_budget: DEFAULT_CATEGORY_BUDGET, strip_fragments: true, optimize: false }
+    }
+}
+
+/// Create a stream from an expression, with the default options.
+///
+/// Namespace names in `ns` modifiers are resolved against `namespaces`.
+pub fn from_expr<'a, P>(expr: &Expression, provider: P, default_count_limit: IntOrInf, namespaces: &NamespaceMap) -> Result<Box<dyn Stream<Item=SolverResult<P>> + 'a>, SemanticError>
+where
+    P: DataProvider + Clone + 'a,
+{
+    from_expr_with_options(expr, provider, default_count_limit, namespaces, SolverOptions::default())
+}
+
+/// Create a stream from an expression, like `from_expr`, with the given options.
+pub fn from_expr_with_options<'a, P>(expr: &Expression, provider: P, default_count_limit: IntOrInf, namespaces: &NamespaceMap, options: SolverOptions) -> Result<Box<dyn Stream<Item=SolverResult<P>> + 'a>, SemanticError>
 where
     P: DataProvider + Clone + 'a,
 {
-    let st = from_expr_inner(expr, provider, default_count_limit)?;
+    let optimized;
+    let expr = if options.optimize {
+        let mut expr = expr.clone();
+        optimize(&mut expr);
+        optimized = expr;
+        &optimized
+    } else {
+        expr
+    };
+    let mut memo = Memo::new(expr);
+    let st = from_expr_inner(expr, provider, default_count_limit, namespaces, options, &mut memo)?;
     Ok(Box::new(cut(Box::into_pin(st))))
 }
 
-fn from_expr_inner<'a, P>(expr: &Expression, provider: P, default_count_limit: IntOrInf) -> Result<Box<dyn Stream<Item=SolverResult<P>> + 'a>, SemanticError>
+/// Count the distinct results of `expr`, without keeping the results around.
+///
+/// Attributes are checked before anything is queried, as in `from_expr`. The returned future resolves to the count
+/// and the warnings met along the way, eg. truncation by a limit, or to the first error.
+pub fn count<'a, P>(expr: &Expression, provider: P, default_count_limit: IntOrInf, namespaces: &NamespaceMap) -> Result<impl Future<Output=CountResult<P>> + 'a, SemanticError>
+where
+    P: DataProvider + Clone + 'a,
+{
+    let st = from_expr(expr, provider, default_count_limit, namespaces)?;
+    let span = expr.get_span();
+    Ok(async move {
+        // the top level is not always unique, eg. a page list with repeated titles.
+        let mut st = pin!(unique(Box::into_pin(st), SolverOptions::default().strip_fragments, span));
+        let mut count = 0;
+        let mut warnings = Vec::new();
+        while let Some(item) = st.next().await {
+            match item {
+                TrioResult::Ok(_) => count += 1,
+                TrioResult::Warn(w) => warnings.push(w),
+                TrioResult::Err(e) => return Err(e),
+            }
+        }
+        Ok((count, warnings))
+    })
+}
+
+/// Create a stream from a sub-expression. If the sub-expression appears more than once, it is evaluated once,
+/// and its results are buffered and shared between its occurrences.
+fn from_expr_inner<'a, 'e, P>(expr: &'e Expression, provider: P, default_count_limit: IntOrInf, namespaces: &NamespaceMap, options: SolverOptions, memo: &mut Memo<'e, 'a, P>) -> Result<Box<dyn Stream<Item=SolverResult<P>> + 'a>, SemanticError>
+where
+    P: DataProvider + Clone + 'a,
+{
+    if !memo.is_repeated(expr) {
+        return from_expr_node(expr, provider, default_count_limit, namespaces, options, memo);
+    }
+    if let Some(st) = memo.replay(expr) {
+        return Ok(Box::new(st));
+    }
+    let st = from_expr_node(expr, provider, default_count_limit, namespaces, options, memo)?;
+    Ok(Box::new(memo.share(expr, Box::into_pin(st))))
+}
+
+fn from_expr_node<'a, 'e, P>(expr: &'e Expression, provider: P, default_count_limit: IntOrInf, namespaces: &NamespaceMap, options: SolverOptions, memo: &mut Memo<'e, 'a, P>) -> Result<Box<dyn Stream<Item=SolverResult<P>> + 'a>, SemanticError>
 where
     P: DataProvider + Clone + 'a,
 {
     match expr {
         Expression::And(expr) => {
-            let st1 = from_expr_inner(&expr.expr1, provider.clone(), default_count_limit)?;
-            let st2 = from_expr_inner(&expr.expr2, provider.clone(), default_count_limit)?;
-            Ok(Box::new(set_intersection(Box::into_pin(st1), Box::into_pin(st2))))
+            // `a & not(b)` is `a - b`, and so is `not(b) & a`.
+            let (universe, complement) = match (as_not(&expr.expr1), as_not(&expr.expr2)) {
+                (None, None) => {
+                    let st1 = from_expr_inner(&expr.expr1, provider.clone(), default_count_limit, namespaces, options, memo)?;
+                    let st2 = from_expr_inner(&expr.expr2, provider.clone(), default_count_limit, namespaces, options, memo)?;
+                    return Ok(Box::new(set_intersection(Box::into_pin(st1), Box::into_pin(st2), options.strip_fragments)));
+                },
+                (None, Some(not)) => (&expr.expr1, not),
+                (Some(not), None) => (&expr.expr2, not),
+                (Some(_), Some(not)) => return Err(SemanticError::UnboundedComplement { span: not.get_span() }),
+            };
+            let st1 = from_expr_inner(universe, provider.clone(), default_count_limit, namespaces, options, memo)?;
+            let st2 = from_expr_inner(&complement.expr, provider.clone(), default_count_limit, namespaces, options, memo)?;
+            Ok(Box::new(set_difference(Box::into_pin(st1), Box::into_pin(st2), options.strip_fragments)))
         },
         Expression::Add(expr) => {
-            let st1 = from_expr_inner(&expr.expr1, provider.clone(), default_count_limit)?;
-            let st2 = from_expr_inner(&expr.expr2, provider.clone(), default_count_limit)?;
-            Ok(Box::new(set_union(Box::into_pin(st1), Box::into_pin(st2))))
+            let st1 = from_expr_inner(&expr.expr1, provider.clone(), default_count_limit, namespaces, options, memo)?;
+            let st2 = from_expr_inner(&expr.expr2, provider.clone(), default_count_limit, namespaces, options, memo)?;
+            Ok(Box::new(set_union(Box::into_pin(st1), Box::into_pin(st2), options.strip_fragments)))
         },
         Expression::Sub(expr) => {
-            let st1 = from_expr_inner(&expr.expr1, provider.clone(), default_count_limit)?;
-            let st2 = from_expr_inner(&expr.expr2, provider.clone(), default_count_limit)?;
-            Ok(Box::new(set_difference(Box::into_pin(st1), Box::into_pin(st2))))
+            let st1 = from_expr_inner(&expr.expr1, provider.clone(), default_count_limit, namespaces, options, memo)?;
+            let st2 = from_expr_inner(&expr.expr2, provider.clone(), default_count_limit, namespaces, options, memo)?;
+            Ok(Box::new(set_difference(Box::into_pin(st1), Box::into_pin(st2), options.strip_fragments)))
         },
         Expression::Xor(expr) => {
-            let st1 = from_expr_inner(&expr.expr1, provider.clone(), default_count_limit)?;
-            let st2 = from_expr_inner(&expr.expr2, provider.clone(), default_count_limit)?;
-            Ok(Box::new(set_xor(Box::into_pin(st1), Box::into_pin(st2))))
+            let st1 = from_expr_inner(&expr.expr1, provider.clone(), default_count_limit, namespaces, options, memo)?;
+            let st2 = from_expr_inner(&expr.expr2, provider.clone(), default_count_limit, namespaces, options, memo)?;
+            Ok(Box::new(set_xor(Box::into_pin(st1), Box::into_pin(st2), options.strip_fragments)))
         },
         Expression::Paren(expr) => {
-            from_expr_inner(&expr.expr, provider, default_count_limit)
+            from_expr_inner(&expr.expr, provider, default_count_limit, namespaces, options, memo)
         },
         Expression::Page(expr) => {
             let pages: Vec<_> = expr.vals.iter().map(|lit| lit.val.to_owned()).collect();
-            Ok(Box::new(pageinfo(pages, provider, expr.get_span())))
+            let st: Box<dyn Stream<Item=SolverResult<P>> + 'a> = Box::new(pageinfo(pages, provider, expr.get_span()));
+            Ok(with_default_limit(st, default_count_limit, expr.get_span()))
+        },
+        Expression::PageId(expr) => {
+            let pageids: Vec<u32> = expr.vals.iter()
+                .map(|lit| u32::try_from(lit.val).ok().filter(|&id| id > 0).ok_or(SemanticError::InvalidPageId { span: lit.get_span() }))
+                .collect::<Result<_, _>>()?;
+            let st: Box<dyn Stream<Item=SolverResult<P>> + 'a> = Box::new(pageinfo_by_id(pageids, provider, expr.get_span()));
+            Ok(with_default_limit(st, default_count_limit, expr.get_span()))
         },
         Expression::Link(expr) => {
-            let (config, limit) = links_config_from_attributes(&expr.attributes)?;
-            let mut st = from_expr_inner(&expr.expr, provider.clone(), default_count_limit)?;
+            let (config, limit) = links_config_from_attributes(&expr.attributes, namespaces)?;
+            let namespace = config.namespace.clone();
+            let mut st = from_expr_inner(&expr.expr, provider.clone(), default_count_limit, namespaces, options, memo)?;
             st = Box::new(links(Box::into_pin(st), provider, config, expr.get_span()));
+            st = with_namespace(st, namespace, expr.get_span());
+            st = with_filters(st, &expr.attributes, namespaces, expr.get_span())?;
+            st = Box::new(unique(Box::into_pin(st), options.strip_fragments, expr.get_span()));
+            st = with_offset(st, &expr.attributes)?;
             if limit.is_some_and(|l| l.is_int()) || (limit.is_none() && default_count_limit.is_int()) {
-                st = Box::new(counted(Box::into_pin(st), limit.unwrap_or(default_count_limit).unwrap_int() as usize, expr.get_span()))
+                st = Box::new(counted(Box::into_pin(st), limit.unwrap_or(default_count_limit).unwrap_int() as usize, limit.is_none(), expr.get_span()))
             }
-            Ok(Box::new(unique(Box::into_pin(st), expr.get_span())))
+            st = with_sample(st, &expr.attributes)?;
+            with_sort(st, &expr.attributes, expr.get_span())
         },
         Expression::LinkTo(expr) => {
-            let (config, limit) = backlinks_config_from_attributes(&expr.attributes)?;
-            let mut st = from_expr_inner(&expr.expr, provider.clone(), default_count_limit)?;
+            let (config, limit) = backlinks_config_from_attributes(&expr.attributes, namespaces)?;
+            let namespace = config.namespace.clone();
+            let mut st = from_expr_inner(&expr.expr, provider.clone(), default_count_limit, namespaces, options, memo)?;
             st = Box::new(backlinks(Box::into_pin(st), provider, config, expr.get_span()));
+            st = with_namespace(st, namespace, expr.get_span());
+            st = with_filters(st, &expr.attributes, namespaces, expr.get_span())?;
+            st = Box::new(unique(Box::into_pin(st), options.strip_fragments, expr.get_span()));
+            st = with_offset(st, &expr.attributes)?;
             if limit.is_some_and(|l| l.is_int()) || (limit.is_none() && default_count_limit.is_int()) {
-                st = Box::new(counted(Box::into_pin(st), limit.unwrap_or(default_count_limit).unwrap_int() as usize, expr.get_span()))
+                st = Box::new(counted(Box::into_pin(st), limit.unwrap_or(default_count_limit).unwrap_int() as usize, limit.is_none(), expr.get_span()))
             }
-            Ok(Box::new(unique(Box::into_pin(st), expr.get_span())))
+            st = with_sample(st, &expr.attributes)?;
+            with_sort(st, &expr.attributes, expr.get_span())
         },
         Expression::Embed(expr) => {
-            let (config, limit) = embeds_config_from_attributes(&expr.attributes)?;
-            let mut st = from_expr_inner(&expr.expr, provider.clone(), default_count_limit)?;
+            let (config, limit) = embeds_config_from_attributes(&expr.attributes, namespaces)?;
+            let namespace = config.namespace.clone();
+            let mut st = from_expr_inner(&expr.expr, provider.clone(), default_count_limit, namespaces, options, memo)?;
             st = Box::new(embeds(Box::into_pin(st), provider, config, expr.get_span()));
+            st = with_namespace(st, namespace, expr.get_span());
+            st = with_filters(st, &expr.attributes, namespaces, expr.get_span())?;
+            st = Box::new(unique(Box::into_pin(st), options.strip_fragments, expr.get_span()));
+            st = with_offset(st, &expr.attributes)?;
+            if limit.is_some_and(|l| l.is_int()) || (limit.is_none() && default_count_limit.is_int()) {
+                st = Box::new(counted(Box::into_pin(st), limit.unwrap_or(default_count_limit).unwrap_int() as usize, limit.is_none(), expr.get_span()))
+            }
+            st = with_sample(st, &expr.attributes)?;
+            with_sort(st, &expr.attributes, expr.get_span())
+        },
+        Expression::FileUsage(expr) => {
+            let (config, limit) = fileusage_config_from_attributes(&expr.attributes, namespaces)?;
+            let namespace = config.namespace.clone();
+            let mut st = from_expr_inner(&expr.expr, provider.clone(), default_count_limit, namespaces, options, memo)?;
+            st = Box::new(file_usage(Box::into_pin(st), provider, config, expr.get_span()));
+            st = with_namespace(st, namespace, expr.get_span());
+            st = with_filters(st, &expr.attributes, namespaces, expr.get_span())?;
+            st = Box::new(unique(Box::into_pin(st), options.strip_fragments, expr.get_span()));
+            st = with_offset(st, &expr.attributes)?;
+            if limit.is_some_and(|l| l.is_int()) || (limit.is_none() && default_count_limit.is_int()) {
+                st = Box::new(counted(Box::into_pin(st), limit.unwrap_or(default_count_limit).unwrap_int() as usize, limit.is_none(), expr.get_span()))
+            }
+            st = with_sample(st, &expr.attributes)?;
+            with_sort(st, &expr.attributes, expr.get_span())
+        },
+        Expression::TemplatesIn(expr) => {
+            let (config, limit) = templates_config_from_attributes(&expr.attributes, namespaces)?;
+            let namespace = config.namespace.clone();
+            let mut st = from_expr_inner(&expr.expr, provider.clone(), default_count_limit, namespaces, options, memo)?;
+            st = Box::new(templates(Box::into_pin(st), provider, config, expr.get_span()));
+            st = with_namespace(st, namespace, expr.get_span());
+            st = with_filters(st, &expr.attributes, namespaces, expr.get_span())?;
+            st = Box::new(unique(Box::into_pin(st), options.strip_fragments, expr.get_span()));
+            st = with_offset(st, &expr.attributes)?;
             if limit.is_some_and(|l| l.is_int()) || (limit.is_none() && default_count_limit.is_int()) {
-                st = Box::new(counted(Box::into_pin(st), limit.unwrap_or(default_count_limit).unwrap_int() as usize, expr.get_span()))
+                st = Box::new(counted(Box::into_pin(st), limit.unwrap_or(default_count_limit).unwrap_int() as usize, limit.is_none(), expr.get_span()))
             }
-            Ok(Box::new(unique(Box::into_pin(st), expr.get_span())))
+            st = with_sample(st, &expr.attributes)?;
+            with_sort(st, &expr.attributes, expr.get_span())
         },
         Expression::InCat(expr) => {
-            let (config, limit, depth) = categorymembers_config_from_attributes(&expr.attributes)?;
-            let mut st = from_expr_inner(&expr.expr, provider.clone(), default_count_limit)?;
-            st = Box::new(categorymembers(Box::into_pin(st), provider, config, depth.unwrap_or(IntOrInf::Int(0)), expr.get_span()));
+            let (config, limit, depth) = categorymembers_config_from_attributes(&expr.attributes, namespaces)?;
+            let mut st = from_expr_inner(&expr.expr, provider.clone(), default_count_limit, namespaces, options, memo)?;
+            st = Box::new(categorymembers(Box::into_pin(st), provider, config, depth.unwrap_or(IntOrInf::Int(0)), options.category_budget, expr.get_span()));
+            st = with_filters(st, &expr.attributes, namespaces, expr.get_span())?;
+            st = Box::new(unique(Box::into_pin(st), options.strip_fragments, expr.get_span()));
+            st = with_offset(st, &expr.attributes)?;
             if limit.is_some_and(|l| l.is_int()) || (limit.is_none() && default_count_limit.is_int()) {
-                st = Box::new(counted(Box::into_pin(st), limit.unwrap_or(default_count_limit).unwrap_int() as usize, expr.get_span()))
+                st = Box::new(counted(Box::into_pin(st), limit.unwrap_or(default_count_limit).unwrap_int() as usize, limit.is_none(), expr.get_span()))
             }
-            Ok(Box::new(unique(Box::into_pin(st), expr.get_span())))
+            st = with_sample(st, &expr.attributes)?;
+            with_sort(st, &expr.attributes, expr.get_span())
         },
         Expression::Prefix(expr) => {
             let (config, limit) = prefix_config_from_attributes(&expr.attributes)?;
-            let mut st = from_expr_inner(&expr.expr, provider.clone(), default_count_limit)?;
+            let mut st = from_expr_inner(&expr.expr, provider.clone(), default_count_limit, namespaces, options, memo)?;
             st = Box::new(prefix(Box::into_pin(st), provider, config, expr.get_span()));
+            st = with_filters(st, &expr.attributes, namespaces, expr.get_span())?;
+            st = Box::new(unique(Box::into_pin(st), options.strip_fragments, expr.get_span()));
+            st = with_offset(st, &expr.attributes)?;
+            if limit.is_some_and(|l| l.is_int()) || (limit.is_none() && default_count_limit.is_int()) {
+                st = Box::new(counted(Box::into_pin(st), limit.unwrap_or(default_count_limit).unwrap_int() as usize, limit.is_none(), expr.get_span()))
+            }
+            st = with_sample(st, &expr.attributes)?;
+            with_sort(st, &expr.attributes, expr.get_span())
+        },
+        Expression::Files(expr) => {
+            let (config, limit) = images_config_from_attributes(&expr.attributes, namespaces)?;
+            let namespace = config.namespace.clone();
+            let mut st = from_expr_inner(&expr.expr, provider.clone(), default_count_limit, namespaces, options, memo)?;
+            st = Box::new(images(Box::into_pin(st), provider, config, expr.get_span()));
+            st = with_namespace(st, namespace, expr.get_span());
+            st = with_filters(st, &expr.attributes, namespaces, expr.get_span())?;
+            st = Box::new(unique(Box::into_pin(st), options.strip_fragments, expr.get_span()));
+            st = with_offset(st, &expr.attributes)?;
+            if limit.is_some_and(|l| l.is_int()) || (limit.is_none() && default_count_limit.is_int()) {
+                st = Box::new(counted(Box::into_pin(st), limit.unwrap_or(default_count_limit).unwrap_int() as usize, limit.is_none(), expr.get_span()))
+            }
+            st = with_sample(st, &expr.attributes)?;
+            with_sort(st, &expr.attributes, expr.get_span())
+        },
+        Expression::CategoriesOf(expr) => {
+            let (config, limit) = categories_config_from_attributes(&expr.attributes, namespaces)?;
+            let namespace = config.namespace.clone();
+            let mut st = from_expr_inner(&expr.expr, provider.clone(), default_count_limit, namespaces, options, memo)?;
+            st = Box::new(categories(Box::into_pin(st), provider, config, expr.get_span()));
+            st = with_namespace(st, namespace, expr.get_span());
+            st = with_filters(st, &expr.attributes, namespaces, expr.get_span())?;
+            st = Box::new(unique(Box::into_pin(st), options.strip_fragments, expr.get_span()));
+            st = with_offset(st, &expr.attributes)?;
             if limit.is_some_and(|l| l.is_int()) || (limit.is_none() && default_count_limit.is_int()) {
-                st = Box::new(counted(Box::into_pin(st), limit.unwrap_or(default_count_limit).unwrap_int() as usize, expr.get_span()))
+                st = Box::new(counted(Box::into_pin(st), limit.unwrap_or(default_count_limit).unwrap_int() as usize, limit.is_none(), expr.get_span()))
             }
-            Ok(Box::new(unique(Box::into_pin(st), expr.get_span())))
+            st = with_sample(st, &expr.attributes)?;
+            with_sort(st, &expr.attributes, expr.get_span())
+        },
+        Expression::LangLinks(expr) => {
+            let (config, limit) = langlinks_config_from_attributes(&expr.attributes)?;
+            let mut st = from_expr_inner(&expr.expr, provider.clone(), default_count_limit, namespaces, options, memo)?;
+            st = Box::new(langlinks(Box::into_pin(st), provider, config, expr.get_span()));
+            st = with_filters(st, &expr.attributes, namespaces, expr.get_span())?;
+            st = Box::new(unique(Box::into_pin(st), options.strip_fragments, expr.get_span()));
+            st = with_offset(st, &expr.attributes)?;
+            if limit.is_some_and(|l| l.is_int()) || (limit.is_none() && default_count_limit.is_int()) {
+                st = Box::new(counted(Box::into_pin(st), limit.unwrap_or(default_count_limit).unwrap_int() as usize, limit.is_none(), expr.get_span()))
+            }
+            st = with_sample(st, &expr.attributes)?;
+            with_sort(st, &expr.attributes, expr.get_span())
         },
         Expression::Toggle(expr) => {
-            let st = from_expr_inner(&expr.expr, provider, default_count_limit)?;
-            Ok(Box::new(toggle(Box::into_pin(st), expr.get_span())))
+            let st = from_expr_inner(&expr.expr, provider, default_count_limit, namespaces, options, memo)?;
+            let st: Box<dyn Stream<Item=SolverResult<P>> + 'a> = Box::new(toggle(Box::into_pin(st), expr.get_span()));
+            Ok(with_default_limit(st, default_count_limit, expr.get_span()))
+        },
+        Expression::Not(expr) => {
+            // a complement without a universe has no end, and neither has one whose universe is such a complement.
+            let Some(universe) = &expr.universe else {
+                return Err(SemanticError::UnboundedComplement { span: expr.get_span() });
+            };
+            let st1 = from_expr_inner(universe, provider.clone(), default_count_limit, namespaces, options, memo)?;
+            let st2 = from_expr_inner(&expr.expr, provider.clone(), default_count_limit, namespaces, options, memo)?;
+            Ok(Box::new(set_difference(Box::into_pin(st1), Box::into_pin(st2), options.strip_fragments)))
         },
         _ => unimplemented!(),
     }
 }
+
+/// The complement without a universe `expr` is, looking through parentheses, if it is one.
+pub(crate) fn as_not(expr: &Expression) -> Option<&ExpressionNot> {
+    match expr {
+        Expression::Paren(expr) => as_not(&expr.expr),
+        Expression::Not(expr) if expr.universe.is_none() => Some(expr),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ast::Expression;
+    use crate::{NamespaceMap, RuntimeWarning, SemanticError, SolverResult};
+    use super::{SolverOptions, count, counted, from_expr, from_expr_with_options, set_difference, set_intersection, set_union};
+    use futures::{channel::mpsc, executor::block_on, StreamExt};
+    use intorinf::IntOrInf;
+    use mwtitle::Title;
+    use nom::error::VerboseError;
+    use provider::{PageInfo, ProtectionEntry, mock::{MockDataProvider, parse_title}};
+    use trio_result::TrioResult;
+
+    /// Namespace names of English Wikipedia, as far as the tests need them.
+    fn namespaces() -> NamespaceMap {
+        [("", 0), ("Talk", 1), ("User", 2), ("User talk", 3), ("Template", 10), ("Category", 14), ("File", 6), ("Image", 6)].into_iter().collect()
+    }
+
+    /// Solve `query` against `provider`, and return the sorted result titles.
+    fn solve(query: &str, provider: MockDataProvider) -> Vec<Title> {
+        let mut titles = solve_in_order(query, provider);
+        titles.sort();
+        titles
+    }
+
+    /// Solve `query` against `provider`, and return the result titles in the order they are yielded.
+    fn solve_in_order(query: &str, provider: MockDataProvider) -> Vec<Title> {
+        let expr = Expression::parse::<VerboseError<_>>(query).unwrap();
+        let st = from_expr(&expr, provider, IntOrInf::Inf, &namespaces()).unwrap();
+        block_on(Box::into_pin(st).filter_map(|x| async { x.ok() }).collect::<Vec<_>>())
+            .into_iter()
+            .map(|p| p.get_title().unwrap().to_owned())
+            .collect()
+    }
+
+    fn titles(raw: &[&str]) -> Vec<Title> {
+        let mut titles: Vec<Title> = raw.iter().map(|r| parse_title(r)).collect();
+        titles.sort();
+        titles
+    }
+
+    fn links_provider() -> MockDataProvider {
+        let mut provider = MockDataProvider::new();
+        provider.links.insert(parse_title("A"), titles(&["X", "Y", "Z"]));
+        provider.links.insert(parse_title("B"), titles(&["Y", "Z", "W"]));
+        provider
+    }
+
+    #[test]
+    fn test_set_operations() {
+        assert_eq!(solve("link(\"A\") & link(\"B\")", links_provider()), titles(&["Y", "Z"]));
+        assert_eq!(solve("link(\"A\") + link(\"B\")", links_provider()), titles(&["W", "X", "Y", "Z"]));
+        assert_eq!(solve("link(\"A\") - link(\"B\")", links_provider()), titles(&["X"]));
+        assert_eq!(solve("link(\"A\") ^ link(\"B\")", links_provider()), titles(&["W", "X"]));
+        assert_eq!(solve("link(\"A\", \"B\")", links_provider()), titles(&["W", "X", "Y", "Z"]));
+    }
+
+    #[test]
+    fn test_union_streams() {
+        let page = |raw: &str| {
+            let talk = parse_title(&format!("Talk:{raw}"));
+            TrioResult::Ok(PageInfo::new(Some(parse_title(raw)), Some(true), Some(false), Some(talk), Some(true), None))
+        };
+        let (tx1, rx1) = mpsc::unbounded();
+        let (tx2, rx2) = mpsc::unbounded();
+        let mut st = Box::pin(set_union::<_, _, MockDataProvider>(rx1, rx2, true));
+
+        // the first item comes out while both inputs are still open.
+        tx1.unbounded_send(page("A")).unwrap();
+        let first = block_on(st.next()).and_then(|x| x.ok()).unwrap();
+        assert_eq!(first.get_title().unwrap(), &parse_title("A"));
+
+        tx2.unbounded_send(page("A")).unwrap();
+        tx2.unbounded_send(page("B")).unwrap();
+        drop(tx1);
+        drop(tx2);
+        let rest: Vec<Title> = block_on(st.filter_map(|x| async { x.ok() }).collect::<Vec<_>>())
+            .into_iter()
+            .map(|p| p.get_title().unwrap().to_owned())
+            .collect();
+        assert_eq!(rest, titles(&["B"]));
+    }
+
+    #[test]
+    fn test_set_operation_copies() {
+        let copy = |assoc: Option<&str>, assoc_exists: Option<bool>| {
+            PageInfo::new(Some(parse_title("A")), Some(true), Some(false), assoc.map(parse_title), assoc_exists, None)
+        };
+        let input = |pages: &[PageInfo]| futures::stream::iter(pages.to_vec()).map(TrioResult::Ok);
+        let assoc_exists = |results: Vec<SolverResult<MockDataProvider>>| -> Vec<_> {
+            results.into_iter().filter_map(|x| x.ok()).map(|p| p.get_assoc_exists()).collect()
+        };
+
+        // the same page, with an existing talk page, a missing one, and an unknown one, in every rotation.
+        let mut copies = vec![copy(Some("Talk:A"), Some(true)), copy(Some("Talk:A"), Some(false)), copy(None, None)];
+        for _ in 0..copies.len() {
+            copies.rotate_left(1);
+            let reversed: Vec<_> = copies.iter().rev().cloned().collect();
+
+            let st = set_intersection(input(&copies), input(&reversed), true);
+            assert_eq!(assoc_exists(block_on(st.collect())), [Ok(true)]);
+            let st = set_intersection(input(&copies[..1]), input(&copies[1..]), true);
+            assert_eq!(assoc_exists(block_on(st.collect())), [Ok(true)]);
+            let st = set_difference(input(&copies), futures::stream::empty(), true);
+            assert_eq!(assoc_exists(block_on(st.collect())), [Ok(true)]);
+            let st = set_union(input(&copies[..1]), input(&copies[1..]), true);
+            assert_eq!(assoc_exists(block_on(st.collect())), [Ok(true)]);
+        }
+
+        // without the preferred copy, the union holds the others back and still picks the same one.
+        let copies = [copy(None, None), copy(Some("Talk:A"), Some(false))];
+        for pages in [&copies[..], &[copies[1].clone(), copies[0].clone()]] {
+            let st = set_union(input(&pages[..1]), input(&pages[1..]), true);
+            assert_eq!(assoc_exists(block_on(st.collect())), [Ok(false)]);
+        }
+    }
+
+    #[test]
+    fn test_union_untitled() {
+        let untitled = || PageInfo::new(None, Some(true), Some(false), None, None, None);
+        let input = |pages: Vec<PageInfo>| futures::stream::iter(pages).map(TrioResult::Ok);
+
+        // pages without a title are not repeats of one another.
+        let st = set_union::<_, _, MockDataProvider>(input(vec![untitled(), untitled()]), input(vec![untitled()]), true);
+        assert_eq!(block_on(st.collect::<Vec<_>>()).len(), 3);
+    }
+
+    #[test]
+    fn test_strip_fragments() {
+        let mut provider = MockDataProvider::new();
+        provider.links.insert(parse_title("P"), vec![parse_title("A").with_fragment("S".to_string()), parse_title("A"), parse_title("B")]);
+        provider.links.insert(parse_title("Q"), vec![parse_title("A").with_fragment("T".to_string())]);
+
+        let solve_with = |query: &str, strip_fragments: bool| {
+            let expr = Expression::parse::<VerboseError<_>>(query).unwrap();
+            let options = SolverOptions { strip_fragments, ..SolverOptions::default() };
+            let st = from_expr_with_options(&expr, provider.clone(), IntOrInf::Inf, &namespaces(), options).unwrap();
+            block_on(Box::into_pin(st).filter_map(|x| async { x.ok() }).collect::<Vec<_>>())
+        };
+
+        for (query, stripped, kept) in [
+            ("link(\"P\")", 2, 3),
+            ("link(\"P\") + link(\"Q\")", 2, 4),
+            ("link(\"P\") & link(\"Q\")", 1, 0),
+            ("link(\"P\") - link(\"Q\")", 1, 3),
+        ] {
+            assert_eq!(solve_with(query, true).len(), stripped, "{query}");
+            assert_eq!(solve_with(query, false).len(), kept, "{query}");
+        }
+        // the same copy is kept whatever the order of the operands.
+        for query in ["link(\"P\") & link(\"Q\")", "link(\"Q\") & link(\"P\")"] {
+            let results = solve_with(query, true);
+            assert_eq!(results[0].get_title().unwrap().fragment(), Some("S"), "{query}");
+        }
+    }
+
+    #[test]
+    fn test_intersection_short_circuit() {
+        let page = |raw: &str| TrioResult::Ok(PageInfo::new(Some(parse_title(raw)), Some(true), Some(false), None, None, None));
+        // the right side never ends while `tx` is alive, so the intersection only ends if it stops polling it.
+        let (tx, rx) = mpsc::unbounded();
+        tx.unbounded_send(page("A")).unwrap();
+        let st = set_intersection::<_, _, MockDataProvider>(futures::stream::empty(), rx, true);
+        assert!(block_on(st.collect::<Vec<_>>()).is_empty());
+        drop(tx);
+
+        // a side with only warnings counts as empty, and the warnings are kept.
+        let (tx, rx) = mpsc::unbounded();
+        let warn = TrioResult::Warn(RuntimeWarning::ResultLimitExceeded { span: ast::Span { start: 0, end: 1 }, limit: 0, default: false });
+        let st = set_intersection::<_, _, MockDataProvider>(futures::stream::iter([warn]), rx, true);
+        let results = block_on(st.collect::<Vec<_>>());
+        assert!(matches!(results[..], [TrioResult::Warn(RuntimeWarning::ResultLimitExceeded { limit: 0, .. })]));
+        drop(tx);
+    }
+
+    #[test]
+    fn test_counted_boundary() {
+        let page = |raw: &str| TrioResult::Ok(PageInfo::new(Some(parse_title(raw)), Some(true), Some(false), None, None, None));
+        let span = ast::Span { start: 0, end: 1 };
+        let run = |n: usize| {
+            let input: Vec<SolverResult<MockDataProvider>> = (0..n).map(|i| page(&i.to_string())).collect();
+            block_on(counted(futures::stream::iter(input), 3, false, span).collect::<Vec<_>>())
+        };
+
+        for n in [2, 3] {
+            let results = run(n);
+            assert_eq!(results.len(), n);
+            assert!(results.iter().all(|x| x.is_ok()));
+        }
+        let results = run(4);
+        assert_eq!(results.iter().filter(|x| x.is_ok()).count(), 3);
+        assert!(matches!(results[3], TrioResult::Warn(RuntimeWarning::ResultLimitExceeded { limit: 3, .. })));
+        assert_eq!(results.len(), 4);
+    }
+
+    #[test]
+    fn test_toggle() {
+        let mut provider = MockDataProvider::new();
+        provider.pages.insert(
+            parse_title("A"),
+            PageInfo::new(Some(parse_title("A")), Some(true), Some(false), Some(parse_title("Talk:A")), Some(true), None),
+        );
+        provider.pages.insert(
+            parse_title("Special:B"),
+            PageInfo::new(Some(parse_title("Special:B")), Some(true), Some(false), Some(parse_title("Special:B")), Some(false), None),
+        );
+
+        assert_eq!(solve("toggle(\"A\")", provider.clone()), titles(&["Talk:A"]));
+        // pages in virtual namespaces are dropped.
+        assert_eq!(solve("toggle(\"Special:B\")", provider), titles(&[]));
+    }
+
+    #[test]
+    fn test_category_recursion() {
+        let mut provider = MockDataProvider::new();
+        provider.category_members.insert(parse_title("Category:Root"), titles(&["A", "Category:Sub"]));
+        provider.category_members.insert(parse_title("Category:Sub"), titles(&["B", "Category:Subsub"]));
+        provider.category_members.insert(parse_title("Category:Subsub"), titles(&["C", "Category:Root"]));
+
+        assert_eq!(solve("incat(\"Category:Root\")", provider.clone()), titles(&["A", "Category:Sub"]));
+        assert_eq!(solve("incat(\"Category:Root\").depth(1)", provider.clone()), titles(&["A", "B", "Category:Sub", "Category:Subsub"]));
+        // the cycle back to the root category does not loop forever.
+        assert_eq!(
+            solve("incat(\"Category:Root\").depth(-1)", provider.clone()),
+            titles(&["A", "B", "C", "Category:Root", "Category:Sub", "Category:Subsub"]),
+        );
+        assert_eq!(solve("incat(\"Category:Root\").depth(-1).ns(0)", provider), titles(&["A", "B", "C"]));
+    }
+
+    #[test]
+    fn test_category_budget() {
+        let mut provider = MockDataProvider::new();
+        provider.category_members.insert(parse_title("Category:Root"), titles(&["A", "Category:S1"]));
+        provider.category_members.insert(parse_title("Category:S1"), titles(&["B", "Category:S2"]));
+        provider.category_members.insert(parse_title("Category:S2"), titles(&["C", "Category:Root", "Category:S3"]));
+        provider.category_members.insert(parse_title("Category:S3"), titles(&["D", "Category:S1"]));
+        let expr = Expression::parse::<VerboseError<_>>("incat(\"Category:Root\").depth(-1)").unwrap();
+        let solve_with_budget = |budget: usize| {
+            let options = SolverOptions { category_budget: budget, ..SolverOptions::default() };
+            let st = from_expr_with_options(&expr, provider.clone(), IntOrInf::Inf, &namespaces(), options).unwrap();
+            let results = block_on(Box::into_pin(st).collect::<Vec<_>>());
+            let warned = results.iter().any(|x| matches!(x, TrioResult::Warn(RuntimeWarning::CategoryBudgetExceeded { .. })));
+            let mut found: Vec<Title> = results.into_iter().filter_map(|x| x.ok()).map(|p| p.get_title().unwrap().to_owned()).collect();
+            found.sort();
+            (found, warned)
+        };
+
+        // the root and `S1` are searched. `S2` is found, but not searched.
+        assert_eq!(solve_with_budget(2), (titles(&["A", "B", "Category:S1", "Category:S2"]), true));
+        assert_eq!(
+            solve_with_budget(4),
+            (titles(&["A", "B", "C", "D", "Category:Root", "Category:S1", "Category:S2", "Category:S3"]), false),
+        );
+    }
+
+    #[test]
+    fn test_optimize_option() {
+        let mut provider = links_provider();
+        provider.category_members.insert(parse_title("Category:C"), titles(&["Y", "Category:D"]));
+        provider.category_members.insert(parse_title("Category:D"), titles(&["Z", "Category:C"]));
+        // the operands of each intersection are swapped, without changing the results.
+        for query in [
+            "incat(\"Category:C\").depth(-1) & link(\"A\")",
+            "toggle(link(\"B\") & \"Y\") - (link(\"A\") & \"X\")",
+        ] {
+            let expr = Expression::parse::<VerboseError<_>>(query).unwrap();
+            let solve_with = |optimize: bool| {
+                let options = SolverOptions { optimize, ..SolverOptions::default() };
+                let st = from_expr_with_options(&expr, provider.clone(), IntOrInf::Inf, &namespaces(), options).unwrap();
+                let mut found: Vec<Title> = block_on(Box::into_pin(st).filter_map(|x| async { x.ok() }).collect::<Vec<_>>())
+                    .into_iter()
+                    .map(|p| p.get_title().unwrap().to_owned())
+                    .collect();
+                found.sort();
+                found
+            };
+            assert_eq!(solve_with(true), solve_with(false));
+        }
+        assert_eq!(solve("incat(\"Category:C\").depth(-1) & link(\"A\")", provider), titles(&["Y", "Z"]));
+    }
+
+    #[test]
+    fn test_category_sort() {
+        let mut provider = MockDataProvider::new();
+        // in the order they were added.
+        let added: Vec<Title> = ["C", "A", "E", "B", "D"].into_iter().map(parse_title).collect();
+        provider.category_members.insert(parse_title("Category:X"), added);
+
+        assert_eq!(solve_in_order("incat(\"Category:X\")", provider.clone()), ["C", "A", "E", "B", "D"].map(parse_title));
+        assert_eq!(solve_in_order("incat(\"Category:X\").sortby(sortkey)", provider.clone()), titles(&["A", "B", "C", "D", "E"]));
+        assert_eq!(solve_in_order("incat(\"Category:X\").sortby(timestamp, desc)", provider.clone()), ["D", "B", "E", "A", "C"].map(parse_title));
+        // pages through the category alphabetically.
+        assert_eq!(solve_in_order("incat(\"Category:X\").sortby(sortkey).offset(2).limit(2)", provider.clone()), titles(&["C", "D"]));
+        assert_eq!(solve_in_order("incat(\"Category:X\").sortby(sortkey, desc).offset(2).limit(2)", provider.clone()), ["C", "B"].map(parse_title));
+
+        let query = "incat(\"Category:X\").sortby(sortkey).since(\"2024-01-01\")";
+        let expr = Expression::parse::<VerboseError<_>>(query).unwrap();
+        assert!(matches!(from_expr(&expr, provider, IntOrInf::Inf, &namespaces()), Err(SemanticError::ConflictAttribute { .. })));
+    }
+
+    #[test]
+    fn test_category_limit_stops_search() {
+        // a chain of 50 categories, each with one page.
+        let mut provider = MockDataProvider::new();
+        for i in 0..50 {
+            let members = [format!("Page {i}"), format!("Category:L{}", i + 1)];
+            provider.category_members.insert(parse_title(&format!("Category:L{i}")), titles(&[&members[0], &members[1]]));
+        }
+        let expr = Expression::parse::<VerboseError<_>>("incat(\"Category:L0\").depth(-1).limit(3)").unwrap();
+        let st = from_expr(&expr, provider.clone(), IntOrInf::Inf, &namespaces()).unwrap();
+        let results = block_on(Box::into_pin(st).collect::<Vec<_>>());
+
+        assert_eq!(results.iter().filter(|x| x.is_ok()).count(), 3);
+        assert!(results.iter().any(|x| matches!(x, TrioResult::Warn(RuntimeWarning::ResultLimitExceeded { limit: 3, .. }))));
+        // the 4th result, which tells the limit is exceeded, is in the 2nd layer.
+        assert_eq!(provider.query_count(), 2);
+    }
+
+    #[test]
+    fn test_file_usage() {
+        let mut provider = MockDataProvider::new();
+        provider.file_usage.insert(parse_title("File:A.png"), titles(&["X", "User:Y", "Template:Z"]));
+        provider.file_usage.insert(parse_title("File:B.png"), titles(&["X", "W"]));
+
+        assert_eq!(solve("fileusage(\"File:A.png\", \"File:B.png\")", provider.clone()), titles(&["Template:Z", "User:Y", "W", "X"]));
+        assert_eq!(solve("fileusage(\"File:A.png\").ns(0, Template)", provider.clone()), titles(&["Template:Z", "X"]));
+
+        let expr = Expression::parse::<VerboseError<_>>("fileusage(\"File:A.png\").depth(1)").unwrap();
+        assert!(matches!(from_expr(&expr, provider, IntOrInf::Inf, &namespaces()), Err(SemanticError::InvalidAttribute { .. })));
+    }
+
+    #[test]
+    fn test_protection_filter() {
+        let protected_page = |raw: &str| PageInfo::new(Some(parse_title(raw)), Some(true), Some(false), None, None, None)
+            .with_protection(vec![ProtectionEntry { action: "edit".to_string(), level: "sysop".to_string(), expiry: None }]);
+        let unprotected_page = |raw: &str| PageInfo::new(Some(parse_title(raw)), Some(true), Some(false), None, None, None)
+            .with_protection(Vec::new());
+        let mut provider = MockDataProvider::new();
+        provider.templates.insert(parse_title("A"), titles(&["Template:X", "Template:Y", "Template:Z"]));
+        provider.pages.insert(parse_title("Template:X"), protected_page("Template:X"));
+        provider.pages.insert(parse_title("Template:Y"), unprotected_page("Template:Y"));
+        provider.pages.insert(parse_title("Template:Z"), unprotected_page("Template:Z"));
+
+        assert_eq!(solve("templatesin(\"A\").protected", provider.clone()), titles(&["Template:X"]));
+        assert_eq!(solve("templatesin(\"A\").unprotected()", provider.clone()), titles(&["Template:Y", "Template:Z"]));
+
+        let expr = Expression::parse::<VerboseError<_>>("templatesin(\"A\").protected.unprotected").unwrap();
+        assert!(matches!(from_expr(&expr, provider, IntOrInf::Inf, &namespaces()), Err(SemanticError::ConflictAttribute { .. })));
+    }
+
+    #[test]
+    fn test_matches_filter() {
+        let mut provider = MockDataProvider::new();
+        provider.links.insert(parse_title("A"), titles(&["List of cats", "List_of_dogs", "Talk:List of cats", "Cat stub", "Lists"]));
+
+        // the namespace is part of the title.
+        assert_eq!(solve("link(\"A\").matches(\"^List of \")", provider.clone()), titles(&["List of cats", "List of dogs"]));
+        assert_eq!(solve("link(\"A\").matches(\"^Talk:\")", provider.clone()), titles(&["Talk:List of cats"]));
+        assert_eq!(solve("link(\"A\").matchtitle(\"stub$\")", provider.clone()), titles(&["Cat stub"]));
+        assert_eq!(solve("link(\"A\").matches(\"(?i)^cat\")", provider.clone()), titles(&["Cat stub"]));
+        assert_eq!(solve("link(\"A\").matches(\"^cat\")", provider.clone()), titles(&[]));
+        // every pattern must match.
+        assert_eq!(solve("link(\"A\").matches(\"^List\").matches(\"dogs\")", provider.clone()), titles(&["List of dogs"]));
+        assert_eq!(solve("link(\"A\").ns(0).matches(\"cats\")", provider.clone()), titles(&["List of cats"]));
+
+        let query = "link(\"A\").matches(\"(unclosed\")";
+        let expr = Expression::parse::<VerboseError<_>>(query).unwrap();
+        match from_expr(&expr, provider, IntOrInf::Inf, &namespaces()) {
+            Err(SemanticError::InvalidRegex { span, .. }) => assert_eq!(&query[span.to_range()], "\"(unclosed\""),
+            _ => panic!("expected an invalid regex error"),
+        }
+    }
+
+    #[test]
+    fn test_random() {
+        let pages: Vec<_> = (0..50).map(|i| format!("P{i:02}")).collect();
+        let mut provider = MockDataProvider::new();
+        provider.links.insert(parse_title("A"), pages.iter().map(|p| parse_title(p)).collect());
+        let all = solve_in_order("link(\"A\")", provider.clone());
+
+        let sample = solve_in_order("link(\"A\").random(5, 42)", provider.clone());
+        assert_eq!(sample.len(), 5);
+        assert_eq!(solve_in_order("link(\"A\").random(5, 42)", provider.clone()), sample);
+        assert_ne!(solve_in_order("link(\"A\").random(5, -42)", provider.clone()), sample);
+        // the sample keeps the order of the results.
+        assert!(sample.iter().all(|t| all.contains(t)));
+        assert!(sample.windows(2).all(|w| all.iter().position(|t| *t == w[0]) < all.iter().position(|t| *t == w[1])));
+
+        assert_eq!(solve("link(\"A\").random(5)", provider.clone()).len(), 5);
+        assert_eq!(solve("link(\"A\").random(0)", provider.clone()), titles(&[]));
+        assert_eq!(solve_in_order("link(\"A\").random(100)", provider.clone()), all);
+        assert_eq!(solve_in_order("link(\"A\").random(2147483647)", provider.clone()), all);
+        // sampling comes after the limit.
+        assert_eq!(solve_in_order("link(\"A\").limit(3).random(5, 1)", provider.clone()), all[..3]);
+
+        // an unseeded sample is drawn at each occurrence, and a seeded one is shared.
+        for (query, lookups) in [
+            ("link(\"A\").random(5) & link(\"A\").random(5)", 2),
+            ("(link(\"A\").random(5) + \"B\") & (link(\"A\").random(5) + \"B\")", 2),
+            ("link(\"A\").random(5, 42) & link(\"A\").random(5, 42)", 1),
+        ] {
+            let before = provider.query_count();
+            solve(query, provider.clone());
+            assert_eq!(provider.query_count() - before, lookups, "{query}");
+        }
+        assert_eq!(solve("link(\"A\").random(5, 42) & link(\"A\").random(5, 42)", provider.clone()).len(), 5);
+
+        for query in ["link(\"A\").random(-1)", "link(\"A\").random(1).random(2)"] {
+            let expr = Expression::parse::<VerboseError<_>>(query).unwrap();
+            assert!(from_expr(&expr, provider.clone(), IntOrInf::Inf, &namespaces()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_namespace_filter() {
+        let mut provider = MockDataProvider::new();
+        provider.links.insert(parse_title("A"), titles(&["X", "Talk:X", "Template:Y", "User:Z"]));
+        provider.backlinks.insert(parse_title("A"), titles(&["W", "Talk:W"]));
+        provider.ignore_namespace = true;
+
+        assert_eq!(solve("link(\"A\")", provider.clone()), titles(&["X", "Talk:X", "Template:Y", "User:Z"]));
+        assert_eq!(solve("link(\"A\").ns(0)", provider.clone()), titles(&["X"]));
+        assert_eq!(solve("link(\"A\").ns(\"Talk\", 10)", provider.clone()), titles(&["Talk:X", "Template:Y"]));
+        assert_eq!(solve("linkto(\"A\").ns(1)", provider.clone()), titles(&["Talk:W"]));
+        // the limit counts the pages left after the filter.
+        assert_eq!(solve_in_order("link(\"A\").ns(0, 2).limit(2)", provider), titles(&["X", "User:Z"]));
+    }
+
+    #[test]
+    fn test_offset() {
+        assert_eq!(solve("link(\"A\").offset(0)", links_provider()), titles(&["X", "Y", "Z"]));
+        assert_eq!(solve("link(\"A\").offset(1)", links_provider()), titles(&["Y", "Z"]));
+        assert_eq!(solve("link(\"A\").offset(1).limit(1)", links_provider()), titles(&["Y"]));
+        assert_eq!(solve("link(\"A\").offset(5)", links_provider()), titles(&[]));
+        // a repeated page is skipped once.
+        assert_eq!(solve("link(\"A\", \"B\").offset(3)", links_provider()), titles(&["W"]));
+        assert_eq!(solve("link(\"A\", \"B\").offset(2).limit(2)", links_provider()), titles(&["W", "Z"]));
+
+        for query in ["link(\"A\").offset(-1)", "link(\"A\").offset(1).offset(2)"] {
+            let expr = Expression::parse::<VerboseError<_>>(query).unwrap();
+            assert!(from_expr(&expr, links_provider(), IntOrInf::Inf, &namespaces()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_sort() {
+        let page_with_id = |raw: &str, pageid: u32| PageInfo::new(Some(parse_title(raw)), Some(true), Some(false), None, None, None)
+            .with_details(Some(pageid), None, None, None);
+        let mut provider = MockDataProvider::new();
+        provider.links.insert(parse_title("P"), vec![parse_title("Talk:C"), parse_title("B"), parse_title("A")]);
+        provider.pages.insert(parse_title("Talk:C"), page_with_id("Talk:C", 3));
+        provider.pages.insert(parse_title("B"), page_with_id("B", 1));
+
+        let in_order = |raw: &[&str]| raw.iter().map(|r| parse_title(r)).collect::<Vec<_>>();
+        assert_eq!(solve_in_order("link(\"P\")", provider.clone()), in_order(&["Talk:C", "B", "A"]));
+        assert_eq!(solve_in_order("link(\"P\").sort(title)", provider.clone()), in_order(&["A", "B", "Talk:C"]));
+        // `A` has no page id, and comes last.
+        assert_eq!(solve_in_order("link(\"P\").sort(pageid)", provider.clone()), in_order(&["B", "Talk:C", "A"]));
+        // sorting comes after the limit.
+        assert_eq!(solve_in_order("link(\"P\").limit(2).sort(title)", provider.clone()), in_order(&["B", "Talk:C"]));
+
+        let expr = Expression::parse::<VerboseError<_>>("link(\"P\").sort(title).sort(pageid)").unwrap();
+        assert!(matches!(from_expr(&expr, provider, IntOrInf::Inf, &namespaces()), Err(SemanticError::DuplicateAttribute { .. })));
+    }
+
+    #[test]
+    fn test_count() {
+        for query in ["link(\"A\")", "link(\"A\") + link(\"B\")", "link(\"A\") - link(\"B\")", "\"A\", \"A\", \"B\""] {
+            let expr = Expression::parse::<VerboseError<_>>(query).unwrap();
+            let (total, warnings) = block_on(count(&expr, links_provider(), IntOrInf::Inf, &namespaces()).unwrap()).unwrap();
+            // a full solve keeps repeated titles of a page list.
+            let mut titles = solve(query, links_provider());
+            titles.dedup();
+            assert_eq!(total, titles.len());
+            assert!(warnings.is_empty());
+        }
+
+        let expr = Expression::parse::<VerboseError<_>>("link(\"A\").limit(2)").unwrap();
+        let (total, warnings) = block_on(count(&expr, links_provider(), IntOrInf::Inf, &namespaces()).unwrap()).unwrap();
+        assert_eq!(total, 2);
+        assert!(matches!(warnings[..], [RuntimeWarning::ResultLimitExceeded { limit: 2, .. }]));
+    }
+
+    #[test]
+    fn test_default_limit() {
+        let mut provider = MockDataProvider::new();
+        provider.pages.insert(
+            parse_title("A"),
+            PageInfo::new(Some(parse_title("A")), Some(true), Some(false), Some(parse_title("Talk:A")), Some(true), None),
+        );
+        provider.pages.insert(
+            parse_title("B"),
+            PageInfo::new(Some(parse_title("B")), Some(true), Some(false), Some(parse_title("Talk:B")), Some(true), None),
+        );
+        let solve_limited = |query: &str| {
+            let expr = Expression::parse::<VerboseError<_>>(query).unwrap();
+            let st = from_expr(&expr, provider.clone(), IntOrInf::Int(1), &namespaces()).unwrap();
+            block_on(Box::into_pin(st).collect::<Vec<_>>())
+        };
+
+        for query in ["\"A\", \"B\", \"C\"", "toggle(\"A\", \"B\")", "toggle(\"A\" + \"B\")"] {
+            let results = solve_limited(query);
+            assert_eq!(results.iter().filter(|x| x.is_ok()).count(), 1, "{query}");
+            assert!(results.iter().any(|x| matches!(x, TrioResult::Warn(RuntimeWarning::ResultLimitExceeded { limit: 1, default: true, .. }))), "{query}");
+        }
+        assert!(solve_limited("\"A\"").iter().all(|x| x.is_ok()));
+
+        // the warning tells the default limit apart from one set in the query.
+        let provider = links_provider();
+        let warning = |query: &str| {
+            let expr = Expression::parse::<VerboseError<_>>(query).unwrap();
+            let st = from_expr(&expr, provider.clone(), IntOrInf::Int(2), &namespaces()).unwrap();
+            block_on(Box::into_pin(st).filter_map(|x| async { x.warn() }).collect::<Vec<_>>()).remove(0)
+        };
+        let default = warning("link(\"A\")");
+        assert!(matches!(default, RuntimeWarning::ResultLimitExceeded { limit: 2, default: true, .. }));
+        assert_eq!(default.to_string(), "default limit `2` reached at `0:9`, use `limit` to raise it");
+        let explicit = warning("link(\"A\").limit(1)");
+        assert!(matches!(explicit, RuntimeWarning::ResultLimitExceeded { limit: 1, default: false, .. }));
+        assert_eq!(explicit.to_string(), "result limit `1` exceeded at `0:18`");
+    }
+
+    #[test]
+    fn test_shared_subexpression() {
+        let mut provider = MockDataProvider::new();
+        provider.category_members.insert(parse_title("Category:A"), titles(&["X", "Y"]));
+        provider.links.insert(parse_title("X"), titles(&["Y"]));
+
+        // one lookup of the category, and one lookup of links per member.
+        let query = "incat(\"Category:A\") & link(incat( \"Category:A\" ))";
+        assert_eq!(solve(query, provider.clone()), titles(&["Y"]));
+        assert_eq!(provider.query_count(), 3);
+
+        // the warning of the shared sub-expression is reported once.
+        let expr = Expression::parse::<VerboseError<_>>("incat(\"Category:A\").limit(1) + (incat(\"Category:A\").limit(1))").unwrap();
+        let st = from_expr(&expr, provider.clone(), IntOrInf::Inf, &namespaces()).unwrap();
+        let results = block_on(Box::into_pin(st).collect::<Vec<_>>());
+        assert_eq!(results.iter().filter(|x| x.is_ok()).count(), 1);
+        assert_eq!(results.iter().filter(|x| x.is_warn()).count(), 1);
+        assert_eq!(provider.query_count(), 4);
+    }
+
+    #[test]
+    fn test_not() {
+        let mut provider = MockDataProvider::new();
+        provider.category_members.insert(parse_title("Category:X"), titles(&["A", "B", "C"]));
+        provider.links.insert(parse_title("P"), titles(&["B", "D"]));
+
+        assert_eq!(solve("incat(\"Category:X\") & not(link(\"P\"))", provider.clone()), titles(&["A", "C"]));
+        assert_eq!(solve("(not(link(\"P\"))) & incat(\"Category:X\")", provider.clone()), titles(&["A", "C"]));
+
+        // with its own universe, a complement is a set like any other.
+        assert_eq!(solve("not(link(\"P\"), incat(\"Category:X\"))", provider.clone()), titles(&["A", "C"]));
+        assert_eq!(solve("not(link(\"P\"), incat(\"Category:X\")) & \"A\"", provider.clone()), titles(&["A"]));
+        assert_eq!(solve("\"D\" + not(link(\"P\"), incat(\"Category:X\"))", provider.clone()), titles(&["A", "C", "D"]));
+
+        for query in ["not(\"A\")", "link(not(\"A\"))", "not(\"A\") & not(\"B\")", "\"A\" + not(\"B\")", "not(\"A\", not(\"B\"))"] {
+            let expr = Expression::parse::<VerboseError<_>>(query).unwrap();
+            assert!(matches!(from_expr(&expr, provider.clone(), IntOrInf::Inf, &namespaces()), Err(SemanticError::UnboundedComplement { .. })), "{query}");
+        }
+    }
+
+    #[test]
+    fn test_page_order() {
+        let provider = MockDataProvider::new();
+
+        assert_eq!(solve_in_order("page(\"B\", \"C\", \"A\", \"B\")", provider), ["B", "C", "A"].map(parse_title));
+    }
+
+    #[test]
+    fn test_pageid() {
+        let mut provider = MockDataProvider::new();
+        for (id, name) in [(1, "A"), (2, "B")] {
+            let info = PageInfo::new(Some(parse_title(name)), Some(true), Some(false), None, None, None).with_details(Some(id), None, None, None);
+            provider.pages.insert(parse_title(name), info);
+        }
+        provider.links.insert(parse_title("B"), titles(&["C"]));
+
+        assert_eq!(solve("pageid(2, 1)", provider.clone()), titles(&["A", "B"]));
+        assert_eq!(solve("link(pageid(2))", provider.clone()), titles(&["C"]));
+
+        let query = "pageid(1, 404)";
+        let expr = Expression::parse::<VerboseError<_>>(query).unwrap();
+        let st = from_expr(&expr, provider.clone(), IntOrInf::Inf, &namespaces()).unwrap();
+        let results = block_on(Box::into_pin(st).collect::<Vec<_>>());
+        assert_eq!(results.len(), 2);
+        match &results[1] {
+            TrioResult::Warn(RuntimeWarning::UnknownPageId { span, pageid }) => {
+                assert_eq!(*pageid, 404);
+                assert_eq!(&query[span.to_range()], query);
+            },
+            _ => panic!("expected an unknown page id warning"),
+        }
+
+        let query = "pageid(1, 0)";
+        let expr = Expression::parse::<VerboseError<_>>(query).unwrap();
+        match from_expr(&expr, provider, IntOrInf::Inf, &namespaces()) {
+            Err(SemanticError::InvalidPageId { span }) => assert_eq!(&query[span.to_range()], "0"),
+            _ => panic!("expected an invalid page id error"),
+        }
+    }
+
+    #[test]
+    fn test_namespace_names() {
+        let mut provider = MockDataProvider::new();
+        provider.links.insert(parse_title("A"), titles(&["X", "Talk:X", "Template:Y", "Category:Z", "File:W"]));
+
+        assert_eq!(solve("link(\"A\").ns(Category, Template)", provider.clone()), titles(&["Category:Z", "Template:Y"]));
+        assert_eq!(solve("link(\"A\").ns(0, talk, image)", provider.clone()), titles(&["File:W", "Talk:X", "X"]));
+        assert_eq!(solve("link(\"A\").ns(\"user talk\", 14)", provider.clone()), titles(&["Category:Z"]));
+
+        let query = "link(\"A\").ns(0, Nonsense)";
+        let expr = Expression::parse::<VerboseError<_>>(query).unwrap();
+        match from_expr(&expr, provider, IntOrInf::Inf, &namespaces()) {
+            Err(SemanticError::UnknownNamespace { span, name }) => {
+                assert_eq!(name, "Nonsense");
+                assert_eq!(&query[span.to_range()], "Nonsense");
+            },
+            _ => panic!("expected an unknown namespace error"),
+        }
+    }
+    #[test]
+    fn test_namespace_wildcard() {
+        let mut provider = MockDataProvider::new();
+        provider.links.insert(parse_title("A"), titles(&["X", "Talk:X", "Template:Y", "Category:Z", "File:W"]));
+
+        assert_eq!(solve("link(\"A\").ns(*)", provider.clone()), titles(&["X", "Talk:X", "Template:Y", "Category:Z", "File:W"]));
+        assert_eq!(solve("link(\"A\").ns(!0)", provider.clone()), titles(&["Talk:X", "Template:Y", "Category:Z", "File:W"]));
+        assert_eq!(solve("link(\"A\").ns(!Talk, !\"Category\")", provider.clone()), titles(&["X", "Template:Y", "File:W"]));
+        assert_eq!(solve("link(\"A\").ns(0, 1, !Talk)", provider.clone()), titles(&["X"]));
+
+        let query = "link(\"A\").ns(0, *)";
+        let expr = Expression::parse::<VerboseError<_>>(query).unwrap();
+        match from_expr(&expr, provider.clone(), IntOrInf::Inf, &namespaces()) {
+            Err(SemanticError::MixedNamespaceWildcard { span }) => assert_eq!(&query[span.to_range()], "*"),
+            _ => panic!("expected a mixed wildcard error"),
+        }
+        let expr = Expression::parse::<VerboseError<_>>("link(\"A\").ns(!Nonsense)").unwrap();
+        assert!(matches!(from_expr(&expr, provider, IntOrInf::Inf, &namespaces()), Err(SemanticError::UnknownNamespace { .. })));
+    }
+}
@@ -1,7 +1,17 @@
 //! Three-way `Result` type.
 //! 
 //! This type extends the `Result` type to include a `Warn` variant.
+//! 
+//! With the `nightly` feature enabled, `TrioResult` implements `Try`, so the `?` operator can be used on it.
+//! Only `Err` short-circuits. `Ok` and `Warn` are both considered successful outcomes, and `?` evaluates to
+//! a `Result<T, W>` holding either the value or the warning, leaving the caller to decide what to do with it.
+
+#![cfg_attr(feature = "nightly", feature(try_trait_v2, try_trait_v2_residual))]
+
+#[cfg(feature = "nightly")]
+use core::{convert::Infallible, ops::{ControlFlow, FromResidual, Residual, Try}};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TrioResult<T, W, E> {
     Ok(T),
     Warn(W),
@@ -20,4 +30,247 @@ impl<T, W, E> TrioResult<T, W, E> {
     pub fn is_err(&self) -> bool {
         matches!(self, Self::Err(_))
     }
+
+    /// Converts into an `Option<T>`, discarding any warning or error.
+    pub fn ok(self) -> Option<T> {
+        match self {
+            Self::Ok(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// Converts into an `Option<W>`, discarding any value or error.
+    pub fn warn(self) -> Option<W> {
+        match self {
+            Self::Warn(w) => Some(w),
+            _ => None,
+        }
+    }
+
+    /// Converts into an `Option<E>`, discarding any value or warning.
+    pub fn err(self) -> Option<E> {
+        match self {
+            Self::Err(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Converts from `&TrioResult<T, W, E>` to `TrioResult<&T, &W, &E>`.
+    pub fn as_ref(&self) -> TrioResult<&T, &W, &E> {
+        match self {
+            Self::Ok(t) => TrioResult::Ok(t),
+            Self::Warn(w) => TrioResult::Warn(w),
+            Self::Err(e) => TrioResult::Err(e),
+        }
+    }
+
+    /// Maps the `Ok` value with `op`, leaving `Warn` and `Err` untouched.
+    pub fn map_ok<U, F: FnOnce(T) -> U>(self, op: F) -> TrioResult<U, W, E> {
+        match self {
+            Self::Ok(t) => TrioResult::Ok(op(t)),
+            Self::Warn(w) => TrioResult::Warn(w),
+            Self::Err(e) => TrioResult::Err(e),
+        }
+    }
+
+    /// Maps the `Warn` value with `op`, leaving `Ok` and `Err` untouched.
+    pub fn map_warn<V, F: FnOnce(W) -> V>(self, op: F) -> TrioResult<T, V, E> {
+        match self {
+            Self::Ok(t) => TrioResult::Ok(t),
+            Self::Warn(w) => TrioResult::Warn(op(w)),
+            Self::Err(e) => TrioResult::Err(e),
+        }
+    }
+
+    /// Maps the `Err` value with `op`, leaving `Ok` and `Warn` untouched.
+    pub fn map_err<F2, O: FnOnce(E) -> F2>(self, op: O) -> TrioResult<T, W, F2> {
+        match self {
+            Self::Ok(t) => TrioResult::Ok(t),
+            Self::Warn(w) => TrioResult::Warn(w),
+            Self::Err(e) => TrioResult::Err(op(e)),
+        }
+    }
+
+    /// Calls `op` if the result is `Ok`, otherwise passes `Warn` and `Err` through.
+    pub fn and_then<U, F: FnOnce(T) -> TrioResult<U, W, E>>(self, op: F) -> TrioResult<U, W, E> {
+        match self {
+            Self::Ok(t) => op(t),
+            Self::Warn(w) => TrioResult::Warn(w),
+            Self::Err(e) => TrioResult::Err(e),
+        }
+    }
+
+    /// Calls `op` if the result is `Err`, otherwise passes `Ok` and `Warn` through.
+    pub fn or_else<F2, O: FnOnce(E) -> TrioResult<T, W, F2>>(self, op: O) -> TrioResult<T, W, F2> {
+        match self {
+            Self::Ok(t) => TrioResult::Ok(t),
+            Self::Warn(w) => TrioResult::Warn(w),
+            Self::Err(e) => op(e),
+        }
+    }
+
+    /// Converts into a `Result<T, E>`, turning a `Warn` into an `Err` with `warn_to_err`.
+    pub fn into_result_with<F: FnOnce(W) -> E>(self, warn_to_err: F) -> Result<T, E> {
+        match self {
+            Self::Ok(t) => Ok(t),
+            Self::Warn(w) => Err(warn_to_err(w)),
+            Self::Err(e) => Err(e),
+        }
+    }
+}
+
+impl<T, W, E> From<Result<T, E>> for TrioResult<T, W, E> {
+    fn from(value: Result<T, E>) -> Self {
+        match value {
+            Ok(t) => Self::Ok(t),
+            Err(e) => Self::Err(e),
+        }
+    }
+}
+
+/// `?` short-circuits on `Err` only. A `Warn` does not short-circuit, it is handed back as `Err(W)` in the output.
+#[cfg(feature = "nightly")]
+impl<T, W, E> Try for TrioResult<T, W, E> {
+    type Output = Result<T, W>;
+    type Residual = TrioResult<Infallible, Infallible, E>;
+
+    fn from_output(output: Self::Output) -> Self {
+        match output {
+            Ok(t) => Self::Ok(t),
+            Err(w) => Self::Warn(w),
+        }
+    }
+
+    fn branch(self) -> ControlFlow<Self::Residual, Self::Output> {
+        match self {
+            Self::Ok(t) => ControlFlow::Continue(Ok(t)),
+            Self::Warn(w) => ControlFlow::Continue(Err(w)),
+            Self::Err(e) => ControlFlow::Break(TrioResult::Err(e)),
+        }
+    }
+}
+
+#[cfg(feature = "nightly")]
+impl<T, W, E, F: From<E>> FromResidual<TrioResult<Infallible, Infallible, E>> for TrioResult<T, W, F> {
+    fn from_residual(residual: TrioResult<Infallible, Infallible, E>) -> Self {
+        match residual {
+            TrioResult::Err(e) => Self::Err(From::from(e)),
+        }
+    }
+}
+
+#[cfg(feature = "nightly")]
+impl<T, W, E> Residual<Result<T, W>> for TrioResult<Infallible, Infallible, E> {
+    type TryType = TrioResult<T, W, E>;
+}
+
+#[cfg(test)]
+mod test {
+    use super::TrioResult;
+
+    type R = TrioResult<i32, &'static str, u8>;
+
+    #[test]
+    fn test_accessors() {
+        assert_eq!(R::Ok(1).ok(), Some(1));
+        assert_eq!(R::Warn("w").ok(), None);
+        assert_eq!(R::Err(3).ok(), None);
+
+        assert_eq!(R::Ok(1).warn(), None);
+        assert_eq!(R::Warn("w").warn(), Some("w"));
+        assert_eq!(R::Err(3).warn(), None);
+
+        assert_eq!(R::Ok(1).err(), None);
+        assert_eq!(R::Warn("w").err(), None);
+        assert_eq!(R::Err(3).err(), Some(3));
+
+        let items = [R::Ok(1), R::Warn("w"), R::Ok(2), R::Err(3)];
+        assert_eq!(items.into_iter().filter_map(R::ok).collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_from_result() {
+        assert!(matches!(R::from(Ok(1)), TrioResult::Ok(1)));
+        assert!(matches!(R::from(Err(3)), TrioResult::Err(3)));
+    }
+
+    #[test]
+    fn test_into_result_with() {
+        let warn_to_err = |w: &str| w.len() as u8;
+
+        assert_eq!(R::Ok(1).into_result_with(warn_to_err), Ok(1));
+        assert_eq!(R::Warn("ww").into_result_with(warn_to_err), Err(2));
+        assert_eq!(R::Err(3).into_result_with(warn_to_err), Err(3));
+    }
+
+    #[test]
+    fn test_as_ref() {
+        let ok = R::Ok(1);
+        let warn = R::Warn("w");
+        let err = R::Err(3);
+
+        assert!(matches!(ok.as_ref(), TrioResult::Ok(&1)));
+        assert!(matches!(warn.as_ref(), TrioResult::Warn(&"w")));
+        assert!(matches!(err.as_ref(), TrioResult::Err(&3)));
+    }
+
+    #[test]
+    fn test_map_ok() {
+        assert!(matches!(R::Ok(1).map_ok(|x| x + 1), TrioResult::Ok(2)));
+        assert!(matches!(R::Warn("w").map_ok(|x| x + 1), TrioResult::Warn("w")));
+        assert!(matches!(R::Err(3).map_ok(|x| x + 1), TrioResult::Err(3)));
+    }
+
+    #[test]
+    fn test_map_warn() {
+        assert!(matches!(R::Ok(1).map_warn(str::len), TrioResult::Ok(1)));
+        assert!(matches!(R::Warn("ww").map_warn(str::len), TrioResult::Warn(2)));
+        assert!(matches!(R::Err(3).map_warn(str::len), TrioResult::Err(3)));
+    }
+
+    #[test]
+    fn test_map_err() {
+        assert!(matches!(R::Ok(1).map_err(|e| e as i64 * 2), TrioResult::Ok(1)));
+        assert!(matches!(R::Warn("w").map_err(|e| e as i64 * 2), TrioResult::Warn("w")));
+        assert!(matches!(R::Err(3).map_err(|e| e as i64 * 2), TrioResult::Err(6)));
+    }
+
+    #[test]
+    fn test_and_then() {
+        fn half(x: i32) -> R {
+            if x % 2 == 0 { TrioResult::Ok(x / 2) } else { TrioResult::Warn("odd") }
+        }
+
+        assert!(matches!(R::Ok(4).and_then(half), TrioResult::Ok(2)));
+        assert!(matches!(R::Ok(3).and_then(half), TrioResult::Warn("odd")));
+        assert!(matches!(R::Warn("w").and_then(half), TrioResult::Warn("w")));
+        assert!(matches!(R::Err(3).and_then(half), TrioResult::Err(3)));
+    }
+
+    #[test]
+    fn test_or_else() {
+        fn recover(e: u8) -> TrioResult<i32, &'static str, ()> {
+            if e == 0 { TrioResult::Ok(0) } else { TrioResult::Err(()) }
+        }
+
+        assert!(matches!(R::Ok(1).or_else(recover), TrioResult::Ok(1)));
+        assert!(matches!(R::Warn("w").or_else(recover), TrioResult::Warn("w")));
+        assert!(matches!(R::Err(0).or_else(recover), TrioResult::Ok(0)));
+        assert!(matches!(R::Err(1).or_else(recover), TrioResult::Err(())));
+    }
+
+    #[cfg(feature = "nightly")]
+    #[test]
+    fn test_try() {
+        fn double(r: R) -> TrioResult<i32, &'static str, u16> {
+            match r? {
+                Ok(x) => TrioResult::Ok(x * 2),
+                Err(w) => TrioResult::Warn(w),
+            }
+        }
+
+        assert!(matches!(double(R::Ok(2)), TrioResult::Ok(4)));
+        assert!(matches!(double(R::Warn("w")), TrioResult::Warn("w")));
+        assert!(matches!(double(R::Err(3)), TrioResult::Err(3u16)));
+    }
 }